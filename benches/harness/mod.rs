@@ -0,0 +1,94 @@
+//!
+//! A small benchmark harness without any dependencies, shared by the benches of this folder.
+//!
+//! Every case is warmed up, timed over the given number of iterations and printed as the time per iteration.
+//! With `RTILE_BENCH_SAVE=path` the results are written as a baseline, and with `RTILE_BENCH_BASELINE=path` they are
+//! compared with a saved baseline, the process fails if any case is slower than the baseline times the tolerance
+//! (`RTILE_BENCH_TOLERANCE`, 1.5 by default).
+//!
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+pub struct Harness {
+    results: Vec<(String, Duration)>,
+}
+
+impl Harness {
+    pub fn new(title: &str) -> Self {
+        println!("{}", title);
+        Self { results: vec![] }
+    }
+
+    pub fn bench<R>(&mut self, name: &str, iterations: u32, mut f: impl FnMut() -> R) {
+        // warm up
+        black_box(f());
+        let start = Instant::now();
+        for _ in 0..iterations {
+            black_box(f());
+        }
+        let per_iteration = start.elapsed() / iterations;
+        println!("  {:<44} {:>12}", name, format_duration(per_iteration));
+        self.results.push((name.to_string(), per_iteration));
+    }
+
+    // saves and checks the results against the baseline, as configured by the environment variables
+    pub fn finish(self) {
+        if let Ok(path) = std::env::var("RTILE_BENCH_SAVE") {
+            let mut baseline = read_baseline(&path);
+            for (name, duration) in &self.results {
+                baseline.insert(name.clone(), duration.as_nanos());
+            }
+            let text: String = baseline
+                .iter()
+                .map(|(name, nanos)| format!("{}\t{}\n", name, nanos))
+                .collect();
+            std::fs::write(&path, text).expect("the baseline is written");
+        }
+        let Ok(path) = std::env::var("RTILE_BENCH_BASELINE") else {
+            return;
+        };
+        let tolerance: f64 = std::env::var("RTILE_BENCH_TOLERANCE")
+            .ok()
+            .and_then(|tolerance| tolerance.parse().ok())
+            .unwrap_or(1.5);
+        let baseline = read_baseline(&path);
+        let mut regressions = 0;
+        for (name, duration) in &self.results {
+            let Some(&nanos) = baseline.get(name) else {
+                continue;
+            };
+            let ratio = duration.as_nanos() as f64 / nanos.max(1) as f64;
+            if ratio > tolerance {
+                println!("  regression: {} is {:.2}x the baseline", name, ratio);
+                regressions += 1;
+            }
+        }
+        if regressions > 0 {
+            std::process::exit(1);
+        }
+    }
+}
+
+fn read_baseline(path: &str) -> BTreeMap<String, u128> {
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|ln| {
+            let (name, nanos) = ln.split_once('\t')?;
+            Some((name.to_string(), nanos.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos >= 10_000_000 {
+        format!("{:.2} ms", nanos as f64 / 1e6)
+    } else if nanos >= 10_000 {
+        format!("{} us", nanos / 1000)
+    } else {
+        format!("{} ns", nanos)
+    }
+}
@@ -0,0 +1,75 @@
+//!
+//! Benchmarks of the composition and the render of the tiles with tens of thousands of lines, e.g. the generated data tables.
+//!
+//! Run with `cargo bench --bench large_tiles`, see `benches/README.md` for the baselines.
+//!
+
+mod harness;
+
+use harness::Harness;
+use rtile::prelude::*;
+
+const ROWS: usize = 50_000;
+
+fn rows() -> Vec<String> {
+    (0..ROWS)
+        .map(|row| {
+            format!(
+                "| {:>6} | value {:<10} | {:>8.2} |",
+                row,
+                row * 7,
+                row as f64 / 3.0
+            )
+        })
+        .collect()
+}
+
+fn main() {
+    let mut harness = Harness::new("large tiles");
+    let rows = rows();
+
+    harness.bench("stack rows one by one (|=)", 5, || {
+        let mut table = RTile::new(vec![]);
+        for row in rows.iter().take(5_000) {
+            table |= RTile::new_without_trimming(vec![row.clone()]);
+        }
+        table
+    });
+
+    harness.bench("stack two large tiles (|)", 20, || {
+        RTile::new(rows.clone()) | RTile::new(rows.clone())
+    });
+
+    harness.bench("side by side large tiles (+)", 10, || {
+        RTile::new(rows.clone()) + RTile::new(rows.clone())
+    });
+
+    let table = RTile::new(rows.clone());
+    harness.bench("render a large tile", 10, || table.to_string());
+
+    let mut store = TileStore::new();
+    store.set("table", RTile::new(rows.clone()));
+    let page = t!("
+        <pre>
+          @{table}
+        </pre>
+    ");
+    harness.bench("render a large inner tile", 10, || store.render(&page));
+
+    store.set("unit", t!("ms"));
+    let template = RTile::new(
+        (0..ROWS)
+            .map(|row| format!("row {}: @{{unit}} | {} @{{unit}}", row, row * 3))
+            .collect(),
+    );
+    harness.bench("render placeholders on every line", 10, || {
+        store.render(&template)
+    });
+
+    harness.bench("persist a large tile", 10, || {
+        store.set("table", RTile::new(rows.clone()));
+    });
+
+    harness.bench("get a large tile", 20, || store.get("table"));
+    harness.finish();
+}
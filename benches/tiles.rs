@@ -0,0 +1,125 @@
+//!
+//! Benchmarks of the everyday operations: the construction of the tiles, their composition with `+` and `|`, the
+//! expansion of the deeply nested inner tiles and the operations of a `TileStore`.
+//!
+//! Run with `cargo bench --bench tiles`, see `benches/README.md` for the baselines and the regression gate.
+//!
+
+mod harness;
+
+use harness::Harness;
+use rtile::prelude::*;
+
+const DEPTH: usize = 100;
+const TILES: usize = 1_000;
+
+fn main() {
+    let mut harness = Harness::new("tiles");
+
+    harness.bench("construct a small tile (t!)", 10_000, || {
+        t!("
+            fn main() {
+                println!(\"hello\");
+            }
+        ")
+    });
+
+    let lines: Vec<String> = (0..100).map(|ln| format!("line {}", ln)).collect();
+    harness.bench("construct a tile of 100 lines", 2_000, || {
+        RTile::new(lines.clone())
+    });
+
+    let cells: Vec<RTile> = (0..100).map(|cell| t!(format!("cell {}", cell))).collect();
+    harness.bench("compose 100 tiles side by side (+)", 200, || {
+        cells
+            .iter()
+            .cloned()
+            .reduce(|row, cell| row + t!(" ") + cell)
+    });
+
+    harness.bench("compose 100 tiles one below the other (|)", 200, || {
+        cells.iter().cloned().reduce(|column, cell| column | cell)
+    });
+
+    harness.bench("compose a grid of 20 x 20 tiles", 50, || {
+        (0..20)
+            .map(|_| {
+                cells
+                    .iter()
+                    .take(20)
+                    .cloned()
+                    .reduce(|row, cell| row + t!(" ") + cell)
+                    .unwrap()
+            })
+            .reduce(|grid, row| grid | row)
+    });
+
+    let mut store = TileStore::new();
+    store.set("level_0", t!("leaf"));
+    for level in 1..DEPTH {
+        store.set(
+            &format!("level_{}", level),
+            RTile::new(vec![
+                format!("begin {}", level),
+                format!("  @{{level_{}}}", level - 1),
+                format!("end {}", level),
+            ]),
+        );
+    }
+    let deep = RTile::new(vec![format!("@{{level_{}}}", DEPTH - 1)]);
+    harness.bench("expand 100 levels of nested tiles", 200, || {
+        store.render(&deep)
+    });
+
+    for tile in 0..TILES {
+        store.set(&format!("tile_{}", tile), t!(format!("value {}", tile)));
+    }
+    let wide = RTile::new(
+        (0..TILES)
+            .map(|tile| format!("{}: @{{tile_{}}}", tile, tile))
+            .collect(),
+    );
+    harness.bench("expand 1000 sibling inner tiles", 100, || {
+        store.render(&wide)
+    });
+
+    let wide_line = RTile::new(vec![(0..500)
+        .map(|tile| format!("@{{tile_{}}}", tile))
+        .collect::<Vec<_>>()
+        .join(", ")]);
+    harness.bench("scan a line of 500 placeholders", 1_000, || {
+        wide_line.inner_tiles_in_raw_data()
+    });
+    harness.bench("expand a line of 500 placeholders", 100, || {
+        store.render(&wide_line)
+    });
+
+    harness.bench("set 1000 tiles in a store", 20, || {
+        let mut store = TileStore::new();
+        for tile in 0..TILES {
+            store.set(&format!("tile_{}", tile), t!(format!("value {}", tile)));
+        }
+        store
+    });
+
+    harness.bench("get 1000 tiles from a store", 100, || {
+        (0..TILES)
+            .filter_map(|tile| store.get(&format!("tile_{}", tile)))
+            .count()
+    });
+
+    harness.bench("fork a store", 1_000, || store.fork());
+
+    let exported = store.export();
+    harness.bench("merge an exported store", 10, || {
+        let mut merged = TileStore::new();
+        merged.merge(exported.clone(), ConflictPolicy::Overwrite)
+    });
+
+    harness.bench("set and remove a tile", 10_000, || {
+        store.set("scratch", t!("scratch"));
+        store.remove("scratch")
+    });
+
+    harness.finish();
+}
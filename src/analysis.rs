@@ -0,0 +1,325 @@
+//!
+//! Analysis of the text of a template against a store, without rendering it, i.e. the spans of its placeholders, the tiles they
+//! refer to, and the diagnostics (invalid placeholders, undefined tiles, cycles), for the editors and the checks of the CI.
+//!
+//! The positions follow the Language Server Protocol, i.e. the lines and the characters are zero based, and the characters
+//! are counted in UTF-16 code units, and `analysis_to_json` writes the diagnostics in the JSON shape of the protocol.
+//!
+
+use crate::indirect_selector;
+use crate::next_placeholder_span;
+use crate::Placeholder;
+use crate::PlaceholderKind;
+use crate::TileStore;
+use std::collections::HashSet;
+
+///
+/// analyze_template, analyzes the text of a template against the tiles of the store, an invalid placeholder is reported
+/// as a diagnostic instead of a panic
+/// ```
+/// use rtile::analysis::*;
+/// use rtile::prelude::*;
+///
+/// let mut store = TileStore::new();
+/// store.set("user", t!("Alice"));
+/// let analysis = analyze_template("Hello @{user}, @{greeting}!\n@{len(items}", &store);
+///
+/// assert_eq!(analysis.tile_at(Position::new(0, 9)), Some("user"));
+/// let messages: Vec<&str> = analysis.diagnostics.iter().map(|d| d.message.as_str()).collect();
+/// assert_eq!(
+///     messages,
+///     vec!["undefined tile `greeting`", "missing `)` in @{len(items} expression"]
+/// );
+/// assert_eq!(analysis.diagnostics[0].range, Range::new(Position::new(0, 15), Position::new(0, 26)));
+/// ```
+///
+pub fn analyze_template(text: &str, store: &TileStore) -> Analysis {
+    let mut analysis = Analysis::default();
+    let mut parsed = vec![];
+    for (line, ln) in text.split('\n').enumerate() {
+        let mut cursor = 0;
+        while let Some((start, span)) = next_placeholder_span(&ln[cursor..]) {
+            let start = cursor + start;
+            let Some((end, placeholder_text)) = span else {
+                analysis.diagnostics.push(Diagnostic {
+                    range: Range::new(position(ln, line, start), position(ln, line, ln.len())),
+                    severity: Severity::Error,
+                    message: "unfinished @{} expression".to_string(),
+                    related_tile: None,
+                });
+                break;
+            };
+            cursor += end;
+            let range = Range::new(position(ln, line, start), position(ln, line, cursor));
+            match Placeholder::try_parse(placeholder_text) {
+                Ok(placeholder) => parsed.push((range, placeholder_text, placeholder)),
+                Err(message) => analysis.diagnostics.push(Diagnostic {
+                    range,
+                    severity: Severity::Error,
+                    message,
+                    related_tile: None,
+                }),
+            }
+        }
+    }
+    // the parameters of a parameterized tile are given by its callers
+    let definitions: HashSet<String> = parsed
+        .iter()
+        .flat_map(|(_, _, placeholder)| match &placeholder.kind {
+            PlaceholderKind::Definition => vec![placeholder.name.clone()],
+            PlaceholderKind::Parameters(parameters) => parameters.clone(),
+            _ => vec![],
+        })
+        .collect();
+    for (range, placeholder_text, placeholder) in parsed {
+        let mut tiles = vec![];
+        let names = placeholder
+            .inner_tile_names(&definitions)
+            .into_iter()
+            .chain(
+                placeholder
+                    .bindings
+                    .iter()
+                    .map(|(_, tile_name)| tile_name.clone()),
+            );
+        for name in names {
+            // the target of an indirect reference is known when rendering only, its selector is the tile referred to
+            let name = match indirect_selector(&name) {
+                Some(selector) => selector.to_string(),
+                None => name,
+            };
+            if !tiles.contains(&name) {
+                tiles.push(name);
+            }
+        }
+        for name in &tiles {
+            let (severity, message) = if !store.contains(name) {
+                (Severity::Warning, format!("undefined tile `{}`", name))
+            } else if let Err(e) = store.topo_order(name) {
+                (Severity::Error, e.to_string())
+            } else {
+                continue;
+            };
+            analysis.diagnostics.push(Diagnostic {
+                range,
+                severity,
+                message,
+                related_tile: Some(name.clone()),
+            });
+        }
+        analysis.placeholders.push(PlaceholderRef {
+            range,
+            text: placeholder_text.to_string(),
+            tiles,
+        });
+    }
+    analysis
+        .diagnostics
+        .sort_by_key(|diagnostic| diagnostic.range.start);
+    analysis
+}
+
+///
+/// analysis_to_json, writes the diagnostics of the analysis as a JSON array of the diagnostics of the Language Server Protocol,
+/// i.e. `range`, `severity` (1 for an error, 2 for a warning), `source` and `message`, along with the name of the tile with the
+/// problem as `relatedTile`, one diagnostic per line
+/// ```
+/// use rtile::analysis::*;
+/// use rtile::prelude::*;
+///
+/// let analysis = analyze_template("Hello @{json_user}!", &TileStore::new());
+/// assert_eq!(
+///     analysis_to_json(&analysis),
+///     r#"[
+///   {"range":{"start":{"line":0,"character":6},"end":{"line":0,"character":18}},"severity":2,"source":"rtile","message":"undefined tile `json_user`","relatedTile":"json_user"}
+/// ]"#
+/// );
+/// assert_eq!(analysis_to_json(&analyze_template("Hello!", &TileStore::new())), "[]");
+/// ```
+///
+pub fn analysis_to_json(analysis: &Analysis) -> String {
+    if analysis.diagnostics.is_empty() {
+        return "[]".to_string();
+    }
+    let diagnostics: Vec<String> = analysis
+        .diagnostics
+        .iter()
+        .map(|diagnostic| format!("  {}", diagnostic.to_json()))
+        .collect();
+    format!("[\n{}\n]", diagnostics.join(",\n"))
+}
+
+///
+/// Analysis, the result of `analyze_template`, the placeholders and the diagnostics are in the order of the text
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Analysis {
+    /// the valid placeholders of the template
+    pub placeholders: Vec<PlaceholderRef>,
+    /// the problems found in the template
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Analysis {
+    /// returns true, if there are no diagnostics
+    pub fn is_ok(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// returns the name of the tile referred to by the placeholder at the position, e.g. for go-to-definition, the first one
+    /// for an expression referring to several tiles
+    pub fn tile_at(&self, position: Position) -> Option<&str> {
+        self.placeholders
+            .iter()
+            .find(|placeholder| placeholder.range.contains(position))
+            .and_then(|placeholder| placeholder.tiles.first())
+            .map(|name| name.as_str())
+    }
+
+    /// returns the ranges of the placeholders referring to the tile, e.g. for find-all-references
+    pub fn references(&self, tile: &str) -> Vec<Range> {
+        self.placeholders
+            .iter()
+            .filter(|placeholder| placeholder.tiles.iter().any(|name| name == tile))
+            .map(|placeholder| placeholder.range)
+            .collect()
+    }
+}
+
+///
+/// PlaceholderRef, a placeholder of the analyzed template
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaceholderRef {
+    /// the range of the placeholder, from its `@` to past its closing brace
+    pub range: Range,
+    /// the text between the braces, e.g. `name:skip_if_blank`
+    pub text: String,
+    /// the names of the tiles referred to, i.e. the name, the operands of an expression and the bound tiles
+    pub tiles: Vec<String>,
+}
+
+///
+/// Diagnostic, a problem found in the analyzed template
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// the range of the placeholder with the problem
+    pub range: Range,
+    /// the severity of the problem
+    pub severity: Severity,
+    /// the description of the problem, the same as the message of the panic when rendering, for an invalid placeholder
+    pub message: String,
+    /// the name of the tile with the problem, if any
+    pub related_tile: Option<String>,
+}
+
+impl Diagnostic {
+    /// returns the diagnostic as a JSON object, see `analysis_to_json`
+    pub fn to_json(&self) -> String {
+        let severity = match self.severity {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+        };
+        let related_tile = match &self.related_tile {
+            Some(name) => format!(",\"relatedTile\":{}", json_string(name)),
+            None => String::new(),
+        };
+        format!(
+            "{{\"range\":{},\"severity\":{},\"source\":\"rtile\",\"message\":{}{}}}",
+            self.range.to_json(),
+            severity,
+            json_string(&self.message),
+            related_tile
+        )
+    }
+}
+
+///
+/// Severity, of a diagnostic, an error fails the rendering while a warning renders (e.g. an undefined tile renders as empty text)
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// the template can't be rendered
+    Error,
+    /// the template renders, likely not as intended
+    Warning,
+}
+
+///
+/// Position, in the text of a template, zero based, the character is counted in UTF-16 code units
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    /// the line, zero based
+    pub line: usize,
+    /// the character of the line, zero based, in UTF-16 code units
+    pub character: usize,
+}
+
+impl Position {
+    /// creates a position
+    pub fn new(line: usize, character: usize) -> Self {
+        Self { line, character }
+    }
+}
+
+///
+/// Range, in the text of a template, the end is exclusive
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Range {
+    /// the first position of the range
+    pub start: Position,
+    /// the position past the range
+    pub end: Position,
+}
+
+impl Range {
+    /// creates a range
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    /// returns true, if the position is within the range
+    pub fn contains(&self, position: Position) -> bool {
+        self.start <= position && position < self.end
+    }
+
+    fn to_json(self) -> String {
+        let position = |position: Position| {
+            format!(
+                "{{\"line\":{},\"character\":{}}}",
+                position.line, position.character
+            )
+        };
+        format!(
+            "{{\"start\":{},\"end\":{}}}",
+            position(self.start),
+            position(self.end)
+        )
+    }
+}
+
+// the position of the byte index of the line
+fn position(ln: &str, line: usize, idx: usize) -> Position {
+    Position::new(line, ln[..idx].encode_utf16().count())
+}
+
+// the text as a JSON string, in double quotes
+fn json_string(text: &str) -> String {
+    let mut json = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
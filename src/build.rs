@@ -0,0 +1,237 @@
+//!
+//! Tile builders for the build system files, i.e. the Makefile rules and the CMake targets.
+//!
+//! The recipe lines of a Makefile rule must start with a tab, the builders keep the tabs, as the rule line always starts at the
+//! first column, i.e. trimming the common indentation of the tile doesn't touch the recipe lines. Keep the placeholders of these
+//! tiles at the first column of the outer tile, as a multi line placeholder is aligned using spaces.
+//!
+
+use crate::RTile;
+
+///
+/// MakeRule, a rule of a Makefile, i.e. the targets, the prerequisites and the recipe
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::build::MakeRule;
+///
+/// let rule = MakeRule::new("app")
+///     .prerequisites(["main.o", "util.o"])
+///     .recipe("$(CC) -o $@ $^");
+/// assert_eq!(rule.to_tile().to_string(), "app: main.o util.o\n\t$(CC) -o $@ $^");
+///
+/// let clean = MakeRule::new("clean").phony().recipe("rm -f app *.o");
+/// let makefile = rule.to_tile() | t!("") | clean.to_tile();
+/// assert_eq!(
+///     makefile.to_string(),
+///     "app: main.o util.o\n\t$(CC) -o $@ $^\n\n.PHONY: clean\nclean:\n\trm -f app *.o"
+/// );
+/// ```
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MakeRule {
+    targets: Vec<String>,
+    prerequisites: Vec<String>,
+    order_only_prerequisites: Vec<String>,
+    recipe: Vec<String>,
+    phony: bool,
+}
+
+impl MakeRule {
+    /// creates a rule for the given target
+    pub fn new(target: &str) -> Self {
+        Self {
+            targets: vec![target.to_string()],
+            ..Default::default()
+        }
+    }
+
+    /// adds another target to the rule
+    pub fn target(mut self, target: &str) -> Self {
+        self.targets.push(target.to_string());
+        self
+    }
+
+    /// adds a prerequisite
+    pub fn prerequisite(mut self, prerequisite: &str) -> Self {
+        self.prerequisites.push(prerequisite.to_string());
+        self
+    }
+
+    /// adds the prerequisites
+    pub fn prerequisites<I, T>(mut self, prerequisites: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.prerequisites
+            .extend(prerequisites.into_iter().map(Into::into));
+        self
+    }
+
+    /// adds an order-only prerequisite, listed after the `|`
+    pub fn order_only(mut self, prerequisite: &str) -> Self {
+        self.order_only_prerequisites.push(prerequisite.to_string());
+        self
+    }
+
+    /// adds a line to the recipe, the line is written after a tab
+    pub fn recipe(mut self, ln: &str) -> Self {
+        self.recipe.push(ln.to_string());
+        self
+    }
+
+    /// marks the targets as phony, i.e. `.PHONY: targets` is written before the rule
+    pub fn phony(mut self) -> Self {
+        self.phony = true;
+        self
+    }
+
+    /// returns the rule as a tile
+    pub fn to_tile(&self) -> RTile {
+        let targets = self.targets.join(" ");
+        let mut lns = vec![];
+        if self.phony {
+            lns.push(format!(".PHONY: {}", targets));
+        }
+        let mut rule = format!("{}:", targets);
+        for prerequisite in &self.prerequisites {
+            rule.push(' ');
+            rule.push_str(prerequisite);
+        }
+        if !self.order_only_prerequisites.is_empty() {
+            rule.push_str(" | ");
+            rule.push_str(&self.order_only_prerequisites.join(" "));
+        }
+        lns.push(rule);
+        lns.extend(self.recipe.iter().map(|ln| format!("\t{}", ln)));
+        RTile::new(lns)
+    }
+}
+
+///
+/// make_variable returns the assignment of a Makefile variable as a tile, i.e. `NAME := value`
+/// ```
+/// use rtile::build::make_variable;
+///
+/// assert_eq!(make_variable("CFLAGS", "-O2 -Wall").to_string(), "CFLAGS := -O2 -Wall");
+/// ```
+///
+pub fn make_variable(name: &str, value: &str) -> RTile {
+    RTile::new(vec![format!("{} := {}", name, value)])
+}
+
+///
+/// CMakeTargetKind, the kind of a CMake target
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CMakeTargetKind {
+    /// `add_executable`
+    Executable,
+    /// `add_library(name STATIC ...)`
+    StaticLibrary,
+    /// `add_library(name SHARED ...)`
+    SharedLibrary,
+    /// `add_library(name INTERFACE)`, its usage requirements are INTERFACE instead of PRIVATE
+    InterfaceLibrary,
+}
+
+///
+/// CMakeTarget, a target of a CMakeLists.txt along with its sources and usage requirements
+/// ```
+/// use rtile::build::{CMakeTarget, CMakeTargetKind};
+///
+/// let target = CMakeTarget::new("app", CMakeTargetKind::Executable)
+///     .sources(["src/main.c", "src/util.c"])
+///     .include_directory("include")
+///     .link_library("m");
+/// assert_eq!(
+///     target.to_tile().to_string(),
+///     "add_executable(app\n    src/main.c\n    src/util.c\n)\ntarget_include_directories(app PRIVATE include)\ntarget_link_libraries(app PRIVATE m)"
+/// );
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct CMakeTarget {
+    name: String,
+    kind: CMakeTargetKind,
+    sources: Vec<String>,
+    include_directories: Vec<String>,
+    compile_definitions: Vec<String>,
+    link_libraries: Vec<String>,
+}
+
+impl CMakeTarget {
+    /// creates a target of the given kind
+    pub fn new(name: &str, kind: CMakeTargetKind) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            sources: vec![],
+            include_directories: vec![],
+            compile_definitions: vec![],
+            link_libraries: vec![],
+        }
+    }
+
+    /// adds the source files
+    pub fn sources<I, T>(mut self, sources: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.sources.extend(sources.into_iter().map(Into::into));
+        self
+    }
+
+    /// adds an include directory
+    pub fn include_directory(mut self, directory: &str) -> Self {
+        self.include_directories.push(directory.to_string());
+        self
+    }
+
+    /// adds a compile definition, e.g. `NAME=VALUE`
+    pub fn compile_definition(mut self, definition: &str) -> Self {
+        self.compile_definitions.push(definition.to_string());
+        self
+    }
+
+    /// adds a library to link with
+    pub fn link_library(mut self, library: &str) -> Self {
+        self.link_libraries.push(library.to_string());
+        self
+    }
+
+    /// returns the target as a tile
+    pub fn to_tile(&self) -> RTile {
+        let (command, kind, visibility) = match self.kind {
+            CMakeTargetKind::Executable => ("add_executable", "", "PRIVATE"),
+            CMakeTargetKind::StaticLibrary => ("add_library", " STATIC", "PRIVATE"),
+            CMakeTargetKind::SharedLibrary => ("add_library", " SHARED", "PRIVATE"),
+            CMakeTargetKind::InterfaceLibrary => ("add_library", " INTERFACE", "INTERFACE"),
+        };
+        let mut lns = vec![];
+        if self.sources.is_empty() {
+            lns.push(format!("{}({}{})", command, self.name, kind));
+        } else {
+            lns.push(format!("{}({}{}", command, self.name, kind));
+            lns.extend(self.sources.iter().map(|source| format!("    {}", source)));
+            lns.push(")".to_string());
+        }
+        for (command, values) in [
+            ("target_include_directories", &self.include_directories),
+            ("target_compile_definitions", &self.compile_definitions),
+            ("target_link_libraries", &self.link_libraries),
+        ] {
+            if !values.is_empty() {
+                lns.push(format!(
+                    "{}({} {} {})",
+                    command,
+                    self.name,
+                    visibility,
+                    values.join(" ")
+                ));
+            }
+        }
+        RTile::new(lns)
+    }
+}
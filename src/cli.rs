@@ -0,0 +1,439 @@
+//!
+//! Generation of the help text, the shell completion scripts (bash, zsh, fish) and the roff man page from a description of a command.
+//!
+
+use crate::detached;
+use crate::RTile;
+
+///
+/// Command, the description of a command line program or of one of its subcommands
+/// ```
+/// use rtile::cli::*;
+///
+/// let tool = Command::new("tool", "Generates the code")
+///     .option(CliOption::new("verbose").short('v').help("Print the generated files"))
+///     .option(CliOption::new("output").value("DIR").help("Write the files into DIR"))
+///     .subcommand(Command::new("check", "Checks the templates"));
+///
+/// assert_eq!(
+///     help(&tool),
+///     "Generates the code\n\nUsage: tool [OPTIONS] [COMMAND]\n\nOptions:\n  -v, --verbose       Print the generated files\n      --output <DIR>  Write the files into DIR\n\nCommands:\n  check  Checks the templates"
+/// );
+/// ```
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Command {
+    /// the name of the command, as typed on the command line
+    pub name: String,
+    /// a one line description of the command
+    pub about: String,
+    /// the options of the command
+    pub options: Vec<CliOption>,
+    /// the subcommands of the command
+    pub subcommands: Vec<Command>,
+}
+
+impl Command {
+    /// creates a command with the given name and description
+    pub fn new(name: &str, about: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            about: about.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// adds an option
+    pub fn option(mut self, option: CliOption) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    /// adds a subcommand
+    pub fn subcommand(mut self, subcommand: Command) -> Self {
+        self.subcommands.push(subcommand);
+        self
+    }
+}
+
+///
+/// CliOption, the description of an option of a command, i.e. `--long`, `-s` or both, optionally taking a value
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliOption {
+    /// the long name, without the leading `--`
+    pub long: String,
+    /// the short name, without the leading `-`
+    pub short: Option<char>,
+    /// the name of the value taken by the option, if any
+    pub value: Option<String>,
+    /// a one line description of the option
+    pub help: String,
+}
+
+impl CliOption {
+    /// creates an option with the given long name, without the leading `--`
+    pub fn new(long: &str) -> Self {
+        Self {
+            long: long.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// sets the short name, without the leading `-`
+    pub fn short(mut self, short: char) -> Self {
+        self.short = Some(short);
+        self
+    }
+
+    /// sets the name of the value taken by the option
+    pub fn value(mut self, value: &str) -> Self {
+        self.value = Some(value.to_string());
+        self
+    }
+
+    /// sets the description
+    pub fn help(mut self, help: &str) -> Self {
+        self.help = help.to_string();
+        self
+    }
+
+    // e.g. "-v, --verbose" or "    --output <DIR>"
+    fn flags(&self) -> String {
+        let short = self
+            .short
+            .map_or("    ".to_string(), |c| format!("-{}, ", c));
+        let value = self
+            .value
+            .as_ref()
+            .map_or(String::new(), |value| format!(" <{}>", value));
+        format!("{}--{}{}", short, self.long, value)
+    }
+}
+
+///
+/// help, returns the help text of the command, the options and the subcommands are aligned in two columns
+///
+pub fn help(command: &Command) -> String {
+    let mut usage = format!("Usage: {}", command.name);
+    if !command.options.is_empty() {
+        usage.push_str(" [OPTIONS]");
+    }
+    if !command.subcommands.is_empty() {
+        usage.push_str(" [COMMAND]");
+    }
+    let mut sections = vec![];
+    if !command.about.is_empty() {
+        sections.push(command.about.clone());
+    }
+    sections.push(usage);
+    if !command.options.is_empty() {
+        let rows = command
+            .options
+            .iter()
+            .map(|option| (option.flags(), option.help.clone()));
+        sections.push(format!("Options:\n{}", columns(rows)));
+    }
+    if !command.subcommands.is_empty() {
+        let rows = command
+            .subcommands
+            .iter()
+            .map(|subcommand| (subcommand.name.clone(), subcommand.about.clone()));
+        sections.push(format!("Commands:\n{}", columns(rows)));
+    }
+    sections.join("\n\n")
+}
+
+// the rows aligned in two columns, indented by two spaces, the columns separated by two spaces
+fn columns(rows: impl Iterator<Item = (String, String)>) -> String {
+    let (left, right): (Vec<String>, Vec<String>) = rows.unzip();
+    detached(|| {
+        let tile = RTile::new_without_trimming(vec!["  ".to_string(); left.len()])
+            + RTile::new_without_trimming(left)
+            + RTile::new_without_trimming(vec!["  ".to_string(); right.len()])
+            + RTile::new_without_trimming(right);
+        tile.to_string()
+            .split('\n')
+            .map(|ln| ln.trim_end())
+            .collect::<Vec<&str>>()
+            .join("\n")
+    })
+}
+
+///
+/// bash_completion, returns the bash completion script of the command, to be sourced (e.g. from `/etc/bash_completion.d`)
+/// ```
+/// use rtile::cli::*;
+///
+/// let tool = Command::new("tool", "")
+///     .option(CliOption::new("verbose").short('v'))
+///     .subcommand(Command::new("check", "").option(CliOption::new("strict")));
+/// let script = bash_completion(&tool);
+/// assert!(script.contains("local words=\"-v --verbose check\""));
+/// assert!(script.contains("check) words=\"--strict\" ;;"));
+/// assert!(script.ends_with("complete -F _tool tool"));
+/// ```
+///
+pub fn bash_completion(command: &Command) -> String {
+    let function = format!("_{}", shell_identifier(&command.name));
+    let mut lns = vec![
+        format!("{}() {{", function),
+        "    local cur=\"${COMP_WORDS[COMP_CWORD]}\"".to_string(),
+        format!(
+            "    local words=\"{}\"",
+            completion_words(command).join(" ")
+        ),
+    ];
+    if !command.subcommands.is_empty() {
+        lns.push("    if [ \"$COMP_CWORD\" -gt 1 ]; then".to_string());
+        lns.push("        case \"${COMP_WORDS[1]}\" in".to_string());
+        for subcommand in &command.subcommands {
+            lns.push(format!(
+                "            {}) words=\"{}\" ;;",
+                subcommand.name,
+                completion_words(subcommand).join(" ")
+            ));
+        }
+        lns.push("        esac".to_string());
+        lns.push("    fi".to_string());
+    }
+    lns.push("    COMPREPLY=($(compgen -W \"$words\" -- \"$cur\"))".to_string());
+    lns.push("}".to_string());
+    lns.push(format!("complete -F {} {}", function, command.name));
+    lns.join("\n")
+}
+
+fn completion_words(command: &Command) -> Vec<String> {
+    let mut words = vec![];
+    for option in &command.options {
+        if let Some(short) = option.short {
+            words.push(format!("-{}", short));
+        }
+        words.push(format!("--{}", option.long));
+    }
+    words.extend(
+        command
+            .subcommands
+            .iter()
+            .map(|subcommand| subcommand.name.clone()),
+    );
+    words
+}
+
+///
+/// zsh_completion, returns the zsh completion script of the command, to be saved as `_<name>` in a directory of the `fpath`
+/// ```
+/// use rtile::cli::*;
+///
+/// let tool = Command::new("tool", "")
+///     .option(CliOption::new("output").short('o').value("DIR").help("Write the files into DIR"))
+///     .subcommand(Command::new("check", "Checks the templates"));
+/// let script = zsh_completion(&tool);
+/// assert!(script.starts_with("#compdef tool\n"));
+/// assert!(script.contains("'(-o --output)'{-o,--output}'[Write the files into DIR]:DIR:_files'"));
+/// assert!(script.contains("'1:command:((check\\:\"Checks the templates\"))'"));
+/// ```
+///
+pub fn zsh_completion(command: &Command) -> String {
+    let mut arguments = vec![];
+    for option in &command.options {
+        let help = format!("[{}]", zsh_escape(&option.help));
+        let value = option
+            .value
+            .as_ref()
+            .map_or(String::new(), |value| format!(":{}:_files", value));
+        arguments.push(match option.short {
+            Some(short) => format!(
+                "'(-{short} --{long})'{{-{short},--{long}}}'{help}{value}'",
+                short = short,
+                long = option.long
+            ),
+            None => format!("'--{}{}{}'", option.long, help, value),
+        });
+    }
+    if !command.subcommands.is_empty() {
+        let subcommands: Vec<String> = command
+            .subcommands
+            .iter()
+            .map(|subcommand| {
+                format!(
+                    "{}\\:\"{}\"",
+                    subcommand.name,
+                    zsh_escape(&subcommand.about).replace('"', "\\\"")
+                )
+            })
+            .collect();
+        arguments.push(format!("'1:command:(({}))'", subcommands.join(" ")));
+        arguments.push("'*::arg:->args'".to_string());
+    }
+    let mut lns = vec![format!("#compdef {}", command.name), String::new()];
+    lns.push("_arguments -s \\".to_string());
+    let count = arguments.len();
+    for (idx, argument) in arguments.into_iter().enumerate() {
+        let continuation = if idx + 1 < count { " \\" } else { "" };
+        lns.push(format!("    {}{}", argument, continuation));
+    }
+    lns.join("\n")
+}
+
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+///
+/// fish_completion, returns the fish completion script of the command, to be saved as `<name>.fish` in `~/.config/fish/completions`
+/// ```
+/// use rtile::cli::*;
+///
+/// let tool = Command::new("tool", "")
+///     .option(CliOption::new("verbose").short('v').help("Print the generated files"))
+///     .subcommand(Command::new("check", "Checks the templates").option(CliOption::new("strict")));
+/// assert_eq!(
+///     fish_completion(&tool),
+///     "complete -c tool -s v -l verbose -d 'Print the generated files'\n\
+///      complete -c tool -n '__fish_use_subcommand' -f -a check -d 'Checks the templates'\n\
+///      complete -c tool -n '__fish_seen_subcommand_from check' -l strict"
+/// );
+/// ```
+///
+pub fn fish_completion(command: &Command) -> String {
+    let mut lns = vec![];
+    for option in &command.options {
+        lns.push(fish_option(&command.name, None, option));
+    }
+    for subcommand in &command.subcommands {
+        let mut ln = format!(
+            "complete -c {} -n '__fish_use_subcommand' -f -a {}",
+            command.name, subcommand.name
+        );
+        if !subcommand.about.is_empty() {
+            ln.push_str(&format!(" -d '{}'", fish_escape(&subcommand.about)));
+        }
+        lns.push(ln);
+    }
+    for subcommand in &command.subcommands {
+        for option in &subcommand.options {
+            lns.push(fish_option(&command.name, Some(&subcommand.name), option));
+        }
+    }
+    lns.join("\n")
+}
+
+fn fish_option(name: &str, subcommand: Option<&str>, option: &CliOption) -> String {
+    let mut ln = format!("complete -c {}", name);
+    if let Some(subcommand) = subcommand {
+        ln.push_str(&format!(" -n '__fish_seen_subcommand_from {}'", subcommand));
+    }
+    if let Some(short) = option.short {
+        ln.push_str(&format!(" -s {}", short));
+    }
+    ln.push_str(&format!(" -l {}", option.long));
+    if option.value.is_some() {
+        ln.push_str(" -r");
+    }
+    if !option.help.is_empty() {
+        ln.push_str(&format!(" -d '{}'", fish_escape(&option.help)));
+    }
+    ln
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+///
+/// man_page, returns the roff man page of the command, in the given section (e.g. 1 for the user commands)
+/// ```
+/// use rtile::cli::*;
+///
+/// let tool = Command::new("tool", "Generates the code")
+///     .option(CliOption::new("output").short('o').value("DIR").help("Write the files into DIR"));
+/// assert_eq!(
+///     man_page(&tool, 1),
+///     ".TH TOOL 1\n.SH NAME\ntool \\- Generates the code\n.SH SYNOPSIS\n\\fBtool\\fR [OPTIONS]\n\
+///      .SH OPTIONS\n.TP\n\\fB\\-o\\fR, \\fB\\-\\-output\\fR \\fIDIR\\fR\nWrite the files into DIR"
+/// );
+/// ```
+///
+pub fn man_page(command: &Command, section: u8) -> String {
+    let mut lns = vec![
+        format!(
+            ".TH {} {}",
+            roff_escape(&command.name.to_uppercase()),
+            section
+        ),
+        ".SH NAME".to_string(),
+    ];
+    if command.about.is_empty() {
+        lns.push(roff_escape(&command.name));
+    } else {
+        lns.push(format!(
+            "{} \\- {}",
+            roff_escape(&command.name),
+            roff_escape(&command.about)
+        ));
+    }
+    lns.push(".SH SYNOPSIS".to_string());
+    let mut synopsis = format!("\\fB{}\\fR", roff_escape(&command.name));
+    if !command.options.is_empty() {
+        synopsis.push_str(" [OPTIONS]");
+    }
+    if !command.subcommands.is_empty() {
+        synopsis.push_str(" [COMMAND]");
+    }
+    lns.push(synopsis);
+    if !command.options.is_empty() {
+        lns.push(".SH OPTIONS".to_string());
+        for option in &command.options {
+            lns.push(".TP".to_string());
+            lns.push(man_option(option));
+            if !option.help.is_empty() {
+                lns.push(roff_escape(&option.help));
+            }
+        }
+    }
+    if !command.subcommands.is_empty() {
+        lns.push(".SH COMMANDS".to_string());
+        for subcommand in &command.subcommands {
+            lns.push(".TP".to_string());
+            lns.push(format!("\\fB{}\\fR", roff_escape(&subcommand.name)));
+            if !subcommand.about.is_empty() {
+                lns.push(roff_escape(&subcommand.about));
+            }
+        }
+    }
+    lns.join("\n")
+}
+
+fn man_option(option: &CliOption) -> String {
+    let mut flags = vec![];
+    if let Some(short) = option.short {
+        flags.push(format!("\\fB\\-{}\\fR", roff_escape(&short.to_string())));
+    }
+    flags.push(format!("\\fB\\-\\-{}\\fR", roff_escape(&option.long)));
+    let mut ln = flags.join(", ");
+    if let Some(value) = &option.value {
+        ln.push_str(&format!(" \\fI{}\\fR", roff_escape(value)));
+    }
+    ln
+}
+
+fn roff_escape(text: &str) -> String {
+    let text = text.replace('\\', "\\e").replace('-', "\\-");
+    // a line starting with a dot or an apostrophe is a request, unless escaped
+    if text.starts_with('.') || text.starts_with('\'') {
+        format!("\\&{}", text)
+    } else {
+        text
+    }
+}
+
+// the name of the bash function, i.e. the characters other than the alphanumerics are replaced with `_`
+fn shell_identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
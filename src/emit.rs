@@ -0,0 +1,407 @@
+//!
+//! Emitter, writes the generated files of a generation run and keeps a manifest of them, to delete the stale outputs of the previous runs.
+//!
+
+use crate::detached;
+use crate::merge::matching_lines;
+use crate::slash_path;
+use crate::ColumnAlign;
+use crate::ColumnSpec;
+use crate::RTile;
+use std::fmt::Display;
+use std::io;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// the default name of the manifest file, kept in the root directory of the emitter
+pub const DEFAULT_MANIFEST: &str = ".rtile-manifest";
+
+///
+/// EmitStatus, returned by `Emitter::emit` for every file
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmitStatus {
+    /// the file did not exist, and is written
+    Created,
+    /// the file existed with a different content, and is written
+    Updated,
+    /// the file existed with the same content, and is left untouched (i.e. its modification time is kept)
+    Unchanged,
+}
+
+///
+/// Emitter, writes the files of a generation run under a root directory
+///
+/// Every emitted file is recorded, `finish` deletes the files recorded in the manifest of the previous run which are not emitted by this run,
+/// then writes the manifest of this run. The files with the same content are not written again, so the build tools don't see them as changed.
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::emit::{EmitStatus, Emitter};
+///
+/// let root = std::env::temp_dir().join(format!("rtile_doc_emitter_{}", std::process::id()));
+///
+/// let mut emitter = Emitter::new(&root);
+/// emitter.emit("src/a.rs", t!("pub struct A;")).unwrap();
+/// emitter.emit("src/b.rs", t!("pub struct B;")).unwrap();
+/// let report = emitter.finish().unwrap();
+/// assert_eq!(report.created, 2);
+///
+/// let mut emitter = Emitter::new(&root);
+/// assert_eq!(emitter.emit("src/a.rs", t!("pub struct A;")).unwrap(), EmitStatus::Unchanged);
+/// let report = emitter.finish().unwrap();
+/// assert_eq!(report.deleted, vec!["src/b.rs"]);
+/// assert!(!root.join("src/b.rs").exists());
+/// std::fs::remove_dir_all(&root).unwrap();
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    root: PathBuf,
+    manifest: String,
+    emitted: Vec<String>,
+    report: EmitReport,
+}
+
+impl Emitter {
+    /// creates an emitter writing the files under the given root directory, using the default manifest file
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            manifest: DEFAULT_MANIFEST.to_string(),
+            emitted: vec![],
+            report: EmitReport::default(),
+        }
+    }
+
+    /// sets the name of the manifest file, relative to the root directory
+    pub fn with_manifest(mut self, manifest: &str) -> Self {
+        self.manifest = manifest.to_string();
+        self
+    }
+
+    /// writes the content (e.g. a tile) to the file with the given path relative to the root directory, unless it already has the same content.
+    /// a path which is absolute or which has a `..` component is rejected with `io::ErrorKind::InvalidInput`, as it could be outside the root
+    pub fn emit(
+        &mut self,
+        path: impl AsRef<Path>,
+        content: impl Display,
+    ) -> io::Result<EmitStatus> {
+        check_within_root(path.as_ref())?;
+        let relative = slash_path(path.as_ref());
+        let path = self.root.join(path.as_ref());
+        let content = content.to_string();
+        let status = match std::fs::read_to_string(&path) {
+            Ok(existing) if existing == content => EmitStatus::Unchanged,
+            Ok(_) => EmitStatus::Updated,
+            Err(_) => EmitStatus::Created,
+        };
+        if status != EmitStatus::Unchanged {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, content)?;
+        }
+        match status {
+            EmitStatus::Created => self.report.created += 1,
+            EmitStatus::Updated => self.report.updated += 1,
+            EmitStatus::Unchanged => self.report.unchanged += 1,
+        }
+        if !self.emitted.contains(&relative) {
+            self.emitted.push(relative);
+        }
+        Ok(status)
+    }
+
+    /// returns the changes the files (relative path and content) of a generation run would make to the files on disk, without writing them,
+    /// i.e. the lines added and removed per file, along with the stale files of the previous run which `finish` would delete.
+    /// the paths are checked the same way as by `emit`
+    pub fn preview<I, P, C>(&self, files: I) -> io::Result<RegenReport>
+    where
+        I: IntoIterator<Item = (P, C)>,
+        P: AsRef<Path>,
+        C: Display,
+    {
+        let mut report = RegenReport::default();
+        let mut previewed = vec![];
+        for (path, content) in files {
+            check_within_root(path.as_ref())?;
+            let relative = slash_path(path.as_ref());
+            if previewed.contains(&relative) {
+                continue;
+            }
+            let (status, existing) = match std::fs::read_to_string(self.root.join(path.as_ref())) {
+                Ok(existing) => (FileStatus::Updated, existing),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    (FileStatus::Created, String::new())
+                }
+                Err(e) => return Err(e),
+            };
+            let (added, removed) = line_changes(&existing, &content.to_string());
+            let status = match status {
+                FileStatus::Updated if added == 0 && removed == 0 => FileStatus::Unchanged,
+                status => status,
+            };
+            report.files.push(FileChange {
+                path: relative.clone(),
+                status,
+                added,
+                removed,
+            });
+            previewed.push(relative);
+        }
+        let previous = std::fs::read_to_string(self.root.join(&self.manifest)).unwrap_or_default();
+        for stale in stale_paths(&previous, &previewed) {
+            if let Ok(existing) = std::fs::read_to_string(self.root.join(stale)) {
+                report.files.push(FileChange {
+                    path: stale.to_string(),
+                    status: FileStatus::Deleted,
+                    added: 0,
+                    removed: existing.lines().count(),
+                });
+            }
+        }
+        report.files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(report)
+    }
+
+    /// returns the paths of the files emitted so far, relative to the root directory
+    pub fn emitted(&self) -> &[String] {
+        &self.emitted
+    }
+
+    /// deletes the stale files of the previous run, writes the manifest of this run and returns the report. the entries of the manifest which
+    /// could be outside the root directory (e.g. a hand edited `../file` or an absolute path) are never deleted
+    pub fn finish(self) -> io::Result<EmitReport> {
+        let Self {
+            root,
+            manifest,
+            mut emitted,
+            mut report,
+        } = self;
+        let manifest_path = root.join(&manifest);
+        let previous = std::fs::read_to_string(&manifest_path).unwrap_or_default();
+        for stale in stale_paths(&previous, &emitted) {
+            match std::fs::remove_file(root.join(stale)) {
+                Ok(()) => report.deleted.push(stale.to_string()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        emitted.sort();
+        if let Some(parent) = manifest_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut lns = emitted.join("\n");
+        lns.push('\n');
+        std::fs::write(manifest_path, lns)?;
+        Ok(report)
+    }
+}
+
+// the paths of the manifest of the previous run which are not emitted by this run, leaving out the ones which could be outside
+// the root directory
+fn stale_paths<'a>(previous: &'a str, emitted: &'a [String]) -> impl Iterator<Item = &'a str> {
+    previous.lines().filter(move |ln| {
+        !ln.is_empty() && is_within_root(Path::new(ln)) && !emitted.iter().any(|path| path == ln)
+    })
+}
+
+// true, if the relative path stays under the root directory, i.e. it has only normal components (and `.`)
+fn is_within_root(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+fn check_within_root(path: &Path) -> io::Result<()> {
+    if is_within_root(path) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "the path {} is not within the root directory",
+                path.display()
+            ),
+        ))
+    }
+}
+
+///
+/// EmitReport, the counts of the files of a generation run, returned by `Emitter::finish`
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmitReport {
+    /// the number of the files which did not exist
+    pub created: usize,
+    /// the number of the files which were written with a different content
+    pub updated: usize,
+    /// the number of the files which already had the same content
+    pub unchanged: usize,
+    /// the stale files of the previous run which are deleted, relative to the root directory
+    pub deleted: Vec<String>,
+}
+
+///
+/// RegenReport, the changes of a generation run to the files on disk, per file, returned by `Emitter::preview`
+///
+/// Its tile (and Display) summarizes the changed files, e.g. for the reviewers to see the blast radius of a template change.
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::emit::Emitter;
+///
+/// let root = std::env::temp_dir().join(format!("rtile_doc_regen_{}", std::process::id()));
+/// let mut emitter = Emitter::new(&root);
+/// emitter.emit("a.rs", "pub struct A;\npub struct B;").unwrap();
+/// emitter.emit("old.rs", "pub struct Old;").unwrap();
+/// emitter.finish().unwrap();
+///
+/// let report = Emitter::new(&root)
+///     .preview([("a.rs", "pub struct A;\npub struct C;\npub struct D;"), ("b.rs", "pub struct B;")])
+///     .unwrap();
+/// assert_eq!(
+///     report.to_string(),
+///     "a.rs   | +2 -1\nb.rs   | +1    created\nold.rs |    -1 deleted\n3 files changed, 3 insertions(+), 2 deletions(-)"
+/// );
+/// std::fs::remove_dir_all(&root).unwrap();
+/// ```
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegenReport {
+    /// the files of the run and the stale files, sorted by their paths
+    pub files: Vec<FileChange>,
+}
+
+impl RegenReport {
+    /// returns the files which would change
+    pub fn changed(&self) -> impl Iterator<Item = &FileChange> {
+        self.files
+            .iter()
+            .filter(|file| file.status != FileStatus::Unchanged)
+    }
+
+    /// returns the number of the lines which would be added, over all the files
+    pub fn added(&self) -> usize {
+        self.files.iter().map(|file| file.added).sum()
+    }
+
+    /// returns the number of the lines which would be removed, over all the files
+    pub fn removed(&self) -> usize {
+        self.files.iter().map(|file| file.removed).sum()
+    }
+
+    /// returns the summary as a tile, a line per changed file and a line of the totals
+    pub fn to_tile(&self) -> RTile {
+        let count = |n: usize, sign: char| match n {
+            0 => String::new(),
+            n => format!("{}{}", sign, n),
+        };
+        let rows: Vec<Vec<String>> = self
+            .changed()
+            .map(|file| {
+                let status = match file.status {
+                    FileStatus::Created => "created",
+                    FileStatus::Deleted => "deleted",
+                    _ => "",
+                };
+                vec![
+                    file.path.clone(),
+                    count(file.added, '+'),
+                    count(file.removed, '-'),
+                    status.to_string(),
+                ]
+            })
+            .collect();
+        let spec = ColumnSpec {
+            align: vec![ColumnAlign::Left, ColumnAlign::Right, ColumnAlign::Right],
+            separators: vec![" | ".to_string(), " ".to_string()],
+            ..Default::default()
+        };
+        let changed = rows.len();
+        let files = if rows.is_empty() {
+            RTile::new_without_trimming(vec![])
+        } else {
+            RTile::from_matrix(&rows, spec)
+        };
+        let totals = format!(
+            "{} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+            changed,
+            plural(changed),
+            self.added(),
+            plural(self.added()),
+            self.removed(),
+            plural(self.removed())
+        );
+        let mut lns: Vec<String> = files
+            .lns
+            .iter()
+            .map(|ln| ln.trim_end().to_string())
+            .collect();
+        lns.push(totals);
+        detached(|| RTile::new(lns))
+    }
+}
+
+impl Display for RegenReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_tile())
+    }
+}
+
+///
+/// FileChange, the change of a file in a `RegenReport`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    /// the path of the file, relative to the root directory
+    pub path: String,
+    /// the status of the file
+    pub status: FileStatus,
+    /// the number of the lines which would be added
+    pub added: usize,
+    /// the number of the lines which would be removed
+    pub removed: usize,
+}
+
+///
+/// FileStatus, of a file in a `RegenReport`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    /// the file does not exist, and would be written
+    Created,
+    /// the file exists with a different content, and would be written
+    Updated,
+    /// the file exists with the same lines
+    Unchanged,
+    /// the file is a stale output of the previous run, and would be deleted
+    Deleted,
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+// the numbers of the lines added and removed from the old text to the new one, the common lines at the start and the end
+// are skipped before matching the rest, as the regenerated files mostly change in a few places
+fn line_changes(old: &str, new: &str) -> (usize, usize) {
+    let old: Vec<String> = old.lines().map(String::from).collect();
+    let new: Vec<String> = new.lines().map(String::from).collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+    let common = matching_lines(old, new).iter().flatten().count();
+    (new.len() - common, old.len() - common)
+}
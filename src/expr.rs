@@ -0,0 +1,334 @@
+// the expressions of the placeholders, e.g. @{count + 1}, @{len(items)} or @{first + " " + last}
+//
+// The operands are the tiles (by their names), the integers and the strings in double quotes. `+` adds two integers, and
+// concatenates the operands otherwise, `-`, `*`, `/` and `%` take the integers only. A tile is an integer when its trimmed
+// value is one. The functions are `len` (the number of lines of a non blank value), `upper`, `lower` and `trim`.
+// The operators are separated by spaces, as the names of the tiles may contain `-`, `/` or `.`.
+
+use std::rc::Rc;
+
+// true, if the text of the placeholder is an expression instead of the name of a tile
+pub(crate) fn is_expression(text: &str) -> bool {
+    text.contains('(')
+        || text.contains('"')
+        || [" + ", " - ", " * ", " / ", " % "]
+            .iter()
+            .any(|op| text.contains(op))
+}
+
+// true, if the name is one of the functions, e.g. `len`
+pub(crate) fn is_function(name: &str) -> bool {
+    matches!(name, "len" | "upper" | "lower" | "trim")
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Expression {
+    text: String,
+    root: Node,
+}
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Int(i64),
+    Str(String),
+    Tile(String),
+    Neg(Box<Node>),
+    Binary(char, Box<Node>, Box<Node>),
+    Call(Function, Box<Node>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Len,
+    Upper,
+    Lower,
+    Trim,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Int(i64),
+    Str(String),
+    Name(String),
+    Op(char),
+    Open,
+    Close,
+}
+
+enum Value {
+    Int(i64),
+    Str(Rc<str>),
+}
+
+impl Value {
+    fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(value) => Some(*value),
+            Value::Str(value) => value.trim().parse().ok(),
+        }
+    }
+
+    fn into_string(self) -> String {
+        match self {
+            Value::Int(value) => value.to_string(),
+            Value::Str(value) => value.to_string(),
+        }
+    }
+}
+
+impl Expression {
+    // the error is the message of the panic on the invalid @{} expression, see `Placeholder::parse`
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let tokens: Vec<Token> = tokenize(text)?
+            .into_iter()
+            .map(|(_, token)| token)
+            .collect();
+        let mut parser = Parser {
+            text,
+            tokens: &tokens,
+            pos: 0,
+        };
+        let root = parser.sum()?;
+        if let Some(token) = tokens.get(parser.pos) {
+            return Err(parser.fail(&format!("unexpected {:?}", token)));
+        }
+        Ok(Expression {
+            text: text.to_string(),
+            root,
+        })
+    }
+
+    // the names of the tiles used by the expression, in the order of their first use
+    pub(crate) fn operands(&self) -> Vec<String> {
+        fn collect(node: &Node, operands: &mut Vec<String>) {
+            match node {
+                Node::Tile(name) if !operands.contains(name) => operands.push(name.clone()),
+                Node::Neg(node) | Node::Call(_, node) => collect(node, operands),
+                Node::Binary(_, left, right) => {
+                    collect(left, operands);
+                    collect(right, operands);
+                }
+                _ => {}
+            }
+        }
+
+        let mut operands = vec![];
+        collect(&self.root, &mut operands);
+        operands
+    }
+
+    // the value of the expression, the values of the tiles are looked up by their names
+    pub(crate) fn evaluate(&self, tile_value: &mut impl FnMut(&str) -> Rc<str>) -> String {
+        self.value(&self.root, tile_value).into_string()
+    }
+
+    fn value(&self, node: &Node, tile_value: &mut impl FnMut(&str) -> Rc<str>) -> Value {
+        match node {
+            Node::Int(value) => Value::Int(*value),
+            Node::Str(value) => Value::Str(value.as_str().into()),
+            Node::Tile(name) => Value::Str(tile_value(name)),
+            Node::Neg(node) => {
+                let value = self.int(self.value(node, tile_value));
+                Value::Int(value.checked_neg().unwrap_or_else(|| self.overflow()))
+            }
+            Node::Binary(op, left, right) => {
+                let left = self.value(left, tile_value);
+                let right = self.value(right, tile_value);
+                if *op == '+' && (left.as_int().is_none() || right.as_int().is_none()) {
+                    return Value::Str((left.into_string() + &right.into_string()).into());
+                }
+                let (left, right) = (self.int(left), self.int(right));
+                if (*op == '/' || *op == '%') && right == 0 {
+                    panic!("division by zero in @{{{}}} expression", self.text);
+                }
+                let value = match op {
+                    '+' => left.checked_add(right),
+                    '-' => left.checked_sub(right),
+                    '*' => left.checked_mul(right),
+                    '/' => left.checked_div(right),
+                    _ => left.checked_rem(right),
+                };
+                Value::Int(value.unwrap_or_else(|| self.overflow()))
+            }
+            Node::Call(function, node) => {
+                let value = self.value(node, tile_value).into_string();
+                match function {
+                    Function::Len if value.trim().is_empty() => Value::Int(0),
+                    Function::Len => Value::Int(value.split('\n').count() as i64),
+                    Function::Upper => Value::Str(value.to_uppercase().into()),
+                    Function::Lower => Value::Str(value.to_lowercase().into()),
+                    Function::Trim => Value::Str(value.trim().into()),
+                }
+            }
+        }
+    }
+
+    fn int(&self, value: Value) -> i64 {
+        value.as_int().unwrap_or_else(|| {
+            panic!(
+                "the operand `{}` is not an integer in @{{{}}} expression",
+                value.into_string(),
+                self.text
+            )
+        })
+    }
+
+    fn overflow(&self) -> i64 {
+        panic!("integer overflow in @{{{}}} expression", self.text)
+    }
+}
+
+// the tokens along with the byte indices they start at
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, String> {
+    let fail = |reason: &str| format!("{} in @{{{}}} expression", reason, text);
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            // a `-` or a `/` after the first character of a name is a part of the name
+            '+' | '-' | '*' | '/' | '%' => Token::Op(c),
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next().map(|(_, c)| c) {
+                        None => return Err(fail("unfinished string")),
+                        Some('"') => break,
+                        Some('\\') => match chars.next().map(|(_, c)| c) {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(c @ ('"' | '\\')) => value.push(c),
+                            _ => return Err(fail("invalid escape")),
+                        },
+                        Some(c) => value.push(c),
+                    }
+                }
+                Token::Str(value)
+            }
+            c => {
+                let mut name = c.to_string();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"' | '+' | '*' | '%') {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                if name.chars().all(|c| c.is_ascii_digit()) {
+                    Token::Int(name.parse().map_err(|_| fail("integer overflow"))?)
+                } else {
+                    Token::Name(name)
+                }
+            }
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+// the expression with the tile `old` renamed to `new` wherever it is an operand, None if it isn't used
+pub(crate) fn rename_operand(text: &str, old: &str, new: &str) -> Option<String> {
+    let tokens = tokenize(text).ok()?;
+    let mut res = text.to_string();
+    let mut renamed = false;
+    for (i, (start, token)) in tokens.iter().enumerate().rev() {
+        let is_call = matches!(tokens.get(i + 1), Some((_, Token::Open)));
+        if matches!(token, Token::Name(name) if name == old) && !is_call {
+            res.replace_range(*start..*start + old.len(), new);
+            renamed = true;
+        }
+    }
+    renamed.then_some(res)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn fail(&self, reason: &str) -> String {
+        format!("{} in @{{{}}} expression", reason, self.text)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let tokens = self.tokens;
+        let token = tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn peek_op(&self, ops: &[char]) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(*op),
+            _ => None,
+        }
+    }
+
+    fn sum(&mut self) -> Result<Node, String> {
+        let mut node = self.product()?;
+        while let Some(op) = self.peek_op(&['+', '-']) {
+            self.pos += 1;
+            node = Node::Binary(op, Box::new(node), Box::new(self.product()?));
+        }
+        Ok(node)
+    }
+
+    fn product(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        while let Some(op) = self.peek_op(&['*', '/', '%']) {
+            self.pos += 1;
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.peek_op(&['-']).is_some() {
+            self.pos += 1;
+            return Ok(Node::Neg(Box::new(self.unary()?)));
+        }
+        self.operand()
+    }
+
+    fn operand(&mut self) -> Result<Node, String> {
+        Ok(match self.next() {
+            Some(Token::Int(value)) => Node::Int(*value),
+            Some(Token::Str(value)) => Node::Str(value.clone()),
+            Some(Token::Open) => {
+                let node = self.sum()?;
+                self.close()?;
+                node
+            }
+            Some(Token::Name(name)) if self.tokens.get(self.pos) != Some(&Token::Open) => {
+                Node::Tile(name.clone())
+            }
+            Some(Token::Name(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                let function = match name.as_str() {
+                    "len" => Function::Len,
+                    "upper" => Function::Upper,
+                    "lower" => Function::Lower,
+                    "trim" => Function::Trim,
+                    _ => return Err(self.fail(&format!("unknown function `{}`", name))),
+                };
+                let node = Node::Call(function, Box::new(self.sum()?));
+                self.close()?;
+                node
+            }
+            Some(token) => return Err(self.fail(&format!("unexpected {:?}", token))),
+            None => return Err(self.fail("missing operand")),
+        })
+    }
+
+    fn close(&mut self) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Close) => Ok(()),
+            _ => Err(self.fail("missing `)`")),
+        }
+    }
+}
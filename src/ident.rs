@@ -0,0 +1,159 @@
+//!
+//! Identifiers of the generated code, i.e. the names of the schemas (e.g. the columns, the fields, the messages) turned into
+//! valid and unique identifiers of the target language, see `sanitize_ident`.
+//!
+
+use std::collections::HashSet;
+
+/// the keywords of Python, a keyword is renamed with a trailing `_`, e.g. `class_`
+pub const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// the reserved words of SQL common to the major databases, compared ignoring the case, a reserved word is renamed with
+/// a trailing `_`, e.g. `order_`
+pub const SQL_KEYWORDS: &[&str] = &[
+    "all",
+    "alter",
+    "and",
+    "any",
+    "as",
+    "asc",
+    "between",
+    "by",
+    "case",
+    "check",
+    "column",
+    "constraint",
+    "create",
+    "cross",
+    "default",
+    "delete",
+    "desc",
+    "distinct",
+    "drop",
+    "else",
+    "end",
+    "exists",
+    "false",
+    "foreign",
+    "from",
+    "full",
+    "group",
+    "having",
+    "in",
+    "index",
+    "inner",
+    "insert",
+    "into",
+    "is",
+    "join",
+    "key",
+    "left",
+    "like",
+    "limit",
+    "not",
+    "null",
+    "on",
+    "or",
+    "order",
+    "outer",
+    "primary",
+    "references",
+    "right",
+    "select",
+    "set",
+    "table",
+    "then",
+    "to",
+    "true",
+    "union",
+    "unique",
+    "update",
+    "user",
+    "using",
+    "values",
+    "when",
+    "where",
+    "with",
+];
+
+///
+/// Lang, the target language of `sanitize_ident`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    /// a keyword is written as a raw identifier, e.g. `r#type`, or with a trailing `_` if it can't be one, e.g. `self_`
+    Rust,
+    /// a keyword is renamed with a trailing `_`
+    Python,
+    /// the identifiers are ASCII and compared ignoring the case, a reserved word is renamed with a trailing `_`
+    Sql,
+}
+
+///
+/// sanitize_ident, used to turn the name into a valid identifier of the language, unique within the scope, i.e. the
+/// characters not allowed are stripped (a leading digit is prefixed with `_`), a keyword is renamed, and a name already in
+/// the scope gets the first free suffix of `_2`, `_3`, and so on. The identifier is added to the scope
+/// ```
+/// use rtile::ident::*;
+/// use std::collections::HashSet;
+///
+/// let mut scope = HashSet::new();
+/// assert_eq!(sanitize_ident("type", Lang::Rust, &mut scope), "r#type");
+/// assert_eq!(sanitize_ident("user-id", Lang::Rust, &mut scope), "userid");
+/// assert_eq!(sanitize_ident("user id", Lang::Rust, &mut scope), "userid_2");
+/// assert_eq!(sanitize_ident("class", Lang::Python, &mut HashSet::new()), "class_");
+/// assert_eq!(sanitize_ident("2fa code", Lang::Sql, &mut HashSet::new()), "_2facode");
+/// ```
+///
+pub fn sanitize_ident(name: &str, lang: Lang, scope: &mut HashSet<String>) -> String {
+    let allowed = |c: char| match lang {
+        Lang::Sql => c == '_' || c.is_ascii_alphanumeric(),
+        Lang::Rust | Lang::Python => c == '_' || c.is_alphanumeric(),
+    };
+    let mut base: String = name.chars().filter(|&c| allowed(c)).collect();
+    if base.chars().next().is_none_or(|c| c.is_numeric()) {
+        base.insert(0, '_');
+    }
+    let in_scope = |ident: &str, scope: &HashSet<String>| match lang {
+        Lang::Sql => scope.iter().any(|other| other.eq_ignore_ascii_case(ident)),
+        Lang::Rust | Lang::Python => scope.contains(ident),
+    };
+    let mut n = 1;
+    loop {
+        let candidate = match n {
+            1 => base.clone(),
+            n => format!("{}_{}", base, n),
+        };
+        let ident = escape_keyword(&candidate, lang);
+        if !in_scope(&ident, scope) {
+            scope.insert(ident.clone());
+            return ident;
+        }
+        n += 1;
+    }
+}
+
+// the identifier, renamed if it is a keyword of the language
+fn escape_keyword(ident: &str, lang: Lang) -> String {
+    match lang {
+        Lang::Rust => match crate::rust::to_ident_tile(ident) {
+            Ok(tile) => tile.to_string(),
+            // the keywords which can't be raw identifiers, i.e. `self`, `Self`, `super`, `crate` and `_`
+            Err(_) => format!("{}_", ident),
+        },
+        Lang::Python if PYTHON_KEYWORDS.contains(&ident) => format!("{}_", ident),
+        Lang::Sql
+            if SQL_KEYWORDS
+                .iter()
+                .any(|keyword| keyword.eq_ignore_ascii_case(ident)) =>
+        {
+            format!("{}_", ident)
+        }
+        Lang::Python | Lang::Sql => ident.to_string(),
+    }
+}
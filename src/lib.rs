@@ -8,10 +8,173 @@
 //! assert_eq!(ts!("@{greet_one}@{greet_two}"), "Welcome to rtile!     Have a great day!");
 //! ```
 //!
+//! The tiles are composed using the operators `+` (side by side) and `|` (one below the other).
+//! `a + b` and `a | b` return a new unnamed tile, while `a += b` and `a |= b` compose the lines in place and keep the name of `a`,
+//! otherwise both forms behave the same. None of the operators touch the tls (thread local storage),
+//! the blank tiles for any missing inner tiles are created when the tile is rendered.
+//!
+//! ```
+//! use rtile::prelude::*;
+//! let mut tile = tp!(row, "a");
+//! tile += t!("|b");
+//! tile |= t!("c");
+//! assert_eq!(tile.to_string(), (t!("a") + t!("|b") | t!("c")).to_string());
+//! assert_eq!(tile.name, Some("row".to_string()));
+//! assert_eq!((t!("a") + t!("b")).name, None);
+//! ```
+//!
+//! Placeholders accept options after a colon, i.e. `@{name:option,option=value}`
+//!
+//! - `align=N` - the continuation lines of a multi-line tile start at the column N of the line, instead of under the placeholder
+//! - `skip_if_blank` - a tile which expands to blank text renders as nothing, and the line is skipped when it has nothing else but white spaces
+//!
+//! ```
+//! use rtile::prelude::*;
+//! tp!(args, vec!["a: u8,", "b: u8)"]);
+//! assert_eq!(ts!("fn foo(@{args}"), "fn foo(a: u8,\n       b: u8)");
+//! assert_eq!(ts!("fn foo(@{args:align=4}"), "fn foo(a: u8,\n    b: u8)");
+//!
+//! tp!(derives);
+//! let tile = t!("struct A;\n@{derives}\nstruct B;\n@{derives:skip_if_blank}\nstruct C;");
+//! assert_eq!(tile.to_string(), "struct A;\n\nstruct B;\nstruct C;");
+//! ```
+//!
+//! A placeholder can bind the placeholders of the tile for the duration of its expansion, i.e. `@{tile with name=other}`
+//! expands the tile with every `@{name}` in it (and in its inner tiles) referring to the tile `other`
+//!
+//! ```
+//! use rtile::prelude::*;
+//! tp!(greet, "Hello @{name}!");
+//! tp!(alice, "Alice");
+//! tp!(bob, "Bob");
+//! assert_eq!(ts!("@{greet with name=alice} @{greet with name=bob}"), "Hello Alice! Hello Bob!");
+//! assert_eq!(ts!("@{greet}"), "Hello !");
+//! ```
+//!
+//! A tile may declare its parameters with `@{params name,...}` on its first line, which expands to nothing, and is called with its
+//! arguments, i.e. `@{tile(name=other,...)}` is `@{tile with name=other,...}` where every parameter has to be given, and nothing else
+//!
+//! ```
+//! use rtile::prelude::*;
+//! tp!(greet, "@{params name}\nHello @{name}!");
+//! tp!(alice, "Alice");
+//! tp!(bob, "Bob");
+//! assert_eq!(ts!("@{greet(name=alice)} @{greet(name=bob)}"), "Hello Alice! Hello Bob!");
+//! assert!(t!("@{greet(nick=bob)}").try_expand().is_err());
+//! ```
+//!
+//! A `-` at the start of a placeholder, i.e. `@{-name}`, eats the white spaces (including the line breaks) before it, and a `-` at
+//! its end, i.e. `@{name-}`, eats the ones after it, the placeholders of the expressions (e.g. `@{-count * 2}`) are left as they are
+//!
+//! ```
+//! use rtile::prelude::*;
+//! tp!(imports);
+//! let tile = t!("
+//!     use std::fmt;
+//!     @{-imports}
+//!
+//!     struct A;
+//! ");
+//! assert_eq!(tile.to_string(), "use std::fmt;\n\nstruct A;");
+//! ```
+//!
+//! The text between `@{raw}` and `@{endraw}` is emitted verbatim, e.g. to generate the templates themselves
+//!
+//! ```
+//! use rtile::prelude::*;
+//! tp!(lang, "rust");
+//! let tile = t!("
+//!     // @{lang}
+//!     @{raw}
+//!     tp!(greet, \"Hello @{name}!\");
+//!     @{endraw}
+//! ");
+//! assert_eq!(tile.to_string(), "// rust\ntp!(greet, \"Hello @{name}!\");");
+//! ```
+//!
+//! A placeholder may span lines, e.g. a long list of bindings, it is joined into a single line when the tile is constructed
+//!
+//! ```
+//! use rtile::prelude::*;
+//! tp!(greet, "Hello @{first} @{last}!");
+//! tp!(ada, "Ada");
+//! tp!(lovelace, "Lovelace");
+//! let tile = t!("
+//!     @{greet with
+//!         first=ada,
+//!         last=lovelace
+//!     }
+//! ");
+//! assert_eq!(tile.to_string(), "Hello Ada Lovelace!");
+//! ```
+//!
+//! With the `mustache` feature, the mustache placeholders `{{name}}`, `{{name:skip_if_blank}}` and `{{{name}}}` are recognized alongside `@{name}`
+//! and the mustache comments `{{! comment}}` expand to nothing, so the existing mustache templates (without sections) can be rendered.
+//! Any other `{{...}}`, or a `{{` without its `}}` on the same line, is kept as it is, e.g. the braces of the generated code
+//!
+#![cfg_attr(feature = "mustache", doc = "```")]
+#![cfg_attr(not(feature = "mustache"), doc = "```ignore")]
+//! use rtile::prelude::*;
+//! tp!(name, "Alice");
+//! assert_eq!(ts!("{{! greeting }}Hello {{ name }}, @{name}!"), "Hello Alice, Alice!");
+//! assert_eq!(ts!("fn f() {{ @{name}"), "fn f() {{ Alice");
+//! assert_eq!(ts!("struct A {{ a: u8 }} // {{name}}"), "struct A {{ a: u8 }} // Alice");
+//! ```
+//!
+//! The enumeration of the tiles is deterministic, i.e. the store APIs returning a `Vec` or a `BTreeMap`/`BTreeSet` list the tiles
+//! sorted by their names (or in the order of their appearance, for the inner tiles), the same for every run and platform.
+//! The APIs returning a `HashMap`/`HashSet` have stable variants, i.e. `get_blank_tiles_sorted`, `export_store_sorted` and `RTile::inner_tiles_in_order`
+//!
+//! A placeholder can refer to a tile indirectly, i.e. `@{@{selector}}` expands the tile whose name is the content of the tile `selector`,
+//! e.g. to select the template using a table. There is one level of indirection, and a blank selector expands to nothing
+//!
+//! ```
+//! use rtile::prelude::*;
+//! tp!(json_body, "{\"id\": @{id}}");
+//! tp!(xml_body, "<id>@{id}</id>");
+//! tp!(id, "7");
+//! tp!(format, "xml_body");
+//! assert_eq!(ts!("@{@{format}}"), "<id>7</id>");
+//! tp!(format, "json_body");
+//! assert_eq!(ts!("@{@{format}}"), "{\"id\": 7}");
+//! ```
+//!
+//! A small fragment can be defined inline with `@{def name}...@{end}`, which is rendered in place and can be used
+//! again as `@{name}` within the same tile, without persisting a tile with that name
+//!
+//! ```
+//! use rtile::prelude::*;
+//! tp!(fruit, "apple");
+//! let tile = t!("
+//!     @{def item}- @{fruit}@{end}
+//!     @{item}
+//!     ");
+//! assert_eq!(tile.to_string(), "- apple\n- apple");
+//! assert!(gtp!(item).is_none());
+//! ```
+//!
+//! A placeholder can hold a small expression instead of a name, to avoid the helper tiles for the tiny computed values.
+//! The operands are the tiles, the integers and the strings in double quotes, `+` adds the integers and concatenates the other
+//! operands, `-`, `*`, `/` and `%` take the integers only. The functions are `len` (the number of lines), `upper`, `lower` and `trim`.
+//! The operators are separated by spaces, as the names of the tiles may contain `-`, `/` or `.`
+//!
+//! ```
+//! use rtile::prelude::*;
+//! tp!(count, "41");
+//! tp!(items, vec!["a", "b", "c"]);
+//! tp!(first, "Ada");
+//! tp!(last, "Lovelace");
+//! assert_eq!(ts!("@{count + 1} @{len(items) * 2}"), "42 6");
+//! assert_eq!(ts!("@{upper(first + \" \" + last)}"), "ADA LOVELACE");
+//! tp!(total, "9");
+//! assert_eq!(ts!("@{count + 1 with count=total}"), "10");
+//! ```
+//!
 
 #![warn(missing_docs)]
 
 use std::any::type_name;
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -19,11 +182,15 @@ use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result;
+use std::hash::Hash;
 use std::iter::Iterator;
 use std::ops::Add;
 use std::ops::AddAssign;
 use std::ops::BitOr;
 use std::ops::BitOrAssign;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
 
 ///
 /// Prelude for RTile
@@ -35,6 +202,50 @@ pub mod prelude {
     pub use crate::*;
 }
 
+pub mod analysis;
+pub mod build;
+pub mod cli;
+pub mod emit;
+mod expr;
+pub mod ident;
+pub mod merge;
+pub mod ops;
+mod owned;
+pub mod project;
+pub mod proto;
+pub mod provenance;
+#[cfg(feature = "repl")]
+pub mod repl;
+pub mod rust;
+#[cfg(feature = "scaffold")]
+pub mod scaffold;
+mod sha256;
+mod store;
+pub mod testing;
+pub mod values;
+mod version;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+pub use owned::clear_global_tiles;
+pub use owned::get_global_tile;
+pub use owned::remove_global_tile;
+pub use owned::set_global_tile;
+pub use owned::OwnedTile;
+pub use store::CacheKey;
+pub use store::CacheStats;
+pub use store::ConflictPolicy;
+pub use store::CycleError;
+pub use store::InputReport;
+pub use store::RTileError;
+pub use store::StoreEvent;
+pub use store::StoreEventKind;
+pub use store::TileStore;
+/// RTileContext, an explicit context of tiles, i.e. a `TileStore` owning its raw and processed tiles, passed between the
+/// functions instead of using the tls (thread local storage), see `TileStore::enter`
+pub type RTileContext = TileStore;
+pub use store::TileVisitor;
+
 use prelude::*;
 
 ///
@@ -48,12 +259,43 @@ use prelude::*;
 ///
 #[macro_export]
 macro_rules! stp {
+    ($s:expr => $i: ident, $t: expr) => {{
+        $s.set(stringify!($i), $t.clone());
+    }};
     ($i: ident, $t: expr) => {{
-        set_tiles(format!("{}", stringify!($i)), $t.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$t));
         set_raw_tiles(format!("{}", stringify!($i)), $t.clone());
     }};
 }
 
+///
+/// give a name to a composed tile and persist it in tls (thread local storage) together with the tiles it was composed from using `+` and `|`,
+/// so it is rendered again from its parts, whereas stp! persists the lines of the composed tile as they are.
+///
+/// the anonymous parts are persisted as `{name}_{index}`, and the named parts are referred to by their names, except the previous value of the tile itself,
+/// which is persisted as it was expanded. a tile whose lines were changed after its composition is persisted as it is. returns the names of the persisted parts
+/// ```
+/// use rtile::prelude::*;
+/// let header = t!("== report ==");
+/// let rows = t!("alpha") | t!("beta");
+/// let parts = stp_deep!(report, header | (t!("rows:") + rows));
+/// assert_eq!(parts, vec!["report_0", "report_1_0", "report_1_1_0", "report_1_1_1", "report_1_1", "report_1"]);
+/// assert_eq!(ts!("@{report}"), "== report ==\nrows:alpha\n     beta");
+///
+/// tp!(report_1_1_1, "beta\ngamma");
+/// assert_eq!(ts!("@{report}"), "== report ==\nrows:alpha\n     beta\n     gamma");
+/// ```
+///
+#[macro_export]
+macro_rules! stp_deep {
+    ($s:expr => $i: ident, $t: expr) => {{
+        $s.set_deep(stringify!($i), &$t)
+    }};
+    ($i: ident, $t: expr) => {{
+        set_deep_tiles(stringify!($i), &$t)
+    }};
+}
+
 ///
 /// give a name to a tile using a variable containing a string value and persist it in tls (thread local storage)
 /// ```
@@ -66,14 +308,20 @@ macro_rules! stp {
 ///
 #[macro_export]
 macro_rules! stq {
+    ($s:expr => $e: expr, $t: expr) => {{
+        $s.set(&format!("{}", $e), $t.clone());
+    }};
     ($e: expr, $t: expr) => {{
-        set_tiles(format!("{}", $e), $t.to_string());
+        set_tiles(format!("{}", $e), expanded_value(&$t));
         set_raw_tiles(format!("{}", $e), $t.clone());
     }};
 }
 
 ///
 /// get the tile which is persisted in the tls (thread local storage)
+///
+/// the result is a copy of the raw lines of the tile, whose placeholders are still expanded using the tls whenever it is rendered,
+/// see `gtp_snapshot!` for a frozen copy of the output, and `gtp_ref!` for a handle which always follows the persisted tile
 /// ```
 /// use rtile::prelude::*;
 /// let tile = t!("tile value");
@@ -84,6 +332,9 @@ macro_rules! stq {
 ///
 #[macro_export]
 macro_rules! gtp {
+    ($s:expr => $i: ident) => {{
+        $s.get(stringify!($i))
+    }};
     ($i: ident) => {{
         get_raw_tile(&stringify!($i).to_string())
     }};
@@ -102,12 +353,88 @@ macro_rules! gtp {
 ///
 #[macro_export]
 macro_rules! gtq {
+    ($s:expr => $e: expr) => {{
+        $s.get(&format!("{}", $e))
+    }};
     ($e: expr) => {{
         let target_tile_name = format!("{}", $e);
         get_raw_tile(&target_tile_name)
     }};
 }
 
+///
+/// get a snapshot of the output of the tile which is persisted in the tls (thread local storage), i.e. the expanded lines as a tile
+///
+/// the snapshot doesn't change when the tile or its inner tiles change later, as its lines don't have any placeholders,
+/// unless the output itself contains the text of a placeholder
+/// ```
+/// use rtile::prelude::*;
+/// tp!(user, "Alice");
+/// tp!(greeting, "Hello @{user}");
+/// let snapshot = gtp_snapshot!(greeting).unwrap();
+/// tp!(user, "Bob");
+/// assert_eq!(snapshot.to_string(), "Hello Alice");
+/// assert_eq!(gtp!(greeting).unwrap().to_string(), "Hello Bob");
+/// assert_eq!(gtp_snapshot!(not_persisted), None);
+/// ```
+///
+#[macro_export]
+macro_rules! gtp_snapshot {
+    ($s:expr => $i: ident) => {{
+        $s.snapshot(stringify!($i))
+    }};
+    ($i: ident) => {{
+        get_snapshot_tile(stringify!($i))
+    }};
+}
+
+///
+/// get a handle to the tile which is persisted in the tls (thread local storage), see `TileRef`
+///
+/// the handle keeps the name only, so it is cheap to clone, and it always renders the current tile
+/// ```
+/// use rtile::prelude::*;
+/// tp!(status, "starting");
+/// let status = gtp_ref!(status);
+/// let line = t!("[") + status.to_tile() + t!("]");
+/// tp!(status, "running");
+/// assert_eq!(status.to_string(), "running");
+/// assert_eq!(line.to_string(), "[running]");
+/// ```
+///
+#[macro_export]
+macro_rules! gtp_ref {
+    ($i: ident) => {{
+        TileRef::new(stringify!($i))
+    }};
+}
+
+///
+/// assert_rendered_tree_eq! compares the rendered files (a map of relative paths to contents) against the files of the golden directory,
+/// and panics with a per-file line diff, if they differ. See `testing::compare_rendered_tree`
+/// ```
+/// use rtile::prelude::*;
+/// use std::collections::BTreeMap;
+///
+/// let golden = std::env::temp_dir().join(format!("rtile_doc_tree_{}", std::process::id()));
+/// std::fs::create_dir_all(&golden).unwrap();
+/// std::fs::write(golden.join("main.rs"), "fn main() {}\n").unwrap();
+///
+/// let mut rendered = BTreeMap::new();
+/// rendered.insert("main.rs", ts!("fn main() {}"));
+/// assert_rendered_tree_eq!(&golden, &rendered);
+/// std::fs::remove_dir_all(&golden).unwrap();
+/// ```
+///
+#[macro_export]
+macro_rules! assert_rendered_tree_eq {
+    ($root: expr, $rendered_files: expr) => {{
+        if let Err(mismatch) = $crate::testing::compare_rendered_tree($root, $rendered_files) {
+            panic!("{}", mismatch);
+        }
+    }};
+}
+
 #[doc(hidden)]
 ///
 /// Tiles with trimming
@@ -161,6 +488,21 @@ impl MacroAttributeForT for Vec<String> {
     }
 }
 
+impl MacroAttributeForT for Option<RTile> {
+    fn process(&self) -> RTile {
+        match self {
+            Some(tile) => MacroAttributeForT::process(tile),
+            None => RTile::new(vec![]),
+        }
+    }
+}
+
+impl MacroAttributeForT for &Option<RTile> {
+    fn process(&self) -> RTile {
+        MacroAttributeForT::process(*self)
+    }
+}
+
 /// tf! is used to flatten the multilines of the tile output into a single string
 ///
 /// ```
@@ -255,6 +597,8 @@ macro_rules! t {
             name: None,
             lns: vec!["".to_string()],
             do_trimming: true,
+            tags: vec![],
+            parts: Parts::default(),
             marker: PhantomData::<Rc<()>>,
         }
     }};
@@ -269,6 +613,11 @@ macro_rules! t {
 
 /// tp! is to used to persist the tile into the tls (thread local storage), with a given name (string literal) and return a tile
 ///
+/// the tile is bound to a local variable named after the tile within the block of the macro, which is not in scope after the
+/// macro (the tile is returned instead), but the lints of the variables apply to the name, e.g. `tp!(HEADER)` warns about the
+/// snake case, and the names which can't be a variable, e.g. `self`, fail to compile, the same goes for kp!, ttp! and kkp!,
+/// see tps! and kps! which don't bind any variable named after the tile
+///
 /// ```
 /// use rtile::prelude::*;
 /// tp!(
@@ -301,17 +650,23 @@ macro_rules! t {
 ///   
 #[macro_export]
 macro_rules! tp {
+    ($s:expr => $i:ident) => {{
+        tq!($s => stringify!($i))
+    }};
+    ($s:expr => $i:ident, $($arg:tt)*) => {{
+        tq!($s => stringify!($i), $($arg)*)
+    }};
     ($i:ident) => {{
         let mut $i = t!();
         $i.name = Some(stringify!($i).to_string());
-        set_tiles(format!("{}", stringify!($i)), $i.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$i));
         set_raw_tiles(format!("{}", stringify!($i)), $i.clone());
         $i
     }};
     ($i:ident, $e:expr) => {{
         let mut $i = t!($e);
         $i.name = Some(stringify!($i).to_string());
-        set_tiles(format!("{}", stringify!($i)), $i.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$i));
         set_raw_tiles(format!("{}", stringify!($i)), $i.clone());
         $i
     }};
@@ -319,7 +674,7 @@ macro_rules! tp {
         let val = format!($($arg)*);
         let mut $i = t!(val);
         $i.name = Some(stringify!($i).to_string());
-        set_tiles(format!("{}", stringify!($i)), $i.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$i));
         set_raw_tiles(format!("{}", stringify!($i)), $i.clone());
         $i
     }};
@@ -362,11 +717,25 @@ macro_rules! tp {
 ///  
 #[macro_export]
 macro_rules! tq {
+    ($s:expr => $e:expr) => {{
+        let mut target_tile = detached(|| t!());
+        let target_tile_name = format!("{}", $e);
+        target_tile.name = Some(target_tile_name.clone());
+        $s.set(&target_tile_name, target_tile.clone());
+        target_tile
+    }};
+    ($s:expr => $e:expr, $($arg:tt)*) => {{
+        let mut target_tile = detached(|| t!($($arg)*));
+        let target_tile_name = format!("{}", $e);
+        target_tile.name = Some(target_tile_name.clone());
+        $s.set(&target_tile_name, target_tile.clone());
+        target_tile
+    }};
     ($e:expr) => {{
         let mut target_tile = t!();
         let target_tile_name = format!("{}", $e);
         target_tile.name = Some(target_tile_name.clone());
-        set_tiles(target_tile_name.clone(), target_tile.to_string());
+        set_tiles(target_tile_name.clone(), expanded_value(&target_tile));
         set_raw_tiles(target_tile_name, target_tile.clone());
         target_tile
     }};
@@ -374,7 +743,7 @@ macro_rules! tq {
         let mut target_tile = t!($val);
         let target_tile_name = format!("{}", $e);
         target_tile.name = Some(target_tile_name.clone());
-        set_tiles(target_tile_name.clone(), target_tile.to_string());
+        set_tiles(target_tile_name.clone(), expanded_value(&target_tile));
         set_raw_tiles(target_tile_name, target_tile.clone());
         target_tile
     }};
@@ -383,12 +752,234 @@ macro_rules! tq {
         let mut target_tile = t!(val);
         let target_tile_name = format!("{}", $e);
         target_tile.name = Some(target_tile_name.clone());
-        set_tiles(target_tile_name.clone(), target_tile.to_string());
+        set_tiles(target_tile_name.clone(), expanded_value(&target_tile));
         set_raw_tiles(target_tile_name, target_tile.clone());
         target_tile
     }};
 }
 
+/// tps! is used to persist the tile into the tls (thread local storage) the same way as tp!, without binding a local variable
+/// named after the tile, see tp!
+/// ```
+/// use rtile::prelude::*;
+///
+/// #[derive(Default)]
+/// struct Generator {
+///     header: String,
+/// }
+///
+/// impl Generator {
+///     fn persist(&self) -> RTile {
+///         tps!(HEADER, "// {}", self.header);
+///         tps!(self, "@{HEADER}")
+///     }
+/// }
+///
+/// let generator = Generator { header: "generated".to_string() };
+/// assert_eq!(generator.persist().name, Some("self".to_string()));
+/// assert_eq!(ts!("@{self}"), "// generated");
+/// ```
+#[macro_export]
+macro_rules! tps {
+    ($s:expr => $i:ident) => {{
+        tq!($s => stringify!($i))
+    }};
+    ($s:expr => $i:ident, $($arg:tt)*) => {{
+        tq!($s => stringify!($i), $($arg)*)
+    }};
+    ($i:ident) => {{
+        tq!(stringify!($i))
+    }};
+    ($i:ident, $($arg:tt)*) => {{
+        tq!(stringify!($i), $($arg)*)
+    }};
+}
+
+/// tpv! is used to persist the tile into the tls (thread local storage) with a semantic version, i.e. as `name@major.minor.patch`,
+/// and return a tile, a placeholder with a version requirement (`@{name@^2}`, `@{name@~2.1}`, `@{name@>=1.2, <3}`, ...) refers
+/// to the highest version compatible with it, the same way as the requirements of cargo, and the render panics if there is none
+///
+/// ```
+/// use rtile::prelude::*;
+///
+/// tpv!(api_stub, "1.4", "fn call() {}");
+/// tpv!(api_stub, "2.0", "async fn call() {}");
+/// tpv!(api_stub, "2.1", "async fn call(timeout: u64) {}");
+/// assert_eq!(ts!("@{api_stub@^1}"), "fn call() {}");
+/// assert_eq!(ts!("@{api_stub@^2}"), "async fn call(timeout: u64) {}");
+/// assert_eq!(ts!("@{api_stub@~2.0}"), "async fn call() {}");
+/// assert_eq!(
+///     t!("@{api_stub@^3}").try_expand().unwrap_err().to_string(),
+///     "no version of the tile api_stub is compatible with ^3"
+/// );
+/// ```
+#[macro_export]
+macro_rules! tpv {
+    ($s:expr => $i:ident, $v:expr) => {{
+        tq!($s => versioned_tile_name(stringify!($i), $v))
+    }};
+    ($s:expr => $i:ident, $v:expr, $($arg:tt)*) => {{
+        tq!($s => versioned_tile_name(stringify!($i), $v), $($arg)*)
+    }};
+    ($i:ident, $v:expr) => {{
+        tq!(versioned_tile_name(stringify!($i), $v))
+    }};
+    ($i:ident, $v:expr, $($arg:tt)*) => {{
+        tq!(versioned_tile_name(stringify!($i), $v), $($arg)*)
+    }};
+}
+
+/// gp! is used to persist the tile into the tls (thread local storage) the same way as tp!, and into the global registry shared
+/// by all the threads, see `set_global_tile`
+/// ```
+/// use rtile::prelude::*;
+///
+/// gp!(gp_doc_name, "Alice");
+/// let output = std::thread::spawn(|| ts!("Hello @{gp_doc_name}")).join().unwrap();
+/// assert_eq!(output, "Hello Alice");
+/// ```
+#[macro_export]
+macro_rules! gp {
+    ($i:ident) => {{
+        let tile = tp!($i);
+        set_global_tile(stringify!($i), &tile);
+        tile
+    }};
+    ($i:ident, $($arg:tt)*) => {{
+        let tile = tp!($i, $($arg)*);
+        set_global_tile(stringify!($i), &tile);
+        tile
+    }};
+}
+
+/// gq! is used to persist the tile into the tls (thread local storage) the same way as tq!, and into the global registry shared
+/// by all the threads, see `set_global_tile`
+/// ```
+/// use rtile::prelude::*;
+///
+/// for (i, name) in ["Alice", "Bob"].iter().enumerate() {
+///     gq!(format!("gq_doc_user_{}", i), *name);
+/// }
+/// let output = std::thread::spawn(|| ts!("@{gq_doc_user_0}, @{gq_doc_user_1}")).join().unwrap();
+/// assert_eq!(output, "Alice, Bob");
+/// ```
+#[macro_export]
+macro_rules! gq {
+    ($e:expr) => {{
+        let tile_name = format!("{}", $e);
+        let tile = tq!(tile_name.clone());
+        set_global_tile(&tile_name, &tile);
+        tile
+    }};
+    ($e:expr, $($arg:tt)*) => {{
+        let tile_name = format!("{}", $e);
+        let tile = tq!(tile_name.clone(), $($arg)*);
+        set_global_tile(&tile_name, &tile);
+        tile
+    }};
+}
+
+/// tpu! is to used to persist the tile into the tls (thread local storage), with a unique name generated from the given prefix (string literal) and return the generated name
+///
+/// ```
+/// use rtile::prelude::*;
+/// let mut rows = vec![];
+/// for i in 1..=3 {
+///     let name = tpu!(row, "row {}", i);
+///     rows.push(format!("@{{{}}}", name));
+/// }
+/// assert_eq!(ts!(rows.join("\n")), "row 1\nrow 2\nrow 3");
+/// ```
+#[macro_export]
+macro_rules! tpu {
+    ($i:ident) => {{
+        let target_tile_name = unique_tile_name(stringify!($i));
+        tq!(target_tile_name);
+        target_tile_name
+    }};
+    ($i:ident, $e:expr) => {{
+        let target_tile_name = unique_tile_name(stringify!($i));
+        tq!(target_tile_name, $e);
+        target_tile_name
+    }};
+    ($i:ident, $($arg:tt)*) => {{
+        let target_tile_name = unique_tile_name(stringify!($i));
+        tq!(target_tile_name, $($arg)*);
+        target_tile_name
+    }};
+}
+
+/// tiles! is to used to persist a block of tiles into the tls (thread local storage) in one statement, i.e. tp! for each `name: value`
+///
+/// a name used more than once fails to compile
+/// ```
+/// use rtile::prelude::*;
+/// tiles! {
+///     first_name: "Alice",
+///     last_name: "Smith",
+///     full_name: "@{first_name} @{last_name}",
+///     address: vec!["1 Main St", "Springfield"],
+/// }
+/// assert_eq!(ts!("@{full_name}, @{address}"), "Alice Smith, 1 Main St\n             Springfield");
+/// ```
+/// ```compile_fail
+/// use rtile::prelude::*;
+/// tiles! {
+///     name: "Alice",
+///     name: "Bob",
+/// }
+/// ```
+#[macro_export]
+macro_rules! tiles {
+    ($s:expr => $($i:ident : $e:expr),* $(,)?) => {{
+        #[allow(non_camel_case_types, dead_code)]
+        enum DuplicateTileNames { $($i),* }
+        $(tp!($s => $i, $e);)*
+    }};
+    ($($i:ident : $e:expr),* $(,)?) => {{
+        #[allow(non_camel_case_types, dead_code)]
+        enum DuplicateTileNames { $($i),* }
+        $(tp!($i, $e);)*
+    }};
+}
+
+/// declare_tiles! is used to persist a block of tiles into the tls (thread local storage) in one statement, i.e. tq! for each
+/// `"name": value`, or a blank tile for each `"name"` without a value, where the names are string literals, e.g. `"user.name"`
+///
+/// a name declared more than once in the block fails to compile
+/// ```
+/// use rtile::prelude::*;
+/// declare_tiles! {
+///     "user.first": "Alice",
+///     "user.last": "Smith",
+///     "user.title",
+///     "user.full": "@{user.title} @{user.first} @{user.last}",
+/// }
+/// assert_eq!(ts!("@{user.full}"), "Alice Smith");
+///
+/// let mut store = TileStore::new();
+/// declare_tiles!(store => "greeting": "Hello @{user.first}", "user.first": "Bob");
+/// assert_eq!(ts!(store => "@{greeting}"), "Hello Bob");
+/// ```
+/// ```compile_fail
+/// use rtile::prelude::*;
+/// declare_tiles! {
+///     "user.name": "Alice",
+///     "user.name": "Bob",
+/// }
+/// ```
+#[macro_export]
+macro_rules! declare_tiles {
+    ($($n:literal $(: $e:expr)?),* $(,)?) => {{
+        const _: () = check_unique_tile_names(&[$($n),*]);
+        $(tq!($n $(, $e)?);)*
+    }};
+    ($s:expr => $($n:literal $(: $e:expr)?),* $(,)?) => {{
+        const _: () = check_unique_tile_names(&[$($n),*]);
+        $(tq!($s => $n $(, $e)?);)*
+    }};
+}
+
 /// tt! is to used to expand the inner tiles and return the expanded ouput as a trimmed tile
 ///
 /// ```
@@ -416,6 +1007,8 @@ macro_rules! tt {
 
 /// ttp! is to used to expand the inner tiles, persist the result to tls (thread local storage) using a string literal and return a trimmed tile
 ///
+/// the tile is bound to a local variable named after the tile within the block of the macro, see tp!
+///
 /// ```
 /// use rtile::prelude::*;
 ///
@@ -428,7 +1021,7 @@ macro_rules! ttp {
     ($i:ident, $e:expr) => {{
         let mut $i = t!(t!($e));
         $i.name = Some(stringify!($i).to_string());
-        set_tiles(format!("{}", stringify!($i)), $i.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$i));
         set_raw_tiles(format!("{}", stringify!($i)), $i.clone());
         $i
     }};
@@ -436,7 +1029,7 @@ macro_rules! ttp {
         let val = format!($($arg)*);
         let mut $i = t!(t!(val));
         $i.name = Some(stringify!($i).to_string());
-        set_tiles(format!("{}", stringify!($i)), $i.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$i));
         set_raw_tiles(format!("{}", stringify!($i)), $i.clone());
         $i
     }};
@@ -458,7 +1051,7 @@ macro_rules! ttq {
         let mut target_tile = t!(t!($val));
         let target_tile_name = format!("{}", $e);
         target_tile.name = Some(target_tile_name.clone());
-        set_tiles(target_tile_name.clone(), target_tile.to_string());
+        set_tiles(target_tile_name.clone(), expanded_value(&target_tile));
         set_raw_tiles(target_tile_name, target_tile.clone());
         target_tile
     }};
@@ -467,7 +1060,7 @@ macro_rules! ttq {
         let mut target_tile = t!(t!(val));
         let target_tile_name = format!("{}", $e);
         target_tile.name = Some(target_tile_name.clone());
-        set_tiles(target_tile_name.clone(), target_tile.to_string());
+        set_tiles(target_tile_name.clone(), expanded_value(&target_tile));
         set_raw_tiles(target_tile_name, target_tile.clone());
         target_tile
     }};
@@ -513,6 +1106,10 @@ macro_rules! sr {
 /// ```
 #[macro_export]
 macro_rules! ts {
+    ($s:expr => $($arg:tt)*) => {{
+        let target_tile = detached(|| t!($($arg)*));
+        $s.render(&target_tile)
+    }};
     () => {{
         "".to_string()
     }};
@@ -578,6 +1175,21 @@ impl MacroAttributeForK for Vec<String> {
     }
 }
 
+impl MacroAttributeForK for Option<RTile> {
+    fn process(&self) -> RTile {
+        match self {
+            Some(tile) => MacroAttributeForK::process(tile),
+            None => RTile::new_without_trimming(vec![]),
+        }
+    }
+}
+
+impl MacroAttributeForK for &Option<RTile> {
+    fn process(&self) -> RTile {
+        MacroAttributeForK::process(*self)
+    }
+}
+
 /// kf! is used to flatten the multilines of the tile output into a single string, without trimming the white spaces. i.e. keep the white spaces
 ///
 /// ```
@@ -618,6 +1230,8 @@ macro_rules! k {
             name: None,
             lns: vec!["".to_string()],
             do_trimming: false,
+            tags: vec![],
+            parts: Parts::default(),
             marker: PhantomData::<Rc<()>>,
         }
     }};
@@ -632,6 +1246,8 @@ macro_rules! k {
 
 /// kp! is to expand any inner tiles, to keep the white spaces (i.e. do not trim any white spaces around the block), with a given name (string literal) and return a tile
 ///
+/// the tile is bound to a local variable named after the tile within the block of the macro, see tp!
+///
 /// ```
 /// use rtile::prelude::*;
 ///
@@ -643,17 +1259,23 @@ macro_rules! k {
 /// ```
 #[macro_export]
 macro_rules! kp {
+    ($s:expr => $i:ident) => {{
+        kq!($s => stringify!($i))
+    }};
+    ($s:expr => $i:ident, $($arg:tt)*) => {{
+        kq!($s => stringify!($i), $($arg)*)
+    }};
     ($i:ident) => {{
         let mut $i = k!();
         $i.name = Some(stringify!($i).to_string());
-        set_tiles(format!("{}", stringify!($i)), $i.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$i));
         set_raw_tiles(format!("{}", stringify!($i)), $i.clone());
         $i
     }};
     ($i:ident, $e:expr) => {{
         let mut $i = k!($e);
         $i.name = Some(stringify!($i).to_string());
-        set_tiles(format!("{}", stringify!($i)), $i.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$i));
         set_raw_tiles(format!("{}", stringify!($i)), $i.clone());
         $i
     }};
@@ -661,12 +1283,36 @@ macro_rules! kp {
         let val = format!($($arg)*);
         let mut $i = k!(val);
         $i.name = Some(stringify!($i).to_string());
-        set_tiles(format!("{}", stringify!($i)), $i.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$i));
         set_raw_tiles(format!("{}", stringify!($i)), $i.clone());
         $i
     }};
 }
 
+/// kps! is used to persist the tile into the tls (thread local storage) the same way as kp!, without binding a local variable
+/// named after the tile, see tp!
+/// ```
+/// use rtile::prelude::*;
+///
+/// kps!(PADDED, " abc ");
+/// assert_eq!(ks!(k!("@{PADDED}")), " abc ");
+/// ```
+#[macro_export]
+macro_rules! kps {
+    ($s:expr => $i:ident) => {{
+        kq!($s => stringify!($i))
+    }};
+    ($s:expr => $i:ident, $($arg:tt)*) => {{
+        kq!($s => stringify!($i), $($arg)*)
+    }};
+    ($i:ident) => {{
+        kq!(stringify!($i))
+    }};
+    ($i:ident, $($arg:tt)*) => {{
+        kq!(stringify!($i), $($arg)*)
+    }};
+}
+
 /// kq! is to expand any inner tiles, to keep the white spaces (i.e. do not trim any white spaces around the block), with a variable having a string value and return a tile
 ///
 /// ```
@@ -682,11 +1328,25 @@ macro_rules! kp {
 /// ```
 #[macro_export]
 macro_rules! kq {
+    ($s:expr => $e:expr) => {{
+        let mut target_tile = detached(|| k!());
+        let target_tile_name = format!("{}", $e);
+        target_tile.name = Some(target_tile_name.clone());
+        $s.set(&target_tile_name, target_tile.clone());
+        target_tile
+    }};
+    ($s:expr => $e:expr, $($arg:tt)*) => {{
+        let mut target_tile = detached(|| k!($($arg)*));
+        let target_tile_name = format!("{}", $e);
+        target_tile.name = Some(target_tile_name.clone());
+        $s.set(&target_tile_name, target_tile.clone());
+        target_tile
+    }};
     ($e:expr) => {{
         let mut target_tile = k!();
         let target_tile_name = format!("{}", $e);
         target_tile.name = Some(target_tile_name.clone());
-        set_tiles(target_tile_name.clone(), target_tile.to_string());
+        set_tiles(target_tile_name.clone(), expanded_value(&target_tile));
         set_raw_tiles(target_tile_name, target_tile.clone());
         target_tile
     }};
@@ -694,7 +1354,7 @@ macro_rules! kq {
         let mut target_tile = k!($val);
         let target_tile_name = format!("{}", $e);
         target_tile.name = Some(target_tile_name.clone());
-        set_tiles(target_tile_name.clone(), target_tile.to_string());
+        set_tiles(target_tile_name.clone(), expanded_value(&target_tile));
         set_raw_tiles(target_tile_name, target_tile.clone());
         target_tile
     }};
@@ -703,7 +1363,7 @@ macro_rules! kq {
         let mut target_tile = k!(val);
         let target_tile_name = format!("{}", $e);
         target_tile.name = Some(target_tile_name.clone());
-        set_tiles(target_tile_name.clone(), target_tile.to_string());
+        set_tiles(target_tile_name.clone(), expanded_value(&target_tile));
         set_raw_tiles(target_tile_name, target_tile.clone());
         target_tile
     }};
@@ -733,6 +1393,8 @@ macro_rules! kk {
 
 /// kkp! is to used to expand the inner tiles, by keeping the white spaces (i.e. do not trim any white spaces around the block), persist the result to tls (thread local storage) using a string literal and return a tile
 ///
+/// the tile is bound to a local variable named after the tile within the block of the macro, see tp!
+///
 /// ```
 /// use rtile::prelude::*;
 ///
@@ -745,7 +1407,7 @@ macro_rules! kkp {
     ($i:ident, $e:expr) => {{
         let mut $i = k!(k!($e));
         $i.name = Some(stringify!($i).to_string());
-        set_tiles(format!("{}", stringify!($i)), $i.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$i));
         set_raw_tiles(format!("{}", stringify!($i)), $i.clone());
         $i
     }};
@@ -753,7 +1415,7 @@ macro_rules! kkp {
         let val = format!($($arg)*);
         let mut $i = k!(k!(val));
         $i.name = Some(stringify!($i).to_string());
-        set_tiles(format!("{}", stringify!($i)), $i.to_string());
+        set_tiles(format!("{}", stringify!($i)), expanded_value(&$i));
         set_raw_tiles(format!("{}", stringify!($i)), $i.clone());
         $i
     }};
@@ -774,7 +1436,7 @@ macro_rules! kkq {
         let mut target_tile = k!(k!($val));
         let target_tile_name = format!("{}", $e);
         target_tile.name = Some(target_tile_name.clone());
-        set_tiles(target_tile_name.clone(), target_tile.to_string());
+        set_tiles(target_tile_name.clone(), expanded_value(&target_tile));
         set_raw_tiles(target_tile_name, target_tile.clone());
         target_tile
     }};
@@ -783,7 +1445,7 @@ macro_rules! kkq {
         let mut target_tile = k!(k!(val));
         let target_tile_name = format!("{}", $e);
         target_tile.name = Some(target_tile_name.clone());
-        set_tiles(target_tile_name.clone(), target_tile.to_string());
+        set_tiles(target_tile_name.clone(), expanded_value(&target_tile));
         set_raw_tiles(target_tile_name, target_tile.clone());
         target_tile
     }};
@@ -799,6 +1461,10 @@ macro_rules! kkq {
 /// ```
 #[macro_export]
 macro_rules! ks {
+    ($s:expr => $($arg:tt)*) => {{
+        let target_tile = detached(|| k!($($arg)*));
+        $s.render(&target_tile)
+    }};
     () => {{
         "".to_string()
     }};
@@ -811,32 +1477,138 @@ macro_rules! ks {
     }};
 }
 
+#[doc(hidden)]
+pub trait MacroAttributeForTcat {
+    #[doc(hidden)]
+    fn process(&self) -> RTile;
+}
+
+impl<T: Display + ?Sized> MacroAttributeForTcat for T {
+    fn process(&self) -> RTile {
+        let lns = self.to_string().split('\n').map(String::from).collect();
+        detached(|| RTile::new_without_trimming(lns))
+    }
+}
+
+/// tcat! is used to place the values side by side like `+` and return a tile, the values are of any type implementing Display,
+/// e.g. the numbers, the strings and the tiles (as their output), the white spaces of the values are kept
+///
+/// ```
+/// use rtile::prelude::*;
+///
+/// let names = t!("one\ntwo\nthree");
+/// let total = 4;
+/// let result = tcat![names, " = ", total, ' ', 1.5];
+/// assert_eq!(result.to_string(), "one   = 4 1.5\ntwo          \nthree        ");
+/// assert_eq!(ts!(result), "one   = 4 1.5\ntwo\nthree");
+/// ```
+#[macro_export]
+macro_rules! tcat {
+    ($($e:expr),* $(,)?) => {{
+        RTile::hjoin(&[$(MacroAttributeForTcat::process(&$e)),*], "")
+    }};
+}
+
 thread_local! {
-    static TL_PROCESSED_TILES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
-    static TL_RAW_TILES: RefCell<HashMap<String, RTile>> = RefCell::new(HashMap::new());
+    static TL_STORE: RefCell<TileStore> = RefCell::new(TileStore::new());
+    static TL_UNIQUE_TILE_NAME_COUNTER: Cell<usize> = const { Cell::new(0) };
+    static TL_NAME_PREFIXES: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+    static TL_PLACEHOLDER_BINDINGS: RefCell<Vec<(String, String)>> = const { RefCell::new(vec![]) };
+    static TL_RENDER_OPTIONS: RefCell<RenderOptions> = RefCell::new(RenderOptions::default());
+    static TL_REGISTRATION_VALIDATOR: Cell<Option<RegistrationValidator>> = const { Cell::new(None) };
+    static TL_POST_PROCESSORS: RefCell<Vec<(Option<String>, PostProcessor)>> = const { RefCell::new(vec![]) };
+    static TL_DETACHED: Cell<bool> = const { Cell::new(false) };
+    static TL_TEMPLATE_ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+    // the directory of the tiles cached by `set_cached_tile`, see `set_cache_dir`
+    static TL_CACHE_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+    // the files being included, see `set_template_root`
+    static TL_INCLUDES: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+    static TL_EVALUATING_DYNAMIC_TILES: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
 }
 
 #[doc(hidden)]
 pub fn set_tiles(key: String, value: String) {
-    TL_PROCESSED_TILES.with_borrow_mut(|v| v.insert(key, value));
+    TL_STORE.with_borrow_mut(|v| v.set_processed(key, value));
 }
 
+// the value persisted along with a tile, which doesn't call the closures of the dynamic tiles
 #[doc(hidden)]
-pub fn set_raw_tiles(key: String, value: RTile) {
-    TL_RAW_TILES.with_borrow_mut(|v| v.insert(key, value));
+pub fn expanded_value(tile: &RTile) -> String {
+    TL_STORE.with_borrow_mut(|v| v.expand(tile))
 }
 
 #[doc(hidden)]
-pub fn get_raw_tile(key: &str) -> Option<RTile> {
-    let key = &key.to_string();
+pub fn set_deep_tiles(key: &str, value: &RTile) -> Vec<String> {
+    TL_STORE.with_borrow_mut(|v| v.set_deep(key, value))
+}
 
-    TL_RAW_TILES.with_borrow(|v| {
-        if v.contains_key(key) {
-            Some(v.get(key).unwrap().clone())
-        } else {
-            None
+#[doc(hidden)]
+pub fn get_snapshot_tile(key: &str) -> Option<RTile> {
+    let tile = get_raw_tile(key)?;
+    Some(snapshot_of(&tile, &tile.to_string()))
+}
+
+// a tile of the output of the given tile, which renders as the output, as the output of a tile is already trimmed or padded
+pub(crate) fn snapshot_of(tile: &RTile, output: &str) -> RTile {
+    RTile {
+        name: None,
+        lns: output.split('\n').map(|ln| ln.to_string()).collect(),
+        do_trimming: tile.do_trimming,
+        tags: vec![],
+        parts: Parts::default(),
+        marker: PhantomData::<Rc<()>>,
+    }
+}
+
+// fails the constant evaluation (i.e. the compilation) of declare_tiles!, when a name is declared more than once
+#[doc(hidden)]
+pub const fn check_unique_tile_names(names: &[&str]) {
+    let mut i = 0;
+    while i < names.len() {
+        let mut j = i + 1;
+        while j < names.len() {
+            if same_bytes(names[i].as_bytes(), names[j].as_bytes()) {
+                panic!("{}", names[i]);
+            }
+            j += 1;
         }
-    })
+        i += 1;
+    }
+}
+
+const fn same_bytes(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// the name of the version of the tile persisted by tpv!, the version is `major`, `major.minor` or `major.minor.patch`
+#[doc(hidden)]
+pub fn versioned_tile_name(name: &str, version: &str) -> String {
+    let version = version::Version::parse(version)
+        .unwrap_or_else(|| panic!("invalid version {} of the tile {}", version, name));
+    format!("{}@{}", name, version)
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn set_raw_tiles(key: String, value: RTile) {
+    check_registration(&key, &value);
+    let location = std::panic::Location::caller();
+    TL_STORE.with_borrow_mut(|v| v.set_raw_at(key, value, location));
+}
+
+#[doc(hidden)]
+pub fn get_raw_tile(key: &str) -> Option<RTile> {
+    TL_STORE.with_borrow(|v| v.get(key))
 }
 
 /// remove_tile, used to remove a tile by name from the tls (thread local storage)
@@ -854,10 +1626,10 @@ pub fn get_raw_tile(key: &str) -> Option<RTile> {
 /// //If the tile is not present, a blank tile would be created using that name
 /// assert_eq!(ts!("@{tile1}-@{tile2}"), "-".to_string());
 /// ```
+#[track_caller]
 pub fn remove_tile(key: &str) {
-    let key = &key.to_string();
-    TL_RAW_TILES.with_borrow_mut(|v| v.remove(key));
-    TL_PROCESSED_TILES.with_borrow_mut(|v| v.remove(key));
+    let location = std::panic::Location::caller();
+    TL_STORE.with_borrow_mut(|v| v.remove_at(key, location));
 }
 
 /// clear_tiles, used to remove all tiles from the tls (thread local storage)
@@ -872,9 +1644,27 @@ pub fn remove_tile(key: &str) {
 /// //If the tile is not present, a blank tile would be created using that name
 /// assert_eq!(ts!("@{tile1}-@{tile2}"), "-".to_string());
 /// ```
+#[track_caller]
 pub fn clear_tiles() {
-    TL_RAW_TILES.with_borrow_mut(|v| v.clear());
-    TL_PROCESSED_TILES.with_borrow_mut(|v| v.clear());
+    let location = std::panic::Location::caller();
+    TL_STORE.with_borrow_mut(|v| v.clear_at(location));
+}
+
+/// refresh_tiles, used to process every tile stored in the tls (thread local storage) again from its raw data,
+/// a render never changes the processed data, so `render_cached` is up to date only after a refresh
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(refresh_name, "Alice");
+/// tp!(refresh_greet, "Hello @{refresh_name}");
+/// stp!(refresh_name, t!("Bob"));
+/// let tile = t!("@{refresh_greet}");
+/// assert_eq!(tile.render_cached(), "Hello Alice");
+/// refresh_tiles();
+/// assert_eq!(tile.render_cached(), "Hello Bob");
+/// ```
+pub fn refresh_tiles() {
+    TL_STORE.with_borrow_mut(|v| v.refresh());
 }
 
 /// get_blank_tiles, used to return blank tiles stored in the tls (thread local storage)
@@ -888,705 +1678,3938 @@ pub fn clear_tiles() {
 /// assert_eq!(result.contains(&"tile2".to_string()), true);
 /// ```
 pub fn get_blank_tiles() -> HashSet<String> {
-    let mut blank_tiles = HashSet::new();
-    TL_RAW_TILES.with_borrow(|v| {
-        for (tile_name, tile) in v.iter() {
-            if tile.lns == Vec::<String>::new() {
-                assert!(!tile_name.is_empty());
-                blank_tiles.insert(tile_name.clone());
-            }
-        }
-    });
-    blank_tiles
+    TL_STORE.with_borrow(|v| v.blank_tiles())
 }
 
-fn trim<I, T>(t1: I, do_trimming: bool) -> Vec<String>
-where
-    I: IntoIterator<Item = T> + Debug,
-    T: Into<String>,
-{
-    let t1: Vec<String> = t1.into_iter().map(Into::into).collect();
-    if !do_trimming {
-        return t1;
-    }
+/// get_blank_tiles_sorted, used to return the names of the blank tiles stored in the tls (thread local storage) in a stable order,
+/// e.g. for the reports and the logs which are compared between runs
+/// ```
+/// use rtile::prelude::*;
+///
+/// t!("@{tile2}-@{tile1}");
+///
+/// let result: Vec<String> = get_blank_tiles_sorted().into_iter().collect();
+/// assert_eq!(result, vec!["tile1", "tile2"]);
+/// ```
+pub fn get_blank_tiles_sorted() -> std::collections::BTreeSet<String> {
+    TL_STORE.with_borrow(|v| v.blank_tiles_sorted())
+}
 
-    let mut lns: Vec<String> = t1.into_iter().map(|ln| ln.trim_end().to_string()).collect();
-    lns = lns.into_iter().skip_while(|ln| ln.is_empty()).collect();
-    lns.reverse();
-    lns = lns.into_iter().skip_while(|ln| ln.is_empty()).collect();
-    lns.reverse();
-    let lf: Vec<&str> = lns
-        .iter()
-        .filter(|ln| !ln.is_empty())
-        .map(|ln| ln.as_str())
-        .collect();
-    let left = if lf.is_empty() {
-        0
-    } else {
-        lf.iter()
-            .map(|ln| ln.len() - ln.trim_start().len())
-            .min()
-            .unwrap_or(0_usize)
-    };
-    let result = lns
-        .into_iter()
-        .map(|ln| ln.chars().skip(left).collect())
-        .collect();
-    result
+/// unique_tile_name, used to generate a tile name which is not yet used in the tls (thread local storage), using a per thread counter
+///
+/// the generated names are deterministic for a given thread, i.e. `{prefix}_u{counter}`
+/// ```
+/// use rtile::prelude::*;
+///
+/// let first = unique_tile_name("cell");
+/// let second = unique_tile_name("cell");
+/// assert_ne!(first, second);
+/// assert!(first.starts_with("cell_u"));
+/// ```
+pub fn unique_tile_name(prefix: &str) -> String {
+    loop {
+        let counter = TL_UNIQUE_TILE_NAME_COUNTER.get() + 1;
+        TL_UNIQUE_TILE_NAME_COUNTER.set(counter);
+        let tile_name = format!("{}_u{}", prefix, counter);
+        if !TL_STORE.with_borrow(|v| v.contains(&tile_name)) {
+            return tile_name;
+        }
+    }
 }
 
-fn append<I, T>(t1: &mut Vec<String>, t2: I)
-where
-    I: IntoIterator<Item = T> + Debug,
-    T: Into<String>,
-{
-    let t2: Vec<String> = t2.into_iter().map(Into::into).collect();
+/// set_render_options, used to set the render options of the current thread, which apply to every render that follows
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(attrs);
+/// let tile = t!("
+///     struct A;
+///     @{attrs}
+///     struct B;
+///     ");
+/// assert_eq!(tile.to_string(), "struct A;\n\nstruct B;");
+/// set_render_options(RenderOptions {
+///     drop_blank_placeholder_lines: true,
+///     ..Default::default()
+/// });
+/// assert_eq!(tile.to_string(), "struct A;\nstruct B;");
+/// set_render_options(RenderOptions::default());
+/// ```
+pub fn set_render_options(options: RenderOptions) {
+    TL_RENDER_OPTIONS.set(options);
+}
 
-    let diff: i32 = t2.len() as i32 - t1.len() as i32;
-    if diff > 0 {
-        t1.extend(vec!["".to_owned(); diff as usize]);
-    }
-    let w = t1.iter().map(|s| s.chars().count()).max().unwrap_or(0);
-    for (i, s) in t2.into_iter().enumerate() {
-        t1[i] = format!("{:<w$}{}", t1[i], s, w = w);
-    }
+/// get_render_options, used to get the render options of the current thread
+/// ```
+/// use rtile::prelude::*;
+///
+/// assert_eq!(get_render_options(), RenderOptions::default());
+/// ```
+pub fn get_render_options() -> RenderOptions {
+    TL_RENDER_OPTIONS.with_borrow(|v| v.clone())
 }
 
-enum ExtraSteps {
-    DoNothing,
-    DoAppendTheTextFromCursorToInnerTileNameOrTheEndOfLine,
+/// set_template_render_options, used to attach the render options to a template persisted in the tls (thread local storage),
+/// which apply instead of the render options of the current thread whenever the template is rendered, see `TileStore::set_render_options`
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(batch_file, "@echo off\necho @{batch_message}");
+/// tp!(batch_message, "done");
+/// set_template_render_options(
+///     "batch_file",
+///     RenderOptions {
+///         line_ending: LineEnding::CrLf,
+///         strict: true,
+///         ..Default::default()
+///     },
+/// );
+/// assert_eq!(gtp!(batch_file).unwrap().to_string(), "@echo off\r\necho done");
+/// clear_template_render_options("batch_file");
+/// assert_eq!(gtp!(batch_file).unwrap().to_string(), "@echo off\necho done");
+/// ```
+pub fn set_template_render_options(name: &str, options: RenderOptions) {
+    TL_STORE.with_borrow_mut(|v| v.set_render_options(name, options));
 }
 
-fn get_next_inner_tile_name(
-    ln: &str,
-    current_cursor: &mut usize,
-    end: &mut usize,
-) -> Option<String> {
-    find_next_inner_tile_name(
-        ln,
-        current_cursor,
-        end,
-        &mut Vec::<String>::new(),
-        ExtraSteps::DoNothing,
-    )
+/// clear_template_render_options, used to detach the render options from a template persisted in the tls (thread local storage),
+/// see `set_template_render_options`
+pub fn clear_template_render_options(name: &str) {
+    TL_STORE.with_borrow_mut(|v| v.clear_render_options(name));
 }
 
-fn find_next_inner_tile_name(
-    ln: &str,
-    current_cursor: &mut usize,
-    end: &mut usize,
-    curr: &mut Vec<String>,
-    extra_steps: ExtraSteps,
-) -> Option<String> {
-    let mut start = ln[*current_cursor..].find("@{").unwrap_or(ln.len());
-    if *current_cursor == ln.len() && start == ln.len() && *end == ln.len() && !ln.is_empty() {
-        return None;
-    }
-    if start < ln.len() {
-        start += *current_cursor;
+// calls the closure with the given render options as the options of the current thread, and restores them afterwards
+pub(crate) fn with_render_options<R>(options: Option<RenderOptions>, f: impl FnOnce() -> R) -> R {
+    struct OptionsGuard(Option<RenderOptions>);
+    impl Drop for OptionsGuard {
+        fn drop(&mut self) {
+            if let Some(options) = self.0.take() {
+                TL_RENDER_OPTIONS.set(options);
+            }
+        }
     }
 
-    if let ExtraSteps::DoAppendTheTextFromCursorToInnerTileNameOrTheEndOfLine = extra_steps {
-        append(curr, vec![&ln[*end..start]]);
-    }
+    let _guard = OptionsGuard(options.map(|options| TL_RENDER_OPTIONS.replace(options)));
+    f()
+}
 
-    if start == ln.len() {
-        return None;
-    }
-    *end = ln[start..].find('}').map_or(0, |i| i + 1);
-    if *end == 0 {
-        panic!("unfinished @{{}} expression");
-    }
-    *end += start;
-    *current_cursor = *end;
-    let tile_name = ln[start + 2..*end - 1].to_string();
-    Some(tile_name)
+/// RegistrationValidator, a function checking a tile before it is persisted, returning the reason when the tile is rejected
+pub type RegistrationValidator = fn(&str, &RTile) -> std::result::Result<(), String>;
+
+/// set_registration_validator, used to check every tile persisted in the current thread (by tp!, tq!, stp!, stq!, kp!, kq!
+/// and `TileStore::set`, i.e. in the tls or in a store) before it is persisted, a rejected tile panics with the reason,
+/// e.g. to enforce the naming conventions, forbid the tabs or cap the sizes of the tiles at the point of their definition
+/// ```
+/// use rtile::prelude::*;
+///
+/// fn snake_case_without_tabs(name: &str, tile: &RTile) -> Result<(), String> {
+///     if name.chars().any(|c| c.is_ascii_uppercase()) {
+///         return Err(format!("`{}` is not snake case", name));
+///     }
+///     if tile.raw().contains('\t') {
+///         return Err("tabs are not allowed".to_string());
+///     }
+///     Ok(())
+/// }
+///
+/// set_registration_validator(snake_case_without_tabs);
+/// tp!(user_name, "Alice");
+/// let result = std::panic::catch_unwind(|| {
+///     tp!(UserName, "Alice");
+/// });
+/// assert!(result.is_err());
+/// assert!(gtp!(UserName).is_none());
+/// clear_registration_validator();
+/// ```
+pub fn set_registration_validator(f: RegistrationValidator) {
+    TL_REGISTRATION_VALIDATOR.set(Some(f));
 }
 
-fn r_format_using_processed_tiles_data(s: &str) -> Vec<String> {
-    let lns: Vec<&str> = s.split('\n').collect();
-    let mut res = vec![];
-    for ln in lns {
-        let mut curr = vec![];
-        let mut current_cursor = 0_usize;
-        let mut end = 0;
-
-        while let Some(tile_name) = find_next_inner_tile_name(
-            ln,
-            &mut current_cursor,
-            &mut end,
-            &mut curr,
-            ExtraSteps::DoAppendTheTextFromCursorToInnerTileNameOrTheEndOfLine,
-        ) {
-            TL_PROCESSED_TILES.with_borrow(|v| {
-                if v.contains_key(&tile_name) {
-                    let tile_value = v.get(&tile_name).unwrap();
-                    let lns: Vec<&str> = tile_value.split('\n').collect();
-                    append(&mut curr, lns);
-                } else {
-                    println!("{} tile is not found", tile_name);
-                }
-            });
-        }
-        res.append(&mut curr);
-    }
-    res
+/// clear_registration_validator, used to remove the validator set by `set_registration_validator`
+pub fn clear_registration_validator() {
+    TL_REGISTRATION_VALIDATOR.set(None);
 }
 
-fn r_format_using_raw_tiles_data(s: &str) -> Vec<String> {
-    let lns: Vec<&str> = s.split('\n').collect();
-    let mut res = vec![];
-    for ln in lns {
-        let mut curr = vec![];
-        let mut current_cursor = 0_usize;
-        let mut end = 0;
-
-        while let Some(tile_name) = find_next_inner_tile_name(
-            ln,
-            &mut current_cursor,
-            &mut end,
-            &mut curr,
-            ExtraSteps::DoAppendTheTextFromCursorToInnerTileNameOrTheEndOfLine,
-        ) {
-            TL_RAW_TILES.with_borrow(|v_raw| {
-                if v_raw.contains_key(&tile_name) {
-                    let tile_value = v_raw.get(&tile_name).unwrap();
-                    check_for_recursion_of_tiles(&tile_name, tile_value);
-                    process_all_required_tiles_data(&tile_name, tile_value);
-
-                    TL_PROCESSED_TILES.with_borrow(|v| {
-                        if v.contains_key(&tile_name) {
-                            let tile_value = v.get(&tile_name).unwrap();
-                            let lns: Vec<&str> = tile_value.split('\n').collect();
-                            append(&mut curr, lns);
-                        } else {
-                            println!("{} tile is not found", tile_name);
-                        }
-                    });
-                } else {
-                    println!("{} tile is not found", tile_name);
-                }
-            });
+// panics with the reason, if the validator of the current thread rejects the tile
+pub(crate) fn check_registration(name: &str, tile: &RTile) {
+    if let Some(validator) = TL_REGISTRATION_VALIDATOR.get() {
+        if let Err(reason) = validator(name, tile) {
+            panic!("the tile {} is rejected: {}", name, reason);
         }
-        res.append(&mut curr);
     }
-    res
 }
 
-fn check_for_recursion_of_tiles(tile_name: &String, tile_value: &RTile) {
-    let mut inner_tiles: Vec<String> = vec![];
-    let mut processed_tiles: HashSet<String> = HashSet::new();
-    let mut direct_parents: HashSet<String> = HashSet::new();
-    direct_parents.insert(tile_name.clone());
-    check_for_recursion_in_inner_tiles(
-        tile_name,
-        tile_value,
-        &mut processed_tiles,
-        &mut inner_tiles,
-        &direct_parents,
-    );
+/// set_template_root, used to set the directory of the files of the `@{include:path}` placeholders of the tiles constructed in the
+/// current thread, the path is relative to the root (for the includes within the included files as well). The file is persisted
+/// in the tls (thread local storage) as a tile named after its path, trimmed the same way as `t!`, and the placeholder refers to
+/// it, an include of a file which is being included panics with the cycle
+/// ```
+/// use rtile::prelude::*;
+///
+/// let root = std::env::temp_dir().join(format!("rtile_doc_includes_{}", std::process::id()));
+/// std::fs::create_dir_all(root.join("partials")).unwrap();
+/// std::fs::write(root.join("partials/header.rtile"), "// generated by @{tool}").unwrap();
+/// set_template_root(&root);
+/// tp!(tool, "rtile");
+/// let tile = t!("
+///     @{include:partials/header.rtile}
+///     fn main() {}
+/// ");
+/// assert_eq!(tile.to_string(), "// generated by rtile\nfn main() {}");
+/// assert_eq!(sr!(tile), "@{partials/header.rtile}\nfn main() {}");
+/// clear_template_root();
+/// std::fs::remove_dir_all(&root).unwrap();
+/// ```
+pub fn set_template_root(path: impl AsRef<Path>) {
+    TL_TEMPLATE_ROOT.set(Some(path.as_ref().to_path_buf()));
 }
 
-fn process_all_required_tiles_data(tile_name: &String, tile_value: &RTile) {
-    let mut inner_tiles: Vec<String> = vec![tile_name.clone()];
-    let mut processed_tiles: HashSet<String> = HashSet::new();
+/// clear_template_root, used to remove the template root set by `set_template_root`
+pub fn clear_template_root() {
+    TL_TEMPLATE_ROOT.set(None);
+}
 
-    find_inner_tiles(
-        tile_name,
-        tile_value,
-        &mut processed_tiles,
-        &mut inner_tiles,
-    );
+/// PostProcessor, a function applied to the rendered output, e.g. a formatter, a linter or a normalization of the text
+pub type PostProcessor = fn(&str) -> String;
 
-    if !inner_tiles.is_empty() {
-        for inner_tile_index in (0..inner_tiles.len()).rev() {
-            let inner_tile_name = inner_tiles.get(inner_tile_index).unwrap();
+/// add_post_processor, used to apply the function to the output of every render of the current thread, after the post
+/// processors added before it and the ones of `RenderOptions::post_processors`, see `add_template_post_processor` to apply it
+/// to the output of a template only
+/// ```
+/// use rtile::prelude::*;
+///
+/// fn strip_trailing_spaces(output: &str) -> String {
+///     output.lines().map(str::trim_end).collect::<Vec<_>>().join("\n")
+/// }
+///
+/// fn upper(output: &str) -> String {
+///     output.to_uppercase()
+/// }
+///
+/// let tile = k!(vec!["fn a() {}  ", "fn b() {}"]);
+/// add_post_processor(strip_trailing_spaces);
+/// assert_eq!(tile.to_string(), "fn a() {}\nfn b() {}");
+/// let options = RenderOptions {
+///     post_processors: vec![upper],
+///     ..Default::default()
+/// };
+/// assert_eq!(tile.to_string_with_options(&options), "FN A() {}\nFN B() {}");
+/// clear_post_processors();
+/// assert_eq!(tile.to_string(), "fn a() {}  \nfn b() {}  ");
+/// ```
+pub fn add_post_processor(f: PostProcessor) {
+    TL_POST_PROCESSORS.with_borrow_mut(|v| v.push((None, f)));
+}
 
-            let result = TL_RAW_TILES.with_borrow(|v| {
-                if v.contains_key(inner_tile_name) {
-                    let inner_tile_value = v.get(inner_tile_name).unwrap();
-                    inner_tile_value.reevaluate()
-                } else {
-                    //tile not found, so return emtpy string
-                    String::new()
-                }
-            });
+/// add_template_post_processor, used to apply the function to the output of the template with the given name, whenever it is
+/// rendered or expanded as an inner tile of the current thread, i.e. before the output of the template is placed in its parent
+/// ```
+/// use rtile::prelude::*;
+///
+/// fn sorted_lines(output: &str) -> String {
+///     let mut lns: Vec<&str> = output.lines().collect();
+///     lns.sort();
+///     lns.join("\n")
+/// }
+///
+/// tp!(post_imports, "use std::fmt;\nuse std::collections::HashMap;");
+/// tp!(post_file, "@{post_imports}\n\nfn main() {}");
+/// add_template_post_processor("post_imports", sorted_lines);
+/// assert_eq!(
+///     gtp!(post_file).unwrap().to_string(),
+///     "use std::collections::HashMap;\nuse std::fmt;\n\nfn main() {}"
+/// );
+/// clear_post_processors();
+/// ```
+pub fn add_template_post_processor(name: &str, f: PostProcessor) {
+    TL_POST_PROCESSORS.with_borrow_mut(|v| v.push((Some(name.to_string()), f)));
+}
 
-            TL_PROCESSED_TILES.with_borrow_mut(|v| v.insert(inner_tile_name.clone(), result));
-        }
+/// clear_post_processors, used to remove the post processors of the current thread, added by `add_post_processor` and
+/// `add_template_post_processor`
+pub fn clear_post_processors() {
+    TL_POST_PROCESSORS.with_borrow_mut(|v| v.clear());
+}
+
+// the post processors of the template, or of every render if no name is given, in the order they were added
+pub(crate) fn post_processors(template: Option<&str>) -> Vec<PostProcessor> {
+    TL_POST_PROCESSORS.with_borrow(|v| {
+        v.iter()
+            .filter(|(name, _)| name.as_deref() == template)
+            .map(|(_, f)| *f)
+            .collect()
+    })
+}
+
+/// set_cached_tile, used to persist the tile returned by `f` in the tls (thread local storage), recomputing it only when the `key` changes
+///
+/// the key is any value which changes whenever the tile has to be recomputed, e.g. a file mtime or a content hash.
+/// persisting a tile with the same name using any other macro / function invalidates the key. returns the persisted tile.
+/// the key is hashed the same way in every run and by every version of rust, see `CacheKey`, so with a cache directory the tile is
+/// reused by the later runs as well, see `set_cache_dir`.
+/// ```
+/// use rtile::prelude::*;
+///
+/// let mut parsed = 0;
+/// for mtime in [100, 100, 250] {
+///     set_cached_tile("types", mtime, || {
+///         parsed += 1;
+///         t!("struct Parsed{};", mtime)
+///     });
+/// }
+/// assert_eq!(parsed, 2);
+/// assert_eq!(ts!("@{types}"), "struct Parsed250;");
+/// ```
+pub fn set_cached_tile<K: CacheKey>(name: &str, key: K, f: impl FnOnce() -> RTile) -> RTile {
+    let key = store::cache_key(key);
+    if let Some(tile) = TL_STORE.with_borrow(|v| v.cached(name, key)) {
+        return tile;
     }
+    // the tls is not borrowed while computing the tile, so `f` may use the macros
+    let tile = load_cached_tile(name, key).unwrap_or_else(|| {
+        let tile = f();
+        save_cached_tile(name, key, &tile);
+        tile
+    });
+    stq!(name, tile);
+    TL_STORE.with_borrow_mut(|v| v.set_cache_key(name.to_string(), key));
+    get_raw_tile(name).unwrap()
 }
 
-fn check_for_recursion_in_inner_tiles(
-    tile_name: &String,
-    tile_value: &RTile,
-    processed_tiles: &mut HashSet<String>,
-    inner_tiles: &mut Vec<String>,
-    direct_parents: &HashSet<String>,
-) {
-    for ln in &tile_value.lns {
-        let mut curr = vec![];
-        let mut current_cursor = 0_usize;
-        let mut end = 0;
+/// set_cache_dir, used to set the directory where `set_cached_tile` and `TileStore::set_cached` persist the computed tiles
+/// of the current thread along with their keys, so the later runs reuse a tile without calling its closure, until its key changes.
+/// A cache file which can't be read or written is ignored, i.e. the tile is computed
+/// ```
+/// use rtile::prelude::*;
+///
+/// let dir = std::env::temp_dir().join(format!("rtile_doc_cache_{}", std::process::id()));
+/// set_cache_dir(&dir);
+/// let mut parsed = 0;
+/// for _run in 0..2 {
+///     let mut store = TileStore::new();
+///     store.set_cached("schema", ("schema.json", 1700000000u64), || {
+///         parsed += 1;
+///         t!("struct Schema;")
+///     });
+///     assert_eq!(ts!(store => "@{schema}"), "struct Schema;");
+/// }
+/// assert_eq!(parsed, 1);
+/// clear_cache_dir();
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn set_cache_dir(path: impl AsRef<Path>) {
+    TL_CACHE_DIR.set(Some(path.as_ref().to_path_buf()));
+}
 
-        while let Some(inner_tile_name) = find_next_inner_tile_name(
-            ln,
-            &mut current_cursor,
-            &mut end,
-            &mut curr,
-            ExtraSteps::DoAppendTheTextFromCursorToInnerTileNameOrTheEndOfLine,
-        ) {
-            if processed_tiles.contains(&inner_tile_name) {
-                continue;
-            } else {
-                TL_RAW_TILES.with_borrow(|v| {
-                    if v.contains_key(&inner_tile_name) {
-                        if direct_parents.contains(&inner_tile_name) {
-                            panic!("detected a recursion");
-                        } else {
-                            let inner_tile_value = v.get(&inner_tile_name).unwrap();
-                            inner_tiles.push(inner_tile_name.clone());
-
-                            let mut all_direct_parents = direct_parents.clone();
-                            all_direct_parents.insert(inner_tile_name.clone());
-                            check_for_recursion_in_inner_tiles(
-                                &inner_tile_name,
-                                inner_tile_value,
-                                processed_tiles,
-                                inner_tiles,
-                                &all_direct_parents,
-                            );
-                        }
-                    } else {
-                        println!("{} tile is not found", inner_tile_name);
-                    }
-                });
-            }
-        }
-        processed_tiles.insert(tile_name.to_string());
-    }
-}
-
-fn find_inner_tiles(
-    tile_name: &String,
-    tile_value: &RTile,
-    processed_tiles: &mut HashSet<String>,
-    inner_tiles: &mut Vec<String>,
-) {
-    for ln in &tile_value.lns {
-        let mut curr = vec![];
-        let mut current_cursor = 0_usize;
-        let mut end = 0;
-        while let Some(inner_tile_name) = find_next_inner_tile_name(
-            ln,
-            &mut current_cursor,
-            &mut end,
-            &mut curr,
-            ExtraSteps::DoAppendTheTextFromCursorToInnerTileNameOrTheEndOfLine,
-        ) {
-            if processed_tiles.contains(&inner_tile_name) {
-                continue;
-            } else {
-                TL_RAW_TILES.with_borrow(|v| {
-                    if v.contains_key(&inner_tile_name) {
-                        let inner_tile_value = v.get(&inner_tile_name).unwrap();
-                        inner_tiles.push(inner_tile_name.clone());
-
-                        find_inner_tiles(
-                            &inner_tile_name,
-                            inner_tile_value,
-                            processed_tiles,
-                            inner_tiles,
-                        );
-                    } else {
-                        println!("{} tile is not found", inner_tile_name);
-                    }
-                });
-            }
-        }
-        processed_tiles.insert(tile_name.to_string());
-    }
-}
-
-fn identify_any_missing_inner_tiles(
-    tile_name: Option<String>,
-    tile_lns: &[String],
-    processed_tiles: &mut HashSet<String>,
-    missing_inner_tiles: &mut HashSet<String>,
-) {
-    for ln in tile_lns {
-        let mut curr = vec![];
-        let mut current_cursor = 0_usize;
-        let mut end = 0;
-        while let Some(inner_tile_name) = find_next_inner_tile_name(
-            ln,
-            &mut current_cursor,
-            &mut end,
-            &mut curr,
-            ExtraSteps::DoAppendTheTextFromCursorToInnerTileNameOrTheEndOfLine,
-        ) {
-            if processed_tiles.contains(&inner_tile_name) {
-                continue;
-            } else {
-                TL_RAW_TILES.with_borrow(|v| {
-                    if v.contains_key(&inner_tile_name) {
-                        let inner_tile_value = v.get(&inner_tile_name).unwrap();
-
-                        identify_any_missing_inner_tiles(
-                            Some(inner_tile_name.clone()),
-                            &inner_tile_value.lns,
-                            processed_tiles,
-                            missing_inner_tiles,
-                        );
-                    } else if missing_inner_tiles.contains(&inner_tile_name) {
-                    } else {
-                        missing_inner_tiles.insert(inner_tile_name.clone());
-                    }
-                });
-            }
-        }
-        if tile_name.is_some() {
-            processed_tiles.insert(tile_name.clone().unwrap());
-        }
-    }
-}
-
-fn get_blank_inner_tiles_names(
-    tile_name: Option<String>,
-    tile_lns: &[String],
-    processed_tiles: &mut HashSet<String>,
-    blank_inner_tiles: &mut Vec<String>,
-) {
-    for ln in tile_lns {
-        let mut curr = vec![];
-        let mut current_cursor = 0_usize;
-        let mut end = 0;
-        while let Some(inner_tile_name) = find_next_inner_tile_name(
-            ln,
-            &mut current_cursor,
-            &mut end,
-            &mut curr,
-            ExtraSteps::DoAppendTheTextFromCursorToInnerTileNameOrTheEndOfLine,
-        ) {
-            if processed_tiles.contains(&inner_tile_name) {
-                continue;
-            } else {
-                TL_RAW_TILES.with_borrow(|v| {
-                    if v.contains_key(&inner_tile_name) {
-                        let inner_tile_value = v.get(&inner_tile_name).unwrap();
-                        if inner_tile_value.lns == Vec::<String>::new() {
-                            blank_inner_tiles.push(inner_tile_name.clone());
-                        }
+/// clear_cache_dir, used to remove the cache directory set by `set_cache_dir`, the cache files are left as they are
+pub fn clear_cache_dir() {
+    TL_CACHE_DIR.set(None);
+}
 
-                        get_blank_inner_tiles_names(
-                            Some(inner_tile_name),
-                            &inner_tile_value.lns,
-                            processed_tiles,
-                            blank_inner_tiles,
-                        );
-                    } else {
-                        println!("{} tile is not found", inner_tile_name);
-                    }
-                });
+// the file of the cached tile, named after the tile (the hash tells apart the names differing only by the replaced characters)
+fn cached_tile_path(name: &str) -> Option<PathBuf> {
+    let dir = TL_CACHE_DIR.with_borrow(|v| v.clone())?;
+    let file_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '-'
             }
-        }
-        if tile_name.is_some() {
-            processed_tiles.insert(tile_name.clone().unwrap());
-        }
-    }
+        })
+        .collect();
+    let hash = content_hash(FNV_OFFSET_BASIS, name.as_bytes());
+    Some(dir.join(format!("{}-{:016x}.rtile", file_name, hash)))
 }
 
-#[doc(hidden)]
-#[derive(Debug, Clone, PartialEq)]
-pub struct RTile {
-    pub name: Option<String>,
-    pub lns: Vec<String>,
-    pub do_trimming: bool,
-    pub marker: PhantomData<Rc<()>>,
+// the tile persisted in the cache directory by an earlier run, if its key is the given key
+pub(crate) fn load_cached_tile(name: &str, key: u64) -> Option<RTile> {
+    let content = std::fs::read_to_string(cached_tile_path(name)?).ok()?;
+    let mut lns = content.split('\n');
+    let header: Vec<&str> = lns.next()?.split(' ').collect();
+    let [format, cached_key, do_trimming, len] = header[..] else {
+        return None;
+    };
+    if format != "rtile-cache" || cached_key != format!("{:016x}", key) {
+        return None;
+    }
+    let len: usize = len.parse().ok()?;
+    let lns: Vec<String> = lns.take(len).map(str::to_string).collect();
+    if lns.len() != len {
+        return None;
+    }
+    Some(RTile {
+        name: Some(name.to_string()),
+        lns,
+        do_trimming: do_trimming.parse().ok()?,
+        tags: vec![],
+        parts: Parts::default(),
+        marker: PhantomData::<Rc<()>>,
+    })
 }
 
-impl RTile {
-    pub fn new_str(lns: Vec<&str>) -> Self {
-        let lns: Vec<String> = lns.iter().map(|&item| item.to_string()).collect();
-        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
-        Self {
-            name: None,
-            lns: trim(lns, true),
-            do_trimming: true,
-            marker: PhantomData::<Rc<()>>,
-        }
+// persists the tile in the cache directory along with its key, see `set_cache_dir`
+pub(crate) fn save_cached_tile(name: &str, key: u64, tile: &RTile) {
+    let Some(path) = cached_tile_path(name) else {
+        return;
+    };
+    let mut content = format!(
+        "rtile-cache {:016x} {} {}",
+        key,
+        tile.do_trimming,
+        tile.lns.len()
+    );
+    for ln in &tile.lns {
+        content.push('\n');
+        content.push_str(ln);
     }
-
-    pub fn new(lns: Vec<String>) -> Self {
-        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
-        Self {
-            name: None,
-            lns: trim(lns, true),
-            do_trimming: true,
-            marker: PhantomData::<Rc<()>>,
-        }
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
     }
+    let _ = std::fs::write(path, content);
+}
 
-    pub fn construct_from_str(val: &str) -> Self {
-        let lns: Vec<&str> = val.split('\n').collect();
-        let lns: Vec<String> = lns.iter().map(|&item| item.to_string()).collect();
-        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
-        Self {
-            name: None,
-            lns: trim(lns, true),
-            do_trimming: true,
+/// store_fingerprint, used to get a hash of all the tiles in the tls (thread local storage), which is stable across runs and platforms
+///
+/// the fingerprint changes whenever a tile is added, removed or changed, so it can be compared before writing any generated files
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(version, "1.0");
+/// let before = store_fingerprint();
+/// tp!(version, "1.0");
+/// assert_eq!(store_fingerprint(), before);
+/// tp!(version, "1.1");
+/// assert_ne!(store_fingerprint(), before);
+/// ```
+pub fn store_fingerprint() -> u64 {
+    TL_STORE.with_borrow(|v| v.fingerprint())
+}
+
+/// record_store_events, used to start (or stop) recording the changes of the tls (thread local storage) in its event log, i.e.
+/// every tile persisted or removed and every `clear_tiles`, along with the time, the hashes of the old and the new raw lines
+/// and the source location of the change, see `store_events`
+/// ```
+/// use rtile::prelude::*;
+///
+/// record_store_events(true);
+/// tp!(audit_header, "v1");
+/// remove_tile("audit_header");
+/// record_store_events(false);
+/// tp!(audit_header, "v2");
+///
+/// let events = store_events();
+/// assert_eq!(events.len(), 2);
+/// assert_eq!(events[0].kind, StoreEventKind::Set);
+/// assert_eq!(events[0].name.as_deref(), Some("audit_header"));
+/// assert_eq!(events[0].old_hash, None);
+/// assert_eq!((events[1].kind, events[1].new_hash), (StoreEventKind::Remove, None));
+/// ```
+pub fn record_store_events(enabled: bool) {
+    TL_STORE.with_borrow_mut(|v| v.record_events(enabled));
+}
+
+/// store_events, used to get the event log of the tls (thread local storage), see `record_store_events`
+pub fn store_events() -> Vec<StoreEvent> {
+    TL_STORE.with_borrow(|v| v.events().to_vec())
+}
+
+/// drain_store_events, used to get the event log of the tls (thread local storage) and to empty it, see `record_store_events`
+pub fn drain_store_events() -> Vec<StoreEvent> {
+    TL_STORE.with_borrow_mut(|v| v.drain_events())
+}
+
+/// limit_store_events, used to keep only the latest `limit` events in the event log of the tls (thread local storage), `None`
+/// keeps all of them, see `TileStore::limit_events`
+pub fn limit_store_events(limit: Option<usize>) {
+    TL_STORE.with_borrow_mut(|v| v.limit_events(limit));
+}
+
+/// export_store, used to get all the tiles persisted in the tls (thread local storage) by their names, e.g. to merge them into another store using `merge_store`
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(exported_tile, "value");
+/// let tiles = export_store();
+/// assert_eq!(tiles["exported_tile"].raw(), "value");
+/// ```
+pub fn export_store() -> HashMap<String, RTile> {
+    TL_STORE.with_borrow(|v| v.export())
+}
+
+/// export_store_sorted, used to get all the tiles persisted in the tls (thread local storage) sorted by their names, e.g. to write them out in a stable order
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(second, "2");
+/// tp!(first, "1");
+/// let names: Vec<String> = export_store_sorted().into_keys().collect();
+/// assert_eq!(names, vec!["first", "second"]);
+/// ```
+pub fn export_store_sorted() -> std::collections::BTreeMap<String, RTile> {
+    TL_STORE.with_borrow(|v| v.export_sorted())
+}
+
+/// describe_template, used to get the documentation of a tile persisted in the tls (thread local storage), i.e. its leading
+/// comment lines (starting with `@#`) and the placeholders of the rest of its lines, or None if no tile is persisted with the name
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(
+///     greeting_doc,
+///     "@# Greets a user
+///      @# by the full name
+///      Hello @{first} @{last}, welcome to @{site}. Bye @{first}!"
+/// );
+/// let doc = describe_template("greeting_doc").unwrap();
+/// assert_eq!(doc.doc, vec!["Greets a user", "by the full name"]);
+/// assert_eq!(doc.placeholders, vec!["first", "last", "site"]);
+/// ```
+pub fn describe_template(name: &str) -> Option<TemplateDoc> {
+    let tile = get_raw_tile(name)?;
+    let mut lns = tile.lns.iter().map(|ln| ln.trim_start()).peekable();
+    let mut doc = vec![];
+    while let Some(comment) = lns.peek().and_then(|ln| ln.strip_prefix("@#")) {
+        // a single space after the marker only separates the comment from it
+        doc.push(
+            comment
+                .strip_prefix(' ')
+                .unwrap_or(comment)
+                .trim_end()
+                .to_string(),
+        );
+        lns.next();
+    }
+    let mut placeholders: Vec<String> = vec![];
+    for ln in lns {
+        for placeholder in crate::placeholders(ln) {
+            for tile_name in placeholder.inner_tile_names(&HashSet::new()) {
+                if !placeholders.contains(&tile_name) {
+                    placeholders.push(tile_name);
+                }
+            }
+        }
+    }
+    Some(TemplateDoc {
+        name: name.to_string(),
+        doc,
+        placeholders,
+    })
+}
+
+/// document_store, used to generate a Markdown reference of the templates persisted in the tls (thread local storage), sorted
+/// by their names, using `describe_template`. The blank tiles (e.g. the ones created for the missing inner tiles) are left out
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(store_doc_greet, "@# Greets a user\nHello @{store_doc_user}!");
+/// tp!(store_doc_user, "Alice");
+/// let reference = document_store();
+/// assert!(reference.starts_with("# Templates\n\n"));
+/// assert!(reference.contains("## `store_doc_greet`\n\nGreets a user\n\nPlaceholders: `store_doc_user`\n"));
+/// assert!(reference.ends_with("\n## `store_doc_user`\n"));
+/// ```
+pub fn document_store() -> String {
+    let blank_tiles = get_blank_tiles();
+    let sections: Vec<String> = export_store_sorted()
+        .into_keys()
+        .filter(|name| !blank_tiles.contains(name))
+        .filter_map(|name| describe_template(&name))
+        .map(|doc| doc.to_markdown())
+        .collect();
+    format!("# Templates\n\n{}", sections.join("\n"))
+}
+
+/// merge_store, used to persist a set of exported tiles in the tls (thread local storage), e.g. to combine the template sets of several crates at startup
+///
+/// a tile clashes when a non blank tile with the same name but a different content is already persisted, and the clash is resolved using the policy.
+/// with `ConflictPolicy::Error` nothing is merged if there are any clashes, and the sorted names of the clashing tiles are returned,
+/// the same as with `ConflictPolicy::Overwrite` for the clashing frozen tiles, see `freeze_tiles`, i.e. the merge is all or nothing.
+/// ```
+/// use rtile::prelude::*;
+///
+/// let mut library = TileStore::new();
+/// tp!(library => header, "// generated by @{tool}");
+/// tp!(library => tool, "library");
+///
+/// tp!(tool, "app");
+/// assert_eq!(merge_store(library.export(), ConflictPolicy::Error), Err(vec!["tool".to_string()]));
+/// assert_eq!(ts!("@{header}"), "");
+///
+/// merge_store(library.export(), ConflictPolicy::Skip).unwrap();
+/// assert_eq!(ts!("@{header}"), "// generated by app");
+///
+/// merge_store(library.export(), ConflictPolicy::Overwrite).unwrap();
+/// assert_eq!(ts!("@{header}"), "// generated by library");
+/// ```
+pub fn merge_store(
+    exported: HashMap<String, RTile>,
+    policy: ConflictPolicy,
+) -> std::result::Result<(), Vec<String>> {
+    TL_STORE.with_borrow_mut(|v| v.merge(exported, policy))
+}
+
+/// freeze_tiles, used to freeze all the non blank tiles persisted in the tls (thread local storage), typically once the setup of the templates is done
+///
+/// any later attempt to persist (tp!, stp!, ...) or to remove a frozen tile panics, instead of silently overwriting the template.
+/// new tiles can still be persisted and the blank tiles can still be filled in. `clear_tiles` removes the frozen tiles as well.
+/// ```should_panic
+/// use rtile::prelude::*;
+///
+/// tp!(template, "Hello @{name}");
+/// freeze_tiles();
+/// tp!(name, "Alice");
+/// assert_eq!(ts!("@{template}"), "Hello Alice");
+///
+/// // panics, as the template is frozen
+/// tp!(template, "Bye @{name}");
+/// ```
+pub fn freeze_tiles() {
+    TL_STORE.with_borrow_mut(|v| v.freeze());
+}
+
+/// get_cache_stats, used to inspect the memoized expansions of the inner tiles of the tls (thread local storage), see `TileStore::cache_stats`
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(greeting, "Hello @{person}, @{signature}");
+/// tp!(signature, "regards, @{sender}");
+/// tp!(sender, "the team");
+/// clear_cache();
+/// for person in ["Alice", "Bob", "Carol"] {
+///     tp!(person, person);
+///     ts!("@{greeting}");
+/// }
+/// let stats = get_cache_stats();
+/// assert!(stats.hits >= 2);
+/// assert!(stats.hit_rate() > 0.0);
+/// ```
+pub fn get_cache_stats() -> CacheStats {
+    TL_STORE.with_borrow(|v| v.cache_stats())
+}
+
+/// clear_cache, used to drop the memoized expansions of the inner tiles of the tls (thread local storage) and to reset their statistics
+pub fn clear_cache() {
+    TL_STORE.with_borrow_mut(|v| v.clear_cache());
+}
+
+/// walk_dependencies, used to walk the inner tiles of the given tile in the tls (thread local storage) depth first, reporting them to the visitor
+///
+/// the walk runs over a fork of the store, so the visitor is free to use the tls.
+/// ```
+/// use rtile::prelude::*;
+///
+/// struct Depths(Vec<(String, usize)>);
+///
+/// impl TileVisitor for Depths {
+///     fn enter(&mut self, name: &str, _tile: &RTile, parents: &[String]) -> bool {
+///         self.0.push((name.to_string(), parents.len()));
+///         true
+///     }
+/// }
+///
+/// tp!(page, "@{header}\n@{body}");
+/// tp!(header, "@{title}");
+/// tp!(title, "rtile");
+/// tp!(body, "@{title} and @{footer}");
+/// let mut depths = Depths(vec![]);
+/// walk_dependencies(&t!("@{page}"), &mut depths);
+/// let expected = [("page", 0), ("header", 1), ("title", 2), ("body", 1), ("footer", 2)];
+/// assert_eq!(depths.0, expected.map(|(name, depth)| (name.to_string(), depth)));
+/// ```
+pub fn walk_dependencies(tile: &RTile, visitor: &mut impl TileVisitor) {
+    let store = TL_STORE.with_borrow_mut(|v| v.fork());
+    store.walk_dependencies(tile, visitor);
+}
+
+/// topo_order, used to get the names of the tile persisted in the tls (thread local storage) with the given name and of its inner tiles,
+/// every tile after the inner tiles it uses, e.g. to define the helpers of a generated script before their use.
+///
+/// the order follows the placeholders of the raw tiles, so it is the same on every run. the tiles bound to the placeholders are not a part of it,
+/// and a tile that isn't persisted has no order.
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(script, "@{main}");
+/// tp!(main, "main() { @{greet}; @{log} }");
+/// tp!(greet, "greet() { @{log}; }");
+/// tp!(log, "log() { :; }");
+/// assert_eq!(topo_order("script").unwrap(), vec!["log", "greet", "main", "script"]);
+/// ```
+pub fn topo_order(name: &str) -> std::result::Result<Vec<String>, CycleError> {
+    TL_STORE.with_borrow(|v| v.topo_order(name))
+}
+
+/// set_store_capacity, used to limit the number of tiles of the tls (thread local storage), the least recently used tiles
+/// which are not pinned are evicted beyond the capacity, None removes the limit, see `TileStore::set_capacity`
+/// ```
+/// use rtile::prelude::*;
+///
+/// set_store_capacity(Some(3));
+/// tp!(document, "@{title}: @{summary}");
+/// pin_tile("document");
+/// for id in 0..100 {
+///     tq!(&format!("document_{}", id), format!("document {}", id));
+/// }
+/// tp!(title, "Report");
+/// tp!(summary, "done");
+/// assert_eq!(ts!("@{document}"), "Report: done");
+/// assert!(with_default_store(|store| store.len()) <= 3);
+/// set_store_capacity(None);
+/// ```
+pub fn set_store_capacity(capacity: Option<usize>) {
+    TL_STORE.with_borrow_mut(|v| v.set_capacity(capacity));
+}
+
+/// pin_tile, used to keep the tile with the given name in the tls (thread local storage) even if the store is bounded, see `set_store_capacity`
+pub fn pin_tile(name: &str) {
+    TL_STORE.with_borrow_mut(|v| v.pin(name));
+}
+
+/// unpin_tile, used to allow the eviction of a pinned tile of the tls (thread local storage), see `pin_tile`
+pub fn unpin_tile(name: &str) {
+    TL_STORE.with_borrow_mut(|v| v.unpin(name));
+}
+
+/// set_dynamic_tile, used to persist a tile computed by the closure in the tls (thread local storage), the closure is called whenever a tile depending on it is rendered
+///
+/// the closure is called while the tls isn't borrowed, so it can read, persist and render the tiles of the tls, see `TileStore::set_dynamic`
+/// ```
+/// use rtile::prelude::*;
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+///
+/// let level = Rc::new(Cell::new("info"));
+/// let current = level.clone();
+/// set_dynamic_tile("log_level", move || t!(current.get()));
+/// tp!(config, "level = @{log_level}");
+/// assert_eq!(ts!("@{config}"), "level = info");
+/// level.set("debug");
+/// assert_eq!(ts!("@{config}"), "level = debug");
+/// ```
+pub fn set_dynamic_tile(name: &str, f: impl Fn() -> RTile + 'static) {
+    TL_STORE.with_borrow_mut(|v| v.set_dynamic(name, f));
+}
+
+/// declare_inputs, used to declare the inputs (the placeholders) expected by a template persisted in the tls (thread local storage), see `check_inputs`
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(greet, "@{salutation} @{name}!");
+/// declare_inputs("greet", ["name", "salutation"]);
+/// tp!(name, "Alice");
+/// let report = check_inputs("greet");
+/// assert_eq!(report.unfilled, vec!["salutation"]);
+/// tp!(salutation, "Hello");
+/// assert!(check_inputs("greet").is_ok());
+/// ```
+pub fn declare_inputs<I, T>(name: &str, inputs: I)
+where
+    I: IntoIterator<Item = T>,
+    T: Into<String>,
+{
+    TL_STORE.with_borrow_mut(|v| v.declare_inputs(name, inputs));
+}
+
+/// check_inputs, used to report the differences between the placeholders of a template and its declared inputs before rendering it
+///
+/// the placeholders which are used but not declared are reported as `extra`, the declared inputs which are not used as `unused`,
+/// and the declared inputs which are not persisted or are blank as `unfilled`
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(letter, "Dear @{recipient}, @{body}");
+/// declare_inputs("letter", ["recipient", "signature"]);
+/// tp!(recipient, "Bob");
+/// let report = check_inputs("letter");
+/// assert_eq!(report.extra, vec!["body"]);
+/// assert_eq!(report.unused, vec!["signature"]);
+/// assert_eq!(report.unfilled, vec!["signature"]);
+/// assert!(!report.is_ok());
+/// ```
+pub fn check_inputs(name: &str) -> InputReport {
+    TL_STORE.with_borrow(|v| v.check_inputs(name))
+}
+
+/// with_default_store, used to access the tls (thread local storage) as a TileStore
+///
+/// the macros must not be used within the closure, as the store is borrowed for the duration of the closure
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(planet, "World");
+/// let mut store = with_default_store(|store| store.clone());
+/// store.set("planet", t!("Mars"));
+/// assert_eq!(ts!(store => "Hello @{planet}"), "Hello Mars");
+/// assert_eq!(ts!("Hello @{planet}"), "Hello World");
+/// assert!(with_default_store(|store| store.contains("planet")));
+/// ```
+pub fn with_default_store<R>(f: impl FnOnce(&mut TileStore) -> R) -> R {
+    TL_STORE.with_borrow_mut(f)
+}
+
+#[doc(hidden)]
+pub fn detached<R>(f: impl FnOnce() -> R) -> R {
+    struct DetachedGuard(bool);
+    impl Drop for DetachedGuard {
+        fn drop(&mut self) {
+            TL_DETACHED.set(self.0);
+        }
+    }
+
+    let _guard = DetachedGuard(TL_DETACHED.replace(true));
+    f()
+}
+
+/// with_prefix, used to namespace every tile persisted within the closure with the given prefix
+///
+/// inside the closure, the tiles persisted using the macros (tp!, tq!, kp!, stp!, ...) are stored as `{prefix}{name}`,
+/// and every lookup of a tile (`@{name}`, gtp!, gtq!, remove_tile) first tries the prefixed name and then the name as it is.
+/// the prefixes of nested calls are concatenated, and a lookup tries the prefixes from the innermost call outward, e.g. within
+/// `a_` and then `b_` the names `a_b_name`, `a_name` and `name`.
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(title, "Company");
+/// let report = with_prefix("payroll_", || {
+///     tp!(header, "Payroll of @{title}");
+///     tp!(title, "ACME Inc");
+///     ts!("@{header}")
+/// });
+/// assert_eq!(report, "Payroll of ACME Inc");
+/// assert_eq!(ts!("@{title} / @{payroll_title}"), "Company / ACME Inc");
+/// assert!(gtp!(header).is_none());
+/// ```
+pub fn with_prefix<R>(prefix: &str, f: impl FnOnce() -> R) -> R {
+    struct PrefixGuard;
+    impl Drop for PrefixGuard {
+        fn drop(&mut self) {
+            TL_NAME_PREFIXES.with_borrow_mut(|v| v.pop());
+        }
+    }
+
+    TL_NAME_PREFIXES.with_borrow_mut(|v| v.push(prefix.to_string()));
+    let _guard = PrefixGuard;
+    f()
+}
+
+// the bindings of the placeholders, i.e. `@{tile with name=other}`, are active while the tile is expanded
+pub(crate) fn with_placeholder_bindings<R>(
+    bindings: &[(String, String)],
+    f: impl FnOnce() -> R,
+) -> R {
+    struct BindingsGuard(usize);
+    impl Drop for BindingsGuard {
+        fn drop(&mut self) {
+            TL_PLACEHOLDER_BINDINGS.with_borrow_mut(|v| v.truncate(self.0));
+        }
+    }
+
+    let _guard = BindingsGuard(TL_PLACEHOLDER_BINDINGS.with_borrow(|v| v.len()));
+    TL_PLACEHOLDER_BINDINGS.with_borrow_mut(|v| v.extend(bindings.iter().cloned()));
+    f()
+}
+
+// a bound name is looked up again in the outer bindings only, so the arguments can be forwarded
+fn bound_tile_name(tile_name: String) -> String {
+    TL_PLACEHOLDER_BINDINGS.with_borrow(|v| {
+        let mut tile_name = tile_name;
+        let mut scope = v.len();
+        while let Some(index) = v[..scope].iter().rposition(|(name, _)| *name == tile_name) {
+            tile_name = v[index].1.clone();
+            scope = index;
+        }
+        tile_name
+    })
+}
+
+pub(crate) fn active_name_prefix() -> String {
+    TL_NAME_PREFIXES.with_borrow(|v| v.concat())
+}
+
+// the prefixes of the enclosing `with_prefix` calls, the innermost (i.e. all of them concatenated) first, e.g. `a_b_` and then `a_`
+pub(crate) fn enclosing_name_prefixes() -> Vec<String> {
+    TL_NAME_PREFIXES.with_borrow(|v| (1..=v.len()).rev().map(|n| v[..n].concat()).collect())
+}
+
+pub(crate) fn scoped_tile_name(tile_name: String) -> String {
+    let prefix = active_name_prefix();
+    if prefix.is_empty() {
+        tile_name
+    } else {
+        format!("{}{}", prefix, tile_name)
+    }
+}
+
+/// rename_placeholder_everywhere, used to rewrite `@{old}` to `@{new}` in every tile stored in the tls (thread local storage) and to rename the stored tile itself
+///
+/// returns the sorted names of the affected tiles, i.e. the tile named `old` (if present) and the tiles which refer to `@{old}`,
+/// including the placeholders with options, bindings or arguments, e.g. `@{old with name=other}`, the versioned placeholders, e.g.
+/// `@{old@^2}`, the mustache placeholders and the operands of the expressions, e.g. `@{old + 1}`. the versions of the tile persisted
+/// by tpv! are renamed along, and the renames are recorded in the event log as `StoreEventKind::Rename`.
+/// with `dry_run` set to true nothing is changed, only the affected tiles are returned.
+/// tiles which are not persisted (e.g. held in a local variable) are not rewritten.
+/// nothing is changed and `RTileError::TileExists` is returned, if a tile named `new` is already persisted, even with `dry_run`.
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(first_name, "Alice");
+/// tp!(greet, "Hello @{first_name}");
+/// tp!(bye, "Bye @{first_name}");
+///
+/// let affected = rename_placeholder_everywhere("first_name", "given_name", true);
+/// assert_eq!(affected, Ok(vec!["bye".to_string(), "first_name".to_string(), "greet".to_string()]));
+/// assert_eq!(sr!(gtp!(greet).unwrap()), "Hello @{first_name}");
+/// assert_eq!(
+///     rename_placeholder_everywhere("first_name", "greet", true),
+///     Err(RTileError::TileExists { name: "greet".to_string() })
+/// );
+///
+/// rename_placeholder_everywhere("first_name", "given_name", false).unwrap();
+/// assert_eq!(sr!(gtp!(greet).unwrap()), "Hello @{given_name}");
+/// assert_eq!(ts!("@{greet}, @{given_name}"), "Hello Alice, Alice");
+/// assert!(gtp!(first_name).is_none());
+/// ```
+#[track_caller]
+pub fn rename_placeholder_everywhere(
+    old: &str,
+    new: &str,
+    dry_run: bool,
+) -> std::result::Result<Vec<String>, RTileError> {
+    let location = std::panic::Location::caller();
+    TL_STORE.with_borrow_mut(|v| v.rename_placeholder_at(old, new, dry_run, location))
+}
+
+fn trim<I, T>(t1: I, do_trimming: bool) -> Vec<String>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<String>,
+{
+    let mut lns: Vec<String> = t1.into_iter().map(Into::into).collect();
+    if !do_trimming {
+        return lns;
+    }
+
+    // in place, as the tiles may have tens of thousands of lines
+    for ln in lns.iter_mut() {
+        ln.truncate(ln.trim_end().len());
+    }
+    let last = lns
+        .iter()
+        .rposition(|ln| !ln.is_empty())
+        .map_or(0, |idx| idx + 1);
+    lns.truncate(last);
+    let first = lns.iter().position(|ln| !ln.is_empty()).unwrap_or(0);
+    lns.drain(..first);
+    let left = lns
+        .iter()
+        .filter(|ln| !ln.is_empty())
+        .map(|ln| ln.len() - ln.trim_start().len())
+        .min()
+        .unwrap_or(0_usize);
+    if left > 0 {
+        for ln in lns.iter_mut() {
+            let idx = ln.char_indices().nth(left).map_or(ln.len(), |(idx, _)| idx);
+            ln.drain(..idx);
+        }
+    }
+    lns
+}
+
+/// the placeholder substituted with the SHA-256 of the rest of the output, see `RenderOptions::content_stamp`
+pub const CONTENT_SHA256_PLACEHOLDER: &str = "@{content_sha256}";
+
+/// stamp_content_sha256, used to substitute every `@{content_sha256}` of the rendered output with the SHA-256 (in lowercase hex)
+/// of the output without them, so a generated file can carry a stamp verified by `verify_content_sha256`, e.g. to detect the hand edits
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(stamp_name, "Person");
+/// let tile = t!("
+///     // @generated, do not edit, sha256: @{content_sha256}
+///     struct @{stamp_name};
+/// ");
+/// let options = RenderOptions {
+///     content_stamp: true,
+///     ..Default::default()
+/// };
+/// let output = tile.to_string_with_options(&options);
+/// assert_eq!(
+///     output,
+///     stamp_content_sha256("// @generated, do not edit, sha256: @{content_sha256}\nstruct Person;")
+/// );
+/// assert!(output.ends_with(" sha256: 65dc4bb7c7831de3617fec2588f5dda13b93449b3a4bad4a010daa79081d6ba4\nstruct Person;"));
+/// assert!(verify_content_sha256(&output));
+/// assert!(!verify_content_sha256(&output.replace("Person", "People")));
+/// ```
+pub fn stamp_content_sha256(output: &str) -> String {
+    if !output.contains(CONTENT_SHA256_PLACEHOLDER) {
+        return output.to_string();
+    }
+    let digest = sha256::sha256_hex(output.replace(CONTENT_SHA256_PLACEHOLDER, "").as_bytes());
+    output.replace(CONTENT_SHA256_PLACEHOLDER, &digest)
+}
+
+/// verify_content_sha256, used to check the stamps of the output of `stamp_content_sha256`, returns true if the output holds a SHA-256
+/// (in lowercase hex) of the output without it, i.e. the output is not edited since it was stamped
+pub fn verify_content_sha256(output: &str) -> bool {
+    let bytes = output.as_bytes();
+    let is_hex = |b: &u8| b.is_ascii_digit() || (b'a'..=b'f').contains(b);
+    let mut idx = 0;
+    while idx + 64 <= bytes.len() {
+        let run = bytes[idx..].iter().take_while(|b| is_hex(b)).count();
+        if run == 64 {
+            let stamp = &output[idx..idx + 64];
+            if sha256::sha256_hex(output.replace(stamp, "").as_bytes()) == stamp {
+                return true;
+            }
+        }
+        idx += run.max(1);
+    }
+    false
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+// fnv-1a, as the std hashers are not guaranteed to be stable across releases
+fn content_hash(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn append<I, T>(t1: &mut Vec<String>, t2: I)
+where
+    I: IntoIterator<Item = T>,
+    T: Into<String>,
+{
+    let t2: Vec<String> = t2.into_iter().map(Into::into).collect();
+    // a line appended to a single line is never padded, so the wide lines are not measured over and over
+    let is_padded = t1.len() > 1 || t2.len() > 1;
+    let w = if is_padded {
+        t1.iter().map(|s| display_width(s)).max().unwrap_or(0)
+    } else {
+        0
+    };
+    for (i, s) in t2.into_iter().enumerate() {
+        if i == t1.len() {
+            t1.push(String::new());
+        }
+        let ln = &mut t1[i];
+        let padding = if is_padded { w - display_width(ln) } else { 0 };
+        if ln.is_empty() && padding == 0 {
+            // nothing to the left, so the line is taken as is
+            *ln = s;
+        } else {
+            ln.reserve(padding + s.len());
+            ln.extend(std::iter::repeat_n(' ', padding));
+            ln.push_str(&s);
+        }
+    }
+}
+
+fn append_with_fill<I, T>(t1: &mut Vec<String>, t2: I, fill: char)
+where
+    I: IntoIterator<Item = T>,
+    T: Into<String>,
+{
+    let t2: Vec<String> = t2.into_iter().map(Into::into).collect();
+
+    let diff: i32 = t2.len() as i32 - t1.len() as i32;
+    if diff > 0 {
+        t1.extend(vec!["".to_owned(); diff as usize]);
+    }
+    let w = t1.iter().map(|s| display_width(s)).max().unwrap_or(0);
+    for (i, s) in t2.into_iter().enumerate() {
+        let padding = w - display_width(&t1[i]);
+        t1[i].extend(std::iter::repeat_n(fill, padding));
+        t1[i].push_str(&s);
+    }
+}
+
+// the characters of the line shown in the terminal, along with their byte indices, the escape sequences (e.g. colors, hyperlinks)
+// are skipped
+fn visible_chars(ln: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut chars = ln.char_indices();
+    std::iter::from_fn(move || {
+        while let Some((idx, c)) = chars.next() {
+            if c != '\x1b' {
+                return Some((idx, c));
+            }
+            match chars.next() {
+                // operating system command, e.g. the OSC 8 hyperlinks, terminated by BEL or ESC \
+                Some((_, ']')) => {
+                    while let Some((_, c)) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next().is_some()) {
+                            break;
+                        }
+                    }
+                }
+                // control sequence, e.g. the colors, terminated by a byte in the range @ to ~
+                Some((_, '[')) => {
+                    for _ in chars.by_ref().take_while(|(_, c)| !('@'..='~').contains(c)) {}
+                }
+                _ => {}
+            }
+        }
+        None
+    })
+}
+
+// the number of columns the line takes in the terminal, the escape sequences (e.g. colors, hyperlinks) take none
+fn display_width(ln: &str) -> usize {
+    visible_chars(ln).count()
+}
+
+// pads the line with spaces on the right, to make it as wide as the given width
+fn pad_to_width(ln: &str, w: usize) -> String {
+    let padding = w.saturating_sub(display_width(ln));
+    format!("{}{}", ln, " ".repeat(padding))
+}
+
+// the relative path with `/` separators, as recorded in the reports and the manifests
+fn slash_path(path: &std::path::Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// breaks the line into lines of at most the given width, at the spaces where possible, the continuation lines keep the indentation of the line
+fn hard_wrap(ln: &str, max_width: usize) -> Vec<String> {
+    let max_width = max_width.max(1);
+    if display_width(ln) <= max_width {
+        return vec![ln.to_string()];
+    }
+    let indent = &ln[..ln.len() - ln.trim_start().len()];
+    let indent = if indent.chars().count() < max_width {
+        indent
+    } else {
+        ""
+    };
+    let mut lns = vec![];
+    let mut rest = ln;
+    let mut prefix = "";
+    loop {
+        let available = max_width - prefix.chars().count();
+        if display_width(rest) <= available {
+            lns.push(format!("{}{}", prefix, rest));
+            return lns;
+        }
+        // the line is cut by the columns, at the last space within them or else at the character just past them, so the escape
+        // sequences are never broken
+        let visible: Vec<(usize, char)> = visible_chars(rest).take(available + 1).collect();
+        let limit = visible.get(available).map_or(rest.len(), |(idx, _)| *idx);
+        let space = visible.iter().rposition(|(_, c)| *c == ' ');
+        let (head, tail) = match space {
+            Some(pos) if visible[..pos].iter().any(|(_, c)| !c.is_whitespace()) => {
+                rest.split_at(visible[pos].0)
+            }
+            _ => rest.split_at(limit),
+        };
+        lns.push(format!("{}{}", prefix, head.trim_end()));
+        rest = tail.trim_start();
+        prefix = indent;
+        if rest.is_empty() {
+            return lns;
+        }
+    }
+}
+
+// re-expresses the leading white spaces of the lines in the given style, the indentation unit of the lines is the narrowest
+// indentation of spaces (a tab is a level), the columns left over by the unit are kept as spaces, e.g. for the alignment
+fn reindent(lns: Vec<String>, style: IndentStyle) -> Vec<String> {
+    let leading = |ln: &str| {
+        let indent = &ln[..ln.len() - ln.trim_start_matches([' ', '\t']).len()];
+        let tabs = indent.matches('\t').count();
+        (indent.len(), tabs, indent.len() - tabs)
+    };
+    let unit = lns
+        .iter()
+        .filter(|ln| !ln.trim().is_empty())
+        .map(|ln| leading(ln).2)
+        .filter(|spaces| *spaces > 0)
+        .min();
+    lns.into_iter()
+        .map(|ln| {
+            let (len, tabs, spaces) = leading(&ln);
+            if len == 0 || ln.trim().is_empty() {
+                return ln;
+            }
+            let (levels, remainder) = match unit {
+                Some(unit) => (tabs + spaces / unit, spaces % unit),
+                None => (tabs, 0),
+            };
+            format!(
+                "{}{}{}",
+                style.unit().repeat(levels),
+                " ".repeat(remainder),
+                &ln[len..]
+            )
+        })
+        .collect()
+}
+
+#[derive(PartialEq)]
+enum CollapsePolicy {
+    Keep,
+    SkipIfBlank,
+}
+
+#[derive(PartialEq)]
+enum PlaceholderKind {
+    Tile,
+    Expression(Box<expr::Expression>),
+    Definition,
+    End,
+    // @{params name, title}, the parameters of a parameterized tile
+    Parameters(Vec<String>),
+    #[cfg(feature = "mustache")]
+    Comment,
+}
+
+struct Placeholder {
+    name: String,
+    bindings: Vec<(String, String)>,
+    kind: PlaceholderKind,
+    align: Option<usize>,
+    collapse: CollapsePolicy,
+}
+
+impl Placeholder {
+    // @{name} or @{name:option,option=value} or @{name with other=tile,...} or @{name(other=tile,...)}, or the markers @{def name} and
+    // @{end} of an inline definition, or the declaration @{params name,...} of the parameters of the tile, the name may be an expression
+    // instead, e.g. @{count + 1}, see the expr module
+    fn parse(text: &str) -> Self {
+        Self::try_parse(text).unwrap_or_else(|message| panic!("{}", message))
+    }
+
+    // the message of the panic of `parse` as the error, e.g. for the analysis of a template
+    fn try_parse(text: &str) -> std::result::Result<Self, String> {
+        #[cfg(feature = "mustache")]
+        if let Some(comment) = text.strip_prefix('!') {
+            // {{! comment}}
+            return Ok(Placeholder {
+                name: comment.to_string(),
+                bindings: vec![],
+                kind: PlaceholderKind::Comment,
+                align: None,
+                collapse: CollapsePolicy::Keep,
+            });
+        }
+        if let Some(name) = text.strip_prefix("def ") {
+            return Ok(Placeholder {
+                name: name.trim().to_string(),
+                bindings: vec![],
+                kind: PlaceholderKind::Definition,
+                align: None,
+                collapse: CollapsePolicy::Keep,
+            });
+        }
+        if let Some(parameters) = text.strip_prefix("params ") {
+            let parameters: Vec<String> = parameters
+                .split(',')
+                .map(|parameter| parameter.trim().to_string())
+                .collect();
+            if parameters
+                .iter()
+                .any(|parameter| parameter.is_empty() || parameter.contains(char::is_whitespace))
+            {
+                return Err(format!("invalid parameter in @{{{}}} expression", text));
+            }
+            return Ok(Placeholder {
+                name: String::new(),
+                bindings: vec![],
+                kind: PlaceholderKind::Parameters(parameters),
+                align: None,
+                collapse: CollapsePolicy::Keep,
+            });
+        }
+        let (name, options) = split_once_outside_quotes(text, ":").unwrap_or((text, ""));
+        let (name, bindings) = split_once_outside_quotes(name, " with ").unwrap_or((name, ""));
+        let (name, arguments) = split_call(name).unwrap_or((name, ""));
+        let parse_bindings = |bindings: &str, kind: &str| {
+            bindings
+                .split(',')
+                .filter(|binding| !binding.trim().is_empty())
+                .map(|binding| match binding.split_once('=') {
+                    Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => {
+                        Ok((key.trim().to_string(), value.trim().to_string()))
+                    }
+                    _ => Err(format!("invalid {} in @{{{}}} expression", kind, text)),
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()
+        };
+        let mut arguments = parse_bindings(arguments, "argument")?;
+        arguments.extend(parse_bindings(bindings, "binding")?);
+        let bindings = arguments;
+        let mut placeholder = Placeholder {
+            name: name.to_string(),
+            bindings,
+            kind: if name == "end" {
+                PlaceholderKind::End
+            } else if expr::is_expression(name) {
+                PlaceholderKind::Expression(Box::new(expr::Expression::parse(name)?))
+            } else {
+                PlaceholderKind::Tile
+            },
+            align: None,
+            collapse: CollapsePolicy::Keep,
+        };
+        for option in options.split(',').map(|option| option.trim()) {
+            let (key, value) = option.split_once('=').unwrap_or((option, ""));
+            match key.trim() {
+                "" => {}
+                "skip_if_blank" => placeholder.collapse = CollapsePolicy::SkipIfBlank,
+                "align" => {
+                    placeholder.align =
+                        Some(value.trim().parse().map_err(|_| {
+                            format!("invalid align value in @{{{}}} expression", text)
+                        })?)
+                }
+                _ => return Err(format!("unknown option in @{{{}}} expression", text)),
+            }
+        }
+        Ok(placeholder)
+    }
+
+    // false for the markers of the inline definitions and for the names defined inline
+    fn refers_to_tile(&self, definitions: &HashSet<String>) -> bool {
+        self.kind == PlaceholderKind::Tile && !definitions.contains(&self.name)
+    }
+
+    // the names of the tiles the placeholder refers to, i.e. its name, or the operands of its expression, other than the names defined inline
+    fn inner_tile_names(&self, definitions: &HashSet<String>) -> Vec<String> {
+        match &self.kind {
+            PlaceholderKind::Expression(expression) => expression
+                .operands()
+                .into_iter()
+                .filter(|name| !definitions.contains(name))
+                .collect(),
+            _ if self.refers_to_tile(definitions) => vec![self.name.clone()],
+            _ => vec![],
+        }
+    }
+}
+
+// the name and the arguments of the call of a parameterized tile, e.g. `greet(name=alice, title=mr)` or `greet()`, the calls of the
+// functions of the expressions, e.g. `len(items)`, have no `=`
+fn split_call(text: &str) -> Option<(&str, &str)> {
+    let (name, rest) = text.trim().split_once('(')?;
+    let arguments = rest.strip_suffix(')')?;
+    let is_name = !name.is_empty() && !name.contains(|c: char| c.is_whitespace() || c == '"');
+    let is_arguments = (arguments.trim().is_empty() || arguments.contains('='))
+        && !arguments.contains(['(', ')', '"']);
+    (is_name && is_arguments && !expr::is_function(name)).then_some((name, arguments))
+}
+
+// splits the text at the first occurrence of the pattern which is not inside a string of an expression
+fn split_once_outside_quotes<'a>(text: &'a str, pattern: &str) -> Option<(&'a str, &'a str)> {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (idx, c) in text.char_indices() {
+        if quoted {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            quoted = true;
+        } else if text[idx..].starts_with(pattern) {
+            return Some((&text[..idx], &text[idx + pattern.len()..]));
+        }
+    }
+    None
+}
+
+fn local_definitions(lns: &[String]) -> HashSet<String> {
+    let mut definitions = HashSet::new();
+    for ln in lns {
+        for placeholder in placeholders(ln) {
+            if placeholder.kind == PlaceholderKind::Definition {
+                definitions.insert(placeholder.name);
+            }
+        }
+    }
+    definitions
+}
+
+// replaces every `@{def name}body@{end}` by its body, and returns the bodies by their names in the order of their definitions
+fn expand_definitions(s: &str) -> (String, Vec<(String, String)>) {
+    let mut result = String::new();
+    let mut definitions = vec![];
+    let mut rest = s;
+    while let Some(start) = rest.find("@{def ") {
+        let name_end = rest[start..]
+            .find('}')
+            .unwrap_or_else(|| panic!("unfinished @{{}} expression"))
+            + start;
+        let body_end = rest[name_end..]
+            .find("@{end}")
+            .unwrap_or_else(|| panic!("unfinished @{{def}} expression"))
+            + name_end;
+        let name = rest[start + 6..name_end].trim().to_string();
+        let body = &rest[name_end + 1..body_end];
+        result.push_str(&rest[..start]);
+        result.push_str(body);
+        definitions.push((name, body.to_string()));
+        rest = &rest[body_end + 6..];
+    }
+    result.push_str(rest);
+    (result, definitions)
+}
+
+fn append_placeholder_value(curr: &mut Vec<String>, placeholder: &Placeholder, value: &str) {
+    let lns: Vec<&str> = value.split('\n').collect();
+    match placeholder.align {
+        Some(column) if lns.len() > 1 => {
+            append(curr, vec![lns[0]]);
+            let diff: i32 = lns.len() as i32 - curr.len() as i32;
+            if diff > 0 {
+                curr.extend(vec!["".to_owned(); diff as usize]);
+            }
+            for (i, s) in lns.into_iter().enumerate().skip(1) {
+                curr[i] = format!("{}{}", pad_to_width(&curr[i], column), s);
+            }
+        }
+        _ => append(curr, lns),
+    }
+}
+
+// the placeholders of a line in order, together with their spans, see `placeholder_spans`
+struct PlaceholderSpans<'a> {
+    ln: &'a str,
+    cursor: usize,
+}
+
+impl<'a> Iterator for PlaceholderSpans<'a> {
+    // the start of the placeholder, its end past the closing brace and its text
+    type Item = (usize, usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, span) = next_placeholder_span(&self.ln[self.cursor..])?;
+        let (end, text) = span.unwrap_or_else(|| panic!("unfinished @{{}} expression"));
+        let start = self.cursor + start;
+        self.cursor += end;
+        Some((start, self.cursor, text))
+    }
+}
+
+// the single scanner of the placeholders of a line, it panics on an unfinished placeholder
+fn placeholder_spans(ln: &str) -> PlaceholderSpans<'_> {
+    PlaceholderSpans { ln, cursor: 0 }
+}
+
+fn placeholders(ln: &str) -> impl Iterator<Item = Placeholder> + '_ {
+    placeholder_spans(ln).map(|(_, _, text)| Placeholder::parse(text))
+}
+
+// the start of the next `@{`, searched by its `@` byte, which the standard library finds with memchr
+fn find_placeholder_start(ln: &str) -> Option<usize> {
+    let bytes = ln.as_bytes();
+    let mut from = 0;
+    while let Some(i) = ln[from..].find('@') {
+        let at = from + i;
+        if bytes.get(at + 1) == Some(&b'{') {
+            return Some(at);
+        }
+        from = at + 1;
+    }
+    None
+}
+
+// the start of the next placeholder together with its end (past the closing brace) and text, the end is None for an unfinished placeholder
+fn next_placeholder_span(ln: &str) -> Option<(usize, Option<(usize, &str)>)> {
+    let start = find_placeholder_start(ln);
+    #[cfg(feature = "mustache")]
+    if let Some(span) = next_mustache_span(&ln[..start.unwrap_or(ln.len())], ln) {
+        return Some(span);
+    }
+    let start = start?;
+    let text = &ln[start + 2..];
+    Some((
+        start,
+        close_placeholder(text, &mut BraceScan::default())
+            .map(|end| (start + 2 + end, &text[..end - 1])),
+    ))
+}
+
+// the first mustache placeholder starting in the head (the text before the next `@{`) of the line, any other `{{...}}` is a text as
+// it is, e.g. the braces of the generated code or the `{{ a: u8 }}` of a format string
+#[cfg(feature = "mustache")]
+fn next_mustache_span<'a>(head: &str, ln: &'a str) -> Option<(usize, Option<(usize, &'a str)>)> {
+    let mut from = 0;
+    while let Some(i) = head[from..].find("{{") {
+        let mustache_start = from + i;
+        from = mustache_start + 2;
+        for (open, close) in [("{{{", "}}}"), ("{{", "}}")] {
+            if !ln[mustache_start..].starts_with(open) {
+                continue;
+            }
+            let text_start = mustache_start + open.len();
+            let Some(i) = ln[text_start..].find(close) else {
+                continue;
+            };
+            let text = ln[text_start..text_start + i].trim();
+            if is_mustache_placeholder(text, open.len() == 3) {
+                return Some((mustache_start, Some((text_start + i + close.len(), text))));
+            }
+        }
+    }
+    None
+}
+
+// {{name}}, {{name:skip_if_blank}}, {{{name}}} or {{! comment}}, where the name is made of the alphanumeric characters, `_` and `.`
+#[cfg(feature = "mustache")]
+fn is_mustache_placeholder(text: &str, triple: bool) -> bool {
+    let is_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+    };
+    if triple {
+        return is_name(text);
+    }
+    if text.starts_with('!') {
+        return true;
+    }
+    match text.split_once(':') {
+        Some((name, option)) => is_name(name) && option == "skip_if_blank",
+        None => is_name(text),
+    }
+}
+
+// the lines of a tile being constructed, with the raw blocks escaped, the placeholders spanning lines joined, the included files
+// persisted as tiles and the white spaces eaten by the trim markers, in this order
+fn prepare_lines(lns: Vec<String>) -> Vec<String> {
+    apply_trim_markers(include_files(join_multiline_placeholders(
+        escape_raw_blocks(lns),
+    )))
+}
+
+// the lines with every `@{include:path}` replaced by the placeholder of the file, i.e. `@{path}`, the file (with its own includes)
+// is persisted as a tile named after its path relative to the template root, see `set_template_root`
+fn include_files(lns: Vec<String>) -> Vec<String> {
+    if !lns.iter().any(|ln| ln.contains("@{include:")) {
+        return lns;
+    }
+    lns.into_iter()
+        .map(|ln| {
+            let mut res = String::with_capacity(ln.len());
+            let mut rest = ln.as_str();
+            while let Some((start, Some((end, text)))) = next_placeholder_span(rest) {
+                res.push_str(&rest[..start]);
+                match text.strip_prefix("include:") {
+                    Some(path) if rest[start..].starts_with("@{") => {
+                        res.push_str(&format!("@{{{}}}", include_file(path)));
+                    }
+                    _ => res.push_str(&rest[start..end]),
+                }
+                rest = &rest[end..];
+            }
+            res.push_str(rest);
+            res
+        })
+        .collect()
+}
+
+// persists the file as a tile and returns its name, i.e. its path relative to the template root, with `/` separators
+fn include_file(path: &str) -> String {
+    struct IncludeGuard;
+    impl Drop for IncludeGuard {
+        fn drop(&mut self) {
+            TL_INCLUDES.with_borrow_mut(|v| v.pop());
+        }
+    }
+
+    let root = TL_TEMPLATE_ROOT
+        .with_borrow(|v| v.clone())
+        .unwrap_or_else(|| panic!("no template root is set for @{{include:{}}}", path));
+    let mut components: Vec<&str> = vec![];
+    for component in Path::new(path.trim()).components() {
+        match component {
+            Component::Normal(component) => components.push(
+                component
+                    .to_str()
+                    .unwrap_or_else(|| panic!("invalid path in @{{include:{}}}", path)),
+            ),
+            Component::CurDir => {}
+            Component::ParentDir if components.pop().is_some() => {}
+            _ => panic!(
+                "the path of @{{include:{}}} is not within the template root",
+                path
+            ),
+        }
+    }
+    let name = components.join("/");
+    if let Some(idx) = TL_INCLUDES.with_borrow(|v| v.iter().position(|included| *included == name))
+    {
+        let mut cycle = TL_INCLUDES.with_borrow(|v| v[idx..].to_vec());
+        cycle.push(name);
+        panic!(
+            "the include of {} is a cycle: {}",
+            cycle[0],
+            cycle.join(" -> ")
+        );
+    }
+    let text = std::fs::read_to_string(root.join(&name))
+        .unwrap_or_else(|error| panic!("failed to include {}: {}", name, error));
+    TL_INCLUDES.with_borrow_mut(|v| v.push(name.clone()));
+    let _guard = IncludeGuard;
+    let tile = RTile::construct_from_str(&text);
+    with_default_store(|store| store.set(&name, tile));
+    name
+}
+
+// the lines of every `@{raw}...@{endraw}` block turned into the string expressions, e.g. `@{"@{name}"}`, so its `@{` sequences are
+// emitted verbatim, and a line left blank by the markers is dropped
+fn escape_raw_blocks(lns: Vec<String>) -> Vec<String> {
+    if !lns.iter().any(|ln| ln.contains("@{raw}")) {
+        return lns;
+    }
+    let mut res = Vec::with_capacity(lns.len());
+    let mut raw = false;
+    for ln in lns.iter().flat_map(|ln| ln.split('\n')) {
+        let mut escaped = String::with_capacity(ln.len());
+        let mut has_marker = false;
+        let mut rest = ln;
+        loop {
+            let marker = if raw { "@{endraw}" } else { "@{raw}" };
+            let Some(idx) = rest.find(marker) else {
+                break;
+            };
+            match raw {
+                true => escaped.push_str(&raw_text(&rest[..idx])),
+                false => escaped.push_str(&rest[..idx]),
+            }
+            rest = &rest[idx + marker.len()..];
+            raw = !raw;
+            has_marker = true;
+        }
+        match raw {
+            true => escaped.push_str(&raw_text(rest)),
+            false => escaped.push_str(rest),
+        }
+        if !(has_marker && escaped.trim().is_empty()) {
+            res.push(escaped);
+        }
+    }
+    if raw {
+        panic!("unfinished @{{raw}} expression");
+    }
+    res
+}
+
+// the text as a string expression, its leading white spaces are kept outside, for the trimming of the tile
+fn raw_text(text: &str) -> String {
+    let body = text.trim_start();
+    if body.is_empty() {
+        return text.to_string();
+    }
+    format!(
+        "{}@{{\"{}\"}}",
+        &text[..text.len() - body.len()],
+        body.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+// the lines with every placeholder spanning lines (e.g. an argument list written one argument per line) joined into a single line,
+// the line breaks inside the placeholder and the white spaces around them become a single space. The closing brace is found by
+// tracking the depth of the braces across the lines, an unfinished placeholder which is never closed is left as it is
+fn join_multiline_placeholders(lns: Vec<String>) -> Vec<String> {
+    if !lns
+        .iter()
+        .any(|ln| unfinished_placeholder_start(ln).is_some())
+    {
+        return lns;
+    }
+    let mut res = Vec::with_capacity(lns.len());
+    let mut lns = lns.into_iter();
+    while let Some(mut ln) = lns.next() {
+        // a joined line may end inside the next placeholder spanning lines
+        while let Some(start) = unfinished_placeholder_start(&ln) {
+            let mut scan = BraceScan::default();
+            let mut inner = match close_placeholder(&ln[start + 2..], &mut scan) {
+                Some(_) => break,
+                None => vec![ln[start + 2..].to_string()],
+            };
+            let mut spanned = vec![];
+            let rest = loop {
+                let Some(next) = lns.next() else {
+                    break None;
+                };
+                if let Some(end) = close_placeholder(&next, &mut scan) {
+                    inner.push(next[..end - 1].to_string());
+                    break Some(next[end..].to_string());
+                }
+                inner.push(next.clone());
+                spanned.push(next);
+            };
+            let Some(rest) = rest else {
+                res.push(ln);
+                res.append(&mut spanned);
+                return res;
+            };
+            let mut joined = String::new();
+            for part in inner
+                .iter()
+                .map(|part| part.trim())
+                .filter(|part| !part.is_empty())
+            {
+                // no space inside the braces of a nested placeholder, e.g. an indirect reference
+                if !(joined.is_empty() || joined.ends_with('{') || part.starts_with('}')) {
+                    joined.push(' ');
+                }
+                joined.push_str(part);
+            }
+            ln = format!("{}@{{{}}}{}", &ln[..start], joined, rest);
+        }
+        res.push(ln);
+    }
+    res
+}
+
+// the lines without the white spaces (including the line breaks) eaten by the trim markers of the placeholders, i.e. `@{-name}`
+// eats the ones before the placeholder and `@{name-}` the ones after it, the markers are dropped
+fn apply_trim_markers(lns: Vec<String>) -> Vec<String> {
+    if !lns.iter().any(|ln| ln.contains("@{-") || ln.contains("-}")) {
+        return lns;
+    }
+    let text = lns.join("\n");
+    let mut res = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    let mut eat_after = false;
+    while let Some((start, Some((end, text)))) = next_placeholder_span(rest) {
+        let before = &rest[..start];
+        res.push_str(if eat_after {
+            before.trim_start()
+        } else {
+            before
+        });
+        eat_after = false;
+        let placeholder = &rest[start..end];
+        rest = &rest[end..];
+        if !placeholder.starts_with("@{") {
+            // a mustache placeholder
+            res.push_str(placeholder);
+            continue;
+        }
+        if expr::is_expression(text) {
+            // e.g. `@{-count * 2}`, where `-` is the negation
+            res.push_str(placeholder);
+            continue;
+        }
+        let mut text = text;
+        if let Some(trimmed) = text.strip_prefix('-').filter(|text| !text.is_empty()) {
+            res.truncate(res.trim_end().len());
+            text = trimmed;
+        }
+        if let Some(trimmed) = text.strip_suffix('-').filter(|text| !text.is_empty()) {
+            eat_after = true;
+            text = trimmed;
+        }
+        res.push_str("@{");
+        res.push_str(text);
+        res.push('}');
+    }
+    res.push_str(if eat_after { rest.trim_start() } else { rest });
+    res.split('\n').map(str::to_string).collect()
+}
+
+// the start of the `@{` of the unfinished placeholder of the line, if any
+fn unfinished_placeholder_start(ln: &str) -> Option<usize> {
+    let mut cursor = 0;
+    while let Some((start, span)) = next_placeholder_span(&ln[cursor..]) {
+        match span {
+            Some((end, _)) => cursor += end,
+            None if ln[cursor + start..].starts_with("@{") => return Some(cursor + start),
+            None => return None,
+        }
+    }
+    None
+}
+
+// the state of the matching of the closing brace of a placeholder, carried over the lines of a placeholder spanning lines
+#[derive(Default)]
+struct BraceScan {
+    // the number of the braces opened after the `@{`, e.g. by the inner placeholder of an indirect reference
+    depth: usize,
+    quoted: bool,
+    escaped: bool,
+}
+
+// the end past the closing brace of the placeholder in the text (which follows its `@{`), None if the text ends inside the placeholder.
+// The nested braces are matched, and the braces in the strings of an expression (with their `\"` and `\\` escapes) are skipped,
+// so `@{name}}` is the placeholder `name` followed by a literal `}`, and `@{"}" + name}` is a single placeholder
+fn close_placeholder(text: &str, scan: &mut BraceScan) -> Option<usize> {
+    for (idx, b) in text.bytes().enumerate() {
+        if scan.quoted {
+            match b {
+                _ if scan.escaped => scan.escaped = false,
+                b'\\' => scan.escaped = true,
+                b'"' => scan.quoted = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => scan.quoted = true,
+            b'{' => scan.depth += 1,
+            b'}' if scan.depth == 0 => return Some(idx + 1),
+            b'}' => scan.depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+// the name of the selector tile of an indirect reference, i.e. `selector` for the placeholder @{@{selector}}
+fn indirect_selector(tile_name: &str) -> Option<&str> {
+    tile_name.strip_prefix("@{")?.strip_suffix('}')
+}
+
+fn is_blank_without_placeholders(ln: &str) -> bool {
+    let mut rest = ln;
+    while let Some((start, span)) = next_placeholder_span(rest) {
+        if !rest[..start].trim().is_empty() {
+            return false;
+        }
+        match span {
+            Some((end, _)) => rest = &rest[end..],
+            None => return false,
+        }
+    }
+    rest.trim().is_empty()
+}
+
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+pub struct RTile {
+    pub name: Option<String>,
+    pub lns: Vec<String>,
+    pub do_trimming: bool,
+    pub tags: Vec<Option<String>>,
+    pub parts: Parts,
+    pub marker: PhantomData<Rc<()>>,
+}
+
+// the tags annotate the lines, and the parts record how the tile was composed, neither is a part of the comparison of the tiles
+impl PartialEq for RTile {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.lns == other.lns && self.do_trimming == other.do_trimming
+    }
+}
+
+// the tiles a tile was composed from using `+` and `|`, kept for `stp_deep!`, it isn't a part of the comparison of the tiles
+#[doc(hidden)]
+#[derive(Debug, Clone, Default)]
+pub struct Parts(Option<Rc<Composition>>);
+
+impl PartialEq for Parts {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Composition {
+    // side by side using `+`, or one below the other using `|`
+    beside: bool,
+    tiles: Vec<RTile>,
+}
+
+impl Composition {
+    // false once the lines of the tile were changed after its composition
+    fn matches(&self, tile: &RTile) -> bool {
+        let mut lns: Vec<String> = vec![];
+        for part in &self.tiles {
+            if self.beside {
+                append(&mut lns, part.lns.iter().map(String::as_str));
+            } else {
+                lns.extend(part.lns.iter().cloned());
+            }
+        }
+        lns == tile.lns
+    }
+}
+
+impl RTile {
+    pub fn new_str(lns: Vec<&str>) -> Self {
+        let lns: Vec<String> = lns.iter().map(|&item| item.to_string()).collect();
+        let lns = prepare_lines(lns);
+        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
+        Self {
+            name: None,
+            lns: trim(lns, true),
+            do_trimming: true,
+            tags: vec![],
+            parts: Parts::default(),
+            marker: PhantomData::<Rc<()>>,
+        }
+    }
+
+    pub fn new(lns: Vec<String>) -> Self {
+        let lns = prepare_lines(lns);
+        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
+        Self {
+            name: None,
+            lns: trim(lns, true),
+            do_trimming: true,
+            tags: vec![],
+            parts: Parts::default(),
+            marker: PhantomData::<Rc<()>>,
+        }
+    }
+
+    pub fn construct_from_str(val: &str) -> Self {
+        let lns: Vec<&str> = val.split('\n').collect();
+        let lns: Vec<String> = lns.iter().map(|&item| item.to_string()).collect();
+        let lns = prepare_lines(lns);
+        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
+        Self {
+            name: None,
+            lns: trim(lns, true),
+            do_trimming: true,
+            tags: vec![],
+            parts: Parts::default(),
+            marker: PhantomData::<Rc<()>>,
+        }
+    }
+
+    pub fn new_without_trimming_str(lns: Vec<&str>) -> Self {
+        let lns: Vec<String> = lns.iter().map(|&item| item.to_string()).collect();
+        let lns = prepare_lines(lns);
+        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
+        Self {
+            name: None,
+            lns: trim(lns, false),
+            do_trimming: false,
+            tags: vec![],
+            parts: Parts::default(),
+            marker: PhantomData::<Rc<()>>,
+        }
+    }
+
+    pub fn new_without_trimming(lns: Vec<String>) -> Self {
+        let lns = prepare_lines(lns);
+        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
+        Self {
+            name: None,
+            lns: trim(lns, false),
+            do_trimming: false,
+            tags: vec![],
+            parts: Parts::default(),
+            marker: PhantomData::<Rc<()>>,
+        }
+    }
+
+    pub fn from_str_without_trimming(val: &str) -> Self {
+        let lns: Vec<&str> = val.split('\n').collect();
+        let lns: Vec<String> = lns.iter().map(|&item| item.to_string()).collect();
+        let lns = prepare_lines(lns);
+        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
+        Self {
+            name: None,
+            lns: trim(lns, false),
+            do_trimming: false,
+            tags: vec![],
+            parts: Parts::default(),
+            marker: PhantomData::<Rc<()>>,
+        }
+    }
+
+    ///
+    /// This function is the same as `new`, but returns the first unfinished or invalid placeholder of the lines as an error
+    /// instead of panicking, nothing is persisted in that case
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// assert_eq!(
+    ///     RTile::try_new(vec!["x @{a".to_string()]),
+    ///     Err(RTileError::UnfinishedPlaceholder { line: "x @{a".to_string() })
+    /// );
+    /// assert!(matches!(
+    ///     RTile::try_new(vec!["@{x:bogus}".to_string()]),
+    ///     Err(RTileError::InvalidPlaceholder { placeholder, .. }) if placeholder == "x:bogus"
+    /// ));
+    /// tp!(name, "Alice");
+    /// assert_eq!(RTile::try_new(vec!["Hello @{name}".to_string()]).unwrap().to_string(), "Hello Alice");
+    /// ```
+    pub fn try_new(lns: Vec<String>) -> std::result::Result<Self, RTileError> {
+        let lns = prepare_lines(lns);
+        TL_STORE.with_borrow(|v| v.check_lines(&lns))?;
+        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
+        Ok(Self {
+            name: None,
+            lns: trim(lns, true),
+            do_trimming: true,
+            tags: vec![],
+            parts: Parts::default(),
+            marker: PhantomData::<Rc<()>>,
+        })
+    }
+
+    ///
+    /// This function is the same as `construct_from_str`, but returns the first unfinished or invalid placeholder as an error
+    /// instead of panicking, see `try_new`
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// assert!(matches!(
+    ///     RTile::try_construct_from_str("let x = 1;\nlet y = @{len(x};"),
+    ///     Err(RTileError::InvalidPlaceholder { .. })
+    /// ));
+    /// ```
+    pub fn try_construct_from_str(val: &str) -> std::result::Result<Self, RTileError> {
+        Self::try_new(val.split('\n').map(|ln| ln.to_string()).collect())
+    }
+
+    pub fn get_names_of_blank_inner_tiles(&self) -> Vec<String> {
+        TL_STORE.with_borrow(|v| v.blank_inner_tiles_names(self))
+    }
+
+    ///
+    /// This function is the same as `render_fresh`, it used to expand the tile using the processed data of the inner tiles as is,
+    /// which could be stale, use `render_cached` for that
+    pub fn reevaluate(&self) -> String {
+        self.render_fresh()
+    }
+
+    ///
+    /// This function returns the expanded output of the tile, the inner tiles are processed again from their raw data first,
+    /// i.e. the output is always up to date. It is the same as `to_string`
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(name, "Alice");
+    /// let tile = t!("Hello @{name}");
+    /// stp!(name, t!("Bob"));
+    /// assert_eq!(tile.render_fresh(), "Hello Bob");
+    /// ```
+    pub fn render_fresh(&self) -> String {
+        render_with_default_store(self)
+    }
+
+    ///
+    /// This function returns the expanded output of the tile using the processed data of the inner tiles as is, without processing them again,
+    /// i.e. it is cheaper than `render_fresh`, but the inner tiles which changed since they were last rendered are expanded using their old data
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(name, "Alice");
+    /// tp!(greet, "Hello @{name}");
+    /// stp!(name, t!("Bob"));
+    /// let tile = t!("@{greet}");
+    /// // the processed data of the tile `greet` is not updated by stp!
+    /// assert_eq!(tile.render_cached(), "Hello Alice");
+    /// assert_eq!(tile.render_fresh(), "Hello Bob");
+    /// refresh_tiles();
+    /// assert_eq!(tile.render_cached(), "Hello Bob");
+    /// ```
+    pub fn render_cached(&self) -> String {
+        TL_STORE.with_borrow(|v| v.render_cached(self))
+    }
+
+    pub fn join<T: Display + Debug>(&self, x: &[T], last: Option<RTile>) -> Self {
+        let mut res = RTile::new_without_trimming(vec![]);
+        for (idx, item) in x.iter().enumerate() {
+            match item {
+                i if type_name::<T>() == "rtile::RTile" => {
+                    let lns = i
+                        .to_string()
+                        .split('\n')
+                        .collect::<Vec<&str>>()
+                        .iter()
+                        .map(|&item| item.to_string())
+                        .collect();
+                    res += RTile::new_without_trimming(lns);
+                }
+                i => {
+                    res += RTile::construct_from_str(i.to_string().as_str());
+                }
+            };
+            if idx < x.len() - 1 {
+                res += self.clone();
+            }
+        }
+        match last {
+            Some(t) => res + t,
+            None => res,
+        }
+    }
+
+    pub fn vjoin<T: Display + Debug>(&self, x: &[T], inline: bool, last: Option<RTile>) -> Self {
+        let last = last.unwrap_or_else(|| RTile::new(vec![]));
+        let mut res = RTile::new_without_trimming(vec![]);
+        for (idx, item) in x.iter().enumerate() {
+            match item {
+                i if type_name::<T>() == "rtile::RTile" => {
+                    let lns = i
+                        .to_string()
+                        .split('\n')
+                        .collect::<Vec<&str>>()
+                        .iter()
+                        .map(|&item| item.to_string())
+                        .collect();
+
+                    res |= RTile::new_without_trimming(lns)
+                        + if inline {
+                            if idx < x.len() - 1 {
+                                self.clone()
+                            } else {
+                                last.clone()
+                            }
+                        } else {
+                            RTile::construct_from_str("")
+                        }
+                }
+                i => {
+                    res |= RTile::construct_from_str(i.to_string().as_str())
+                        + if inline {
+                            if idx < x.len() - 1 {
+                                self.clone()
+                            } else {
+                                last.clone()
+                            }
+                        } else {
+                            RTile::construct_from_str("")
+                        }
+                }
+            };
+            if !inline {
+                let tile = if idx < x.len() - 1 {
+                    self.clone()
+                } else {
+                    last.clone()
+                };
+                res |= tile;
+            }
+        }
+        res
+    }
+
+    ///
+    /// This function is like `join`, but takes optional tiles (or the tiles themselves), the `None` tiles are left out along with
+    /// their separators. `join` itself takes any displayed items, e.g. the texts and the numbers, and an `Option` is not displayed,
+    /// nor can it be told apart from them by a trait bound, so the optional tiles are joined here
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let items = [Some(t!("a")), None, Some(t!("b"))];
+    /// assert_eq!(k!(", ").join_some(items, None).to_string(), "a, b");
+    /// let user = Some(t!("user"));
+    /// let admin: Option<RTile> = None;
+    /// let tiles = [t!("id").into(), user, admin];
+    /// assert_eq!(k!(", ").join_some(tiles.iter().cloned(), None).to_string(), "id, user");
+    /// ```
+    pub fn join_some<I>(&self, x: I, last: Option<RTile>) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Option<RTile>>,
+    {
+        let tiles: Vec<RTile> = x.into_iter().filter_map(Into::into).collect();
+        self.join(&tiles, last)
+    }
+
+    ///
+    /// This function is like `vjoin`, but takes optional tiles (or the tiles themselves), the `None` tiles are left out instead of
+    /// leaving blank lines, see `join_some`
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let items = [Some(t!("a")), None, Some(t!("b"))];
+    /// assert_eq!(t!(",").vjoin_some(items, true, None).to_string(), "a,\nb ");
+    /// ```
+    pub fn vjoin_some<I>(&self, x: I, inline: bool, last: Option<RTile>) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Option<RTile>>,
+    {
+        let tiles: Vec<RTile> = x.into_iter().filter_map(Into::into).collect();
+        self.vjoin(&tiles, inline, last)
+    }
+
+    ///
+    /// This function returns the fallback tile, if this tile is absent (i.e. has no lines) or has only blank lines, else returns this tile
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let fallback = t!("// nothing to emit");
+    /// assert_eq!(t!("").or(fallback.clone()).to_string(), "// nothing to emit");
+    /// assert_eq!(t!("#[derive(Debug)]").or(fallback).to_string(), "#[derive(Debug)]");
+    /// ```
+    pub fn or(self, fallback: RTile) -> Self {
+        if self.lns.iter().all(|ln| ln.trim().is_empty()) {
+            fallback
+        } else {
+            self
+        }
+    }
+
+    ///
+    /// This function returns the trimmed raw data of the tile
+    ///
+    /// ex:
+    /// let tile = t!("   @{another_tile_one}             @{another_tile_two}       ");
+    /// calling tile.raw(); would return a trimmed, non-expanded raw data of the tile
+    /// so tile.raw() == "@{another_tile_one}             @{another_tile_two}".to_string();
+    pub fn raw(&self) -> String {
+        trim(self.lns.clone(), self.do_trimming)
+            .join("\n")
+            .to_string()
+    }
+
+    pub fn has_inner_tiles_in_raw_data(&self) -> bool {
+        for ln in &self.lns {
+            match next_placeholder_span(ln) {
+                None => continue,
+                Some((_, None)) => panic!("unfinished @{{}} expression"),
+                Some(_) => return true,
+            }
+        }
+        false
+    }
+
+    pub fn inner_tiles_in_raw_data(&self) -> Vec<Vec<String>> {
+        let mut result = vec![];
+        for ln in &self.lns {
+            let mut tiles_on_line = vec![];
+            for placeholder in placeholders(ln) {
+                tiles_on_line.extend(placeholder.inner_tile_names(&HashSet::new()));
+            }
+            result.push(tiles_on_line);
+        }
+        result
+    }
+
+    pub fn inner_tiles(&self) -> HashSet<String> {
+        TL_STORE
+            .with_borrow(|v| v.inner_tiles(self))
+            .into_iter()
+            .collect()
+    }
+
+    ///
+    /// This function returns the names of the inner tiles (transitively), in the order of their first appearance, i.e. a stable variant of `inner_tiles`
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(name, "@{first} @{last}");
+    /// assert_eq!(
+    ///     t!("@{greeting}, @{name}").inner_tiles_in_order(),
+    ///     vec!["greeting", "name", "first", "last"]
+    /// );
+    /// ```
+    ///
+    pub fn inner_tiles_in_order(&self) -> Vec<String> {
+        let mut inner_tiles = TL_STORE.with_borrow(|v| v.inner_tiles(self));
+        let mut seen = HashSet::new();
+        inner_tiles.retain(|tile_name| seen.insert(tile_name.clone()));
+        inner_tiles
+    }
+
+    pub fn flatten(&self) -> String {
+        self.lns
+            .iter()
+            .map(|item| item.trim())
+            .collect::<Vec<&str>>()
+            .join("")
+    }
+
+    ///
+    /// This function returns the (width, height) of the raw (non-expanded) lines of the tile, i.e. a placeholder is measured as the `@{...}` text
+    /// use `rendered_dimensions` for the size of the output
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(name, "Alice\nBob");
+    /// assert_eq!(t!("name: @{name}").dimensions(), (13, 1));
+    /// ```
+    pub fn dimensions(&self) -> (usize, usize) {
+        let width = self.lns.iter().map(|s| display_width(s)).max().unwrap_or(0);
+        let height = self.lns.len();
+        (width, height)
+    }
+
+    ///
+    /// This function returns the (width, height) of the output of the tile, i.e. after the placeholders are expanded
+    /// the tile is expanded in a fork of the store, so the missing inner tiles are not added to the store as blank tiles
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(name, "Alice\nBob");
+    /// assert_eq!(t!("name: @{name}").rendered_dimensions(), (11, 2));
+    /// ```
+    pub fn rendered_dimensions(&self) -> (usize, usize) {
+        let mut store = TL_STORE.with_borrow_mut(|v| v.fork());
+        let output = store.render(self);
+        if output.is_empty() {
+            return (0, 0);
+        }
+        let width = output.split('\n').map(display_width).max().unwrap_or(0);
+        (width, output.split('\n').count())
+    }
+
+    ///
+    /// This function appends a line with a tag to the tile, the tags are kept when the tiles are composed using `|` and `+`, or
+    /// interleaved, and they aren't a part of the comparison of the tiles
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut tile = t!("// header");
+    /// tile.push_line_tagged("fn generated() {}", "generated");
+    /// assert_eq!(
+    ///     tile.lines_with_tags(),
+    ///     vec![("// header", None), ("fn generated() {}", Some("generated"))]
+    /// );
+    /// ```
+    pub fn push_line_tagged(&mut self, text: &str, tag: &str) -> &mut Self {
+        self.tags.resize(self.lns.len(), None);
+        self.lns.push(text.to_string());
+        self.tags.push(Some(tag.to_string()));
+        self
+    }
+
+    ///
+    /// This function returns a tile with only the listed inner tiles substituted with their current values, the other placeholders are kept as they are
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(lang, "rust");
+    /// let tile = t!("@{lang}: @{name}");
+    /// assert_eq!(tile.bake(&["lang"]).raw(), "rust: @{name}");
+    /// ```
+    pub fn bake(&self, tile_names: &[&str]) -> Self {
+        let mut lns = vec![];
+        for ln in &self.lns {
+            let mut curr: Vec<String> = vec![];
+            let mut end = 0;
+            for (start, placeholder_end, text) in placeholder_spans(ln) {
+                let placeholder = Placeholder::parse(text);
+                if placeholder.kind == PlaceholderKind::Tile
+                    && tile_names.contains(&placeholder.name.as_str())
+                {
+                    append(&mut curr, vec![&ln[end..start]]);
+                    let value = get_raw_tile(&placeholder.name)
+                        .map(|tile| tile.to_string())
+                        .unwrap_or_default();
+                    append_placeholder_value(&mut curr, &placeholder, &value);
+                } else {
+                    append(&mut curr, vec![&ln[end..placeholder_end]]);
+                }
+                end = placeholder_end;
+            }
+            append(&mut curr, vec![&ln[end..]]);
+            lns.append(&mut curr);
+        }
+        Self {
+            name: None,
+            lns,
+            do_trimming: self.do_trimming,
+            tags: vec![],
+            parts: Parts::default(),
+            marker: PhantomData::<Rc<()>>,
+        }
+    }
+
+    ///
+    /// This function returns the tile rendered once for every item, one below the other, with the placeholders of the given name
+    /// referring to the item, i.e. a multi line item is a column of its own in every rendering, the same way as an inner tile.
+    /// The tiles are rendered in a fork of the tls (thread local storage), which is left untouched
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(vis, "pub");
+    /// let items = [t!("id: u64,"), t!("tags: Vec<String>,\n// sorted")];
+    /// let fields = t!("@{vis} @{field}").expand_each("field", &items);
+    /// assert_eq!(fields.to_string(), "pub id: u64,\npub tags: Vec<String>,\n    // sorted");
+    /// ```
+    pub fn expand_each(&self, item: &str, items: &[RTile]) -> Self {
+        let mut res = detached(|| RTile::new(vec![]));
+        for value in items {
+            let mut store = with_default_store(|store| store.fork());
+            store.set(item, value.clone());
+            let output = store.render(self);
+            res |= detached(|| RTile::construct_from_str(&output));
+        }
+        res
+    }
+
+    ///
+    /// This function wraps the text of every line of the tile in the OSC 8 hyperlink escapes, so the modern terminals show it as a clickable link
+    /// the escapes take no columns, i.e. the linked tile lines up with the other tiles the same way as the plain one
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let tile = t!("docs").link("https://docs.rs/rtile");
+    /// assert_eq!(tile.to_string(), "\x1b]8;;https://docs.rs/rtile\x1b\\docs\x1b]8;;\x1b\\");
+    /// assert_eq!(tile.rendered_dimensions(), (4, 1));
+    /// ```
+    pub fn link(self, url: &str) -> Self {
+        let mut tile = self;
+        for ln in tile.lns.iter_mut() {
+            let text = ln.trim();
+            if text.is_empty() {
+                continue;
+            }
+            let start = ln.len() - ln.trim_start().len();
+            let end = start + text.len();
+            *ln = format!(
+                "{}\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\{}",
+                &ln[..start],
+                url,
+                text,
+                &ln[end..]
+            );
+        }
+        tile
+    }
+
+    ///
+    /// This function renders the tile with a ruler of the column numbers across the top and the row numbers down the side, to debug the layout
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// assert_eq!(t!("ab\ncd").with_ruler(), "   12\n1 |ab\n2 |cd");
+    /// ```
+    pub fn with_ruler(&self) -> String {
+        let output = self.to_string();
+        let lns: Vec<&str> = if output.is_empty() {
+            vec![]
+        } else {
+            output.split('\n').collect()
+        };
+        let width = lns.iter().map(|ln| display_width(ln)).max().unwrap_or(0);
+        let margin = lns.len().to_string().len() + 2;
+        let tens: String = (1..=width)
+            .map(|col| match col % 10 {
+                0 => char::from_digit(((col / 10) % 10) as u32, 10).unwrap(),
+                _ => ' ',
+            })
+            .collect();
+        let units: String = (1..=width)
+            .map(|col| char::from_digit((col % 10) as u32, 10).unwrap())
+            .collect();
+        let mut ruler = vec![];
+        if !tens.trim().is_empty() {
+            ruler.push(format!("{:margin$}{}", "", tens.trim_end()));
+        }
+        ruler.push(format!("{:margin$}{}", "", units));
+        for (row, ln) in lns.iter().enumerate() {
+            ruler.push(format!("{:<w$}|{}", row + 1, ln, w = margin - 1));
+        }
+        ruler.join("\n")
+    }
+
+    ///
+    /// This function returns the raw (non-expanded) data of the tile with the placeholders highlighted using the given style
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let tile = t!("Hello @{name}");
+    /// assert_eq!(
+    ///     tile.highlight_placeholders(HighlightStyle::Ansi),
+    ///     "Hello \x1b[36m@{name}\x1b[0m"
+    /// );
+    /// ```
+    pub fn highlight_placeholders(&self, style: HighlightStyle) -> String {
+        let escape = |text: &str| match style {
+            HighlightStyle::Ansi => text.to_string(),
+            HighlightStyle::Html => text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+        };
+        let mut lns = vec![];
+        for ln in trim(self.lns.clone(), self.do_trimming) {
+            let mut highlighted = String::new();
+            let mut rest = ln.as_str();
+            while let Some((start, Some((end, _)))) = next_placeholder_span(rest) {
+                highlighted.push_str(&escape(&rest[..start]));
+                let placeholder = escape(&rest[start..end]);
+                match style {
+                    HighlightStyle::Ansi => {
+                        highlighted.push_str(&format!("\x1b[36m{}\x1b[0m", placeholder))
+                    }
+                    HighlightStyle::Html => highlighted.push_str(&format!(
+                        "<span class=\"rtile-placeholder\">{}</span>",
+                        placeholder
+                    )),
+                }
+                rest = &rest[end..];
+            }
+            highlighted.push_str(&escape(rest));
+            lns.push(highlighted);
+        }
+        lns.join("\n")
+    }
+
+    ///
+    /// This function returns the raw lines of the tile together with their tags, if any
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut generated = t!("");
+    /// generated.push_line_tagged("generated", "gen");
+    /// let tile = t!("verbatim") | generated;
+    /// assert_eq!(
+    ///     tile.lines_with_tags(),
+    ///     vec![("verbatim", None), ("", None), ("generated", Some("gen"))]
+    /// );
+    /// ```
+    pub fn lines_with_tags(&self) -> Vec<(&str, Option<&str>)> {
+        self.lns
+            .iter()
+            .enumerate()
+            .map(|(i, ln)| (ln.as_str(), self.tags.get(i).and_then(|tag| tag.as_deref())))
+            .collect()
+    }
+
+    ///
+    /// This function places the other tile to the left of this tile, i.e. `tile = other + tile` keeping the name of the tile
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut row = t!("c");
+    /// row.prepend_h(t!("b")).prepend_h(t!("a"));
+    /// assert_eq!(row.to_string(), "abc");
+    /// ```
+    pub fn prepend_h(&mut self, other: RTile) -> &mut Self {
+        self.tags = merge_tags(other.tags, std::mem::take(&mut self.tags));
+        let mut lns = other.lns;
+        append(&mut lns, std::mem::take(&mut self.lns));
+        self.lns = lns;
+        self
+    }
+
+    ///
+    /// This function places the other tile above this tile, i.e. `tile = other | tile` keeping the name of the tile
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut column = t!("c");
+    /// column.prepend_v(t!("b")).prepend_v(t!("a"));
+    /// assert_eq!(column.to_string(), "a\nb\nc");
+    /// ```
+    pub fn prepend_v(&mut self, other: RTile) -> &mut Self {
+        self.tags = stack_tags(other.lns.len(), other.tags, std::mem::take(&mut self.tags));
+        let mut lns = other.lns;
+        lns.append(&mut self.lns);
+        self.lns = lns;
+        self
+    }
+
+    ///
+    /// This function returns a tile with the numbers formatted as a column, the numbers are aligned on their decimal points,
+    /// along with the currency or the unit symbol of the format placed alike on every line
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let format = NumFormat { precision: Some(2), thousands_sep: Some(','), ..Default::default() };
+    /// assert_eq!(RTile::from_numbers([1250.0, 52000.5], format).to_string(), " 1,250.00\n52,000.50");
+    /// let format = NumFormat { precision: Some(2), symbol: Some("$"), ..Default::default() };
+    /// assert_eq!(RTile::from_numbers([3.5, 1250.0], format).to_string(), "$   3.50\n$1250.00");
+    /// ```
+    pub fn from_numbers<I>(numbers: I, format: NumFormat) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<f64>,
+    {
+        let parts: Vec<(String, String)> = numbers
+            .into_iter()
+            .map(|number| format_number(number.into(), &format))
+            .collect();
+        let integer_width = parts
+            .iter()
+            .map(|(integer, _)| integer.chars().count())
+            .max()
+            .unwrap_or(0);
+        let fraction_width = parts
+            .iter()
+            .map(|(_, fraction)| fraction.chars().count())
+            .max()
+            .unwrap_or(0);
+        // the attached symbols are a part of the integer parts, the leading and the trailing ones are placed around the numbers
+        let (leading, trailing) = match (format.symbol, format.symbol_position) {
+            (Some(symbol), SymbolPosition::Leading) => (symbol, ""),
+            (Some(symbol), SymbolPosition::Trailing) => ("", symbol),
+            _ => ("", ""),
+        };
+        let leading_width = leading.chars().count();
+        let width = format
+            .width
+            .max(leading_width + integer_width + fraction_width + trailing.chars().count())
+            - leading_width;
+        let lns = parts
+            .iter()
+            .map(|(integer, fraction)| {
+                let ln = format!("{integer:>integer_width$}{fraction:<fraction_width$}{trailing}");
+                if format.align_right {
+                    format!("{leading}{ln:>width$}")
+                } else {
+                    format!("{leading}{ln:<width$}")
+                }
+            })
+            .collect();
+        Self {
+            name: None,
+            lns,
+            do_trimming: false,
+            tags: vec![],
+            parts: Parts::default(),
             marker: PhantomData::<Rc<()>>,
         }
     }
 
-    pub fn new_without_trimming_str(lns: Vec<&str>) -> Self {
-        let lns: Vec<String> = lns.iter().map(|&item| item.to_string()).collect();
-        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
+    ///
+    /// This function returns a tile with the rows of cells laid out as an aligned grid, every column is as wide as its widest cell,
+    /// a cell spanning lines makes its row taller, and the missing cells of the shorter rows are blank.
+    /// A cell wider than the maximum width of its column is wrapped or truncated, as per the overflow policy of the column
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let spec = ColumnSpec {
+    ///     align: vec![ColumnAlign::Left, ColumnAlign::Right],
+    ///     separators: vec![" | ".to_string()],
+    ///     ..Default::default()
+    /// };
+    /// let tile = RTile::from_matrix(&[vec!["apple", "3"], vec!["kiwi", "12"]], spec);
+    /// assert_eq!(tile.to_string(), "apple |  3\nkiwi  | 12");
+    /// ```
+    pub fn from_matrix<R, T>(rows: &[R], spec: ColumnSpec) -> Self
+    where
+        R: AsRef<[T]>,
+        T: Display,
+    {
+        let rows: Vec<Vec<Vec<String>>> = rows
+            .iter()
+            .map(|row| {
+                row.as_ref()
+                    .iter()
+                    .enumerate()
+                    .map(|(column, cell)| {
+                        cell.to_string()
+                            .split('\n')
+                            .flat_map(|ln| spec.fit(column, ln))
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                rows.iter()
+                    .filter_map(|row| row.get(column))
+                    .flatten()
+                    .map(|ln| display_width(ln))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let mut lns = vec![];
+        for row in &rows {
+            let height = row.iter().map(|cell| cell.len()).max().unwrap_or(1);
+            for i in 0..height {
+                let mut ln = String::new();
+                for (column, width) in widths.iter().enumerate() {
+                    if column > 0 {
+                        ln.push_str(spec.separator(column - 1));
+                    }
+                    let text = row
+                        .get(column)
+                        .and_then(|cell| cell.get(i))
+                        .map_or("", |ln| ln.as_str());
+                    let padding = width - display_width(text);
+                    let left = match spec.align.get(column).copied().unwrap_or_default() {
+                        ColumnAlign::Left => 0,
+                        ColumnAlign::Right => padding,
+                        ColumnAlign::Center => padding / 2,
+                    };
+                    ln.push_str(&" ".repeat(left));
+                    ln.push_str(text);
+                    ln.push_str(&" ".repeat(padding - left));
+                }
+                lns.push(ln);
+            }
+        }
         Self {
             name: None,
-            lns: trim(lns, false),
+            lns,
             do_trimming: false,
+            tags: vec![],
+            parts: Parts::default(),
             marker: PhantomData::<Rc<()>>,
         }
     }
 
-    pub fn new_without_trimming(lns: Vec<String>) -> Self {
-        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
+    ///
+    /// This function returns a tile with the template stamped once for every value of the range (or any iterator), one below the other,
+    /// with every `{i}` in the template substituted by the value. The template is trimmed the same way as `t!`, and may span lines
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// assert_eq!(
+    ///     RTile::from_range(1..=3, "{i} => Some(Case{i}),").to_string(),
+    ///     "1 => Some(Case1),\n2 => Some(Case2),\n3 => Some(Case3),"
+    /// );
+    /// assert_eq!(
+    ///     RTile::from_range(["a", "b"], "let @{prefix}_{i} = {i}();").raw(),
+    ///     "let @{prefix}_a = a();\nlet @{prefix}_b = b();"
+    /// );
+    /// ```
+    pub fn from_range<I, T>(range: I, template: &str) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Display,
+    {
+        // the placeholders are completed only once the value is substituted, so the template itself creates no blank tiles
+        let template = detached(|| RTile::construct_from_str(template));
+        let lns = range
+            .into_iter()
+            .flat_map(|value| {
+                let value = value.to_string();
+                template.lns.iter().map(move |ln| ln.replace("{i}", &value))
+            })
+            .collect();
+        RTile::new(lns)
+    }
+
+    ///
+    /// This function returns a tile with the lines of the given tiles interleaved row by row, i.e. the first line of every tile, then the second line of every tile, ...
+    /// the tiles with less lines contribute the fill line instead
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let code = t!("let a = 1;\nlet b = a;");
+    /// let comments = t!("// one\n// copy");
+    /// assert_eq!(
+    ///     RTile::interleave(&[code, comments], "").to_string(),
+    ///     "let a = 1;\n// one\nlet b = a;\n// copy"
+    /// );
+    /// ```
+    ///
+    /// the tags of the lines are kept, the fill lines have none
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut code = t!("let a = 1;");
+    /// code.push_line_tagged("let b = a;", "generated");
+    /// let tile = RTile::interleave(&[code, t!("// one")], "");
+    /// assert_eq!(
+    ///     tile.lines_with_tags(),
+    ///     vec![("let a = 1;", None), ("// one", None), ("let b = a;", Some("generated")), ("", None)]
+    /// );
+    /// ```
+    pub fn interleave(tiles: &[RTile], fill: &str) -> Self {
+        let height = tiles.iter().map(|tile| tile.lns.len()).max().unwrap_or(0);
+        let has_tags = tiles.iter().any(|tile| !tile.tags.is_empty());
+        let mut lns = vec![];
+        let mut tags = vec![];
+        for i in 0..height {
+            for tile in tiles {
+                lns.push(tile.lns.get(i).map_or(fill, |ln| ln.as_str()).to_string());
+                if has_tags {
+                    tags.push(tile.tags.get(i).cloned().flatten());
+                }
+            }
+        }
         Self {
             name: None,
-            lns: trim(lns, false),
-            do_trimming: false,
+            lns,
+            do_trimming: tiles.first().is_none_or(|tile| tile.do_trimming),
+            tags,
+            parts: Parts::default(),
+            marker: PhantomData::<Rc<()>>,
+        }
+    }
+
+    ///
+    /// This function places the other tile to the side of this tile like `+`, but fills the padding with the given character instead of spaces
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let toc = t!("Intro\nInstallation\nUsage").add_with_fill(t!("1\n4\n9"), '.');
+    /// assert_eq!(toc.to_string(), "Intro.......1\nInstallation4\nUsage.......9");
+    /// ```
+    pub fn add_with_fill(self, other: RTile, fill: char) -> Self {
+        let mut tile = self;
+        append_with_fill(&mut tile.lns, other.lns, fill);
+        tile.name = None;
+        tile
+    }
+
+    ///
+    /// This function places the other tile to the side of this tile like `+`, with the separator repeated on every line in between,
+    /// i.e. the separator column is as tall as the taller tile. Use `hjoin` for more than two tiles, to size every separator column alike
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let tile = t!("a\nb\nc").add_with_separator(t!("1"), " │ ");
+    /// assert_eq!(tile.to_string(), "a │ 1\nb │\nc │");
+    /// ```
+    pub fn add_with_separator(self, other: RTile, separator: &str) -> Self {
+        RTile::hjoin(&[self, other], separator)
+    }
+
+    ///
+    /// This function returns the tiles placed side by side like `+`, with the separator repeated on every line between every two tiles,
+    /// i.e. every separator column is as tall as the tallest tile
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let tile = RTile::hjoin(&[t!("a"), t!("1\n2"), t!("x")], " | ");
+    /// assert_eq!(tile.to_string(), "a | 1 | x\n  | 2 |");
+    /// ```
+    pub fn hjoin(tiles: &[RTile], separator: &str) -> Self {
+        let height = tiles.iter().map(|tile| tile.lns.len()).max().unwrap_or(0);
+        let separator =
+            detached(|| RTile::new_without_trimming(vec![separator.to_string(); height]));
+        let mut tiles = tiles.iter();
+        let mut res = tiles
+            .next()
+            .cloned()
+            .unwrap_or_else(|| RTile::new_without_trimming(vec![]));
+        for tile in tiles {
+            res += separator.clone();
+            res += tile.clone();
+        }
+        res.name = None;
+        res
+    }
+
+    ///
+    /// This function returns the tile with a "do not edit" banner on top, naming the generating tool, its version, the source and the
+    /// template (i.e. the name of the tile) in `//` comments, see `with_banner` for the other comment styles and the timestamps
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let person = tp!(person, "struct Person;");
+    /// assert_eq!(
+    ///     person.with_provenance("codegen", "1.0", "person.proto").to_string(),
+    ///     "// @generated by codegen 1.0, do not edit\n// source: person.proto\n// template: person\nstruct Person;"
+    /// );
+    /// ```
+    pub fn with_provenance(self, tool_name: &str, version: &str, source: &str) -> Self {
+        self.with_banner(&provenance::Provenance::new(tool_name, version, source))
+    }
+
+    ///
+    /// This function returns the tile with the banner of the provenance on top, the template of the banner is the name of the tile,
+    /// unless the provenance names it
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    /// use rtile::provenance::*;
+    ///
+    /// let provenance = Provenance::new("codegen", "1.0", "").comment(CommentStyle::for_language("python").unwrap());
+    /// assert_eq!(
+    ///     t!("x = 1").with_banner(&provenance).to_string(),
+    ///     "# @generated by codegen 1.0, do not edit\nx = 1"
+    /// );
+    /// ```
+    pub fn with_banner(mut self, provenance: &provenance::Provenance) -> Self {
+        let banner = match (&provenance.template, &self.name) {
+            (None, Some(name)) => provenance.clone().template(name).banner(),
+            _ => provenance.banner(),
+        };
+        self.prepend_v(banner);
+        self.name = None;
+        self
+    }
+
+    ///
+    /// This function returns a hash of the expanded output of the tile, which is stable across runs and platforms
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(name, "Person");
+    /// let tile = t!("struct @{name};");
+    /// let hash = tile.content_hash();
+    /// assert_eq!(hash, t!("struct Person;").content_hash());
+    /// tp!(name, "Animal");
+    /// assert_ne!(tile.content_hash(), hash);
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        content_hash(FNV_OFFSET_BASIS, self.to_string().as_bytes())
+    }
+
+    ///
+    /// This function returns the expanded output of the tile, or the first problem which would make `to_string` panic or
+    /// print, see `TileStore::try_render`
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(a, "@{b}");
+    /// tp!(b, "@{a}");
+    /// assert!(matches!(t!("@{a}").try_expand(), Err(RTileError::Recursion(_))));
+    /// tp!(c, "@{d}");
+    /// tp!(d, "D");
+    /// assert_eq!(t!("@{c}").try_expand(), Ok("D".to_string()));
+    /// // a tile constructed without a store, i.e. detached, is only checked here
+    /// let tile = detached(|| t!("@{c"));
+    /// assert!(matches!(tile.try_expand(), Err(RTileError::UnfinishedPlaceholder { .. })));
+    /// let tile = detached(|| t!("@{c:bogus}"));
+    /// assert!(matches!(tile.try_expand(), Err(RTileError::InvalidPlaceholder { .. })));
+    /// ```
+    pub fn try_expand(&self) -> std::result::Result<String, RTileError> {
+        TL_STORE.with_borrow_mut(|v| {
+            v.sync_global_tiles();
+            v.check_expansion(self)
+        })?;
+        Ok(self.to_string())
+    }
+
+    ///
+    /// This function returns the expanded output of the tile together with a report of the render
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(attributes);
+    /// let tile = t!("@{attributes}struct @{name}");
+    /// let (output, report) = tile.to_string_with_report();
+    /// assert_eq!(output, "struct");
+    /// assert_eq!(report.empty_placeholders, vec!["attributes", "name"]);
+    /// ```
+    pub fn to_string_with_report(&self) -> (String, RenderReport) {
+        evaluate_dynamic_tiles_with_default_store(self);
+        let (output, empty_placeholders) =
+            TL_STORE.with_borrow_mut(|v| v.expand_with_empty_inner_tiles(self));
+        (output, RenderReport { empty_placeholders })
+    }
+
+    ///
+    /// This function returns the expanded output of the tile, rendered using the given render options instead of the options of the current thread
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// tp!(attrs);
+    /// let tile = t!("@{attrs}\nstruct A;");
+    /// let options = RenderOptions { drop_blank_placeholder_lines: true, ..Default::default() };
+    /// assert_eq!(tile.to_string_with_options(&options), "struct A;");
+    /// ```
+    pub fn to_string_with_options(&self, options: &RenderOptions) -> String {
+        with_render_options(Some(options.clone()), || {
+            evaluate_dynamic_tiles_with_default_store(self);
+            TL_STORE.with_borrow_mut(|v| v.expand_for_render(self))
+        })
+    }
+}
+
+/// RenderReport, returned along with the output by `RTile::to_string_with_report`
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(attributes);
+/// tp!(name, "Person");
+/// tp!(members, "");
+/// let tile = t!("
+///     @{attributes}
+///     struct @{name} {
+///         @{members}
+///     }
+/// ");
+/// let (output, report) = tile.to_string_with_report();
+/// assert_eq!(output, "struct Person {\n\n}");
+/// assert_eq!(report.empty_placeholders, vec!["attributes", "members"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderReport {
+    /// names of the placeholders (including the ones in the inner tiles) which expanded to empty text, in the order of their first appearance
+    pub empty_placeholders: Vec<String>,
+}
+
+/// TemplateDoc, the documentation of a template, returned by `describe_template`
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(template_doc_row, "@# One row of the table\n| @{cells} |");
+/// let doc = describe_template("template_doc_row").unwrap();
+/// assert_eq!(
+///     doc.to_markdown(),
+///     "## `template_doc_row`\n\nOne row of the table\n\nPlaceholders: `cells`\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateDoc {
+    /// name of the template
+    pub name: String,
+    /// leading comment lines of the template, without the `@#` markers
+    pub doc: Vec<String>,
+    /// names of the placeholders of the template (not of its inner tiles), in the order of their first appearance
+    pub placeholders: Vec<String>,
+}
+
+impl TemplateDoc {
+    /// returns the documentation as a Markdown section, as used by `document_store`
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("## `{}`\n", self.name);
+        if !self.doc.is_empty() {
+            markdown += &format!("\n{}\n", self.doc.join("\n"));
+        }
+        if !self.placeholders.is_empty() {
+            let placeholders: Vec<String> = self
+                .placeholders
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect();
+            markdown += &format!("\nPlaceholders: {}\n", placeholders.join(", "));
+        }
+        markdown
+    }
+}
+
+/// TileRef, a handle to a tile of the tls (thread local storage) by its name, returned by `gtp_ref!`
+///
+/// unlike the copy returned by `gtp!`, the handle always follows the tile persisted with the name, e.g. after the tile is persisted again.
+/// a handle to a tile which isn't persisted renders as empty text
+/// ```
+/// use rtile::prelude::*;
+///
+/// let config = TileRef::new("config");
+/// assert!(!config.exists());
+/// assert_eq!(config.to_string(), "");
+/// tp!(config, "debug = @{debug}");
+/// tp!(debug, "true");
+/// assert_eq!(config.get().unwrap().raw(), "debug = @{debug}");
+/// assert_eq!(config.to_string(), "debug = true");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TileRef {
+    name: String,
+}
+
+impl TileRef {
+    /// creates a handle to the tile with the given name
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+
+    /// returns the name of the tile
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// returns true, if a tile is persisted with the name
+    pub fn exists(&self) -> bool {
+        TL_STORE.with_borrow(|v| v.contains(&self.name))
+    }
+
+    /// returns a copy of the tile persisted with the name, as `gtp!` does
+    pub fn get(&self) -> Option<RTile> {
+        get_raw_tile(&self.name)
+    }
+
+    /// returns a tile with the placeholder of the tile, to compose the tile using `+` and `|` while following its changes
+    pub fn to_tile(&self) -> RTile {
+        RTile {
+            name: None,
+            lns: vec![format!("@{{{}}}", self.name)],
+            do_trimming: true,
+            tags: vec![],
+            parts: Parts::default(),
             marker: PhantomData::<Rc<()>>,
         }
     }
+}
+
+impl Display for TileRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if !self.exists() {
+            return Ok(());
+        }
+        write!(f, "{}", render_with_default_store(&self.to_tile()))
+    }
+}
+
+/// RenderOptions, used to control how the tiles are rendered, see `set_render_options` and `RTile::to_string_with_options`
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(optional_attribute);
+/// tp!(derive, "#[derive(Debug)]");
+/// let tile = t!("
+///     @{derive}
+///         @{optional_attribute}
+///     pub struct A;
+///     ");
+/// let options = RenderOptions {
+///     drop_blank_placeholder_lines: true,
+///     ..Default::default()
+/// };
+/// assert_eq!(tile.to_string(), "#[derive(Debug)]\n\npub struct A;");
+/// assert_eq!(tile.to_string_with_options(&options), "#[derive(Debug)]\npub struct A;");
+///
+/// let body = t!("Fixes the padding of the multi line placeholders");
+/// let options = RenderOptions {
+///     max_width: Some(20),
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     body.to_string_with_options(&options),
+///     "Fixes the padding of\nthe multi line\nplaceholders"
+/// );
+///
+/// tp!(imports, "use std::fmt;");
+/// tp!(types, "pub struct A;\npub struct B;");
+/// let file = t!("
+///     @{imports}
+///
+///     @{types}
+///     ");
+/// let options = RenderOptions {
+///     chunk_hints: rtile::provenance::CommentStyle::for_language("rust"),
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     file.to_string_with_options(&options),
+///     "// section: imports\nuse std::fmt;\n\n// section: types\npub struct A;\npub struct B;"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderOptions {
+    /// drop a line entirely, if it has nothing other than white spaces and placeholders which expanded to blank text, i.e. `skip_if_blank` for every placeholder
+    pub drop_blank_placeholder_lines: bool,
+    /// hard width guarantee, no line of the output is wider than the given number of columns, the wider lines are wrapped at the spaces
+    /// (or broken, if a word doesn't fit) and the continuation lines keep the indentation of the wrapped line
+    pub max_width: Option<usize>,
+    /// the indentation of the output, e.g. the spaces before a nested multi line placeholder and the indentation inside its value
+    /// are re-expressed as the levels of the given style, see `IndentStyle`
+    pub indent_style: Option<IndentStyle>,
+    /// the placeholders `@{content_sha256}` are substituted with the SHA-256 of the rest of the output, see `stamp_content_sha256`,
+    /// instead of expanding to empty text
+    pub content_stamp: bool,
+    /// a separator comment in the given style is inserted before every section of the rendered template, i.e. a line with
+    /// nothing other than a tile placeholder, so a change of a section stays within it in the diffs, the `@# chunks: LANGUAGE`
+    /// (or `@# chunks: none`) metadata of a template overrides it, and inserts the separators in the nested templates too
+    pub chunk_hints: Option<provenance::CommentStyle>,
+    /// the functions applied in order to the output of the render, before the ones added by `add_post_processor`
+    pub post_processors: Vec<PostProcessor>,
+    /// the line ending of the output, see `LineEnding`
+    pub line_ending: LineEnding,
+    /// the render panics with the first problem found in the tile, see `TileStore::try_render`, e.g. a missing tile, instead of
+    /// expanding the missing tile to empty text
+    pub strict: bool,
+}
+
+/// LineEnding, used by `RenderOptions::line_ending` to choose the line ending of the output
+/// ```
+/// use rtile::prelude::*;
+///
+/// let options = RenderOptions {
+///     line_ending: LineEnding::CrLf,
+///     ..Default::default()
+/// };
+/// assert_eq!(t!("@echo off\necho done").to_string_with_options(&options), "@echo off\r\necho done");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`, the line ending of the templates
+    #[default]
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl LineEnding {
+    /// returns the text with every line ending replaced by this line ending
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// assert_eq!(LineEnding::CrLf.apply("a\nb"), "a\r\nb");
+    /// assert_eq!(LineEnding::Lf.apply("a\r\nb"), "a\nb");
+    /// ```
+    pub fn apply(&self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n");
+        match self {
+            LineEnding::Lf => text,
+            LineEnding::CrLf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// IndentStyle, used by `RenderOptions::indent_style` to guarantee the indentation of the output, whatever the indentation of the templates
+///
+/// The indentation unit of the templates is the narrowest indentation of spaces in the output (a tab is a level), so the
+/// levels of the nested tiles are re-expressed in the given style, while the columns left over by the unit are kept as spaces.
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(body, "
+///     if ready:
+///       start()
+/// ");
+/// let tile = t!("
+///     def main():
+///       @{body}
+/// ");
+/// let options = RenderOptions {
+///     indent_style: Some(IndentStyle::for_language("python").unwrap()),
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     tile.to_string_with_options(&options),
+///     "def main():\n    if ready:\n        start()"
+/// );
+///
+/// let options = RenderOptions {
+///     indent_style: Some(IndentStyle::Tabs),
+///     ..Default::default()
+/// };
+/// assert_eq!(tile.to_string_with_options(&options), "def main():\n\tif ready:\n\t\tstart()");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    /// every level is the given number of spaces
+    Spaces(usize),
+    /// every level is a tab
+    Tabs,
+}
+
+impl IndentStyle {
+    ///
+    /// This function returns the style of the given number of spaces.
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// assert_eq!(IndentStyle::spaces(2), IndentStyle::Spaces(2));
+    /// ```
+    ///
+    pub fn spaces(n: usize) -> Self {
+        IndentStyle::Spaces(n)
+    }
+
+    ///
+    /// This function returns the style of the tabs.
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// assert_eq!(IndentStyle::tabs(), IndentStyle::Tabs);
+    /// ```
+    ///
+    pub fn tabs() -> Self {
+        IndentStyle::Tabs
+    }
+
+    ///
+    /// This function returns the conventional style of the given language (case insensitive), or None if the language is not known.
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// assert_eq!(IndentStyle::for_language("go"), Some(IndentStyle::Tabs));
+    /// assert_eq!(IndentStyle::for_language("Python"), Some(IndentStyle::Spaces(4)));
+    /// assert_eq!(IndentStyle::for_language("cobol"), None);
+    /// ```
+    ///
+    pub fn for_language(language: &str) -> Option<Self> {
+        match language.to_ascii_lowercase().as_str() {
+            "go" | "make" | "makefile" => Some(IndentStyle::Tabs),
+            "python" | "rust" | "java" | "kotlin" | "c" | "cpp" | "c++" | "csharp" | "c#"
+            | "swift" | "php" => Some(IndentStyle::Spaces(4)),
+            "javascript" | "typescript" | "json" | "yaml" | "ruby" | "html" | "css" | "lua"
+            | "dart" | "nix" | "terraform" => Some(IndentStyle::Spaces(2)),
+            _ => None,
+        }
+    }
+
+    ///
+    /// This function returns the text of one level of indentation.
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// assert_eq!(IndentStyle::Spaces(2).unit(), "  ");
+    /// assert_eq!(IndentStyle::Tabs.unit(), "\t");
+    /// ```
+    ///
+    pub fn unit(&self) -> String {
+        match self {
+            IndentStyle::Spaces(n) => " ".repeat(*n),
+            IndentStyle::Tabs => "\t".to_string(),
+        }
+    }
+}
+
+/// check_indentation_consistency, used to validate the rendered output of the languages with significant white spaces,
+/// e.g. Python or YAML, returns the issues in the order of the lines
+///
+/// the lines mixing tabs and spaces (or indented differently from the first indented line) and the dedents which don't return
+/// to an outer level of indentation are flagged, the blank lines and the comment lines (starting with `#`) are skipped
+/// ```
+/// use rtile::prelude::*;
+///
+/// let output = "def main():\n    if ready:\n        start()\n      stop()\n   \treturn";
+/// let issues = check_indentation_consistency(output);
+/// assert_eq!(
+///     issues,
+///     vec![
+///         IndentationIssue { line: 4, kind: IndentationIssueKind::UnexpectedDedent },
+///         IndentationIssue { line: 5, kind: IndentationIssueKind::MixedTabsAndSpaces },
+///     ]
+/// );
+/// assert_eq!(issues[0].to_string(), "line 4: the dedent doesn't match any outer indentation level");
+/// assert!(check_indentation_consistency("a:\n  b: 1\nc: 2").is_empty());
+/// ```
+pub fn check_indentation_consistency(output: &str) -> Vec<IndentationIssue> {
+    let mut issues = vec![];
+    let mut indent_char = None;
+    let mut levels = vec![0];
+    for (idx, ln) in output.lines().enumerate() {
+        let content = ln.trim_start_matches([' ', '\t']);
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let indent = &ln[..ln.len() - content.len()];
+        let mixed = indent.contains(' ') && indent.contains('\t');
+        if let Some(c) = indent.chars().next() {
+            if mixed || *indent_char.get_or_insert(c) != c {
+                issues.push(IndentationIssue {
+                    line: idx + 1,
+                    kind: IndentationIssueKind::MixedTabsAndSpaces,
+                });
+            }
+        }
+        let width = indent.len();
+        let mut dedented = false;
+        while levels.last().is_some_and(|level| *level > width) {
+            levels.pop();
+            dedented = true;
+        }
+        if levels.last() != Some(&width) {
+            if dedented {
+                issues.push(IndentationIssue {
+                    line: idx + 1,
+                    kind: IndentationIssueKind::UnexpectedDedent,
+                });
+            }
+            levels.push(width);
+        }
+    }
+    issues
+}
+
+/// IndentationIssue, an issue found by `check_indentation_consistency`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndentationIssue {
+    /// the line of the output, starting from 1
+    pub line: usize,
+    /// the kind of the issue
+    pub kind: IndentationIssueKind,
+}
+
+impl Display for IndentationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let issue = match self.kind {
+            IndentationIssueKind::MixedTabsAndSpaces => "mixed tabs and spaces in the indentation",
+            IndentationIssueKind::UnexpectedDedent => {
+                "the dedent doesn't match any outer indentation level"
+            }
+        };
+        write!(f, "line {}: {}", self.line, issue)
+    }
+}
+
+/// IndentationIssueKind, the kind of an `IndentationIssue`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentationIssueKind {
+    /// the indentation mixes tabs and spaces, or uses a different character than the first indented line
+    MixedTabsAndSpaces,
+    /// the line is dedented to a level which is not one of the outer levels
+    UnexpectedDedent,
+}
+
+/// HighlightStyle, used by `RTile::highlight_placeholders` to mark the placeholders of the raw tile
+/// ```
+/// use rtile::prelude::*;
+///
+/// let tile = t!("<b>@{name}</b>");
+/// assert_eq!(tile.highlight_placeholders(HighlightStyle::Ansi), "<b>\x1b[36m@{name}\x1b[0m</b>");
+/// assert_eq!(
+///     tile.highlight_placeholders(HighlightStyle::Html),
+///     "&lt;b&gt;<span class=\"rtile-placeholder\">@{name}</span>&lt;/b&gt;"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightStyle {
+    /// the placeholders are colored using the ansi escape codes, for the terminals
+    Ansi,
+    /// the text is html escaped and the placeholders are wrapped in `<span class="rtile-placeholder">`
+    Html,
+}
+
+/// NumFormat, used by `RTile::from_numbers` to format a numeric column
+/// ```
+/// use rtile::prelude::*;
+///
+/// let format = NumFormat {
+///     precision: Some(2),
+///     thousands_sep: Some(','),
+///     ..Default::default()
+/// };
+/// let salaries = RTile::from_numbers([52000.5, 1250.0, 987654.25], format);
+/// assert_eq!(salaries.to_string(), " 52,000.50\n  1,250.00\n987,654.25");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumFormat {
+    /// the minimum width of the column, the narrower columns are padded with spaces
+    pub width: usize,
+    /// the number of digits after the decimal point, `None` keeps the digits of the number as they are
+    pub precision: Option<usize>,
+    /// the separator placed between the groups of thousands of the integer part, if any
+    pub thousands_sep: Option<char>,
+    /// pad the column on the left (true) or on the right (false), to reach the width
+    pub align_right: bool,
+    /// the locale of the decimal separator, `None` uses `.`, see `Locale::num_format` for the thousands separator of the locale too
+    pub locale: Option<Locale>,
+    /// the currency or the unit symbol of the numbers, e.g. `$` or ` kg`, placed as per the symbol position
+    pub symbol: Option<&'static str>,
+    /// where the symbol is placed on every line of the column
+    pub symbol_position: SymbolPosition,
+}
+
+/// SymbolPosition, used by `NumFormat` to place the currency or the unit symbol of a numeric column
+/// ```
+/// use rtile::prelude::*;
+///
+/// let format = |symbol, symbol_position| NumFormat {
+///     precision: Some(2),
+///     symbol: Some(symbol),
+///     symbol_position,
+///     ..Default::default()
+/// };
+/// let amounts = [3.5, -1250.0];
+/// let column = |symbol, position| RTile::from_numbers(amounts, format(symbol, position)).to_string();
+/// assert_eq!(column("$", SymbolPosition::Leading), "$    3.50\n$-1250.00");
+/// assert_eq!(column("$", SymbolPosition::Attached), "    $3.50\n-$1250.00");
+/// assert_eq!(column(" €", SymbolPosition::Trailing), "    3.50 €\n-1250.00 €");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymbolPosition {
+    /// at the left edge of the column, the numbers are padded between the symbol and the number
+    #[default]
+    Leading,
+    /// right before the digits of every number, after the sign
+    Attached,
+    /// right after every number, the numbers are aligned on their decimal points all the same
+    Trailing,
+}
 
-    pub fn from_str_without_trimming(val: &str) -> Self {
-        let lns: Vec<&str> = val.split('\n').collect();
-        let lns: Vec<String> = lns.iter().map(|&item| item.to_string()).collect();
-        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
+impl Default for NumFormat {
+    fn default() -> Self {
         Self {
-            name: None,
-            lns: trim(lns, false),
-            do_trimming: false,
-            marker: PhantomData::<Rc<()>>,
+            width: 0,
+            precision: None,
+            thousands_sep: None,
+            align_right: true,
+            locale: None,
+            symbol: None,
+            symbol_position: SymbolPosition::Leading,
         }
     }
+}
 
-    pub fn get_names_of_blank_inner_tiles(&self) -> Vec<String> {
-        let mut processed_tiles: HashSet<String> = HashSet::new();
-        let mut blank_inner_tiles = vec![];
-        get_blank_inner_tiles_names(
-            self.name.clone(),
-            &self.lns,
-            &mut processed_tiles,
-            &mut blank_inner_tiles,
-        );
-        blank_inner_tiles
+/// Locale, used to format the numbers and the dates of the human readable outputs (e.g. invoices, summaries) for a language
+/// ```
+/// use rtile::prelude::*;
+///
+/// let prices = RTile::from_numbers([1250.0, 52000.5], Locale::DE.num_format(Some(2)));
+/// assert_eq!(prices.to_string(), " 1.250,00\n52.000,50");
+/// assert_eq!(Locale::FR.format_number(-1234.5, Some(1)), "-1\u{202f}234,5");
+/// assert_eq!(Locale::DE.format_date("%d. %B %Y", 2024, 3, 7), "07. März 2024");
+/// assert_eq!(Locale::EN.format_date("%b %e, %Y", 2024, 3, 7), "Mar 7, 2024");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    /// the separator of the integer part and the fraction part of a number
+    pub decimal_sep: char,
+    /// the separator placed between the groups of thousands of the integer part
+    pub thousands_sep: char,
+    /// the names of the months, from January
+    pub month_names: [&'static str; 12],
+    /// the abbreviated names of the months, from January
+    pub short_month_names: [&'static str; 12],
+}
+
+impl Locale {
+    /// English
+    pub const EN: Locale = Locale {
+        decimal_sep: '.',
+        thousands_sep: ',',
+        month_names: [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ],
+        short_month_names: [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ],
+    };
+
+    /// German
+    pub const DE: Locale = Locale {
+        decimal_sep: ',',
+        thousands_sep: '.',
+        month_names: [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+        short_month_names: [
+            "Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.", "Okt.", "Nov.",
+            "Dez.",
+        ],
+    };
+
+    /// French, the thousands are separated by a narrow no-break space
+    pub const FR: Locale = Locale {
+        decimal_sep: ',',
+        thousands_sep: '\u{202f}',
+        month_names: [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        short_month_names: [
+            "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.",
+            "nov.", "déc.",
+        ],
+    };
+
+    /// Spanish
+    pub const ES: Locale = Locale {
+        decimal_sep: ',',
+        thousands_sep: '.',
+        month_names: [
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+        short_month_names: [
+            "ene.", "feb.", "mar.", "abr.", "may.", "jun.", "jul.", "ago.", "sept.", "oct.",
+            "nov.", "dic.",
+        ],
+    };
+
+    /// returns the format of a numeric column using the decimal and the thousands separators of the locale
+    pub fn num_format(&self, precision: Option<usize>) -> NumFormat {
+        NumFormat {
+            precision,
+            thousands_sep: Some(self.thousands_sep),
+            locale: Some(*self),
+            ..Default::default()
+        }
     }
 
-    pub fn reevaluate(&self) -> String {
-        // calling r_format_using_processed_tiles_data, as all the inner tiles are supposed to be reevaluated / processed by now
-        trim(
-            r_format_using_processed_tiles_data(self.lns.join("\n").as_str()),
-            self.do_trimming,
-        )
-        .join("\n")
+    /// returns the number formatted using the decimal and the thousands separators of the locale
+    pub fn format_number(&self, number: f64, precision: Option<usize>) -> String {
+        let (integer, fraction) = format_number(number, &self.num_format(precision));
+        integer + &fraction
     }
 
-    pub fn join<T: Display + Debug>(&self, x: &[T], last: Option<RTile>) -> Self {
-        let mut res = RTile::new_without_trimming(vec![]);
-        for (idx, item) in x.iter().enumerate() {
-            match item {
-                i if type_name::<T>() == "rtile::RTile" => {
-                    let lns = i
-                        .to_string()
-                        .split('\n')
-                        .collect::<Vec<&str>>()
-                        .iter()
-                        .map(|&item| item.to_string())
-                        .collect();
-                    res += RTile::new_without_trimming(lns);
-                }
-                i => {
-                    res += RTile::construct_from_str(i.to_string().as_str());
+    /// returns the date formatted using the pattern, where `%Y` is the year, `%m` and `%d` are the month and the day with two digits,
+    /// `%e` is the day without padding, `%B` and `%b` are the name and the abbreviated name of the month, and `%%` is a `%`.
+    /// panics on a month out of 1..=12
+    pub fn format_date(&self, pattern: &str, year: i32, month: u32, day: u32) -> String {
+        let month_idx = match month {
+            1..=12 => month as usize - 1,
+            _ => panic!("invalid month {} in the date", month),
+        };
+        let mut result = String::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                result.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => result += &year.to_string(),
+                Some('m') => result += &format!("{:02}", month),
+                Some('d') => result += &format!("{:02}", day),
+                Some('e') => result += &day.to_string(),
+                Some('B') => result += self.month_names[month_idx],
+                Some('b') => result += self.short_month_names[month_idx],
+                Some('%') => result.push('%'),
+                Some(other) => {
+                    result.push('%');
+                    result.push(other);
                 }
-            };
-            if idx < x.len() - 1 {
-                res += self.clone();
+                None => result.push('%'),
             }
         }
-        match last {
-            Some(t) => res + t,
-            None => res,
-        }
+        result
     }
+}
 
-    pub fn vjoin<T: Display + Debug>(&self, x: &[T], inline: bool, last: Option<RTile>) -> Self {
-        let last = last.unwrap_or_else(|| RTile::new(vec![]));
-        let mut res = RTile::new_without_trimming(vec![]);
-        for (idx, item) in x.iter().enumerate() {
-            match item {
-                i if type_name::<T>() == "rtile::RTile" => {
-                    let lns = i
-                        .to_string()
-                        .split('\n')
-                        .collect::<Vec<&str>>()
-                        .iter()
-                        .map(|&item| item.to_string())
-                        .collect();
+/// ColumnSpec, used by `RTile::from_matrix` to lay out the columns of a grid
+/// ```
+/// use rtile::prelude::*;
+///
+/// let spec = ColumnSpec {
+///     align: vec![ColumnAlign::Left, ColumnAlign::Right, ColumnAlign::Center],
+///     separators: vec![" | ".to_string(), " ".to_string()],
+///     ..Default::default()
+/// };
+/// let rows = vec![
+///     vec!["name".to_string(), "qty".to_string(), "unit".to_string()],
+///     vec!["apple".to_string(), 3.to_string(), "kg".to_string()],
+///     vec!["kiwi".to_string(), 12.to_string(), "pcs".to_string()],
+/// ];
+/// let grid = RTile::from_matrix(&rows, spec);
+/// assert_eq!(grid.to_string(), "name  | qty unit\napple |   3  kg \nkiwi  |  12 pcs ");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnSpec {
+    /// the alignment of every column, the columns beyond the list are aligned to the left
+    pub align: Vec<ColumnAlign>,
+    /// the texts placed between the columns, the first one after the first column and so on,
+    /// the last one is used for the columns beyond the list, no separators place a space between the columns
+    pub separators: Vec<String>,
+    /// the maximum width of every column, the columns beyond the list (or with `None`) have no maximum width
+    pub max_widths: Vec<Option<usize>>,
+    /// the overflow policy of every column, i.e. what happens to the cells wider than the maximum width of the column,
+    /// the columns beyond the list grow
+    pub overflow: Vec<Overflow>,
+}
 
-                    res |= RTile::new_without_trimming(lns)
-                        + if inline {
-                            if idx < x.len() - 1 {
-                                self.clone()
-                            } else {
-                                last.clone()
-                            }
-                        } else {
-                            RTile::construct_from_str("")
-                        }
-                }
-                i => {
-                    res |= RTile::construct_from_str(i.to_string().as_str())
-                        + if inline {
-                            if idx < x.len() - 1 {
-                                self.clone()
-                            } else {
-                                last.clone()
-                            }
-                        } else {
-                            RTile::construct_from_str("")
-                        }
-                }
-            };
-            if !inline {
-                let tile = if idx < x.len() - 1 {
-                    self.clone()
-                } else {
-                    last.clone()
-                };
-                res |= tile;
+impl ColumnSpec {
+    // the line of the cell fitted into the maximum width of the column
+    fn fit(&self, column: usize, ln: &str) -> Vec<String> {
+        let max_width = self.max_widths.get(column).copied().flatten();
+        let overflow = self.overflow.get(column).unwrap_or(&Overflow::Grow);
+        match (max_width, overflow) {
+            (Some(max_width), Overflow::Wrap) => hard_wrap(ln, max_width),
+            (Some(max_width), Overflow::Truncate(marker)) if display_width(ln) > max_width => {
+                let available = max_width.saturating_sub(display_width(marker));
+                let mut truncated: String = ln.chars().take(available).collect();
+                truncated.push_str(marker);
+                vec![truncated.chars().take(max_width).collect()]
             }
+            _ => vec![ln.to_string()],
         }
-        res
     }
 
-    ///
-    /// This function returns the trimmed raw data of the tile
-    ///
-    /// ex:
-    /// let tile = t!("   @{another_tile_one}             @{another_tile_two}       ");
-    /// calling tile.raw(); would return a trimmed, non-expanded raw data of the tile
-    /// so tile.raw() == "@{another_tile_one}             @{another_tile_two}".to_string();
-    pub fn raw(&self) -> String {
-        trim(self.lns.clone(), self.do_trimming)
-            .join("\n")
-            .to_string()
+    // the text placed after the given column
+    fn separator(&self, column: usize) -> &str {
+        self.separators
+            .get(column)
+            .or(self.separators.last())
+            .map_or(" ", |separator| separator.as_str())
     }
+}
 
-    pub fn has_inner_tiles_in_raw_data(&self) -> bool {
-        for ln in &self.lns {
-            let start = ln[..].find("@{").unwrap_or(ln.len());
-            if start == ln.len() {
-                continue;
-            }
-            let end = ln[start..].find('}').map_or(0, |i| i + 1);
-            if end == 0 {
-                panic!("unfinished @{{}} expression");
+/// Overflow, the policy of a column of `ColumnSpec` for the cells wider than the maximum width of the column
+/// ```
+/// use rtile::prelude::*;
+///
+/// let spec = ColumnSpec {
+///     separators: vec![" | ".to_string()],
+///     max_widths: vec![Some(8), Some(8), Some(8)],
+///     overflow: vec![Overflow::Wrap, Overflow::Truncate("...".to_string()), Overflow::Grow],
+///     ..Default::default()
+/// };
+/// let text = "a long description";
+/// let grid = RTile::from_matrix(&[[text, text, text]], spec);
+/// assert_eq!(
+///     grid.to_string(),
+///     "a long   | a lon... | a long description\ndescript |          |                   \nion      |          |                   "
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// the column grows to fit the cell, i.e. the maximum width is ignored
+    #[default]
+    Grow,
+    /// the cell is wrapped into lines of at most the maximum width, at the spaces where possible
+    Wrap,
+    /// the cell is cut to the maximum width, ending with the given marker (e.g. `...`)
+    Truncate(String),
+}
+
+/// ColumnAlign, the alignment of a column of `ColumnSpec`, a centered text leans to the left
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColumnAlign {
+    /// the text is padded on the right
+    #[default]
+    Left,
+    /// the text is padded on the left
+    Right,
+    /// the text is padded on both sides
+    Center,
+}
+
+// splits the formatted number into its integer part (with the thousands separators) and its fraction part (with the decimal point)
+fn format_number(number: f64, format: &NumFormat) -> (String, String) {
+    let formatted = match format.precision {
+        Some(precision) => format!("{number:.precision$}"),
+        None => number.to_string(),
+    };
+    let (integer, fraction) = match formatted.find('.') {
+        Some(idx) => formatted.split_at(idx),
+        None => (formatted.as_str(), ""),
+    };
+    let integer = match format.thousands_sep {
+        Some(sep) => {
+            let (sign, digits) =
+                integer.split_at(integer.len() - integer.trim_start_matches('-').len());
+            let mut grouped = String::new();
+            for (idx, c) in digits.chars().enumerate() {
+                if idx > 0 && (digits.len() - idx) % 3 == 0 && c.is_ascii_digit() {
+                    grouped.push(sep);
+                }
+                grouped.push(c);
             }
-            return true;
+            format!("{sign}{grouped}")
+        }
+        None => integer.to_string(),
+    };
+    let integer = match (format.symbol, format.symbol_position) {
+        (Some(symbol), SymbolPosition::Attached) => {
+            let digits = integer.trim_start_matches('-');
+            let sign = &integer[..integer.len() - digits.len()];
+            format!("{sign}{symbol}{digits}")
+        }
+        _ => integer,
+    };
+    let fraction = match (format.locale, fraction.strip_prefix('.')) {
+        (Some(locale), Some(digits)) => format!("{}{}", locale.decimal_sep, digits),
+        _ => fraction.to_string(),
+    };
+    (integer, fraction)
+}
+
+// renders the tile using the tls (thread local storage), the closures of the dynamic tiles are called while the tls isn't borrowed,
+// so they are free to persist and render the tiles of the tls. a closure rendering its own tile gets the previous value of the tile
+fn render_with_default_store(tile: &RTile) -> String {
+    let options = TL_STORE.with_borrow(|v| v.template_render_options(tile));
+    with_render_options(options, || {
+        evaluate_dynamic_tiles_with_default_store(tile);
+        TL_STORE.with_borrow_mut(|v| v.expand_for_render(tile))
+    })
+}
+
+// the closures of the dynamic tiles are called without borrowing the tls, so they can use the tls themselves
+fn evaluate_dynamic_tiles_with_default_store(tile: &RTile) {
+    struct EvaluatingGuard(String);
+    impl Drop for EvaluatingGuard {
+        fn drop(&mut self) {
+            TL_EVALUATING_DYNAMIC_TILES.with_borrow_mut(|v| v.remove(&self.0));
         }
-        false
     }
 
-    pub fn inner_tiles_in_raw_data(&self) -> Vec<Vec<String>> {
-        let mut result = vec![];
-        for ln in &self.lns {
-            let mut tiles_on_line = vec![];
-            let mut current_cursor = 0_usize;
-            let mut end = 0;
-            while let Some(tile_name) = get_next_inner_tile_name(ln, &mut current_cursor, &mut end)
-            {
-                tiles_on_line.push(tile_name.clone());
-                current_cursor = end;
-            }
-            result.push(tiles_on_line);
+    let mut evaluated: HashSet<String> = TL_EVALUATING_DYNAMIC_TILES.with_borrow(|v| v.clone());
+    loop {
+        // a dynamic tile may depend on other dynamic tiles
+        let pending = TL_STORE.with_borrow(|v| v.pending_dynamic_tiles(tile, &evaluated));
+        if pending.is_empty() {
+            break;
+        }
+        for (tile_name, dynamic_tile) in pending {
+            TL_EVALUATING_DYNAMIC_TILES.with_borrow_mut(|v| v.insert(tile_name.clone()));
+            let guard = EvaluatingGuard(tile_name.clone());
+            let value = dynamic_tile.evaluate();
+            drop(guard);
+            TL_STORE.with_borrow_mut(|v| v.set_dynamic_value(tile_name.clone(), value));
+            evaluated.insert(tile_name);
         }
-        result
     }
+}
 
-    pub fn inner_tiles(&self) -> HashSet<String> {
-        let mut inner_tiles: Vec<String> = vec![];
-        let mut processed_tiles: HashSet<String> = HashSet::new();
+fn create_blank_tiles_of_any_missing_inner_tiles(name: Option<String>, lns: &[String]) {
+    if TL_DETACHED.get() {
+        // the tile is constructed for an explicit store, so the tls (thread local storage) is left untouched
+        return;
+    }
+    TL_STORE.with_borrow_mut(|v| v.create_blank_tiles_of_any_missing_inner_tiles(name, lns));
+}
 
-        find_inner_tiles(&String::new(), self, &mut processed_tiles, &mut inner_tiles);
+// records the parts of the tile before the other tile is added beside or below it, see `stp_deep!`
+fn record_parts(tile: &mut RTile, other: &RTile, beside: bool) {
+    let mut tiles = match tile.parts.0.take() {
+        Some(composition) if composition.beside == beside => Rc::unwrap_or_clone(composition).tiles,
+        parts => vec![RTile {
+            parts: Parts(parts),
+            ..tile.clone()
+        }],
+    };
+    tiles.push(other.clone());
+    tile.parts = Parts(Some(Rc::new(Composition { beside, tiles })));
+}
 
-        inner_tiles.into_iter().collect()
+// a template of placeholders for the parts of the composed tile, and the anonymous parts named after the tile, inner parts first.
+// a part named like the persisted tile is its previous value, which is taken as it was expanded, as the names of its parts are reused
+pub(crate) fn decompose(
+    name: &str,
+    tile: &RTile,
+    previous: &RTile,
+    parts: &mut Vec<(String, RTile)>,
+) -> RTile {
+    let Some(composition) = tile
+        .parts
+        .0
+        .as_ref()
+        .filter(|composition| composition.matches(tile))
+    else {
+        return tile.clone();
+    };
+    let mut placeholders = vec![];
+    for (i, part) in composition.tiles.iter().enumerate() {
+        if part.lns.is_empty() {
+            continue;
+        }
+        let part_name = match &part.name {
+            Some(part_name) if Some(part_name) != previous.name.as_ref() => part_name.clone(),
+            Some(_) => {
+                let part_name = format!("{}_{}", name, i);
+                parts.push((part_name.clone(), previous.clone()));
+                part_name
+            }
+            None => {
+                let part_name = format!("{}_{}", name, i);
+                let part_template = decompose(&part_name, part, previous, parts);
+                parts.push((part_name.clone(), part_template));
+                part_name
+            }
+        };
+        placeholders.push(format!("@{{{}}}", part_name));
     }
-
-    pub fn flatten(&self) -> String {
-        self.lns
-            .iter()
-            .map(|item| item.trim())
-            .collect::<Vec<&str>>()
-            .join("")
+    RTile {
+        name: None,
+        lns: if composition.beside {
+            vec![placeholders.concat()]
+        } else {
+            placeholders
+        },
+        do_trimming: tile.do_trimming,
+        tags: vec![],
+        parts: Parts::default(),
+        marker: PhantomData::<Rc<()>>,
     }
+}
 
-    pub fn dimensions(&self) -> (usize, usize) {
-        let width = self
-            .lns
-            .iter()
-            .map(|s| s.chars().count())
-            .max()
-            .unwrap_or(0);
-        let height = self.lns.len();
-        (width, height)
+// the tags of the lines below the upper lines
+fn stack_tags(
+    upper_height: usize,
+    upper_tags: Vec<Option<String>>,
+    lower_tags: Vec<Option<String>>,
+) -> Vec<Option<String>> {
+    if lower_tags.is_empty() {
+        return upper_tags;
     }
+    let mut tags = upper_tags;
+    tags.resize(upper_height, None);
+    tags.extend(lower_tags);
+    tags
 }
 
-fn create_blank_tiles_of_any_missing_inner_tiles(name: Option<String>, lns: &[String]) {
-    let mut processed_tiles: HashSet<String> = HashSet::new();
-    let mut missing_inner_tiles: HashSet<String> = HashSet::new();
-    identify_any_missing_inner_tiles(name, lns, &mut processed_tiles, &mut missing_inner_tiles);
-    if !missing_inner_tiles.is_empty() {
-        for missing_inner_tile_name in missing_inner_tiles {
-            TL_RAW_TILES.with_borrow_mut(|v| {
-                v.insert(
-                    missing_inner_tile_name.clone(),
-                    RTile {
-                        name: Some(missing_inner_tile_name.clone()),
-                        lns: vec![],
-                        do_trimming: true,
-                        marker: PhantomData::<Rc<()>>,
-                    },
-                )
-            });
-            TL_PROCESSED_TILES
-                .with_borrow_mut(|v| v.insert(missing_inner_tile_name.clone(), String::new()));
+// the tags of the lines side by side, the tag of the left line wins
+fn merge_tags(
+    left_tags: Vec<Option<String>>,
+    right_tags: Vec<Option<String>>,
+) -> Vec<Option<String>> {
+    let mut tags = left_tags;
+    if tags.len() < right_tags.len() {
+        tags.resize(right_tags.len(), None);
+    }
+    for (tag, right_tag) in tags.iter_mut().zip(right_tags) {
+        if tag.is_none() {
+            *tag = right_tag;
         }
     }
+    tags
 }
 
 impl Add for RTile {
     type Output = Self;
 
     fn add(self, other: RTile) -> Self::Output {
-        let mut lns = self.lns.clone();
-        append(&mut lns, other.lns);
-
-        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
-
-        Self {
-            name: None,
-            lns,
-            do_trimming: self.do_trimming,
-            marker: PhantomData::<Rc<()>>,
-        }
+        let mut tile = self;
+        tile += other;
+        tile.name = None;
+        tile
     }
 }
 
 impl AddAssign for RTile {
     fn add_assign(&mut self, other: Self) {
+        record_parts(self, &other, true);
+        self.tags = merge_tags(std::mem::take(&mut self.tags), other.tags);
         append(&mut self.lns, other.lns);
     }
 }
@@ -1595,46 +5618,144 @@ impl BitOr for RTile {
     type Output = Self;
 
     fn bitor(self, other: RTile) -> Self::Output {
-        let lns = [&self.lns[..], &other.lns[..]].concat();
-
-        create_blank_tiles_of_any_missing_inner_tiles(None, &lns);
-
-        Self {
-            name: None,
-            lns,
-            do_trimming: self.do_trimming,
-            marker: PhantomData::<Rc<()>>,
-        }
+        let mut tile = self;
+        tile |= other;
+        tile.name = None;
+        tile
     }
 }
 
 impl BitOrAssign for RTile {
     fn bitor_assign(&mut self, other: Self) {
-        self.lns = [&self.lns[..], &other.lns[..]].concat();
+        record_parts(self, &other, false);
+        self.tags = stack_tags(self.lns.len(), std::mem::take(&mut self.tags), other.tags);
+        self.lns.extend(other.lns);
     }
 }
 
 impl Display for RTile {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        if self.do_trimming {
-            write!(
-                f,
-                "{}",
-                trim(
-                    r_format_using_raw_tiles_data(self.lns.join("\n").as_str()),
-                    true,
-                )
-                .join("\n")
-            )
-        } else {
-            let ktile_vec = r_format_using_raw_tiles_data(self.lns.join("\n").as_str());
-            let blank_vec = vec![""; ktile_vec.len()];
-            let output = k!(blank_vec) + k!(ktile_vec) + k!(blank_vec);
-            write!(
-                f,
-                "{}",
-                r_format_using_raw_tiles_data(output.lns.join("\n").as_str()).join("\n")
-            )
+        write!(f, "{}", render_with_default_store(self))
+    }
+}
+
+/// RSpacer, a blank pseudo tile which takes its size from the tile it is composed with, using `+` or `|`
+///
+/// - `RSpacer::h(n)` is n columns wide, beside a tile it is as tall as the tile, stacked with a tile it adds nothing
+/// - `RSpacer::v(n)` is n lines tall, stacked with a tile it is as wide as the tile, beside a tile it only makes it taller
+/// - `RSpacer::flex()` takes both the width and the height of the tile, e.g. to reserve the room of a tile
+/// ```
+/// use rtile::prelude::*;
+///
+/// let names = k!("one\ntwo\nthree");
+/// let numbers = k!("1\n2\n3");
+/// assert_eq!((names.clone() + RSpacer::h(2) + numbers).to_string(), "one    1\ntwo    2\nthree  3");
+/// assert_eq!((names.clone() | RSpacer::v(1) | k!("four")).to_string(), "one  \ntwo  \nthree\n     \nfour ");
+/// assert_eq!((RSpacer::flex() + k!("ab\ncd")).to_string(), "  ab\n  cd");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RSpacer {
+    width: Option<usize>,
+    height: Option<usize>,
+}
+
+impl RSpacer {
+    /// creates a horizontal spacer, n columns wide
+    pub fn h(n: usize) -> Self {
+        Self {
+            width: Some(n),
+            height: None,
+        }
+    }
+
+    /// creates a vertical spacer, n lines tall
+    pub fn v(n: usize) -> Self {
+        Self {
+            width: None,
+            height: Some(n),
+        }
+    }
+
+    /// creates a spacer as wide and as tall as the tile it is composed with
+    pub fn flex() -> Self {
+        Self {
+            width: None,
+            height: None,
         }
     }
+
+    /// returns the spacer as a blank tile, as placed beside the given tile
+    pub fn beside(&self, tile: &RTile) -> RTile {
+        let (width, height) = tile.dimensions();
+        let flex_width = if self.height.is_none() { width } else { 0 };
+        self.blank(
+            self.width.unwrap_or(flex_width),
+            self.height.unwrap_or(height),
+        )
+    }
+
+    /// returns the spacer as a blank tile, as stacked with the given tile
+    pub fn below(&self, tile: &RTile) -> RTile {
+        let (width, height) = tile.dimensions();
+        let flex_height = if self.width.is_none() { height } else { 0 };
+        self.blank(
+            self.width.unwrap_or(width),
+            self.height.unwrap_or(flex_height),
+        )
+    }
+
+    /// returns the spacer as a blank tile on its own, the sizes taken from a tile are one column wide or one line tall
+    pub fn to_tile(&self) -> RTile {
+        self.blank(self.width.unwrap_or(1), self.height.unwrap_or(1))
+    }
+
+    fn blank(&self, width: usize, height: usize) -> RTile {
+        RTile::new_without_trimming(vec![" ".repeat(width); height])
+    }
+}
+
+impl Add<RSpacer> for RTile {
+    type Output = RTile;
+
+    fn add(self, spacer: RSpacer) -> Self::Output {
+        let blank = spacer.beside(&self);
+        self + blank
+    }
+}
+
+impl Add<RTile> for RSpacer {
+    type Output = RTile;
+
+    fn add(self, tile: RTile) -> Self::Output {
+        self.beside(&tile) + tile
+    }
+}
+
+impl AddAssign<RSpacer> for RTile {
+    fn add_assign(&mut self, spacer: RSpacer) {
+        *self += spacer.beside(self);
+    }
+}
+
+impl BitOr<RSpacer> for RTile {
+    type Output = RTile;
+
+    fn bitor(self, spacer: RSpacer) -> Self::Output {
+        let blank = spacer.below(&self);
+        self | blank
+    }
+}
+
+impl BitOr<RTile> for RSpacer {
+    type Output = RTile;
+
+    fn bitor(self, tile: RTile) -> Self::Output {
+        self.below(&tile) | tile
+    }
+}
+
+impl BitOrAssign<RSpacer> for RTile {
+    fn bitor_assign(&mut self, spacer: RSpacer) {
+        *self |= spacer.below(self);
+    }
 }
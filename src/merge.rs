@@ -0,0 +1,213 @@
+//!
+//! Three way merge of the generated output and the user edits, to regenerate the output without clobbering the edits.
+//!
+
+use crate::RTile;
+
+///
+/// MergeOutcome, returned by `three_way_merge`
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeOutcome {
+    /// the regenerated lines with the user edits applied, the conflicts are marked as `<<<<<<< user`, `=======` and `>>>>>>> generated`
+    pub merged: Vec<String>,
+    /// the conflicts, in the order of their appearance in the merged lines
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeOutcome {
+    /// returns true, if the user edits could be applied without any conflicts
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// returns the merged lines as a tile, which keeps the white spaces
+    pub fn to_tile(&self) -> RTile {
+        crate::detached(|| RTile::new_without_trimming(self.merged.clone()))
+    }
+}
+
+///
+/// MergeConflict, a region which was changed both by the user and by the regeneration
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// index of the `<<<<<<< user` marker in the merged lines
+    pub line: usize,
+    /// the lines of the region as edited by the user
+    pub user: Vec<String>,
+    /// the lines of the region as regenerated
+    pub generated: Vec<String>,
+}
+
+///
+/// three_way_merge re-applies the edits the user made to the previously generated output (`generated_old` -> `user_edited`)
+/// onto the regenerated output (`regenerated`), line by line, `generated_old` being the common ancestor of the two others
+///
+/// the regions changed only by the user or only by the regeneration are taken as they are, the regions changed by both
+/// in different ways are reported as conflicts. the expanded lines (which are padded to the width of their tile) are compared
+/// without their trailing white spaces, but they are merged as they are, the lines kept by the user keeping the white spaces
+/// of the user.
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::merge::three_way_merge;
+///
+/// let generated_old = k!("struct A {\n    a: u8,\n}");
+/// let user_edited = k!("// edited\nstruct A {\n    a: u8,\n}");
+/// let regenerated = k!("struct A {\n    a: u8,\n    b: u8,\n}");
+///
+/// let outcome = three_way_merge(&regenerated, &generated_old, &user_edited);
+/// assert!(outcome.is_clean());
+/// assert_eq!(
+///     outcome.to_tile().to_string(),
+///     k!("// edited\nstruct A {\n    a: u8,\n    b: u8,\n}").to_string()
+/// );
+/// ```
+///
+pub fn three_way_merge(
+    regenerated: &RTile,
+    generated_old: &RTile,
+    user_edited: &RTile,
+) -> MergeOutcome {
+    let generated = lines_of(regenerated);
+    let old = lines_of(generated_old);
+    let user = lines_of(user_edited);
+    let generated_keys = keys_of(&generated);
+    let old_keys = keys_of(&old);
+    let user_keys = keys_of(&user);
+
+    let user_matches = matching_lines(&old_keys, &user_keys);
+    let generated_matches = matching_lines(&old_keys, &generated_keys);
+
+    let mut merged = vec![];
+    let mut conflicts = vec![];
+    let (mut o, mut u, mut g) = (0, 0, 0);
+    loop {
+        // the next line of the old output which is kept by both the user and the regeneration
+        let stable = (o..old.len()).find_map(|i| match (user_matches[i], generated_matches[i]) {
+            (Some(ui), Some(gi)) => Some((i, ui, gi)),
+            _ => None,
+        });
+        let (o_end, u_end, g_end) = stable.unwrap_or((old.len(), user.len(), generated.len()));
+        if (o_end, u_end, g_end) != (o, u, g) {
+            let old_chunk = &old_keys[o..o_end];
+            let user_chunk = &user_keys[u..u_end];
+            let generated_chunk = &generated_keys[g..g_end];
+            if user_chunk == generated_chunk {
+                merged.extend_from_slice(&user[u..u_end]);
+            } else if user_chunk == old_chunk {
+                merged.extend_from_slice(&generated[g..g_end]);
+            } else if generated_chunk == old_chunk {
+                merged.extend_from_slice(&user[u..u_end]);
+            } else {
+                conflicts.push(MergeConflict {
+                    line: merged.len(),
+                    user: user[u..u_end].to_vec(),
+                    generated: generated[g..g_end].to_vec(),
+                });
+                merged.push("<<<<<<< user".to_string());
+                merged.extend_from_slice(&user[u..u_end]);
+                merged.push("=======".to_string());
+                merged.extend_from_slice(&generated[g..g_end]);
+                merged.push(">>>>>>> generated".to_string());
+            }
+        }
+        match stable {
+            Some(_) => {
+                merged.push(user[u_end].clone());
+                (o, u, g) = (o_end + 1, u_end + 1, g_end + 1);
+            }
+            None => break,
+        }
+    }
+    MergeOutcome { merged, conflicts }
+}
+
+fn lines_of(tile: &RTile) -> Vec<String> {
+    let output = tile.to_string();
+    if output.is_empty() {
+        vec![]
+    } else {
+        output.split('\n').map(|ln| ln.to_string()).collect()
+    }
+}
+
+// the lines as they are compared, i.e. without their trailing white spaces
+fn keys_of(lns: &[String]) -> Vec<&str> {
+    lns.iter().map(|ln| ln.trim_end()).collect()
+}
+
+// for every line of `a`, the index of the matching line of `b` in their longest common subsequence, the common prefix and
+// suffix are matched first, and the rest is matched using the linear space algorithm of Hirschberg
+pub(crate) fn matching_lines<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; a.len()];
+    match_lines(a, b, 0, 0, &mut matches);
+    matches
+}
+
+fn match_lines<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    a_start: usize,
+    b_start: usize,
+    matches: &mut [Option<usize>],
+) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    for i in 0..prefix {
+        matches[a_start + i] = Some(b_start + i);
+    }
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let (a_start, b_start) = (a_start + prefix, b_start + prefix);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    for i in 0..suffix {
+        matches[a_start + a.len() - suffix + i] = Some(b_start + b.len() - suffix + i);
+    }
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+    if a.len() == 1 {
+        matches[a_start] = b.iter().position(|y| *y == a[0]).map(|j| b_start + j);
+        return;
+    }
+    // the split of `b` maximizing the common lines of the first half of `a` with its head and of the second half with its tail
+    let mid = a.len() / 2;
+    let forward = lcs_lengths(&a[..mid], b, false);
+    let backward = lcs_lengths(&a[mid..], b, true);
+    let split = (0..=b.len())
+        .max_by_key(|&j| (forward[j] + backward[b.len() - j], std::cmp::Reverse(j)))
+        .unwrap_or(0);
+    match_lines(&a[..mid], &b[..split], a_start, b_start, matches);
+    match_lines(
+        &a[mid..],
+        &b[split..],
+        a_start + mid,
+        b_start + split,
+        matches,
+    );
+}
+
+// the lengths of the longest common subsequences of `a` with every head of `b`, or of their tails when reversed
+fn lcs_lengths<T: PartialEq>(a: &[T], b: &[T], reversed: bool) -> Vec<usize> {
+    let at = |s: &[T], i: usize| if reversed { s.len() - 1 - i } else { i };
+    let mut row = vec![0_usize; b.len() + 1];
+    for i in 0..a.len() {
+        let x = &a[at(a, i)];
+        let mut diagonal = 0;
+        for j in 0..b.len() {
+            let up = row[j + 1];
+            row[j + 1] = if *x == b[at(b, j)] {
+                diagonal + 1
+            } else {
+                up.max(row[j])
+            };
+            diagonal = up;
+        }
+    }
+    row
+}
@@ -0,0 +1,57 @@
+//!
+//! Low level line operations used by the tile operators, exposed for building custom operators.
+//!
+
+///
+/// hmerge places the lines of `right` to the side of the lines in `left`, the same way `+` combines two tiles
+///
+/// Every line of `left` is padded with spaces to the width of the widest line in `left` before the
+/// corresponding line of `right` is appended. If `right` has more lines than `left`, blank lines are added to `left` first.
+/// ```
+/// use rtile::ops::hmerge;
+/// let mut left = vec!["one".to_string(), "three".to_string()];
+/// hmerge(&mut left, vec!["|1", "|3", "|5"]);
+/// assert_eq!(left, vec!["one  |1", "three|3", "     |5"]);
+/// ```
+///
+pub fn hmerge<I, T>(left: &mut Vec<String>, right: I)
+where
+    I: IntoIterator<Item = T>,
+    T: Into<String>,
+{
+    crate::append(left, right);
+}
+
+///
+/// hmerge_with_fill is the same as hmerge, but pads the lines of `left` with the given character instead of spaces
+/// ```
+/// use rtile::ops::hmerge_with_fill;
+/// let mut left = vec!["Intro".to_string(), "Installation".to_string()];
+/// hmerge_with_fill(&mut left, vec![" 1", " 4"], '.');
+/// assert_eq!(left, vec!["Intro....... 1", "Installation 4"]);
+/// ```
+///
+pub fn hmerge_with_fill<I, T>(left: &mut Vec<String>, right: I, fill: char)
+where
+    I: IntoIterator<Item = T>,
+    T: Into<String>,
+{
+    crate::append_with_fill(left, right, fill);
+}
+
+///
+/// strip_margin removes the leading and trailing blank lines, the trailing white spaces of every line
+/// and the common left margin of the block of lines, the same way `t!` trims its input
+/// ```
+/// use rtile::ops::strip_margin;
+/// let result = strip_margin(vec!["", "    one  ", "      two", "", "    three", ""]);
+/// assert_eq!(result, vec!["one", "  two", "", "three"]);
+/// ```
+///
+pub fn strip_margin<I, T>(lines: I) -> Vec<String>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<String>,
+{
+    crate::trim(lines, true)
+}
@@ -0,0 +1,184 @@
+//!
+//! OwnedTile, a self-contained snapshot of a tile which can be sent to and rendered on any thread, and the global registry of
+//! the tiles shared by all the threads, see `set_global_tile`
+//!
+
+use super::*;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+use std::sync::PoisonError;
+use std::sync::RwLock;
+
+// the raw lines and the trimming of the tiles of the global registry, by their names
+type GlobalTiles = RwLock<HashMap<String, (Vec<String>, bool)>>;
+
+static GLOBAL_TILES: OnceLock<GlobalTiles> = OnceLock::new();
+
+// counts the changes of the global registry, so the stores refresh the tiles they took from it, see `sync_global_tiles`
+static GLOBAL_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+///
+/// OwnedTile, a snapshot of a tile along with all the tiles it depends on (transitively), captured from a store
+///
+/// The tiles are kept as plain lines, so the snapshot is `Send + Sync` and can be rendered on any thread, independent of
+/// the tls (thread local storage) of the thread it was captured on. The later changes to the store are not seen by the snapshot.
+/// The dynamic tiles are captured with the values they are evaluated to, and the names resolved through `with_prefix` are kept.
+/// ```
+/// use rtile::prelude::*;
+///
+/// tp!(name, "Alice");
+/// tp!(greet, "Hello @{name}!");
+/// let owned = OwnedTile::new(&t!("@{greet}"));
+/// tp!(name, "Bob");
+///
+/// let output = std::thread::spawn(move || owned.render()).join().unwrap();
+/// assert_eq!(output, "Hello Alice!");
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedTile {
+    lns: Vec<String>,
+    do_trimming: bool,
+    dependencies: Vec<(String, Vec<String>, bool)>,
+}
+
+impl OwnedTile {
+    /// captures the tile along with its dependencies from the tls (thread local storage)
+    pub fn new(tile: &RTile) -> Self {
+        evaluate_dynamic_tiles_with_default_store(tile);
+        TL_STORE.with_borrow(|v| Self::capture(v, tile))
+    }
+
+    /// captures the tile along with its dependencies from the given store
+    pub fn from_store(store: &mut TileStore, tile: &RTile) -> Self {
+        store.evaluate_dynamic_tiles(tile);
+        Self::capture(store, tile)
+    }
+
+    // the dynamic tiles are captured with the values they are evaluated to, and the tiles found through the prefixes of
+    // `with_prefix` under the names they are referred to as well, so the snapshot renders the same way on any thread
+    fn capture(store: &TileStore, tile: &RTile) -> Self {
+        Self {
+            lns: tile.lns.clone(),
+            do_trimming: tile.do_trimming,
+            dependencies: store
+                .dependencies(tile)
+                .into_iter()
+                .map(|(tile_name, tile)| (tile_name, tile.lns, tile.do_trimming))
+                .collect(),
+        }
+    }
+
+    /// returns the names of the captured dependencies, in the order they were found
+    pub fn dependencies(&self) -> Vec<&str> {
+        self.dependencies
+            .iter()
+            .map(|(tile_name, _, _)| tile_name.as_str())
+            .collect()
+    }
+
+    /// returns a store with the captured dependencies, along with the tile
+    pub fn to_store(&self) -> (TileStore, RTile) {
+        let mut store = TileStore::new();
+        for (tile_name, lns, do_trimming) in &self.dependencies {
+            store.set_raw(
+                tile_name.clone(),
+                owned_to_tile(Some(tile_name), lns, *do_trimming),
+            );
+        }
+        (store, owned_to_tile(None, &self.lns, self.do_trimming))
+    }
+
+    /// expands the tile using the captured dependencies
+    pub fn render(&self) -> String {
+        let (mut store, tile) = self.to_store();
+        store.render(&tile)
+    }
+}
+
+impl Display for OwnedTile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.render())
+    }
+}
+
+fn owned_to_tile(name: Option<&String>, lns: &[String], do_trimming: bool) -> RTile {
+    RTile {
+        name: name.cloned(),
+        lns: lns.to_vec(),
+        do_trimming,
+        tags: vec![],
+        parts: Parts::default(),
+        marker: PhantomData::<Rc<()>>,
+    }
+}
+
+/// set_global_tile, used to persist the raw lines of the tile in the global registry, which is shared by all the threads, so the
+/// tiles persisted on one thread (e.g. the main thread) are seen by the others (e.g. the worker threads of the code generation).
+/// An inner tile missing in the tls (thread local storage) or in a store is taken from the registry, instead of a blank tile, and
+/// the later changes of the registry are seen by the next render, unless the tile is persisted in the tls or the store itself
+/// ```
+/// use rtile::prelude::*;
+///
+/// set_global_tile("global_doc_license", &t!("// SPDX-License-Identifier: MIT"));
+/// gp!(global_doc_header, "@{global_doc_license}\n// generated");
+/// let output = std::thread::spawn(|| t!("@{global_doc_header}").to_string()).join().unwrap();
+/// assert_eq!(output, "// SPDX-License-Identifier: MIT\n// generated");
+/// assert!(get_global_tile("global_doc_missing").is_none());
+///
+/// let tile = t!("Hello @{global_doc_name}");
+/// assert_eq!(tile.to_string(), "Hello");
+/// set_global_tile("global_doc_name", &t!("Alice"));
+/// assert_eq!(tile.to_string(), "Hello Alice");
+/// set_global_tile("global_doc_name", &t!("Bob"));
+/// assert_eq!(tile.to_string(), "Hello Bob");
+/// ```
+pub fn set_global_tile(name: &str, tile: &RTile) {
+    check_registration(name, tile);
+    global_tiles()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(name.to_string(), (tile.lns.clone(), tile.do_trimming));
+    GLOBAL_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// get_global_tile, used to get the tile persisted in the global registry, see `set_global_tile`
+pub fn get_global_tile(name: &str) -> Option<RTile> {
+    let tiles = GLOBAL_TILES
+        .get()?
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    let (lns, do_trimming) = tiles.get(name)?;
+    Some(owned_to_tile(Some(&name.to_string()), lns, *do_trimming))
+}
+
+/// remove_global_tile, used to remove the tile from the global registry, returns the removed tile, see `set_global_tile`
+pub fn remove_global_tile(name: &str) -> Option<RTile> {
+    let (lns, do_trimming) = GLOBAL_TILES
+        .get()?
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(name)?;
+    GLOBAL_GENERATION.fetch_add(1, Ordering::SeqCst);
+    Some(owned_to_tile(Some(&name.to_string()), &lns, do_trimming))
+}
+
+/// clear_global_tiles, used to remove all the tiles from the global registry, see `set_global_tile`
+pub fn clear_global_tiles() {
+    if let Some(tiles) = GLOBAL_TILES.get() {
+        tiles
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        GLOBAL_GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+pub(crate) fn global_generation() -> u64 {
+    GLOBAL_GENERATION.load(Ordering::SeqCst)
+}
+
+fn global_tiles() -> &'static GlobalTiles {
+    GLOBAL_TILES.get_or_init(|| RwLock::new(HashMap::new()))
+}
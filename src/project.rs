@@ -0,0 +1,795 @@
+//!
+//! Generation projects, i.e. an `rtile.toml` file describing the template roots, the global values and the output files of a
+//! generation run, executed as a whole by `run_project`.
+//!
+//! ```toml
+//! [project]
+//! templates = ["templates"]   # the template roots, relative to the project file
+//! output = "generated"        # the root directory of the output files, relative to the project file
+//!
+//! [values]
+//! crate_name = "demo"
+//! version = "0.1.0"
+//!
+//! [[output]]
+//! template = "lib.rs"         # the path of the template, relative to a template root
+//! path = "src/@{crate_name}.rs"
+//! ```
+//!
+//! Every file under a template root is a tile named after its path relative to the root, with `/` separators
+//! (e.g. `partials/header.rs`), the first root holding a path wins. Every global value is a tile as well, so the templates refer
+//! to both using placeholders. The path of an output file is expanded the same way. The templates and the values are trimmed the
+//! same way as `t!`, and every output file ends with a newline.
+//!
+//! The project file supports the subset of toml used above: the tables, the arrays of tables, the basic and the literal strings
+//! (including the multi line strings), the arrays, and the numbers and the booleans, which are kept as written.
+//!
+
+use crate::detached;
+use crate::emit::EmitReport;
+use crate::emit::Emitter;
+use crate::slash_path;
+use crate::CycleError;
+use crate::RTile;
+use crate::TileStore;
+use crate::TileVisitor;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// the default name of the project file, used by `run_project` when given a directory
+pub const DEFAULT_PROJECT_FILE: &str = "rtile.toml";
+
+///
+/// Project, the generation plan described by a project file
+/// ```
+/// use rtile::project::Project;
+///
+/// let project = Project::parse(
+///     r#"
+///     [values]
+///     name = "Alice"
+///
+///     [[output]]
+///     template = "greet.txt"
+///     path = "@{name}.txt"
+///     "#,
+/// )
+/// .unwrap();
+/// assert_eq!(project.template_roots, vec![std::path::PathBuf::from("templates")]);
+/// assert_eq!(project.values, vec![("name".to_string(), "Alice".to_string())]);
+/// assert_eq!(project.outputs[0].path, "@{name}.txt");
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    /// the directories holding the templates, searched in order
+    pub template_roots: Vec<PathBuf>,
+    /// the root directory of the output files
+    pub output_root: PathBuf,
+    /// the name of the manifest file of the emitter, relative to the output root, see `Emitter::with_manifest`
+    pub manifest: Option<String>,
+    /// the global values, in the order of the project file
+    pub values: Vec<(String, String)>,
+    /// the output files
+    pub outputs: Vec<OutputMapping>,
+}
+
+///
+/// OutputMapping, an output file of a project, i.e. the template rendered into it
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputMapping {
+    /// the name of the template, i.e. its path relative to a template root
+    pub template: String,
+    /// the path of the output file relative to the output root, expanded using the values
+    pub path: String,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self {
+            template_roots: vec![PathBuf::from("templates")],
+            output_root: PathBuf::from("."),
+            manifest: None,
+            values: vec![],
+            outputs: vec![],
+        }
+    }
+}
+
+impl Project {
+    /// parses the project file, the paths are kept as written
+    pub fn parse(text: &str) -> Result<Self, ProjectError> {
+        let mut project = Self::default();
+        let mut seen_tables = vec![];
+        for table in parse_toml(text)? {
+            if !table.array {
+                if seen_tables.contains(&table.name) {
+                    return Err(parse_error(
+                        table.line,
+                        format!("the table [{}] is defined twice", table.name),
+                    ));
+                }
+                seen_tables.push(table.name.clone());
+            }
+            match (table.name.as_str(), table.array) {
+                ("", false) => {
+                    if let Some(entry) = table.entries.first() {
+                        return Err(parse_error(
+                            entry.line,
+                            format!("the key `{}` is outside of a table", entry.key),
+                        ));
+                    }
+                }
+                ("project", false) => {
+                    for entry in table.entries {
+                        match entry.key.as_str() {
+                            "templates" => project.template_roots = entry.paths()?,
+                            "output" => {
+                                project.output_root = PathBuf::from(entry.string()?);
+                            }
+                            "manifest" => project.manifest = Some(entry.string()?),
+                            key => {
+                                return Err(parse_error(
+                                    entry.line,
+                                    format!("unknown key `{}` in [project]", key),
+                                ))
+                            }
+                        }
+                    }
+                }
+                ("values", false) => {
+                    for entry in table.entries {
+                        let value = match entry.value {
+                            TomlValue::String(value) | TomlValue::Other(value) => value,
+                            TomlValue::Array(_) => {
+                                return Err(parse_error(
+                                    entry.line,
+                                    format!("the value `{}` must not be an array", entry.key),
+                                ))
+                            }
+                        };
+                        project.values.push((entry.key, value));
+                    }
+                }
+                ("output", true) => {
+                    let mut template = None;
+                    let mut path = None;
+                    for entry in table.entries {
+                        match entry.key.as_str() {
+                            "template" => template = Some(entry.string()?),
+                            "path" => path = Some(entry.string()?),
+                            key => {
+                                return Err(parse_error(
+                                    entry.line,
+                                    format!("unknown key `{}` in [[output]]", key),
+                                ))
+                            }
+                        }
+                    }
+                    let (Some(template), Some(path)) = (template, path) else {
+                        return Err(parse_error(
+                            table.line,
+                            "an [[output]] needs both the `template` and the `path`",
+                        ));
+                    };
+                    project.outputs.push(OutputMapping { template, path });
+                }
+                (name, true) => {
+                    return Err(parse_error(
+                        table.line,
+                        format!("unknown table [[{}]]", name),
+                    ))
+                }
+                (name, false) => {
+                    return Err(parse_error(table.line, format!("unknown table [{}]", name)))
+                }
+            }
+        }
+        Ok(project)
+    }
+
+    /// loads the project file, the relative paths are resolved against the directory of the project file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ProjectError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|error| ProjectError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let mut project = Self::parse(&text)?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        for root in &mut project.template_roots {
+            *root = base.join(&*root);
+        }
+        project.output_root = base.join(&project.output_root);
+        Ok(project)
+    }
+
+    /// renders the output files, returns their paths relative to the output root along with their contents, nothing is written
+    pub fn render(&self) -> Result<Vec<(String, String)>, ProjectError> {
+        let mut store = TileStore::new();
+        for (name, value) in &self.values {
+            let tile = detached(|| RTile::construct_from_str(value));
+            store.set_raw(
+                name.to_string(),
+                RTile {
+                    name: Some(name.to_string()),
+                    ..tile
+                },
+            );
+        }
+        let mut templates: Vec<String> = vec![];
+        for root in &self.template_roots {
+            let files = template_files(root).map_err(|error| ProjectError::Io {
+                path: root.clone(),
+                error,
+            })?;
+            for (name, path) in files {
+                if templates.contains(&name) {
+                    continue;
+                }
+                if self
+                    .values
+                    .iter()
+                    .any(|(value_name, _)| *value_name == name)
+                {
+                    return Err(ProjectError::Config(format!(
+                        "the template `{}` has the same name as a value",
+                        name
+                    )));
+                }
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|error| ProjectError::Io { path, error })?;
+                let tile = detached(|| RTile::construct_from_str(&text));
+                store.set_raw(
+                    name.clone(),
+                    RTile {
+                        name: Some(name.clone()),
+                        ..tile
+                    },
+                );
+                templates.push(name);
+            }
+        }
+        let mut outputs = vec![];
+        for output in &self.outputs {
+            if !templates.contains(&output.template) {
+                return Err(ProjectError::Config(format!(
+                    "the template `{}` is not found in the template roots",
+                    output.template
+                )));
+            }
+            let template = detached(|| RTile::new(vec![format!("@{{{}}}", output.template)]));
+            let path = detached(|| RTile::new(vec![output.path.clone()]));
+            check_dependencies(&store, &output.template, &[&template, &path])?;
+            let mut content = store.render(&template);
+            content.push('\n');
+            outputs.push((slash_path(Path::new(&store.render(&path))), content));
+        }
+        Ok(outputs)
+    }
+
+    /// renders the output files and writes them under the output root using an `Emitter`, i.e. the stale files of the previous run are deleted
+    pub fn run(&self) -> Result<EmitReport, ProjectError> {
+        let mut emitter = Emitter::new(&self.output_root);
+        if let Some(manifest) = &self.manifest {
+            emitter = emitter.with_manifest(manifest);
+        }
+        let io_error = |error| ProjectError::Io {
+            path: self.output_root.clone(),
+            error,
+        };
+        for (path, content) in self.render()? {
+            emitter.emit(&path, content).map_err(io_error)?;
+        }
+        emitter.finish().map_err(io_error)
+    }
+}
+
+///
+/// run_project, loads the project file (or the `rtile.toml` of the given directory) and writes all of its output files
+/// ```
+/// use rtile::project::run_project;
+///
+/// let root = std::env::temp_dir().join(format!("rtile_doc_project_{}", std::process::id()));
+/// std::fs::create_dir_all(root.join("templates")).unwrap();
+/// std::fs::write(root.join("templates/greet.txt"), "Hello @{name}!").unwrap();
+/// std::fs::write(
+///     root.join("rtile.toml"),
+///     "[project]\noutput = \"out\"\n[values]\nname = \"Alice\"\n[[output]]\ntemplate = \"greet.txt\"\npath = \"@{name}.txt\"\n",
+/// )
+/// .unwrap();
+///
+/// let report = run_project(&root).unwrap();
+/// assert_eq!(report.created, 1);
+/// assert_eq!(std::fs::read_to_string(root.join("out/Alice.txt")).unwrap(), "Hello Alice!\n");
+/// std::fs::remove_dir_all(&root).unwrap();
+/// ```
+///
+pub fn run_project(path: impl AsRef<Path>) -> Result<EmitReport, ProjectError> {
+    let path = path.as_ref();
+    if path.is_dir() {
+        Project::load(path.join(DEFAULT_PROJECT_FILE))?.run()
+    } else {
+        Project::load(path)?.run()
+    }
+}
+
+///
+/// ProjectError, returned when a project file can't be loaded or its output files can't be rendered or written
+///
+#[derive(Debug)]
+pub enum ProjectError {
+    /// a file or a directory could not be read or written
+    Io {
+        /// the path of the file or the directory
+        path: PathBuf,
+        /// the underlying error
+        error: io::Error,
+    },
+    /// the project file is not valid
+    Parse {
+        /// the number (starting from 1) of the line of the error
+        line: usize,
+        /// the description of the error
+        message: String,
+    },
+    /// the project refers to a template which doesn't exist, or the names of its templates and values clash
+    Config(String),
+    /// a template refers to tiles which are neither templates nor values
+    MissingTiles {
+        /// the name of the template of the output file
+        template: String,
+        /// the names of the missing tiles
+        names: Vec<String>,
+    },
+    /// a template refers back to itself through its inner tiles
+    Cycle(CycleError),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ProjectError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            ProjectError::Config(message) => write!(f, "{}", message),
+            ProjectError::MissingTiles { template, names } => write!(
+                f,
+                "the template `{}` refers to the missing tiles: {}",
+                template,
+                names.join(", ")
+            ),
+            ProjectError::Cycle(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProjectError::Io { error, .. } => Some(error),
+            ProjectError::Cycle(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> ProjectError {
+    ProjectError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+// the files under the root, named after their paths relative to the root, sorted by name, a file root is named after its file name
+pub(crate) fn template_files(root: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                collect(&path, files)?;
+            } else {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    if root.is_file() {
+        let name = root.file_name().unwrap_or_default();
+        return Ok(vec![(slash_path(Path::new(name)), root.to_path_buf())]);
+    }
+    let mut files = vec![];
+    collect(root, &mut files)?;
+    let mut files: Vec<(String, PathBuf)> = files
+        .into_iter()
+        .map(|path| {
+            let name = slash_path(path.strip_prefix(root).unwrap_or(&path));
+            (name, path)
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// fails on the first recursion or on any missing tile, as the store would expand them as blank tiles or panic
+fn check_dependencies(
+    store: &TileStore,
+    template: &str,
+    tiles: &[&RTile],
+) -> Result<(), ProjectError> {
+    #[derive(Default)]
+    struct Check {
+        missing: Vec<String>,
+        cycle: Option<Vec<String>>,
+    }
+
+    impl TileVisitor for Check {
+        fn missing(&mut self, name: &str, _parents: &[String]) {
+            self.missing.push(name.to_string());
+        }
+
+        fn recursion(&mut self, name: &str, parents: &[String]) {
+            if self.cycle.is_none() {
+                let start = parents
+                    .iter()
+                    .position(|parent| parent == name)
+                    .unwrap_or(0);
+                let mut cycle = parents[start..].to_vec();
+                cycle.push(name.to_string());
+                self.cycle = Some(cycle);
+            }
+        }
+    }
+
+    let mut check = Check::default();
+    for tile in tiles {
+        store.walk_dependencies(tile, &mut check);
+    }
+    if let Some(cycle) = check.cycle {
+        return Err(ProjectError::Cycle(CycleError { cycle }));
+    }
+    if !check.missing.is_empty() {
+        check.missing.sort();
+        check.missing.dedup();
+        return Err(ProjectError::MissingTiles {
+            template: template.to_string(),
+            names: check.missing,
+        });
+    }
+    Ok(())
+}
+
+// the values of the subset of toml supported by the project file
+#[derive(Debug, Clone, PartialEq)]
+enum TomlValue {
+    String(String),
+    // the numbers, the booleans and the dates, kept as written
+    Other(String),
+    Array(Vec<TomlValue>),
+}
+
+struct TomlEntry {
+    key: String,
+    value: TomlValue,
+    line: usize,
+}
+
+impl TomlEntry {
+    // a string or an array of strings
+    fn paths(self) -> Result<Vec<PathBuf>, ProjectError> {
+        let values = match self.value {
+            TomlValue::Array(values) => values,
+            value => vec![value],
+        };
+        values
+            .into_iter()
+            .map(|value| match value {
+                TomlValue::String(value) => Ok(PathBuf::from(value)),
+                _ => Err(parse_error(
+                    self.line,
+                    format!(
+                        "the value of `{}` must be a string or an array of strings",
+                        self.key
+                    ),
+                )),
+            })
+            .collect()
+    }
+
+    fn string(self) -> Result<String, ProjectError> {
+        match self.value {
+            TomlValue::String(value) => Ok(value),
+            _ => Err(parse_error(
+                self.line,
+                format!("the value of `{}` must be a string", self.key),
+            )),
+        }
+    }
+}
+
+struct TomlTable {
+    name: String,
+    array: bool,
+    line: usize,
+    entries: Vec<TomlEntry>,
+}
+
+// the tables of the document in order, starting with the root table (named "")
+fn parse_toml(text: &str) -> Result<Vec<TomlTable>, ProjectError> {
+    let mut parser = TomlParser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let mut tables = vec![TomlTable {
+        name: String::new(),
+        array: false,
+        line: 1,
+        entries: vec![],
+    }];
+    loop {
+        parser.skip_blank_lines();
+        let Some(c) = parser.peek() else {
+            break;
+        };
+        let line = parser.line;
+        if c == '[' {
+            let array = parser.peek_at(1) == Some('[');
+            parser.pos += if array { 2 } else { 1 };
+            let mut name = String::new();
+            while let Some(c) = parser.peek() {
+                if c == ']' || c == '\n' {
+                    break;
+                }
+                name.push(c);
+                parser.pos += 1;
+            }
+            let closing = if array { "]]" } else { "]" };
+            if !parser.eat(closing) {
+                return Err(parse_error(line, format!("expected `{}`", closing)));
+            }
+            let name = name.trim().to_string();
+            if name.is_empty() || !name.chars().all(is_bare_key_char) {
+                return Err(parse_error(line, format!("invalid table name `{}`", name)));
+            }
+            tables.push(TomlTable {
+                name,
+                array,
+                line,
+                entries: vec![],
+            });
+        } else {
+            let key = parser.key()?;
+            parser.skip_spaces();
+            if !parser.eat("=") {
+                return Err(parse_error(
+                    line,
+                    format!("expected `=` after the key `{}`", key),
+                ));
+            }
+            parser.skip_spaces();
+            let value = parser.value()?;
+            let table = tables.last_mut().unwrap();
+            if table.entries.iter().any(|entry| entry.key == key) {
+                return Err(parse_error(
+                    line,
+                    format!("the key `{}` is defined twice", key),
+                ));
+            }
+            table.entries.push(TomlEntry { key, value, line });
+        }
+        parser.end_of_line()?;
+    }
+    Ok(tables)
+}
+
+fn is_bare_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+struct TomlParser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl TomlParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        let matches = expected
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c));
+        if matches {
+            for _ in expected.chars() {
+                self.next();
+            }
+        }
+        matches
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            if !(self.eat("\n") || self.eat("\r\n")) {
+                break;
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), ProjectError> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.peek().is_none() || self.eat("\n") || self.eat("\r\n") {
+            Ok(())
+        } else {
+            Err(parse_error(self.line, "expected the end of the line"))
+        }
+    }
+
+    fn key(&mut self) -> Result<String, ProjectError> {
+        match self.peek() {
+            Some('"') | Some('\'') => match self.value()? {
+                TomlValue::String(key) => Ok(key),
+                _ => unreachable!(),
+            },
+            _ => {
+                let mut key = String::new();
+                while let Some(c) = self.peek().filter(|&c| is_bare_key_char(c)) {
+                    key.push(c);
+                    self.pos += 1;
+                }
+                if key.is_empty() {
+                    return Err(parse_error(self.line, "expected a key"));
+                }
+                if self.peek() == Some('.') {
+                    return Err(parse_error(self.line, "the dotted keys are not supported"));
+                }
+                Ok(key)
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<TomlValue, ProjectError> {
+        let line = self.line;
+        if self.eat("\"\"\"") {
+            self.eat_newline();
+            return self.string("\"\"\"", true, line);
+        }
+        if self.eat("'''") {
+            self.eat_newline();
+            return self.string("'''", false, line);
+        }
+        if self.eat("\"") {
+            return self.string("\"", true, line);
+        }
+        if self.eat("'") {
+            return self.string("'", false, line);
+        }
+        if self.eat("[") {
+            let mut values = vec![];
+            loop {
+                self.skip_blank_lines();
+                if self.eat("]") {
+                    break;
+                }
+                values.push(self.value()?);
+                self.skip_blank_lines();
+                if self.eat("]") {
+                    break;
+                }
+                if !self.eat(",") {
+                    return Err(parse_error(self.line, "expected `,` or `]` in the array"));
+                }
+            }
+            return Ok(TomlValue::Array(values));
+        }
+        let mut value = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == ',' || c == ']' || c == '#' {
+                break;
+            }
+            value.push(c);
+            self.pos += 1;
+        }
+        let is_value = matches!(value.as_str(), "true" | "false" | "inf" | "nan")
+            || value
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_digit() || c == '+' || c == '-');
+        if is_value {
+            Ok(TomlValue::Other(value))
+        } else {
+            Err(parse_error(
+                line,
+                "expected a value, i.e. a string, a number, a boolean or an array",
+            ))
+        }
+    }
+
+    // a multi line string starts on the line after its opening quotes
+    fn eat_newline(&mut self) {
+        let _ = self.eat("\n") || self.eat("\r\n");
+    }
+
+    fn string(
+        &mut self,
+        quotes: &str,
+        escapes: bool,
+        line: usize,
+    ) -> Result<TomlValue, ProjectError> {
+        let multi_line = quotes.len() == 3;
+        let mut value = String::new();
+        loop {
+            if self.eat(quotes) {
+                return Ok(TomlValue::String(value));
+            }
+            match self.next() {
+                None => return Err(parse_error(line, "unfinished string")),
+                Some('\n') if !multi_line => return Err(parse_error(line, "unfinished string")),
+                Some('\\') if escapes => value.push(self.escape()?),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, ProjectError> {
+        let line = self.line;
+        let c = match self.next() {
+            Some('b') => '\u{8}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('f') => '\u{c}',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(c @ ('u' | 'U')) => {
+                let len = if c == 'u' { 4 } else { 8 };
+                let digits: String = (0..len).filter_map(|_| self.next()).collect();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        parse_error(line, format!("invalid unicode escape `\\{}{}`", c, digits))
+                    })?
+            }
+            Some(c) => return Err(parse_error(line, format!("invalid escape `\\{}`", c))),
+            None => return Err(parse_error(line, "unfinished string")),
+        };
+        Ok(c)
+    }
+}
@@ -0,0 +1,371 @@
+//!
+//! Tile builders for the protobuf (proto3) files, i.e. the messages, the fields, the enums and their reserved numbers and names.
+//!
+
+use crate::RTile;
+
+///
+/// FieldLabel, the label of a message field
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldLabel {
+    /// no label, i.e. a singular field
+    #[default]
+    Singular,
+    /// `optional`, a singular field with the explicit presence
+    Optional,
+    /// `repeated`
+    Repeated,
+}
+
+///
+/// ProtoField, a field of a message
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtoField {
+    /// the name of the field
+    pub name: String,
+    /// the type of the field, e.g. `string` or `map<string, int32>`
+    pub ty: String,
+    /// the field number
+    pub number: u32,
+    /// the label of the field
+    pub label: FieldLabel,
+}
+
+impl ProtoField {
+    /// creates a singular field
+    pub fn new(name: &str, ty: &str, number: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            ty: ty.to_string(),
+            number,
+            label: FieldLabel::Singular,
+        }
+    }
+
+    /// sets the label of the field
+    pub fn label(mut self, label: FieldLabel) -> Self {
+        self.label = label;
+        self
+    }
+
+    fn to_line(&self) -> String {
+        let label = match self.label {
+            FieldLabel::Singular => "",
+            FieldLabel::Optional => "optional ",
+            FieldLabel::Repeated => "repeated ",
+        };
+        format!("{}{} {} = {};", label, self.ty, self.name, self.number)
+    }
+}
+
+///
+/// Reserved, the reserved numbers and names of a message or an enum
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Reserved {
+    // inclusive ranges, None is `max`
+    ranges: Vec<(u32, Option<u32>)>,
+    names: Vec<String>,
+}
+
+impl Reserved {
+    fn to_lines(&self) -> Vec<String> {
+        let mut lns = vec![];
+        if let Some(ranges) = reserved_numbers(&self.ranges) {
+            lns.push(ranges);
+        }
+        if !self.names.is_empty() {
+            let names: Vec<String> = self
+                .names
+                .iter()
+                .map(|name| format!("\"{}\"", name))
+                .collect();
+            lns.push(format!("reserved {};", names.join(", ")));
+        }
+        lns
+    }
+}
+
+///
+/// reserved_numbers formats the reserved numbers as a `reserved` statement, the numbers are sorted and the overlapping
+/// or adjacent ranges are merged, returns None if there are no numbers
+/// ```
+/// use rtile::proto::reserved_numbers;
+///
+/// let ranges = [(15, None), (2, Some(2)), (9, Some(10)), (11, Some(11)), (3, Some(3))];
+/// assert_eq!(reserved_numbers(&ranges), Some("reserved 2 to 3, 9 to 11, 15 to max;".to_string()));
+/// assert_eq!(reserved_numbers(&[]), None);
+/// ```
+///
+pub fn reserved_numbers(ranges: &[(u32, Option<u32>)]) -> Option<String> {
+    let mut ranges = ranges.to_vec();
+    ranges.sort_by_key(|(start, end)| (*start, end.is_none(), *end));
+    let mut merged: Vec<(u32, Option<u32>)> = vec![];
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, last_end))
+                if last_end.is_none_or(|last_end| start <= last_end.saturating_add(1)) =>
+            {
+                *last_end = match (*last_end, end) {
+                    (Some(last_end), Some(end)) => Some(last_end.max(end)),
+                    _ => None,
+                };
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+    if merged.is_empty() {
+        return None;
+    }
+    let ranges: Vec<String> = merged
+        .iter()
+        .map(|(start, end)| match end {
+            Some(end) if end == start => start.to_string(),
+            Some(end) => format!("{} to {}", start, end),
+            None => format!("{} to max", start),
+        })
+        .collect();
+    Some(format!("reserved {};", ranges.join(", ")))
+}
+
+///
+/// ProtoMessage, a message along with its fields, reserved numbers and names, and nested messages and enums
+/// ```
+/// use rtile::proto::*;
+///
+/// let message = ProtoMessage::new("Person")
+///     .field(ProtoField::new("name", "string", 1))
+///     .field(ProtoField::new("emails", "string", 3).label(FieldLabel::Repeated))
+///     .reserved(2)
+///     .reserved_name("age")
+///     .nested_enum(ProtoEnum::new("Kind").value("KIND_UNSPECIFIED", 0).value("KIND_ADMIN", 1));
+/// assert_eq!(
+///     message.to_tile().to_string(),
+///     "message Person {\n  enum Kind {\n    KIND_UNSPECIFIED = 0;\n    KIND_ADMIN = 1;\n  }\n  reserved 2;\n  reserved \"age\";\n  string name = 1;\n  repeated string emails = 3;\n}"
+/// );
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtoMessage {
+    name: String,
+    fields: Vec<ProtoField>,
+    reserved: Reserved,
+    messages: Vec<ProtoMessage>,
+    enums: Vec<ProtoEnum>,
+}
+
+impl ProtoMessage {
+    /// creates an empty message
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            fields: vec![],
+            reserved: Reserved::default(),
+            messages: vec![],
+            enums: vec![],
+        }
+    }
+
+    /// adds a field
+    pub fn field(mut self, field: ProtoField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// reserves the field number
+    pub fn reserved(mut self, number: u32) -> Self {
+        self.reserved.ranges.push((number, Some(number)));
+        self
+    }
+
+    /// reserves the field numbers from start to end (inclusive), None is `max`
+    pub fn reserved_range(mut self, start: u32, end: Option<u32>) -> Self {
+        self.reserved.ranges.push((start, end));
+        self
+    }
+
+    /// reserves the field name
+    pub fn reserved_name(mut self, name: &str) -> Self {
+        self.reserved.names.push(name.to_string());
+        self
+    }
+
+    /// adds a nested message
+    pub fn nested_message(mut self, message: ProtoMessage) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    /// adds a nested enum
+    pub fn nested_enum(mut self, proto_enum: ProtoEnum) -> Self {
+        self.enums.push(proto_enum);
+        self
+    }
+
+    /// returns the message as a tile, the nested definitions come first, then the reserved statements and the fields
+    pub fn to_tile(&self) -> RTile {
+        let mut body: Vec<RTile> = vec![];
+        body.extend(self.enums.iter().map(|proto_enum| proto_enum.to_tile()));
+        body.extend(self.messages.iter().map(|message| message.to_tile()));
+        let mut lns = self.reserved.to_lines();
+        lns.extend(self.fields.iter().map(|field| field.to_line()));
+        if !lns.is_empty() {
+            body.push(RTile::new(lns));
+        }
+        block(&format!("message {}", self.name), body)
+    }
+}
+
+///
+/// ProtoEnum, an enum along with its values and reserved numbers and names, the first value must be 0 in proto3
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtoEnum {
+    name: String,
+    values: Vec<(String, i32)>,
+    reserved: Reserved,
+}
+
+impl ProtoEnum {
+    /// creates an empty enum
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            values: vec![],
+            reserved: Reserved::default(),
+        }
+    }
+
+    /// adds a value
+    pub fn value(mut self, name: &str, number: i32) -> Self {
+        self.values.push((name.to_string(), number));
+        self
+    }
+
+    /// reserves the value number
+    pub fn reserved(mut self, number: u32) -> Self {
+        self.reserved.ranges.push((number, Some(number)));
+        self
+    }
+
+    /// reserves the value numbers from start to end (inclusive), None is `max`
+    pub fn reserved_range(mut self, start: u32, end: Option<u32>) -> Self {
+        self.reserved.ranges.push((start, end));
+        self
+    }
+
+    /// reserves the value name
+    pub fn reserved_name(mut self, name: &str) -> Self {
+        self.reserved.names.push(name.to_string());
+        self
+    }
+
+    /// returns the enum as a tile
+    pub fn to_tile(&self) -> RTile {
+        let mut lns = self.reserved.to_lines();
+        lns.extend(
+            self.values
+                .iter()
+                .map(|(name, number)| format!("{} = {};", name, number)),
+        );
+        let body = if lns.is_empty() {
+            vec![]
+        } else {
+            vec![RTile::new(lns)]
+        };
+        block(&format!("enum {}", self.name), body)
+    }
+}
+
+///
+/// ProtoFile, a proto3 file with its package, imports, messages and enums
+/// ```
+/// use rtile::proto::*;
+///
+/// let file = ProtoFile::new("acme.v1")
+///     .import("google/protobuf/timestamp.proto")
+///     .message(ProtoMessage::new("Empty"));
+/// assert_eq!(
+///     file.to_tile().to_string(),
+///     "syntax = \"proto3\";\n\npackage acme.v1;\n\nimport \"google/protobuf/timestamp.proto\";\n\nmessage Empty {}"
+/// );
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtoFile {
+    package: String,
+    imports: Vec<String>,
+    messages: Vec<ProtoMessage>,
+    enums: Vec<ProtoEnum>,
+}
+
+impl ProtoFile {
+    /// creates an empty file of the given package
+    pub fn new(package: &str) -> Self {
+        Self {
+            package: package.to_string(),
+            imports: vec![],
+            messages: vec![],
+            enums: vec![],
+        }
+    }
+
+    /// adds an import
+    pub fn import(mut self, path: &str) -> Self {
+        self.imports.push(path.to_string());
+        self
+    }
+
+    /// adds a message
+    pub fn message(mut self, message: ProtoMessage) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    /// adds an enum
+    pub fn proto_enum(mut self, proto_enum: ProtoEnum) -> Self {
+        self.enums.push(proto_enum);
+        self
+    }
+
+    /// returns the file as a tile, the sections are separated by blank lines
+    pub fn to_tile(&self) -> RTile {
+        let mut sections = vec![RTile::new(vec!["syntax = \"proto3\";".to_string()])];
+        if !self.package.is_empty() {
+            sections.push(RTile::new(vec![format!("package {};", self.package)]));
+        }
+        if !self.imports.is_empty() {
+            sections.push(RTile::new(
+                self.imports
+                    .iter()
+                    .map(|path| format!("import \"{}\";", path))
+                    .collect(),
+            ));
+        }
+        sections.extend(self.enums.iter().map(|proto_enum| proto_enum.to_tile()));
+        sections.extend(self.messages.iter().map(|message| message.to_tile()));
+        let mut tile = RTile::new(vec![]);
+        for (idx, section) in sections.into_iter().enumerate() {
+            if idx > 0 {
+                tile |= RTile::new_without_trimming(vec!["".to_string()]);
+            }
+            tile |= section;
+        }
+        tile
+    }
+}
+
+// `header {` and `}` around the body tiles indented by two spaces, or `header {}` if there is no body
+fn block(header: &str, body: Vec<RTile>) -> RTile {
+    if body.is_empty() {
+        return RTile::new(vec![format!("{} {{}}", header)]);
+    }
+    let mut tile = RTile::new(vec![format!("{} {{", header)]);
+    for inner in body {
+        let (_, height) = inner.dimensions();
+        tile |= RTile::new_without_trimming(vec!["  ".to_string(); height]) + inner;
+    }
+    tile | RTile::new(vec!["}".to_string()])
+}
@@ -0,0 +1,211 @@
+//!
+//! Provenance banners, i.e. the "do not edit" comments prepended to the generated files, naming the tool, the source and the template
+//! of the file in the same form across all the generated files, see `RTile::with_provenance`.
+//!
+
+use crate::detached;
+use crate::RTile;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+///
+/// Provenance, the metadata of a generated file written in its banner
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::provenance::*;
+///
+/// let provenance = Provenance::new("codegen", "1.2.0", "schema/person.proto")
+///     .template("person.rs")
+///     .comment(CommentStyle::for_language("python").unwrap())
+///     .timestamp(Timestamp::Fixed("2024-01-01T00:00:00Z".to_string()));
+/// let banner: Vec<String> = provenance.banner().to_string().lines().map(String::from).collect();
+/// assert_eq!(
+///     banner,
+///     vec![
+///         "# @generated by codegen 1.2.0, do not edit",
+///         "# source: schema/person.proto",
+///         "# template: person.rs",
+///         "# timestamp: 2024-01-01T00:00:00Z",
+///     ]
+/// );
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// the name of the generating tool
+    pub tool_name: String,
+    /// the version of the generating tool
+    pub version: String,
+    /// the source the file is generated from, e.g. the path of a schema
+    pub source: String,
+    /// the name of the template, `RTile::with_provenance` uses the name of the tile when it is not set
+    pub template: Option<String>,
+    /// the comment syntax of the banner
+    pub comment: CommentStyle,
+    /// the timestamp of the banner, none by default so the regenerated files don't change
+    pub timestamp: Timestamp,
+}
+
+impl Provenance {
+    /// creates the provenance of a file generated by the tool from the source, the banner uses `//` comments and has no timestamp
+    pub fn new(tool_name: &str, version: &str, source: &str) -> Self {
+        Self {
+            tool_name: tool_name.to_string(),
+            version: version.to_string(),
+            source: source.to_string(),
+            template: None,
+            comment: CommentStyle::default(),
+            timestamp: Timestamp::None,
+        }
+    }
+
+    /// sets the name of the template
+    pub fn template(mut self, template: &str) -> Self {
+        self.template = Some(template.to_string());
+        self
+    }
+
+    /// sets the comment syntax of the banner
+    pub fn comment(mut self, comment: CommentStyle) -> Self {
+        self.comment = comment;
+        self
+    }
+
+    /// sets the timestamp of the banner
+    pub fn timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// returns the banner as a tile, one comment per line, the empty fields are left out
+    pub fn banner(&self) -> RTile {
+        let mut lns = vec![format!(
+            "@generated by {} {}, do not edit",
+            self.tool_name, self.version
+        )];
+        if !self.source.is_empty() {
+            lns.push(format!("source: {}", self.source));
+        }
+        if let Some(template) = &self.template {
+            lns.push(format!("template: {}", template));
+        }
+        if let Some(timestamp) = self.timestamp.value() {
+            lns.push(format!("timestamp: {}", timestamp));
+        }
+        let lns = lns
+            .into_iter()
+            .map(|ln| format!("{}{}{}", self.comment.prefix, ln, self.comment.suffix))
+            .collect();
+        // the banner is taken as it is, e.g. a source path with `@{` isn't a placeholder
+        detached(|| RTile::new(lns))
+    }
+}
+
+///
+/// CommentStyle, the comment syntax of a language, every line of the banner is wrapped in the prefix and the suffix
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentStyle {
+    /// the text before the comment, e.g. `// `
+    pub prefix: &'static str,
+    /// the text after the comment, e.g. ` -->`, empty for the line comments
+    pub suffix: &'static str,
+}
+
+impl Default for CommentStyle {
+    fn default() -> Self {
+        Self {
+            prefix: "// ",
+            suffix: "",
+        }
+    }
+}
+
+impl CommentStyle {
+    ///
+    /// This function returns the comment style of the given language, if it is known.
+    ///
+    /// ```
+    /// use rtile::provenance::CommentStyle;
+    ///
+    /// assert_eq!(CommentStyle::for_language("yaml"), Some(CommentStyle { prefix: "# ", suffix: "" }));
+    /// assert_eq!(
+    ///     CommentStyle::for_language("html"),
+    ///     Some(CommentStyle { prefix: "<!-- ", suffix: " -->" })
+    /// );
+    /// ```
+    ///
+    pub fn for_language(language: &str) -> Option<Self> {
+        let (prefix, suffix) = match language.to_ascii_lowercase().as_str() {
+            "rust" | "c" | "cpp" | "c++" | "csharp" | "c#" | "java" | "kotlin" | "go" | "swift"
+            | "javascript" | "typescript" | "dart" | "php" | "proto" | "protobuf" => ("// ", ""),
+            "python" | "ruby" | "yaml" | "toml" | "shell" | "bash" | "sh" | "make" | "makefile"
+            | "cmake" | "terraform" | "nix" | "dockerfile" => ("# ", ""),
+            "sql" | "lua" | "haskell" => ("-- ", ""),
+            "html" | "xml" | "markdown" => ("<!-- ", " -->"),
+            "css" => ("/* ", " */"),
+            _ => return None,
+        };
+        Some(Self { prefix, suffix })
+    }
+}
+
+///
+/// Timestamp, of the banner, the generated files are reproducible only without a timestamp or with a fixed one
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Timestamp {
+    /// no timestamp, i.e. deterministic
+    #[default]
+    None,
+    /// the given text, e.g. the time of the commit of the source
+    Fixed(String),
+    /// the current time in UTC, or the time of the `SOURCE_DATE_EPOCH` environment variable when it is set, for the reproducible builds
+    Now,
+}
+
+impl Timestamp {
+    /// returns the text of the timestamp, e.g. `2024-01-01T00:00:00Z`
+    pub fn value(&self) -> Option<String> {
+        match self {
+            Timestamp::None => None,
+            Timestamp::Fixed(value) => Some(value.clone()),
+            Timestamp::Now => {
+                let seconds = std::env::var("SOURCE_DATE_EPOCH")
+                    .ok()
+                    .and_then(|value| value.trim().parse().ok())
+                    .unwrap_or_else(|| {
+                        SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map_or(0, |duration| duration.as_secs())
+                    });
+                Some(utc_timestamp(seconds))
+            }
+        }
+    }
+}
+
+// the seconds since the unix epoch in the ISO 8601 form, the date is computed from the days (Howard Hinnant's civil_from_days)
+fn utc_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
@@ -0,0 +1,207 @@
+//!
+//! An interactive prompt to define the tiles of the tls (thread local storage), inspect them, render the templates and show
+//! the dependency trees, for teaching and for prototyping the templates before committing them to code.
+//!
+//! A line starting with `:` is a command (see `:help`), any other line is rendered as a template. Within the text of `:let`
+//! and of a template, `\n` starts a new line.
+//!
+
+use crate::describe_template;
+use crate::detached;
+use crate::export_store_sorted;
+use crate::get_blank_tiles;
+use crate::get_raw_tile;
+use crate::with_default_store;
+use crate::RTile;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+
+/// the prompt printed by `run_repl` before reading a line
+pub const PROMPT: &str = "rtile> ";
+
+const HELP: &str = ":let NAME = TEXT  persist the tile NAME
+:tiles            list the persisted tiles
+:raw NAME         show the raw text of the tile NAME
+:doc NAME         show the documentation of the tile NAME
+:tree NAME        show the tiles used by the tile NAME
+:rm NAME          remove the tile NAME
+:clear            remove all the tiles
+:help             show this help
+:quit             leave the prompt
+TEXT              render TEXT";
+
+///
+/// Repl, evaluates the lines of the interactive prompt against the tiles of the tls, see `run_repl`
+/// ```
+/// use rtile::repl::Repl;
+///
+/// let mut repl = Repl::new();
+/// assert_eq!(repl.eval(":let repl_first = Alice"), "");
+/// assert_eq!(repl.eval(":let repl_greet = Hello @{repl_first}!"), "");
+/// assert_eq!(repl.eval("@{repl_greet}\\nBye @{repl_first}"), "Hello Alice!\nBye Alice");
+/// assert_eq!(repl.eval(":tree repl_greet"), "repl_greet\n`-- repl_first");
+/// assert!(!repl.is_done());
+/// repl.eval(":quit");
+/// assert!(repl.is_done());
+/// ```
+///
+#[derive(Debug, Clone, Default)]
+pub struct Repl {
+    done: bool,
+}
+
+impl Repl {
+    /// creates a prompt
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// returns true, after `:quit`
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// evaluates a line, and returns the text to print, the errors (e.g. an invalid `@{}` expression) are returned as text
+    pub fn eval(&mut self, line: &str) -> String {
+        let line = line.trim();
+        let Some(command) = line.strip_prefix(':') else {
+            return match std::panic::catch_unwind(|| render(line)) {
+                Ok(output) => output,
+                Err(payload) => error(payload),
+            };
+        };
+        let (command, argument) = command
+            .split_once(char::is_whitespace)
+            .map(|(command, argument)| (command, argument.trim()))
+            .unwrap_or((command, ""));
+        match (command, argument) {
+            ("let", definition) => match definition.split_once('=') {
+                Some((name, text)) if !name.trim().is_empty() => {
+                    let (name, text) = (name.trim(), unescape(text.trim()));
+                    let tile = detached(|| RTile::construct_from_str(&text));
+                    with_default_store(|store| store.set(name, tile));
+                    String::new()
+                }
+                _ => "usage: :let NAME = TEXT".to_string(),
+            },
+            ("tiles", "") => {
+                let blank_tiles = get_blank_tiles();
+                export_store_sorted()
+                    .into_keys()
+                    .map(|name| match blank_tiles.contains(&name) {
+                        true => format!("{} (blank)", name),
+                        false => name,
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            }
+            ("raw", name) if !name.is_empty() => match get_raw_tile(name) {
+                Some(tile) => tile.raw(),
+                None => unknown(name),
+            },
+            ("doc", name) if !name.is_empty() => match describe_template(name) {
+                Some(doc) => doc.to_markdown(),
+                None => unknown(name),
+            },
+            ("tree", name) if !name.is_empty() => match get_raw_tile(name) {
+                Some(_) => {
+                    let mut lns = vec![name.to_string()];
+                    tree(name, "", &mut vec![name.to_string()], &mut lns);
+                    lns.join("\n")
+                }
+                None => unknown(name),
+            },
+            ("rm", name) if !name.is_empty() => {
+                with_default_store(|store| store.remove(name));
+                String::new()
+            }
+            ("clear", "") => {
+                with_default_store(|store| store.clear());
+                String::new()
+            }
+            ("help", "") => HELP.to_string(),
+            ("quit" | "q", "") => {
+                self.done = true;
+                String::new()
+            }
+            _ => format!("unknown command `:{}`, see :help", command),
+        }
+    }
+}
+
+///
+/// run_repl, reads the lines of the input and prints the prompt and the results to the output, until `:quit` or the end of the input
+/// ```no_run
+/// use rtile::repl::run_repl;
+///
+/// run_repl(std::io::stdin().lock(), std::io::stdout()).unwrap();
+/// ```
+///
+pub fn run_repl(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut repl = Repl::new();
+    let mut lines = input.lines();
+    while !repl.is_done() {
+        write!(output, "{}", PROMPT)?;
+        output.flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let result = repl.eval(&line);
+        if !result.is_empty() {
+            writeln!(output, "{}", result)?;
+        }
+    }
+    Ok(())
+}
+
+fn render(text: &str) -> String {
+    RTile::construct_from_str(&unescape(text)).to_string()
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", "\n")
+}
+
+fn unknown(name: &str) -> String {
+    format!("no tile named `{}`", name)
+}
+
+fn error(payload: Box<dyn std::any::Any + Send>) -> String {
+    let message = payload
+        .downcast_ref::<String>()
+        .map(|message| message.as_str())
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("the template can't be rendered");
+    format!("error: {}", message)
+}
+
+// the placeholders of the tile as the branches of the tree, the tiles already on the path are marked as a cycle, and the
+// blank tiles (e.g. created for a missing inner tile) as blank
+fn tree(name: &str, indent: &str, path: &mut Vec<String>, lns: &mut Vec<String>) {
+    let children = describe_template(name)
+        .map(|doc| doc.placeholders)
+        .unwrap_or_default();
+    for (i, child) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let (branch, next_indent) = match last {
+            true => ("`-- ", format!("{}    ", indent)),
+            false => ("|-- ", format!("{}|   ", indent)),
+        };
+        let note = if path.contains(child) {
+            " (cycle)"
+        } else if get_raw_tile(child).is_none() {
+            " (missing)"
+        } else if get_blank_tiles().contains(child) {
+            " (blank)"
+        } else {
+            ""
+        };
+        lns.push(format!("{}{}{}{}", indent, branch, child, note));
+        if note.is_empty() {
+            path.push(child.clone());
+            tree(child, &next_indent, path, lns);
+            path.pop();
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//!
+//! Tiles of the values destined for the Rust code generated by the templates, i.e. the identifiers and the string literals,
+//! validated and escaped the way `quote::format_ident!` and `proc_macro2::Literal::string` would, so the user data (e.g. a
+//! field named `type`, or a description with quotes) never turns into uncompilable output.
+//!
+
+use crate::detached;
+use crate::RTile;
+use std::fmt;
+
+/// the keywords of Rust (strict and reserved, of all the editions), a keyword is written as a raw identifier, e.g. `r#type`
+pub const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+// the keywords which can't be raw identifiers
+const NON_RAW_KEYWORDS: &[&str] = &["crate", "self", "Self", "super", "_"];
+
+///
+/// to_ident_tile, used to get the tile of a Rust identifier, a keyword is written as a raw identifier
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::rust::*;
+///
+/// tp!(ident_field, to_ident_tile("type").unwrap());
+/// tp!(ident_value, to_lit_str_tile("say \"hi\"\n"));
+/// assert_eq!(
+///     t!("let @{ident_field} = @{ident_value};").to_string(),
+///     r#"let r#type = "say \"hi\"\n";"#
+/// );
+/// assert_eq!(
+///     to_ident_tile("user-id").unwrap_err().to_string(),
+///     "`user-id` is not a valid identifier, `-` is not allowed"
+/// );
+/// ```
+///
+pub fn to_ident_tile(s: &str) -> Result<RTile, IdentError> {
+    let mut chars = s.chars();
+    match chars.next() {
+        None => return Err(IdentError::Empty),
+        Some(c) if !(c == '_' || c.is_alphabetic()) => {
+            return Err(IdentError::InvalidChar {
+                ident: s.to_string(),
+                c,
+            })
+        }
+        _ => {}
+    }
+    if let Some(c) = chars.find(|&c| !(c == '_' || c.is_alphanumeric())) {
+        return Err(IdentError::InvalidChar {
+            ident: s.to_string(),
+            c,
+        });
+    }
+    if NON_RAW_KEYWORDS.contains(&s) {
+        return Err(IdentError::Reserved(s.to_string()));
+    }
+    let ident = match KEYWORDS.contains(&s) {
+        true => format!("r#{}", s),
+        false => s.to_string(),
+    };
+    Ok(detached(|| RTile::new(vec![ident])))
+}
+
+///
+/// to_lit_str_tile, used to get the tile of a Rust string literal of the text, in double quotes, the quotes, the backslashes
+/// and the control characters are escaped, so the literal is a single line
+///
+/// ```
+/// use rtile::rust::to_lit_str_tile;
+///
+/// assert_eq!(to_lit_str_tile("C:\\temp").to_string(), "\"C:\\\\temp\"");
+/// assert_eq!(to_lit_str_tile("say \"hi\"\n").to_string(), "\"say \\\"hi\\\"\\n\"");
+/// ```
+///
+pub fn to_lit_str_tile(s: &str) -> RTile {
+    let mut lit = String::from('"');
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => lit.push_str("\\\""),
+            '\\' => lit.push_str("\\\\"),
+            '\n' => lit.push_str("\\n"),
+            '\r' => lit.push_str("\\r"),
+            '\t' => lit.push_str("\\t"),
+            '\0' => lit.push_str("\\0"),
+            // the brace of `@{` is escaped, so the literal is never expanded as a placeholder
+            '@' if chars.peek() == Some(&'{') => {
+                chars.next();
+                lit.push_str("@\\u{7b}");
+            }
+            c if c.is_control() => lit.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => lit.push(c),
+        }
+    }
+    lit.push('"');
+    detached(|| RTile::new(vec![lit]))
+}
+
+///
+/// IdentError, returned when a text can't be a Rust identifier
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdentError {
+    /// the text is empty
+    Empty,
+    /// the text has a character not allowed in an identifier
+    InvalidChar {
+        /// the text
+        ident: String,
+        /// the first character not allowed
+        c: char,
+    },
+    /// the text is a keyword which can't be a raw identifier, e.g. `self`
+    Reserved(String),
+}
+
+impl fmt::Display for IdentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentError::Empty => write!(f, "an identifier can't be empty"),
+            IdentError::InvalidChar { ident, c } => write!(
+                f,
+                "`{}` is not a valid identifier, `{}` is not allowed",
+                ident, c
+            ),
+            IdentError::Reserved(ident) => {
+                write!(
+                    f,
+                    "`{}` is a keyword which can't be a raw identifier",
+                    ident
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for IdentError {}
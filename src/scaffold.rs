@@ -0,0 +1,203 @@
+//!
+//! Scaffolding of the http handlers from a minimal description of the routes of an api, i.e. the handler stubs,
+//! the router registration and the test skeletons, rendered as a set of named tiles and files.
+//!
+//! The fields of every route are persisted under the namespace of its operation (see `with_prefix`), so the same templates
+//! are expanded once per route, then the per-route tiles are joined into the handlers, the router and the tests tiles.
+//!
+
+use crate::prelude::*;
+use std::collections::BTreeMap;
+
+///
+/// Method, the http method of a route
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// GET
+    Get,
+    /// POST
+    Post,
+    /// PUT
+    Put,
+    /// PATCH
+    Patch,
+    /// DELETE
+    Delete,
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let method = match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Patch => "PATCH",
+            Method::Delete => "DELETE",
+        };
+        write!(f, "{}", method)
+    }
+}
+
+///
+/// Route, a route of the api, the operation id is used as the name of the handler and of its test
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    /// the http method
+    pub method: Method,
+    /// the path, e.g. `/users/{id}`
+    pub path: String,
+    /// the name of the operation, e.g. `get_user`
+    pub operation_id: String,
+    /// a one line description of the operation
+    pub summary: String,
+}
+
+impl Route {
+    /// creates a route
+    pub fn new(method: Method, path: &str, operation_id: &str, summary: &str) -> Self {
+        Self {
+            method,
+            path: path.to_string(),
+            operation_id: operation_id.to_string(),
+            summary: summary.to_string(),
+        }
+    }
+}
+
+///
+/// ApiSpec, the name of the api and its routes
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiSpec {
+    /// the name of the api, used in the headers of the files
+    pub name: String,
+    /// the routes
+    pub routes: Vec<Route>,
+}
+
+///
+/// Scaffold, the named tiles rendered for an api, returned by `scaffold`
+///
+/// The tiles `{operation_id}_handler`, `{operation_id}_registration` and `{operation_id}_test` are rendered for every route,
+/// along with the `handlers`, `router` and `tests` tiles joining them.
+/// ```
+/// use rtile::scaffold::*;
+///
+/// let spec = ApiSpec {
+///     name: "users".to_string(),
+///     routes: vec![Route::new(Method::Get, "/users/{id}", "get_user", "Returns the user")],
+/// };
+/// let mut scaffold = scaffold(&spec);
+/// assert_eq!(
+///     scaffold.render("get_user_registration").unwrap(),
+///     "router.route(\"GET\", \"/users/{id}\", handlers::get_user);"
+/// );
+/// assert_eq!(
+///     scaffold.files().keys().collect::<Vec<_>>(),
+///     vec!["src/handlers.rs", "src/router.rs", "tests/api.rs"]
+/// );
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct Scaffold {
+    store: TileStore,
+}
+
+impl Scaffold {
+    /// returns the tile with the given name
+    pub fn tile(&self, name: &str) -> Option<RTile> {
+        self.store.get(name)
+    }
+
+    /// returns the output of the tile with the given name
+    pub fn render(&mut self, name: &str) -> Option<String> {
+        let tile = self.store.get(name)?;
+        Some(self.store.render(&tile))
+    }
+
+    /// renders the files of the scaffold in one batch, by their relative paths
+    pub fn files(&mut self) -> BTreeMap<String, String> {
+        [
+            ("src/handlers.rs", "handlers_file"),
+            ("src/router.rs", "router_file"),
+            ("tests/api.rs", "tests_file"),
+        ]
+        .into_iter()
+        .filter_map(|(path, name)| Some((path.to_string(), self.render(name)?)))
+        .collect()
+    }
+}
+
+///
+/// scaffold renders the handler stubs, the router registration and the test skeletons of the api, see `Scaffold`
+///
+pub fn scaffold(spec: &ApiSpec) -> Scaffold {
+    let mut store = TileStore::new();
+    tp!(store => header, "// generated by the rtile scaffold of the @{api_name} api");
+    tq!(store => "api_name", spec.name.clone());
+    tp!(store => handler_template, "
+        /// @{summary}
+        ///
+        /// @{method} @{path}
+        pub async fn @{operation_id}(request: Request) -> Response {
+            todo!(\"@{operation_id}\")
+        }
+    ");
+    tp!(store => registration_template, "router.route(\"@{method}\", \"@{path}\", handlers::@{operation_id});");
+    tp!(store => test_template, "
+        #[test]
+        fn test_@{operation_id}() {
+            // @{method} @{path}
+            todo!(\"test @{operation_id}\")
+        }
+    ");
+
+    for route in &spec.routes {
+        with_prefix(&format!("{}_", route.operation_id), || {
+            tq!(store => "summary", route.summary.clone());
+            tq!(store => "method", route.method.to_string());
+            tq!(store => "path", route.path.clone());
+            tq!(store => "operation_id", route.operation_id.clone());
+            for kind in ["handler", "registration", "test"] {
+                let tile = store.get(&format!("{}_template", kind)).unwrap();
+                let output = store.render(&tile);
+                kq!(store => kind, output);
+            }
+        });
+    }
+
+    let joined = |kind: &str, separator: &str| {
+        spec.routes
+            .iter()
+            .map(|route| format!("@{{{}_{}}}", route.operation_id, kind))
+            .collect::<Vec<String>>()
+            .join(separator)
+    };
+    tq!(store => "handlers", joined("handler", "\n\n"));
+    tq!(store => "registrations", joined("registration", "\n"));
+    tq!(store => "tests", joined("test", "\n\n"));
+
+    tp!(store => handlers_file, "
+        @{header}
+
+        @{handlers}
+    ");
+    tp!(store => router, "
+        pub fn register(router: &mut Router) {
+            @{registrations}
+        }
+    ");
+    tp!(store => router_file, "
+        @{header}
+
+        @{router}
+    ");
+    tp!(store => tests_file, "
+        @{header}
+
+        @{tests}
+    ");
+    Scaffold { store }
+}
@@ -0,0 +1,2638 @@
+//!
+//! TileStore, the storage of the named tiles which the inner tiles are expanded against
+//!
+
+use super::*;
+use crate::owned::global_generation;
+use crate::provenance::CommentStyle;
+use crate::version::split_requirement;
+use crate::version::split_version;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::hash::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::panic::Location;
+use std::time::SystemTime;
+
+type RawTiles = TileMap<RTile>;
+// the expanded values are shared, so looking up a large inner tile or forking the processed data doesn't copy the text
+type ProcessedTiles = TileMap<Rc<str>>;
+
+/// a layer of entries over an optional shared parent layer, a removed entry of the parent is recorded as None
+#[derive(Debug, Clone)]
+struct TileMap<V> {
+    entries: HashMap<String, Option<V>>,
+    parent: Option<Rc<TileMap<V>>>,
+}
+
+impl<V> Default for TileMap<V> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            parent: None,
+        }
+    }
+}
+
+impl<V: Clone> TileMap<V> {
+    fn get(&self, key: &str) -> Option<&V> {
+        match self.entries.get(key) {
+            Some(value) => value.as_ref(),
+            None => self.parent.as_ref().and_then(|parent| parent.get(key)),
+        }
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        if !self.entries.contains_key(key) {
+            // copy the entry of the parent on the first write
+            let value = self.parent.as_ref()?.get(key)?.clone();
+            self.entries.insert(key.to_string(), Some(value));
+        }
+        self.entries.get_mut(key).and_then(|value| value.as_mut())
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn insert(&mut self, key: String, value: V) {
+        self.entries.insert(key, Some(value));
+    }
+
+    fn remove(&mut self, key: &str) -> Option<V> {
+        let value = self.get(key).cloned();
+        if self
+            .parent
+            .as_ref()
+            .is_some_and(|parent| parent.contains_key(key))
+        {
+            self.entries.insert(key.to_string(), None);
+        } else {
+            self.entries.remove(key);
+        }
+        value
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.parent = None;
+    }
+
+    fn len(&self) -> usize {
+        let mut keys: HashSet<&String> = HashSet::new();
+        let mut len = 0;
+        let mut layer = Some(self);
+        while let Some(current) = layer {
+            for (key, value) in &current.entries {
+                if keys.insert(key) && value.is_some() {
+                    len += 1;
+                }
+            }
+            layer = current.parent.as_deref();
+        }
+        len
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&String, &V)> {
+        let mut keys: HashSet<&String> = HashSet::new();
+        let mut result = vec![];
+        let mut layer = Some(self);
+        while let Some(current) = layer {
+            for (key, value) in &current.entries {
+                if keys.insert(key) {
+                    if let Some(value) = value {
+                        result.push((key, value));
+                    }
+                }
+            }
+            layer = current.parent.as_deref();
+        }
+        // sorted by the keys, so every enumeration of the store is deterministic
+        result.sort_by(|a, b| a.0.cmp(b.0));
+        result.into_iter()
+    }
+
+    fn fork(&mut self) -> Self {
+        if !self.entries.is_empty() {
+            let entries = std::mem::take(&mut self.entries);
+            match self.parent.as_mut().and_then(Rc::get_mut) {
+                // no fork shares the parent anymore, so the entries are moved into it instead of adding a layer
+                Some(parent) => parent.entries.extend(entries),
+                None => {
+                    let frozen = Rc::new(Self {
+                        entries,
+                        parent: self.parent.take(),
+                    });
+                    self.parent = Some(frozen);
+                }
+            }
+        }
+        Self {
+            entries: HashMap::new(),
+            parent: self.parent.clone(),
+        }
+    }
+}
+
+///
+/// TileStore, a storage of named tiles against which the inner tiles (`@{name}`) are expanded
+///
+/// The tls (thread local storage) used by the macros is just the default instance of a TileStore, see `with_default_store`.
+/// An explicit store keeps its tiles isolated from the tiles of the tls, the macros tp!, tq!, kp!, kq!, stp!, stq!, gtp!, gtq!, ts! and ks!
+/// accept a store handle before the arguments, i.e. `tp!(store => name, value)` and `ts!(store => value)`.
+/// ```
+/// use rtile::prelude::*;
+///
+/// let mut store = TileStore::new();
+/// tp!(store => name, "Alice");
+/// tp!(store => greet, "Hello @{name}!");
+/// assert_eq!(ts!(store => "@{greet}"), "Hello Alice!");
+///
+/// store.set("name", t!("Bob"));
+/// assert_eq!(store.render(&store.get("greet").unwrap()), "Hello Bob!");
+///
+/// // the tls (thread local storage) is not affected
+/// assert_eq!(gtp!(greet), None);
+/// assert_eq!(gtp!(store => greet).unwrap().raw(), "Hello @{name}!");
+/// ```
+///
+#[derive(Debug, Clone, Default)]
+pub struct TileStore {
+    raw_tiles: RawTiles,
+    processed_tiles: ProcessedTiles,
+    cache_keys: TileMap<u64>,
+    frozen_tiles: Rc<HashSet<String>>,
+    declared_inputs: TileMap<Vec<String>>,
+    // the render options attached to the templates, see `set_render_options`
+    render_options: TileMap<RenderOptions>,
+    dynamic_tiles: TileMap<DynamicTile>,
+    // the blank tiles created for the missing inner tiles, see `try_render`
+    implicit_tiles: TileMap<()>,
+    // the tiles taken from the global registry, and the generation of the registry they were taken at, see `sync_global_tiles`
+    global_copies: TileMap<()>,
+    global_generation: u64,
+    expansions: Rc<RefCell<ExpansionCache>>,
+    recency: RefCell<Recency>,
+    // the log of the changes, recorded only when asked for, see `record_events`, and its limit, see `limit_events`
+    recording_events: bool,
+    events: Vec<StoreEvent>,
+    events_limit: Option<usize>,
+}
+
+/// the capacity of a bounded store, its pinned tiles and when every tile was last used, see `TileStore::set_capacity`
+#[derive(Debug, Clone, Default)]
+struct Recency {
+    capacity: Option<usize>,
+    pinned: HashSet<String>,
+    last_used: HashMap<String, u64>,
+    clock: u64,
+}
+
+impl Recency {
+    fn touch(&mut self, tile_name: &str) {
+        if self.capacity.is_none() {
+            return;
+        }
+        self.clock += 1;
+        self.last_used.insert(tile_name.to_string(), self.clock);
+    }
+}
+
+/// a tile computed by a closure whenever a tile depending on it is rendered, see `set_dynamic_tile`
+#[derive(Clone)]
+pub(crate) struct DynamicTile(Rc<dyn Fn() -> RTile>);
+
+impl DynamicTile {
+    // calls the closure detached from the tls (thread local storage), so the tiles it creates don't register any blank tiles
+    pub(crate) fn evaluate(&self) -> RTile {
+        detached(|| (self.0)())
+    }
+}
+
+impl Debug for DynamicTile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "DynamicTile")
+    }
+}
+
+impl TileStore {
+    /// creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// persists the tile in the store with the given name, any missing inner tiles are created as blank tiles
+    #[track_caller]
+    pub fn set(&mut self, name: &str, tile: RTile) {
+        check_registration(name, &tile);
+        let mut tile = tile;
+        tile.name = Some(name.to_string());
+        self.create_blank_tiles_of_any_missing_inner_tiles(None, &tile.lns);
+        let processed = self.expand(&tile);
+        self.set_processed(name.to_string(), processed);
+        self.set_raw(name.to_string(), tile);
+    }
+
+    /// persists the composed tile with the given name together with the tiles it was composed from, see `stp_deep!`
+    pub fn set_deep(&mut self, name: &str, tile: &RTile) -> Vec<String> {
+        let previous = match self.get(name) {
+            Some(previous) => {
+                let output = self.expand(&previous);
+                snapshot_of(&previous, &output)
+            }
+            None => snapshot_of(tile, ""),
+        };
+        let previous = RTile {
+            name: Some(name.to_string()),
+            ..previous
+        };
+        let mut parts = vec![];
+        let template = decompose(name, tile, &previous, &mut parts);
+        let mut part_names = vec![];
+        for (part_name, part) in parts {
+            self.set(&part_name, part);
+            part_names.push(part_name);
+        }
+        self.set(name, template);
+        part_names
+    }
+
+    ///
+    /// persists the tile returned by `f` with the given name, unless the tile was persisted by an earlier call with the same `key`
+    ///
+    /// the key is any value which changes whenever the tile has to be recomputed, e.g. a file mtime or a content hash, see
+    /// `CacheKey`. persisting the tile with any other function invalidates the key, the tile is reused by the later runs through the
+    /// cache directory, see `set_cached_tile` and `set_cache_dir`.
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut store = TileStore::new();
+    /// let mut computed = 0;
+    /// for version in [1, 1, 2] {
+    ///     store.set_cached("schema", version, || {
+    ///         computed += 1;
+    ///         t!("schema v{}", version)
+    ///     });
+    /// }
+    /// assert_eq!(computed, 2);
+    /// assert_eq!(ts!(store => "@{schema}"), "schema v2");
+    /// ```
+    ///
+    pub fn set_cached<K: CacheKey>(
+        &mut self,
+        name: &str,
+        key: K,
+        f: impl FnOnce() -> RTile,
+    ) -> RTile {
+        let key = cache_key(key);
+        match self.cached(name, key) {
+            Some(tile) => tile,
+            None => {
+                let tile = load_cached_tile(name, key).unwrap_or_else(|| {
+                    let tile = f();
+                    save_cached_tile(name, key, &tile);
+                    tile
+                });
+                self.set(name, tile);
+                self.set_cache_key(name.to_string(), key);
+                self.get(name).unwrap()
+            }
+        }
+    }
+
+    /// returns all the tiles of the store by their names, see `export_store`
+    pub fn export(&self) -> HashMap<String, RTile> {
+        self.raw_tiles
+            .iter()
+            .map(|(tile_name, tile)| (tile_name.clone(), tile.clone()))
+            .collect()
+    }
+
+    /// returns all the tiles of the store sorted by their names, see `export_store_sorted`
+    pub fn export_sorted(&self) -> BTreeMap<String, RTile> {
+        self.raw_tiles
+            .iter()
+            .map(|(tile_name, tile)| (tile_name.clone(), tile.clone()))
+            .collect()
+    }
+
+    /// persists the given tiles in the store, resolving any clashing names using the policy, see `merge_store`
+    pub fn merge(
+        &mut self,
+        tiles: HashMap<String, RTile>,
+        policy: ConflictPolicy,
+    ) -> std::result::Result<(), Vec<String>> {
+        let mut conflicts: Vec<String> = tiles
+            .iter()
+            .filter(|(tile_name, tile)| {
+                self.raw_tiles.get(tile_name).is_some_and(|existing| {
+                    // the blank tiles are created for the missing inner tiles, so they never clash
+                    !existing.lns.is_empty()
+                        && (existing.lns != tile.lns || existing.do_trimming != tile.do_trimming)
+                })
+            })
+            .map(|(tile_name, _)| tile_name.clone())
+            .collect();
+        conflicts.sort();
+        if policy == ConflictPolicy::Error && !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+        // the frozen tiles can't be replaced, so nothing is merged if any of them would be
+        let frozen_tiles = self.frozen_tiles.clone();
+        let is_frozen =
+            |tile_name: &String| frozen_tiles.contains(&scoped_tile_name(tile_name.clone()));
+        if policy == ConflictPolicy::Overwrite && conflicts.iter().any(is_frozen) {
+            conflicts.retain(is_frozen);
+            return Err(conflicts);
+        }
+
+        // in the order of the names, so the merge (e.g. its events and the evictions of a bounded store) is the same for every run
+        let mut tiles: Vec<(String, RTile)> = tiles.into_iter().collect();
+        tiles.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut merged_tiles = vec![];
+        for (tile_name, mut tile) in tiles {
+            if policy == ConflictPolicy::Skip && conflicts.contains(&tile_name) {
+                continue;
+            }
+            // a frozen tile left here is identical to the merged one
+            if is_frozen(&tile_name) {
+                continue;
+            }
+            tile.name = Some(tile_name.clone());
+            self.set_raw(tile_name.clone(), tile);
+            merged_tiles.push(tile_name);
+        }
+        // all the tiles are merged before rendering any of them, as they may refer to each other
+        for tile_name in merged_tiles {
+            // the tile may have been evicted from a bounded store
+            let Some(tile) = self.get(&tile_name) else {
+                continue;
+            };
+            self.create_blank_tiles_of_any_missing_inner_tiles(None, &tile.lns);
+            let processed = self.expand(&tile);
+            self.set_processed(tile_name, processed);
+        }
+        Ok(())
+    }
+
+    /// returns the tile persisted with the given name
+    pub fn get(&self, name: &str) -> Option<RTile> {
+        let name = resolve_tile_name(&self.raw_tiles, name.to_string());
+        let tile = self.raw_tiles.get(&name).cloned();
+        if tile.is_some() {
+            self.recency.borrow_mut().touch(&name);
+        }
+        tile
+    }
+
+    /// returns a snapshot of the output of the tile persisted with the given name, see `gtp_snapshot!`
+    pub fn snapshot(&mut self, name: &str) -> Option<RTile> {
+        let tile = self.get(name)?;
+        let output = self.render(&tile);
+        Some(snapshot_of(&tile, &output))
+    }
+
+    /// returns true, if a tile is persisted with the given name
+    pub fn contains(&self, name: &str) -> bool {
+        self.raw_tiles
+            .contains_key(&resolve_tile_name(&self.raw_tiles, name.to_string()))
+    }
+
+    /// removes the tile persisted with the given name and returns it
+    #[track_caller]
+    pub fn remove(&mut self, name: &str) -> Option<RTile> {
+        self.remove_at(name, Location::caller())
+    }
+
+    pub(crate) fn remove_at(
+        &mut self,
+        name: &str,
+        location: &'static Location<'static>,
+    ) -> Option<RTile> {
+        let name = resolve_tile_name(&self.raw_tiles, name.to_string());
+        self.check_not_frozen(&name);
+        let old_hash = self.raw_tiles.get(&name).map(raw_hash);
+        if self.recording_events && old_hash.is_some() {
+            self.record(
+                StoreEventKind::Remove,
+                Some(&name),
+                old_hash,
+                None,
+                location,
+            );
+        }
+        self.processed_tiles.remove(&name);
+        self.cache_keys.remove(&name);
+        self.dynamic_tiles.remove(&name);
+        self.implicit_tiles.remove(&name);
+        self.global_copies.remove(&name);
+        self.recency.get_mut().last_used.remove(&name);
+        self.raw_tiles.remove(&name)
+    }
+
+    ///
+    /// creates an empty store, which holds at most the given number of tiles, see `set_capacity`
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut store = TileStore::with_capacity(2);
+    /// tp!(store => layout, "<main>@{content}</main>");
+    /// store.pin("layout");
+    /// for page in ["home", "about", "contact"] {
+    ///     tp!(store => title, page.to_uppercase());
+    ///     tp!(store => content, page);
+    /// }
+    /// assert_eq!(ts!(store => "@{layout}"), "<main>contact</main>");
+    /// assert_eq!(store.len(), 2);
+    /// assert!(!store.contains("title"));
+    /// ```
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut store = Self::default();
+        store.set_capacity(Some(capacity));
+        store
+    }
+
+    ///
+    /// limits the number of tiles of the store, when a tile is persisted beyond the capacity, the least recently used tiles are evicted
+    ///
+    /// a tile is used when it is persisted, returned by `get` or expanded by a render. the pinned and the frozen tiles are never
+    /// evicted (so the store may hold more tiles than the capacity), and an evicted inner tile expands to nothing, like any
+    /// missing tile. None removes the limit, see `set_store_capacity`
+    ///
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        let recency = self.recency.get_mut();
+        recency.capacity = capacity;
+        if capacity.is_none() {
+            recency.last_used.clear();
+        }
+        self.evict();
+    }
+
+    /// returns the capacity of the store, None if the store is not bounded
+    pub fn capacity(&self) -> Option<usize> {
+        self.recency.borrow().capacity
+    }
+
+    /// pins the tile with the given name (persisted or not yet), so it is never evicted from a bounded store, see `pin_tile`
+    pub fn pin(&mut self, name: &str) {
+        let name = resolve_tile_name(&self.raw_tiles, name.to_string());
+        self.recency.get_mut().pinned.insert(name);
+    }
+
+    /// unpins the tile with the given name, see `pin`
+    pub fn unpin(&mut self, name: &str) {
+        let name = resolve_tile_name(&self.raw_tiles, name.to_string());
+        self.recency.get_mut().pinned.remove(&name);
+        self.evict();
+    }
+
+    /// returns true, if the tile with the given name is pinned
+    pub fn is_pinned(&self, name: &str) -> bool {
+        let name = resolve_tile_name(&self.raw_tiles, name.to_string());
+        self.recency.borrow().pinned.contains(&name)
+    }
+
+    // evicts the least recently used tiles, which are neither pinned nor frozen, until the store is within its capacity
+    fn evict(&mut self) {
+        let Some(capacity) = self.recency.get_mut().capacity else {
+            return;
+        };
+        let len = self.raw_tiles.len();
+        if len <= capacity {
+            return;
+        }
+        let recency = self.recency.get_mut();
+        let mut candidates: Vec<(u64, String)> = self
+            .raw_tiles
+            .iter()
+            .filter(|(tile_name, _)| {
+                !recency.pinned.contains(*tile_name) && !self.frozen_tiles.contains(*tile_name)
+            })
+            .map(|(tile_name, _)| {
+                let last_used = recency.last_used.get(tile_name).copied().unwrap_or(0);
+                (last_used, tile_name.clone())
+            })
+            .collect();
+        candidates.sort();
+        for (_, tile_name) in candidates.into_iter().take(len - capacity) {
+            self.raw_tiles.remove(&tile_name);
+            self.processed_tiles.remove(&tile_name);
+            self.cache_keys.remove(&tile_name);
+            self.dynamic_tiles.remove(&tile_name);
+            self.implicit_tiles.remove(&tile_name);
+            self.global_copies.remove(&tile_name);
+            self.recency.get_mut().last_used.remove(&tile_name);
+        }
+    }
+
+    /// removes all the tiles, including the frozen tiles
+    #[track_caller]
+    pub fn clear(&mut self) {
+        self.clear_at(Location::caller());
+    }
+
+    pub(crate) fn clear_at(&mut self, location: &'static Location<'static>) {
+        self.record(StoreEventKind::Clear, None, None, None, location);
+        self.raw_tiles.clear();
+        self.processed_tiles.clear();
+        self.cache_keys.clear();
+        self.frozen_tiles = Rc::default();
+        self.declared_inputs.clear();
+        self.render_options.clear();
+        self.dynamic_tiles.clear();
+        self.implicit_tiles.clear();
+        self.global_copies.clear();
+        self.expansions = Rc::default();
+        self.recency.get_mut().last_used.clear();
+    }
+
+    ///
+    /// persists a tile computed by the closure with the given name, the closure is called whenever a tile depending on it is rendered
+    ///
+    /// the closure is not called when the tile is persisted, so the values which are never rendered are never computed.
+    /// the closure is called detached from the tls (thread local storage), i.e. the tiles it creates don't register any blank tiles.
+    /// persisting a tile with the same name using any other function replaces the closure, see `set_dynamic_tile`.
+    /// ```
+    /// use rtile::prelude::*;
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// let mut store = TileStore::new();
+    /// let counter = Rc::new(Cell::new(0));
+    /// let calls = counter.clone();
+    /// store.set_dynamic("build", move || {
+    ///     calls.set(calls.get() + 1);
+    ///     t!("build #{}", calls.get())
+    /// });
+    /// tp!(store => footer, "-- @{build}");
+    /// assert_eq!(counter.get(), 0);
+    /// assert_eq!(ts!(store => "@{footer}"), "-- build #1");
+    /// assert_eq!(ts!(store => "@{footer}"), "-- build #2");
+    /// ```
+    ///
+    pub fn set_dynamic(&mut self, name: &str, f: impl Fn() -> RTile + 'static) {
+        let tile = RTile {
+            name: Some(name.to_string()),
+            lns: vec![],
+            do_trimming: true,
+            tags: vec![],
+            parts: Parts::default(),
+            marker: PhantomData::<Rc<()>>,
+        };
+        self.set_processed(name.to_string(), String::new());
+        self.set_raw(name.to_string(), tile);
+        self.dynamic_tiles
+            .insert(scoped_tile_name(name.to_string()), DynamicTile(Rc::new(f)));
+    }
+
+    /// returns true, if the tile with the given name is computed by a closure, see `set_dynamic`
+    pub fn is_dynamic(&self, name: &str) -> bool {
+        self.dynamic_tiles
+            .contains_key(&resolve_tile_name(&self.raw_tiles, name.to_string()))
+    }
+
+    // calls the closures of the dynamic tiles the given tile depends on, so they are expanded with their current values
+    pub(crate) fn evaluate_dynamic_tiles(&mut self, tile: &RTile) {
+        let mut evaluated: HashSet<String> = HashSet::new();
+        loop {
+            // a dynamic tile may depend on other dynamic tiles
+            let pending = self.pending_dynamic_tiles(tile, &evaluated);
+            if pending.is_empty() {
+                return;
+            }
+            for (tile_name, dynamic_tile) in pending {
+                self.set_dynamic_value(tile_name.clone(), dynamic_tile.evaluate());
+                evaluated.insert(tile_name);
+            }
+        }
+    }
+
+    // the dynamic inner tiles of the tile, which are not evaluated yet, the closures are called by the caller, without borrowing the store
+    pub(crate) fn pending_dynamic_tiles(
+        &self,
+        tile: &RTile,
+        evaluated: &HashSet<String>,
+    ) -> Vec<(String, DynamicTile)> {
+        if self.dynamic_tiles.iter().next().is_none() {
+            return vec![];
+        }
+        self.inner_tiles(tile)
+            .into_iter()
+            .filter(|tile_name| !evaluated.contains(tile_name))
+            .filter_map(|tile_name| {
+                let dynamic_tile = self.dynamic_tiles.get(&tile_name)?.clone();
+                Some((tile_name, dynamic_tile))
+            })
+            .collect()
+    }
+
+    pub(crate) fn set_dynamic_value(&mut self, tile_name: String, value: RTile) {
+        let mut value = value;
+        value.name = Some(tile_name.clone());
+        self.create_blank_tiles_of_any_missing_inner_tiles(None, &value.lns);
+        self.raw_tiles.insert(tile_name, value);
+    }
+
+    /// declares the inputs (the placeholders) expected by the template with the given name, see `declare_inputs`
+    pub fn declare_inputs<I, T>(&mut self, name: &str, inputs: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let inputs = inputs.into_iter().map(Into::into).collect();
+        self.declared_inputs
+            .insert(scoped_tile_name(name.to_string()), inputs);
+    }
+
+    ///
+    /// attaches the render options to the template with the given name, which apply instead of the render options of the current
+    /// thread whenever the template is rendered, so every caller gets the output the template needs, see `set_template_render_options`
+    ///
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// fn semicolons(output: &str) -> String {
+    ///     format!("{};", output)
+    /// }
+    ///
+    /// let mut store = TileStore::new();
+    /// tp!(store => statement, "let x = 1");
+    /// store.set_render_options(
+    ///     "statement",
+    ///     RenderOptions {
+    ///         post_processors: vec![semicolons],
+    ///         ..Default::default()
+    ///     },
+    /// );
+    /// let statement = store.get("statement").unwrap();
+    /// assert_eq!(store.render(&statement), "let x = 1;");
+    /// assert_eq!(store.render_options("statement").unwrap().post_processors.len(), 1);
+    /// ```
+    ///
+    pub fn set_render_options(&mut self, name: &str, options: RenderOptions) {
+        self.render_options
+            .insert(scoped_tile_name(name.to_string()), options);
+    }
+
+    /// returns the render options attached to the template with the given name, see `set_render_options`
+    pub fn render_options(&self, name: &str) -> Option<&RenderOptions> {
+        self.render_options
+            .get(&resolve_tile_name(&self.raw_tiles, name.to_string()))
+    }
+
+    /// detaches the render options from the template with the given name, see `set_render_options`
+    pub fn clear_render_options(&mut self, name: &str) {
+        self.render_options
+            .remove(&resolve_tile_name(&self.raw_tiles, name.to_string()));
+    }
+
+    // the render options attached to the rendered tile, if it is a named template
+    pub(crate) fn template_render_options(&self, tile: &RTile) -> Option<RenderOptions> {
+        self.render_options(tile.name.as_deref()?).cloned()
+    }
+
+    /// checks the placeholders of the template with the given name against its declared inputs, see `check_inputs`
+    pub fn check_inputs(&self, name: &str) -> InputReport {
+        let name = resolve_tile_name(&self.raw_tiles, name.to_string());
+        let declared: Vec<String> = self.declared_inputs.get(&name).cloned().unwrap_or_default();
+        let mut used: Vec<String> = vec![];
+        if let Some(tile) = self.raw_tiles.get(&name) {
+            let definitions = local_definitions(&tile.lns);
+            for ln in &tile.lns {
+                for placeholder in placeholders(ln) {
+                    for input in placeholder.inner_tile_names(&definitions) {
+                        if !used.contains(&input) {
+                            used.push(input);
+                        }
+                    }
+                }
+            }
+        }
+        InputReport {
+            extra: used
+                .iter()
+                .filter(|input| !declared.contains(input))
+                .cloned()
+                .collect(),
+            unused: declared
+                .iter()
+                .filter(|input| !used.contains(input))
+                .cloned()
+                .collect(),
+            unfilled: declared
+                .iter()
+                .filter(|input| {
+                    self.raw_tiles
+                        .get(&resolve_tile_name(&self.raw_tiles, input.to_string()))
+                        .is_none_or(|tile| tile.lns.is_empty())
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// freezes all the non blank tiles of the store, so any later attempt to persist or remove a tile with the same name panics, see `freeze_tiles`
+    pub fn freeze(&mut self) {
+        let mut frozen_tiles = (*self.frozen_tiles).clone();
+        for (tile_name, tile) in self.raw_tiles.iter() {
+            if !tile.lns.is_empty() {
+                frozen_tiles.insert(tile_name.clone());
+            }
+        }
+        self.frozen_tiles = Rc::new(frozen_tiles);
+    }
+
+    /// returns true, if the tile with the given name is frozen
+    pub fn is_frozen(&self, name: &str) -> bool {
+        self.frozen_tiles
+            .contains(&resolve_tile_name(&self.raw_tiles, name.to_string()))
+    }
+
+    /// returns the number of tiles in the store
+    pub fn len(&self) -> usize {
+        self.raw_tiles.len()
+    }
+
+    /// returns true, if there are no tiles in the store
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///
+    /// starts (or stops) recording the changes of the store in its event log, see `events`
+    ///
+    /// stopping the recording keeps the events recorded so far, and `clear` is recorded as an event. the log grows until it is
+    /// drained, see `drain_events`, or it keeps only the latest events, see `limit_events`.
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut store = TileStore::new();
+    /// store.record_events(true);
+    /// tp!(store => title, "Report");
+    /// tp!(store => title, "Payroll");
+    /// store.remove("title");
+    ///
+    /// let events = store.events();
+    /// let kinds: Vec<StoreEventKind> = events.iter().map(|event| event.kind).collect();
+    /// assert_eq!(kinds, vec![StoreEventKind::Set, StoreEventKind::Set, StoreEventKind::Remove]);
+    /// assert_eq!(events[1].old_hash, events[0].new_hash);
+    /// assert_eq!(events[2].old_hash, events[1].new_hash);
+    /// assert_eq!(events[1].location.file(), file!());
+    /// ```
+    ///
+    pub fn record_events(&mut self, enabled: bool) {
+        self.recording_events = enabled;
+    }
+
+    /// returns the events recorded so far, in the order of the changes
+    pub fn events(&self) -> &[StoreEvent] {
+        &self.events
+    }
+
+    /// returns the events recorded so far, in the order of the changes, and empties the log
+    pub fn drain_events(&mut self) -> Vec<StoreEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    ///
+    /// keeps only the latest `limit` events in the log, the older ones are dropped, `None` (the default) keeps all of them
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut store = TileStore::new();
+    /// store.record_events(true);
+    /// store.limit_events(Some(2));
+    /// for version in ["v1", "v2", "v3"] {
+    ///     tp!(store => title, version);
+    /// }
+    /// assert_eq!(store.events().len(), 2);
+    /// assert_eq!(store.drain_events().len(), 2);
+    /// assert!(store.events().is_empty());
+    /// ```
+    ///
+    pub fn limit_events(&mut self, limit: Option<usize>) {
+        self.events_limit = limit;
+        self.truncate_events();
+    }
+
+    fn truncate_events(&mut self) {
+        if let Some(limit) = self.events_limit {
+            let excess = self.events.len().saturating_sub(limit);
+            self.events.drain(..excess);
+        }
+    }
+
+    fn record(
+        &mut self,
+        kind: StoreEventKind,
+        name: Option<&str>,
+        old_hash: Option<u64>,
+        new_hash: Option<u64>,
+        location: &'static Location<'static>,
+    ) {
+        if self.recording_events {
+            self.events.push(StoreEvent {
+                kind,
+                name: name.map(String::from),
+                timestamp: SystemTime::now(),
+                old_hash,
+                new_hash,
+                location,
+            });
+            self.truncate_events();
+        }
+    }
+
+    ///
+    /// runs the closure with this store in place of the tls (thread local storage), the tls is restored afterwards
+    ///
+    /// the macros without a store handle (e.g. tp!, t!, gtp!, ts!) and the tiles rendered using `to_string` use this store
+    /// within the closure, so the code written against the tls runs against an explicit context as it is.
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// fn page(context: &mut RTileContext) -> String {
+    ///     context.enter(|| ts!("<h1>@{title}</h1>"))
+    /// }
+    ///
+    /// let mut shop = RTileContext::new();
+    /// let mut blog = RTileContext::new();
+    /// shop.enter(|| tp!(title, "Shop"));
+    /// tp!(blog => title, "Blog");
+    /// assert_eq!(page(&mut shop), "<h1>Shop</h1>");
+    /// assert_eq!(page(&mut blog), "<h1>Blog</h1>");
+    /// assert!(gtp!(title).is_none());
+    /// ```
+    ///
+    pub fn enter<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        struct EnterGuard<'a>(&'a mut TileStore);
+        impl Drop for EnterGuard<'_> {
+            fn drop(&mut self) {
+                with_default_store(|store| std::mem::swap(store, self.0));
+            }
+        }
+
+        with_default_store(|store| std::mem::swap(store, self));
+        let _guard = EnterGuard(self);
+        f()
+    }
+
+    ///
+    /// returns a child store which starts with the tiles of this store, without copying them
+    ///
+    /// the tiles are shared between the stores until they are written, i.e. setting or removing a tile in the child
+    /// does not affect this store and vice versa. useful for speculative or per request renders which override a few tiles.
+    /// the child records its events (if this store does) in a log of its own, which starts empty.
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut store = TileStore::new();
+    /// tp!(store => lang, "en");
+    /// tp!(store => greet, "@{lang}: hello");
+    ///
+    /// let mut request = store.fork();
+    /// tp!(request => lang, "fr");
+    /// assert_eq!(ts!(request => "@{greet}"), "fr: hello");
+    /// assert_eq!(ts!(store => "@{greet}"), "en: hello");
+    /// ```
+    ///
+    pub fn fork(&mut self) -> TileStore {
+        TileStore {
+            raw_tiles: self.raw_tiles.fork(),
+            processed_tiles: self.processed_tiles.fork(),
+            cache_keys: self.cache_keys.fork(),
+            frozen_tiles: self.frozen_tiles.clone(),
+            declared_inputs: self.declared_inputs.fork(),
+            render_options: self.render_options.fork(),
+            dynamic_tiles: self.dynamic_tiles.fork(),
+            implicit_tiles: self.implicit_tiles.fork(),
+            global_copies: self.global_copies.fork(),
+            global_generation: self.global_generation,
+            expansions: self.expansions.clone(),
+            recency: self.recency.clone(),
+            recording_events: self.recording_events,
+            events: vec![],
+            events_limit: self.events_limit,
+        }
+    }
+
+    /// returns the sorted names of the blank tiles, i.e. the tiles without any lines, see `get_blank_tiles_sorted`
+    pub fn blank_tiles_sorted(&self) -> BTreeSet<String> {
+        self.blank_tiles().into_iter().collect()
+    }
+
+    /// returns the names of the blank tiles, i.e. the tiles without any lines
+    pub fn blank_tiles(&self) -> HashSet<String> {
+        let mut blank_tiles = HashSet::new();
+        for (tile_name, tile) in self.raw_tiles.iter() {
+            if tile.lns == Vec::<String>::new() {
+                assert!(!tile_name.is_empty());
+                blank_tiles.insert(tile_name.clone());
+            }
+        }
+        blank_tiles
+    }
+
+    /// returns a hash of the names and the raw data of all the tiles, which is stable across runs and platforms, see `store_fingerprint`
+    pub fn fingerprint(&self) -> u64 {
+        let mut tiles: Vec<(&String, &RTile)> = self.raw_tiles.iter().collect();
+        tiles.sort_by(|a, b| a.0.cmp(b.0));
+        tiles
+            .into_iter()
+            .fold(FNV_OFFSET_BASIS, |hash, (tile_name, tile)| {
+                let hash = content_hash(hash, tile_name.as_bytes());
+                let hash = content_hash(hash, &[0, tile.do_trimming as u8]);
+                let hash = content_hash(hash, tile.lns.join("\n").as_bytes());
+                content_hash(hash, &[0])
+            })
+    }
+
+    /// expands the inner tiles of the given tile using the tiles of this store and returns the output
+    pub fn render(&mut self, tile: &RTile) -> String {
+        let options = self.template_render_options(tile);
+        with_render_options(options, || {
+            self.evaluate_dynamic_tiles(tile);
+            self.expand_for_render(tile)
+        })
+    }
+
+    /// expands the inner tiles of the given tile the same way as `render`, or returns the first problem which would make the
+    /// render panic or print, i.e. a missing tile (not persisted, or just the blank tile created for it when a tile referring
+    /// to it was persisted), a recursion of the tiles, or an unfinished or invalid placeholder
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut store = TileStore::new();
+    /// tp!(store => greet, "Hello @{name}!");
+    /// let tile = detached(|| t!("@{greet}"));
+    /// assert_eq!(
+    ///     store.try_render(&tile),
+    ///     Err(RTileError::MissingTile { name: "name".to_string(), parents: vec!["greet".to_string()] })
+    /// );
+    /// tp!(store => name, "Alice");
+    /// assert_eq!(store.try_render(&tile), Ok("Hello Alice!".to_string()));
+    /// ```
+    ///
+    pub fn try_render(&mut self, tile: &RTile) -> std::result::Result<String, RTileError> {
+        self.sync_global_tiles();
+        self.check_expansion(tile)?;
+        Ok(self.render(tile))
+    }
+
+    // the first unfinished or invalid placeholder of the lines of a tile being constructed, see `RTile::try_new`
+    pub(crate) fn check_lines(&self, lns: &[String]) -> std::result::Result<(), RTileError> {
+        check_placeholders(&self.raw_tiles, lns)
+    }
+
+    // once the global registry changed, the tiles taken from it are taken again (or become blank, if they were removed from it), and
+    // the blank tiles created for the missing inner tiles are taken from it, if it has them now, see `set_global_tile`
+    pub(crate) fn sync_global_tiles(&mut self) {
+        let generation = global_generation();
+        if self.global_generation == generation {
+            return;
+        }
+        self.global_generation = generation;
+        let names: Vec<String> = self
+            .global_copies
+            .iter()
+            .chain(self.implicit_tiles.iter())
+            .map(|(tile_name, _)| tile_name.clone())
+            .collect();
+        for tile_name in names {
+            match get_global_tile(&tile_name) {
+                Some(tile) if self.raw_tiles.get(&tile_name) != Some(&tile) => {
+                    self.implicit_tiles.remove(&tile_name);
+                    self.global_copies.insert(tile_name.clone(), ());
+                    self.raw_tiles.insert(tile_name.clone(), tile.clone());
+                    self.create_blank_tiles_of_any_missing_inner_tiles(
+                        Some(tile_name.clone()),
+                        &tile.lns,
+                    );
+                    let processed = self.expand(&tile);
+                    self.set_processed(tile_name, processed);
+                }
+                None if self.global_copies.remove(&tile_name).is_some() => {
+                    self.raw_tiles.remove(&tile_name);
+                    self.create_blank_tiles_of_any_missing_inner_tiles(
+                        None,
+                        &[format!("@{{{}}}", tile_name)],
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // the first problem found in the tile and its inner tiles, before any of them is parsed by the render
+    pub(crate) fn check_expansion(&self, tile: &RTile) -> std::result::Result<(), RTileError> {
+        check_placeholders(&self.raw_tiles, &tile.lns)?;
+        let mut check = ExpansionCheck {
+            raw_tiles: &self.raw_tiles,
+            implicit_tiles: &self.implicit_tiles,
+            error: None,
+        };
+        walk(&self.raw_tiles, None, &tile.lns, &mut check);
+        match check.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    // the render of the tile asked for by the caller, i.e. checked when the render is strict, with the line endings of the render
+    #[track_caller]
+    pub(crate) fn expand_for_render(&mut self, tile: &RTile) -> String {
+        self.sync_global_tiles();
+        let options = get_render_options();
+        if options.strict {
+            if let Err(error) = self.check_expansion(tile) {
+                panic!("{}", error);
+            }
+        }
+        let output = self.expand(tile);
+        match options.line_ending {
+            LineEnding::Lf => output,
+            line_ending => line_ending.apply(&output),
+        }
+    }
+
+    // the render, without calling the closures of the dynamic tiles
+    pub(crate) fn expand(&mut self, tile: &RTile) -> String {
+        self.expand_in_layer(tile).0
+    }
+
+    // the expanded output of the tile, and the values of its inner tiles as processed by this render, see `RTile::to_string_with_report`
+    pub(crate) fn expand_with_empty_inner_tiles(&mut self, tile: &RTile) -> (String, Vec<String>) {
+        self.sync_global_tiles();
+        let (output, processed_tiles) = self.expand_in_layer(tile);
+        let empty_inner_tiles = self
+            .inner_tiles(tile)
+            .into_iter()
+            .filter(|tile_name| {
+                processed_tiles
+                    .get(tile_name)
+                    .is_none_or(|value| value.trim().is_empty())
+            })
+            .collect();
+        (output, empty_inner_tiles)
+    }
+
+    fn expand_in_layer(&mut self, tile: &RTile) -> (String, ProcessedTiles) {
+        self.create_blank_tiles_of_any_missing_inner_tiles(None, &tile.lns);
+        if self.recency.get_mut().capacity.is_some() {
+            for tile_name in self.inner_tiles(tile) {
+                self.recency.get_mut().touch(&tile_name);
+            }
+        }
+        // the inner tiles are processed in a layer of this render, so a render never changes the processed data of the store
+        let mut processed_tiles = self.processed_tiles.fork();
+        let template_lns = with_chunk_hints(&tile.lns, get_render_options().chunk_hints);
+        let lns = testing::with_template(tile.name.as_deref(), || {
+            r_format_using_raw_tiles_data(
+                &self.raw_tiles,
+                &mut processed_tiles,
+                &self.expansions,
+                &template_lns,
+            )
+        });
+        let lns = trim(lns, tile.do_trimming);
+        let lns = match get_render_options().indent_style {
+            Some(style) => reindent(lns, style),
+            None => lns,
+        };
+        let lns = match get_render_options().max_width {
+            Some(max_width) => lns.iter().flat_map(|ln| hard_wrap(ln, max_width)).collect(),
+            None => lns,
+        };
+        let mut lns = lns;
+        if !tile.do_trimming {
+            // keep the white spaces, so every line is as wide as the tile
+            let w = lns.iter().map(|s| display_width(s)).max().unwrap_or(0);
+            for ln in lns.iter_mut() {
+                let padding = w - display_width(ln);
+                ln.extend(std::iter::repeat_n(' ', padding));
+            }
+        }
+        let mut output = lns.join("\n");
+        if let Some(name) = tile.name.as_deref() {
+            output = post_process(output, &post_processors(Some(name)));
+        }
+        output = post_process(output, &get_render_options().post_processors);
+        output = post_process(output, &post_processors(None));
+        let output = match get_render_options().content_stamp {
+            true => stamp_content_sha256(&output),
+            false => output,
+        };
+        (output, processed_tiles)
+    }
+
+    /// rewrites `@{old}` to `@{new}` in every tile of the store and renames the tile itself, see `rename_placeholder_everywhere`
+    #[track_caller]
+    pub fn rename_placeholder(
+        &mut self,
+        old: &str,
+        new: &str,
+        dry_run: bool,
+    ) -> std::result::Result<Vec<String>, RTileError> {
+        self.rename_placeholder_at(old, new, dry_run, Location::caller())
+    }
+
+    pub(crate) fn rename_placeholder_at(
+        &mut self,
+        old: &str,
+        new: &str,
+        dry_run: bool,
+        location: &'static Location<'static>,
+    ) -> std::result::Result<Vec<String>, RTileError> {
+        // the tile itself and its versions persisted by tpv!, e.g. `old@2.1.0`, along with their new names
+        let renamed_tiles: Vec<(String, String)> = self
+            .raw_tiles
+            .iter()
+            .map(|(tile_name, _)| tile_name)
+            .filter(|tile_name| {
+                tile_name.as_str() == old
+                    || split_version(tile_name).is_some_and(|(name, _)| name == old)
+            })
+            .map(|tile_name| {
+                (
+                    tile_name.clone(),
+                    format!("{}{}", new, &tile_name[old.len()..]),
+                )
+            })
+            .collect();
+        let rewritten_tiles: Vec<(String, Vec<String>)> = self
+            .raw_tiles
+            .iter()
+            .filter_map(|(tile_name, tile)| {
+                let lns: Vec<Option<String>> = tile
+                    .lns
+                    .iter()
+                    .map(|ln| rename_in_line(ln, old, new))
+                    .collect();
+                lns.iter().any(Option::is_some).then(|| {
+                    let lns = lns
+                        .into_iter()
+                        .zip(tile.lns.iter())
+                        .map(|(renamed, ln)| renamed.unwrap_or_else(|| ln.clone()))
+                        .collect();
+                    (tile_name.clone(), lns)
+                })
+            })
+            .collect();
+        let mut affected_tiles: Vec<String> = renamed_tiles
+            .iter()
+            .map(|(tile_name, _)| tile_name.clone())
+            .chain(
+                rewritten_tiles
+                    .iter()
+                    .map(|(tile_name, _)| tile_name.clone()),
+            )
+            .collect();
+        affected_tiles.sort();
+        affected_tiles.dedup();
+        if affected_tiles.is_empty() {
+            return Ok(affected_tiles);
+        }
+        // renaming onto a persisted tile would replace it, and merge its placeholders with the renamed ones
+        if old != new {
+            if let Some((_, new_name)) = renamed_tiles
+                .iter()
+                .find(|(_, new_name)| self.raw_tiles.contains_key(new_name))
+            {
+                return Err(RTileError::TileExists {
+                    name: new_name.clone(),
+                });
+            }
+            if self.raw_tiles.contains_key(new) {
+                return Err(RTileError::TileExists {
+                    name: new.to_string(),
+                });
+            }
+        }
+        if dry_run {
+            return Ok(affected_tiles);
+        }
+        affected_tiles
+            .iter()
+            .for_each(|tile_name| self.check_not_frozen(tile_name));
+
+        for (tile_name, lns) in rewritten_tiles {
+            if let Some(tile) = self.raw_tiles.get_mut(&tile_name) {
+                let old_hash = raw_hash(tile);
+                tile.lns = lns;
+                let new_hash = raw_hash(tile);
+                self.record(
+                    StoreEventKind::Set,
+                    Some(&tile_name),
+                    Some(old_hash),
+                    Some(new_hash),
+                    location,
+                );
+            }
+        }
+        for (old_name, new_name) in renamed_tiles {
+            self.cache_keys.remove(&old_name);
+            if let Some(mut tile) = self.raw_tiles.remove(&old_name) {
+                let hash = raw_hash(&tile);
+                self.record(
+                    StoreEventKind::Rename,
+                    Some(&old_name),
+                    Some(hash),
+                    None,
+                    location,
+                );
+                self.record(
+                    StoreEventKind::Set,
+                    Some(&new_name),
+                    None,
+                    Some(hash),
+                    location,
+                );
+                tile.name = Some(new_name.clone());
+                self.raw_tiles.insert(new_name.clone(), tile);
+            }
+            if let Some(value) = self.processed_tiles.remove(&old_name) {
+                self.processed_tiles.insert(new_name, value);
+            }
+        }
+        Ok(affected_tiles)
+    }
+
+    pub(crate) fn set_processed(&mut self, key: String, value: String) {
+        let key = scoped_tile_name(key);
+        self.check_not_frozen(&key);
+        self.processed_tiles.insert(key, value.into());
+    }
+
+    #[track_caller]
+    pub(crate) fn set_raw(&mut self, key: String, value: RTile) {
+        self.set_raw_at(key, value, Location::caller());
+    }
+
+    pub(crate) fn set_raw_at(
+        &mut self,
+        key: String,
+        value: RTile,
+        location: &'static Location<'static>,
+    ) {
+        let key = scoped_tile_name(key);
+        self.check_not_frozen(&key);
+        if self.recording_events {
+            let old_hash = self.raw_tiles.get(&key).map(raw_hash);
+            let new_hash = Some(raw_hash(&value));
+            self.record(
+                StoreEventKind::Set,
+                Some(&key),
+                old_hash,
+                new_hash,
+                location,
+            );
+        }
+        self.cache_keys.remove(&key);
+        self.dynamic_tiles.remove(&key);
+        self.implicit_tiles.remove(&key);
+        self.global_copies.remove(&key);
+        self.recency.get_mut().touch(&key);
+        self.raw_tiles.insert(key, value);
+        self.evict();
+    }
+
+    fn check_not_frozen(&self, tile_name: &str) {
+        if self.frozen_tiles.contains(tile_name) {
+            panic!("the tile {} is frozen", tile_name);
+        }
+    }
+
+    pub(crate) fn set_cache_key(&mut self, name: String, key: u64) {
+        self.cache_keys.insert(scoped_tile_name(name), key);
+    }
+
+    pub(crate) fn cached(&self, name: &str, key: u64) -> Option<RTile> {
+        let name = resolve_tile_name(&self.raw_tiles, name.to_string());
+        if self.cache_keys.get(&name) == Some(&key) {
+            self.raw_tiles.get(&name).cloned()
+        } else {
+            None
+        }
+    }
+
+    /// processes every tile of this store again from its raw data, i.e. `render_cached` is up to date afterwards, frozen tiles are left as is
+    pub fn refresh(&mut self) {
+        let tiles: Vec<RTile> = self
+            .raw_tiles
+            .iter()
+            .filter(|(tile_name, _)| !self.frozen_tiles.contains(*tile_name))
+            .map(|(_, tile)| tile.clone())
+            .collect();
+        for tile in tiles {
+            let value = self.expand(&tile);
+            let tile_name = tile.name.unwrap_or_default();
+            self.processed_tiles.insert(tile_name, value.into());
+        }
+    }
+
+    /// expands the inner tiles of the given tile using the processed data of this store as is, see `RTile::render_cached`
+    pub fn render_cached(&self, tile: &RTile) -> String {
+        reevaluate(
+            &self.raw_tiles,
+            &self.processed_tiles,
+            &self.expansions,
+            tile,
+        )
+    }
+
+    ///
+    /// returns the statistics of the memoized expansions of the inner tiles, see `get_cache_stats`
+    ///
+    /// while rendering, the expansion of every inner tile is memoized, keyed by a fingerprint of the tile and of the values
+    /// of its own inner tiles, so a parent tile rendered again after one of its inner tiles changed only expands the tiles
+    /// on the path to the changed tile. the memoized expansions are shared with the forks of the store.
+    /// ```
+    /// use rtile::prelude::*;
+    ///
+    /// let mut store = TileStore::new();
+    /// tp!(store => greet, "Hello @{name}! @{footer}");
+    /// tp!(store => footer, "-- @{team}");
+    /// tp!(store => team, "the team");
+    /// for name in ["Alice", "Bob"] {
+    ///     tp!(store => name, name);
+    ///     ts!(store => "@{greet}");
+    /// }
+    /// let stats = store.cache_stats();
+    /// assert!(stats.hits > 0);
+    /// assert_eq!(stats.hits + stats.misses, stats.lookups());
+    /// ```
+    ///
+    pub fn cache_stats(&self) -> CacheStats {
+        let expansions = self.expansions.borrow();
+        CacheStats {
+            entries: expansions.entries.len(),
+            ..expansions.stats
+        }
+    }
+
+    /// drops the memoized expansions of the inner tiles and resets their statistics, see `cache_stats`
+    pub fn clear_cache(&mut self) {
+        *self.expansions.borrow_mut() = ExpansionCache::default();
+    }
+
+    pub(crate) fn inner_tiles(&self, tile: &RTile) -> Vec<String> {
+        let mut inner_tiles = InnerTiles(vec![]);
+        walk(&self.raw_tiles, None, &tile.lns, &mut inner_tiles);
+        inner_tiles.0
+    }
+
+    /// walks the inner tiles of the given tile depth first, reporting them to the visitor, see `walk_dependencies`
+    pub fn walk_dependencies(&self, tile: &RTile, visitor: &mut impl TileVisitor) {
+        walk(&self.raw_tiles, tile.name.as_ref(), &tile.lns, visitor);
+    }
+
+    /// returns the names of the given tile and of its inner tiles, every tile after its inner tiles, see `topo_order`
+    pub fn topo_order(&self, name: &str) -> std::result::Result<Vec<String>, CycleError> {
+        let name = resolve_tile_name(&self.raw_tiles, name.to_string());
+        let Some(tile) = self.raw_tiles.get(&name) else {
+            return Ok(vec![]);
+        };
+        let mut order = TopoOrder::default();
+        walk(&self.raw_tiles, Some(&name), &tile.lns, &mut order);
+        if let Some(cycle) = order.cycle {
+            return Err(CycleError { cycle });
+        }
+        order.names.push(name);
+        Ok(order.names)
+    }
+
+    // the tiles the given tile depends on transitively, including the tiles bound to its placeholders, by their names (both the
+    // resolved ones and the ones as written)
+    pub(crate) fn dependencies(&self, tile: &RTile) -> Vec<(String, RTile)> {
+        let mut dependencies: Vec<(String, RTile)> = vec![];
+        let mut pending = vec![tile.clone()];
+        while let Some(tile) = pending.pop() {
+            let definitions = local_definitions(&tile.lns);
+            for ln in &tile.lns {
+                for placeholder in placeholders(ln) {
+                    let inner_tile_names = placeholder.inner_tile_names(&definitions);
+                    let selectors: Vec<String> = inner_tile_names
+                        .iter()
+                        .filter_map(|name| indirect_selector(name).map(|s| s.to_string()))
+                        .collect();
+                    let names = inner_tile_names
+                        .iter()
+                        .chain(selectors.iter())
+                        .chain(placeholder.bindings.iter().map(|(_, bound)| bound));
+                    for name in names {
+                        let resolved = resolve_tile_name(&self.raw_tiles, name.clone());
+                        let Some(inner_tile) = self.raw_tiles.get(&resolved) else {
+                            continue;
+                        };
+                        // a name resolved through an enclosing prefix (see `with_prefix`) is kept as written too, so the tiles
+                        // expand the same way without the prefix
+                        let written = match indirect_selector(name) {
+                            Some(selector) => self
+                                .raw_tiles
+                                .get(&resolve_tile_name(&self.raw_tiles, selector.to_string()))
+                                .map(|selector| selector.lns.join("\n").trim().to_string()),
+                            None => Some(name.clone()),
+                        };
+                        let written = written.filter(|written| {
+                            *written != resolved && split_requirement(written).is_none()
+                        });
+                        for name in std::iter::once(resolved).chain(written) {
+                            if !dependencies.iter().any(|(known, _)| *known == name) {
+                                dependencies.push((name, inner_tile.clone()));
+                                pending.push(inner_tile.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        dependencies
+    }
+
+    pub(crate) fn blank_inner_tiles_names(&self, tile: &RTile) -> Vec<String> {
+        let mut blank_inner_tiles = BlankInnerTiles(vec![]);
+        walk(
+            &self.raw_tiles,
+            tile.name.as_ref(),
+            &tile.lns,
+            &mut blank_inner_tiles,
+        );
+        blank_inner_tiles.0
+    }
+
+    pub(crate) fn create_blank_tiles_of_any_missing_inner_tiles(
+        &mut self,
+        name: Option<String>,
+        lns: &[String],
+    ) {
+        let mut missing_inner_tiles = MissingInnerTiles(HashSet::new());
+        // a recursion of tiles is left to be reported by the render
+        walk(
+            &self.raw_tiles,
+            name.as_ref(),
+            lns,
+            &mut missing_inner_tiles,
+        );
+        // the missing tiles of the global registry are taken from it, along with their own missing inner tiles
+        let mut missing: Vec<String> = missing_inner_tiles.0.into_iter().collect();
+        let mut blank = vec![];
+        let mut global = vec![];
+        while let Some(tile_name) = missing.pop() {
+            if self.raw_tiles.contains_key(&tile_name) {
+                continue;
+            }
+            let Some(tile) = get_global_tile(&tile_name) else {
+                blank.push(tile_name);
+                continue;
+            };
+            let mut missing_inner_tiles = MissingInnerTiles(HashSet::new());
+            walk(
+                &self.raw_tiles,
+                Some(&tile_name),
+                &tile.lns,
+                &mut missing_inner_tiles,
+            );
+            missing.extend(missing_inner_tiles.0);
+            self.raw_tiles.insert(tile_name.clone(), tile.clone());
+            self.global_copies.insert(tile_name.clone(), ());
+            global.push((tile_name, tile));
+        }
+        for missing_inner_tile_name in blank {
+            if self.raw_tiles.contains_key(&missing_inner_tile_name) {
+                continue;
+            }
+            self.raw_tiles.insert(
+                missing_inner_tile_name.clone(),
+                RTile {
+                    name: Some(missing_inner_tile_name.clone()),
+                    lns: vec![],
+                    do_trimming: true,
+                    tags: vec![],
+                    parts: Parts::default(),
+                    marker: PhantomData::<Rc<()>>,
+                },
+            );
+            self.processed_tiles
+                .insert(missing_inner_tile_name.clone(), Rc::from(""));
+            self.implicit_tiles.insert(missing_inner_tile_name, ());
+        }
+        for (tile_name, tile) in global.into_iter().rev() {
+            let processed = self.expand(&tile);
+            self.set_processed(tile_name, processed);
+        }
+    }
+}
+
+// the key of `set_cached`, the same in every run (the cache files of the earlier runs are matched by it), see `set_cache_dir`
+pub(crate) fn cache_key<K: CacheKey>(key: K) -> u64 {
+    let mut bytes = vec![];
+    key.write_key(&mut bytes);
+    content_hash(FNV_OFFSET_BASIS, &bytes)
+}
+
+///
+/// CacheKey, the keys of `set_cached_tile` and `TileStore::set_cached`
+///
+/// the bytes of a key are defined here rather than by `std::hash::Hash`, whose output may change with the versions of rust,
+/// so the cache files written by a run are matched by the later runs after an upgrade of the compiler as well. the integers
+/// are written as little endian 64 bit numbers, so the bytes are the same on every platform.
+/// ```
+/// use rtile::prelude::*;
+///
+/// struct Schema {
+///     path: String,
+///     version: u32,
+/// }
+///
+/// impl CacheKey for Schema {
+///     fn write_key(&self, bytes: &mut Vec<u8>) {
+///         (self.path.as_str(), self.version).write_key(bytes);
+///     }
+/// }
+///
+/// let schema = Schema { path: "schema.json".to_string(), version: 2 };
+/// set_cached_tile("schema", &schema, || t!("struct Schema;"));
+/// assert_eq!(ts!("@{schema}"), "struct Schema;");
+/// ```
+///
+pub trait CacheKey {
+    /// appends the bytes of the key
+    fn write_key(&self, bytes: &mut Vec<u8>);
+}
+
+macro_rules! cache_key_of_integers {
+    ($($ty:ty => $wide:ty),*) => {
+        $(
+            impl CacheKey for $ty {
+                fn write_key(&self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&(*self as $wide).to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+cache_key_of_integers!(
+    u8 => u64, u16 => u64, u32 => u64, u64 => u64, usize => u64, u128 => u128,
+    i8 => i64, i16 => i64, i32 => i64, i64 => i64, isize => i64, i128 => i128
+);
+
+impl CacheKey for bool {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        bytes.push(*self as u8);
+    }
+}
+
+impl CacheKey for char {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        (*self as u32).write_key(bytes);
+    }
+}
+
+// the text is terminated the same way as by `Hash`, so the keys made of several texts are not ambiguous
+impl CacheKey for str {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(self.as_bytes());
+        bytes.push(0xff);
+    }
+}
+
+impl CacheKey for String {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        self.as_str().write_key(bytes);
+    }
+}
+
+impl CacheKey for Path {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        self.to_string_lossy().write_key(bytes);
+    }
+}
+
+impl CacheKey for PathBuf {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        self.as_path().write_key(bytes);
+    }
+}
+
+// the time since the unix epoch, e.g. the mtime of a file
+impl CacheKey for SystemTime {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        let since_epoch = self
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        since_epoch.write_key(bytes);
+    }
+}
+
+impl CacheKey for std::time::Duration {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        self.as_secs().write_key(bytes);
+        self.subsec_nanos().write_key(bytes);
+    }
+}
+
+// the slices are prefixed with their lengths
+impl<T: CacheKey> CacheKey for [T] {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        self.len().write_key(bytes);
+        for item in self {
+            item.write_key(bytes);
+        }
+    }
+}
+
+impl<T: CacheKey> CacheKey for Vec<T> {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        self.as_slice().write_key(bytes);
+    }
+}
+
+impl<T: CacheKey> CacheKey for Option<T> {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Some(value) => {
+                bytes.push(1);
+                value.write_key(bytes);
+            }
+            None => bytes.push(0),
+        }
+    }
+}
+
+impl<T: CacheKey + ?Sized> CacheKey for &T {
+    fn write_key(&self, bytes: &mut Vec<u8>) {
+        (**self).write_key(bytes);
+    }
+}
+
+macro_rules! cache_key_of_tuples {
+    ($(($($name:ident),+)),*) => {
+        $(
+            impl<$($name: CacheKey),+> CacheKey for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn write_key(&self, bytes: &mut Vec<u8>) {
+                    let ($($name,)+) = self;
+                    $($name.write_key(bytes);)+
+                }
+            }
+        )*
+    };
+}
+
+cache_key_of_tuples!((A), (A, B), (A, B, C), (A, B, C, D), (A, B, C, D, E));
+
+/// InputReport, returned by `check_inputs`, lists how the placeholders of a template differ from its declared inputs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputReport {
+    /// the placeholders used by the template, which are not declared as inputs
+    pub extra: Vec<String>,
+    /// the declared inputs, which are not used by the template
+    pub unused: Vec<String>,
+    /// the declared inputs, which are not persisted or are blank
+    pub unfilled: Vec<String>,
+}
+
+impl InputReport {
+    /// returns true, if the template uses exactly the declared inputs and all of them are filled
+    pub fn is_ok(&self) -> bool {
+        self.extra.is_empty() && self.unused.is_empty() && self.unfilled.is_empty()
+    }
+}
+
+/// ConflictPolicy, decides what happens to the tiles with clashing names while merging the tiles into a store, see `merge_store`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// keep the existing tile
+    Skip,
+    /// replace the existing tile, unless it is frozen, in which case nothing is merged and the clashing frozen names are returned
+    Overwrite,
+    /// merge nothing and return the clashing names
+    Error,
+}
+
+/// TileVisitor, receives the tiles reached by `walk_dependencies`, every method has a default, so a visitor implements only what it needs
+///
+/// every tile is reached once, the parents are the names of the tiles leading to it, starting with the walked tile if it is named
+pub trait TileVisitor {
+    /// called when the walk reaches a persisted inner tile, returning false skips the inner tiles of this tile
+    fn enter(&mut self, _name: &str, _tile: &RTile, _parents: &[String]) -> bool {
+        true
+    }
+
+    /// called once the inner tiles of the tile are walked, so the tiles leave in the order they can be expanded
+    fn leave(&mut self, _name: &str, _tile: &RTile) {}
+
+    /// called when the walk reaches a tile that is not persisted, including the tiles bound to the placeholders
+    fn missing(&mut self, _name: &str, _parents: &[String]) {}
+
+    /// called when an inner tile refers back to one of its parents
+    fn recursion(&mut self, _name: &str, _parents: &[String]) {}
+}
+
+///
+/// StoreEvent, a change of a store recorded in its event log, see `TileStore::record_events` and `store_events`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreEvent {
+    /// the kind of the change
+    pub kind: StoreEventKind,
+    /// the name of the tile, None for `Clear`
+    pub name: Option<String>,
+    /// the time of the change
+    pub timestamp: SystemTime,
+    /// the hash of the raw lines of the tile before the change, None if it wasn't persisted
+    pub old_hash: Option<u64>,
+    /// the hash of the raw lines of the tile after the change, None if it was removed
+    pub new_hash: Option<u64>,
+    /// the source location which made the change, e.g. the call of tp! or of `TileStore::set`
+    pub location: &'static Location<'static>,
+}
+
+/// StoreEventKind, the kind of a `StoreEvent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreEventKind {
+    /// a tile was persisted, replacing the previous one if any
+    Set,
+    /// a tile was removed
+    Remove,
+    /// all the tiles were removed
+    Clear,
+    /// a tile was renamed, recorded under its old name and followed by the `Set` of the tile under its new name, see
+    /// `TileStore::rename_placeholder`
+    Rename,
+}
+
+// the line with the tile `old` renamed to `new` in its placeholders, i.e. the names of the tiles (also versioned, e.g. `@{old@^2}`,
+// or of the mustache placeholders) and the operands of the expressions, None if the line doesn't refer to the tile
+fn rename_in_line(ln: &str, old: &str, new: &str) -> Option<String> {
+    let mut renames = vec![];
+    let mut cursor = 0;
+    while let Some((_, Some((end, text)))) = next_placeholder_span(&ln[cursor..]) {
+        // the text is a part of the line
+        let text_start = text.as_ptr() as usize - ln.as_ptr() as usize;
+        cursor += end;
+        let Ok(placeholder) = Placeholder::try_parse(text) else {
+            continue;
+        };
+        let Some(name_start) = text.find(&placeholder.name) else {
+            continue;
+        };
+        let name_start = text_start + name_start;
+        let name_end = name_start + placeholder.name.len();
+        let renamed = match placeholder.kind {
+            PlaceholderKind::Tile | PlaceholderKind::Definition => {
+                let name = split_requirement(&placeholder.name)
+                    .map(|(name, _)| name)
+                    .or_else(|| split_version(&placeholder.name).map(|(name, _)| name))
+                    .unwrap_or(&placeholder.name);
+                if name == old {
+                    Some(format!("{}{}", new, &placeholder.name[old.len()..]))
+                } else {
+                    // e.g. the selector of `@{@{old}}`
+                    rename_in_line(&placeholder.name, old, new)
+                }
+            }
+            PlaceholderKind::Expression(_) => expr::rename_operand(&placeholder.name, old, new),
+            _ => None,
+        };
+        if let Some(renamed) = renamed {
+            renames.push((name_start..name_end, renamed));
+        }
+    }
+    if renames.is_empty() {
+        return None;
+    }
+    let mut res = ln.to_string();
+    for (range, renamed) in renames.into_iter().rev() {
+        res.replace_range(range, &renamed);
+    }
+    Some(res)
+}
+
+// the hash of the raw lines of the tile, as recorded in the event log
+fn raw_hash(tile: &RTile) -> u64 {
+    content_hash(FNV_OFFSET_BASIS, tile.lns.join("\n").as_bytes())
+}
+
+/// CycleError, returned by `topo_order` for a tile that refers back to itself through its inner tiles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+    /// the names of the tiles of the cycle, starting and ending with the same tile
+    pub cycle: Vec<String>,
+}
+
+impl Display for CycleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "detected a recursion: {}", self.cycle.join(" -> "))
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+///
+/// RTileError, returned by `RTile::try_expand` and `TileStore::try_render` instead of the panic (or the print) of the render
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RTileError {
+    /// an inner tile is not persisted
+    MissingTile {
+        /// the name of the missing tile
+        name: String,
+        /// the names of the inner tiles leading to it, outermost first
+        parents: Vec<String>,
+    },
+    /// an inner tile refers back to itself
+    Recursion(CycleError),
+    /// no version of a tile persisted by tpv! is compatible with the requirement of a placeholder, e.g. `@{api@^2}`
+    IncompatibleVersion {
+        /// the name of the tile
+        name: String,
+        /// the version requirement
+        requirement: String,
+    },
+    /// a parameterized tile, i.e. declaring its parameters with `@{params ...}`, is called with an unknown argument or
+    /// without one of its parameters
+    InvalidArguments {
+        /// the name of the tile
+        name: String,
+        /// the description of the error
+        message: String,
+    },
+    /// a tile is renamed onto the name of another persisted tile, see `TileStore::rename_placeholder`
+    TileExists {
+        /// the name of the persisted tile
+        name: String,
+    },
+    /// a placeholder is not closed, i.e. `@{` without its `}`
+    UnfinishedPlaceholder {
+        /// the line of the placeholder
+        line: String,
+    },
+    /// a placeholder is not valid, e.g. an invalid `@{}` expression
+    InvalidPlaceholder {
+        /// the text between the braces
+        placeholder: String,
+        /// the description of the problem
+        message: String,
+    },
+}
+
+impl Display for RTileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            RTileError::MissingTile { name, parents } if parents.is_empty() => {
+                write!(f, "{} tile is not found", name)
+            }
+            RTileError::MissingTile { name, parents } => {
+                write!(f, "{} tile is not found, in {}", name, parents.join(" -> "))
+            }
+            RTileError::Recursion(error) => write!(f, "{}", error),
+            RTileError::InvalidArguments { name, message } => {
+                write!(f, "invalid arguments of the tile {}: {}", name, message)
+            }
+            RTileError::IncompatibleVersion { name, requirement } => write!(
+                f,
+                "no version of the tile {} is compatible with {}",
+                name, requirement
+            ),
+            RTileError::TileExists { name } => write!(f, "the tile {} already exists", name),
+            RTileError::UnfinishedPlaceholder { line } => {
+                write!(f, "unfinished @{{}} expression in `{}`", line)
+            }
+            RTileError::InvalidPlaceholder { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for RTileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RTileError::Recursion(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+// the error of a version requirement, e.g. `api@^2`, without any compatible version
+fn incompatible_version(tile_name: &str) -> RTileError {
+    let (name, requirement) = tile_name.rsplit_once('@').unwrap_or((tile_name, ""));
+    RTileError::IncompatibleVersion {
+        name: name.to_string(),
+        requirement: requirement.to_string(),
+    }
+}
+
+// the first unfinished or invalid placeholder of the lines, or the first call of a parameterized tile with invalid arguments
+fn check_placeholders(raw_tiles: &RawTiles, lns: &[String]) -> std::result::Result<(), RTileError> {
+    for ln in lns.iter().flat_map(|ln| ln.split('\n')) {
+        let mut rest = ln;
+        while let Some((_, span)) = next_placeholder_span(rest) {
+            let Some((end, text)) = span else {
+                return Err(RTileError::UnfinishedPlaceholder {
+                    line: ln.to_string(),
+                });
+            };
+            let placeholder = match Placeholder::try_parse(text) {
+                Ok(placeholder) => placeholder,
+                Err(message) => {
+                    return Err(RTileError::InvalidPlaceholder {
+                        placeholder: text.to_string(),
+                        message,
+                    })
+                }
+            };
+            if placeholder.kind == PlaceholderKind::Tile {
+                let tile_name = resolve_tile_name(raw_tiles, placeholder.name.clone());
+                if let Some(tile) = raw_tiles.get(&tile_name) {
+                    check_arguments(tile, &placeholder)?;
+                }
+            }
+            rest = &rest[end..];
+        }
+    }
+    Ok(())
+}
+
+// the parameters declared by the `@{params ...}` marker of the tile, i.e. of its first line other than the `@#` comments
+fn declared_parameters(tile: &RTile) -> Option<Vec<String>> {
+    let ln = tile
+        .lns
+        .iter()
+        .map(|ln| ln.trim())
+        .find(|ln| !ln.starts_with("@#"))?;
+    if !ln.starts_with("@{params ") {
+        return None;
+    }
+    let (_, Some((_, text))) = next_placeholder_span(ln)? else {
+        return None;
+    };
+    match Placeholder::try_parse(text).ok()?.kind {
+        PlaceholderKind::Parameters(parameters) => Some(parameters),
+        _ => None,
+    }
+}
+
+// the arguments (i.e. the bindings) of the placeholder against the parameters of the tile it refers to, every parameter is
+// to be given, and nothing else
+fn check_arguments(tile: &RTile, placeholder: &Placeholder) -> std::result::Result<(), RTileError> {
+    let Some(parameters) = declared_parameters(tile) else {
+        return Ok(());
+    };
+    let invalid = |message: String| RTileError::InvalidArguments {
+        name: placeholder.name.clone(),
+        message,
+    };
+    if let Some((argument, _)) = placeholder
+        .bindings
+        .iter()
+        .find(|(argument, _)| !parameters.contains(argument))
+    {
+        return Err(invalid(format!("`{}` is not a parameter", argument)));
+    }
+    match parameters.iter().find(|parameter| {
+        !placeholder
+            .bindings
+            .iter()
+            .any(|(argument, _)| argument == *parameter)
+    }) {
+        Some(parameter) => Err(invalid(format!(
+            "the parameter `{}` is not given",
+            parameter
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn resolve_tile_name(raw_tiles: &RawTiles, tile_name: String) -> String {
+    if let Some(selector) = indirect_selector(&tile_name) {
+        let selector = resolve_tile_name(raw_tiles, selector.to_string());
+        // the content of the selector is the name of the tile, a blank selector resolves to itself and expands to nothing
+        return match raw_tiles.get(&selector).map(|tile| tile.lns.join("\n")) {
+            Some(target) if !target.trim().is_empty() && !target.contains("@{") => {
+                resolve_tile_name(raw_tiles, target.trim().to_string())
+            }
+            _ => selector,
+        };
+    }
+    let tile_name = bound_tile_name(tile_name);
+    for prefix in enclosing_name_prefixes() {
+        let prefixed_tile_name = resolve_version(raw_tiles, format!("{}{}", prefix, tile_name));
+        if raw_tiles.contains_key(&prefixed_tile_name) {
+            return prefixed_tile_name;
+        }
+    }
+    resolve_version(raw_tiles, tile_name)
+}
+
+// the highest version persisted by tpv! which is compatible with the requirement of the name, e.g. `api@2.3.0` for
+// `api@^2`, or the name as it is
+fn resolve_version(raw_tiles: &RawTiles, tile_name: String) -> String {
+    let Some((name, requirement)) = split_requirement(&tile_name) else {
+        return tile_name;
+    };
+    raw_tiles
+        .iter()
+        .filter_map(|(key, _)| match split_version(key) {
+            Some((key_name, version)) if key_name == name && requirement.matches(version) => {
+                Some((version, key))
+            }
+            _ => None,
+        })
+        .max()
+        .map(|(_, key)| key.clone())
+        .unwrap_or(tile_name)
+}
+
+fn reevaluate(
+    raw_tiles: &RawTiles,
+    processed_tiles: &ProcessedTiles,
+    expansions: &RefCell<ExpansionCache>,
+    tile: &RTile,
+) -> String {
+    // calling r_format_using_processed_tiles_data, as all the inner tiles are supposed to be reevaluated / processed by now
+    let template_lns = with_chunk_hints(&tile.lns, None);
+    let lns = testing::with_template(tile.name.as_deref(), || {
+        r_format_using_processed_tiles_data(raw_tiles, processed_tiles, expansions, &template_lns)
+    });
+    let output = trim(lns, tile.do_trimming).join("\n");
+    match tile.name.as_deref() {
+        Some(name) => post_process(output, &post_processors(Some(name))),
+        None => output,
+    }
+}
+
+fn post_process(output: String, post_processors: &[PostProcessor]) -> String {
+    post_processors.iter().fold(output, |output, f| f(&output))
+}
+
+// the lines of the template with a separator comment before every section, i.e. a line with nothing other than a tile
+// placeholder, in the comment style of the `@# chunks: LANGUAGE` metadata of the template, or else of the given default
+fn with_chunk_hints(lns: &[String], default: Option<CommentStyle>) -> Cow<'_, [String]> {
+    let mut style = default;
+    for comment in lns
+        .iter()
+        .map_while(|ln| ln.trim_start().strip_prefix("@#"))
+    {
+        if let Some(language) = comment.trim().strip_prefix("chunks:") {
+            style = match language.trim() {
+                "none" => None,
+                language => Some(CommentStyle::for_language(language).unwrap_or_else(|| {
+                    panic!("unknown language `{}` of the chunks metadata", language)
+                })),
+            };
+        }
+    }
+    let Some(style) = style else {
+        return Cow::Borrowed(lns);
+    };
+    let mut hinted = Vec::with_capacity(lns.len());
+    for ln in lns {
+        let section = ln.trim();
+        let name = match next_placeholder_span(section) {
+            Some((0, Some((end, text)))) if end == section.len() => Placeholder::try_parse(text)
+                .ok()
+                .filter(|placeholder| placeholder.kind == PlaceholderKind::Tile)
+                .map(|placeholder| placeholder.name),
+            _ => None,
+        };
+        if let Some(name) = name {
+            let indent = &ln[..ln.len() - ln.trim_start().len()];
+            hinted.push(format!(
+                "{}{}section: {}{}",
+                indent, style.prefix, name, style.suffix
+            ));
+        }
+        hinted.push(ln.clone());
+    }
+    Cow::Owned(hinted)
+}
+
+// reevaluate, memoized by the fingerprint of the tile and of the values of its inner tiles
+fn reevaluate_memoized(
+    raw_tiles: &RawTiles,
+    processed_tiles: &ProcessedTiles,
+    expansions: &RefCell<ExpansionCache>,
+    tile: &RTile,
+) -> Rc<str> {
+    let Some(key) = expansion_key(raw_tiles, processed_tiles, tile) else {
+        return reevaluate(raw_tiles, processed_tiles, expansions, tile).into();
+    };
+    if let Some(value) = expansions.borrow_mut().lookup(key) {
+        return value;
+    }
+    let value: Rc<str> = reevaluate(raw_tiles, processed_tiles, expansions, tile).into();
+    expansions.borrow_mut().insert(key, value.clone());
+    value
+}
+
+// the fingerprint of the expansion of the tile, None if the expansion depends on more than the values of its inner tiles,
+// i.e. a placeholder with bindings, or if the slots are being recorded
+fn expansion_key(
+    raw_tiles: &RawTiles,
+    processed_tiles: &ProcessedTiles,
+    tile: &RTile,
+) -> Option<u64> {
+    if testing::is_recording_slots() {
+        return None;
+    }
+    let mut hasher = DefaultHasher::new();
+    tile.lns.hash(&mut hasher);
+    tile.do_trimming.hash(&mut hasher);
+    let options = get_render_options();
+    options.drop_blank_placeholder_lines.hash(&mut hasher);
+    options.content_stamp.hash(&mut hasher);
+    if let Some(name) = tile.name.as_deref() {
+        post_processors(Some(name)).hash(&mut hasher);
+    }
+    for ln in &tile.lns {
+        for placeholder in placeholders(ln) {
+            if !placeholder.bindings.is_empty() {
+                return None;
+            }
+            let names = match &placeholder.kind {
+                PlaceholderKind::Expression(expression) => expression.operands(),
+                _ => vec![placeholder.name],
+            };
+            for tile_name in names {
+                let tile_name = resolve_tile_name(raw_tiles, tile_name);
+                processed_tiles.get(&tile_name).hash(&mut hasher);
+                tile_name.hash(&mut hasher);
+            }
+        }
+    }
+    Some(hasher.finish())
+}
+
+// the number of memoized expansions, beyond which the memo starts over
+const EXPANSION_CACHE_CAPACITY: usize = 4096;
+
+/// the memoized expansions of the inner tiles, see `TileStore::cache_stats`
+#[derive(Debug, Default)]
+struct ExpansionCache {
+    entries: HashMap<u64, Rc<str>>,
+    stats: CacheStats,
+}
+
+impl ExpansionCache {
+    fn lookup(&mut self, key: u64) -> Option<Rc<str>> {
+        let value = self.entries.get(&key).cloned();
+        if value.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        value
+    }
+
+    fn insert(&mut self, key: u64, value: Rc<str>) {
+        if self.entries.len() >= EXPANSION_CACHE_CAPACITY {
+            self.entries.clear();
+        }
+        self.entries.insert(key, value);
+    }
+}
+
+/// CacheStats, the statistics of the memoized expansions of the inner tiles, returned by `get_cache_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// the number of expansions reused from the memo
+    pub hits: u64,
+    /// the number of expansions computed and memoized
+    pub misses: u64,
+    /// the number of memoized expansions
+    pub entries: usize,
+}
+
+impl CacheStats {
+    /// returns the number of lookups, i.e. the hits and the misses
+    pub fn lookups(&self) -> u64 {
+        self.hits + self.misses
+    }
+
+    /// returns the ratio of the hits to the lookups, 0 if there were no lookups
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups() == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.lookups() as f64
+        }
+    }
+}
+
+fn r_format_using_processed_tiles_data(
+    raw_tiles: &RawTiles,
+    processed_tiles: &ProcessedTiles,
+    expansions: &RefCell<ExpansionCache>,
+    lns: &[String],
+) -> Vec<String> {
+    r_format(raw_tiles, lns, |tile_name, placeholder| {
+        if placeholder.bindings.is_empty() {
+            processed_tiles.get(tile_name).cloned()
+        } else {
+            // the bound tile is expanded in a scratch copy, to keep the processed data of its inner tiles intact
+            let tile_value = raw_tiles.get(tile_name)?;
+            let mut scratch_processed_tiles = processed_tiles.clone();
+            check_for_recursion_of_tiles(raw_tiles, tile_name, tile_value);
+            process_all_required_tiles_data(
+                raw_tiles,
+                &mut scratch_processed_tiles,
+                expansions,
+                tile_name,
+                tile_value,
+            );
+            scratch_processed_tiles.get(tile_name).cloned()
+        }
+    })
+}
+
+fn r_format_using_raw_tiles_data(
+    raw_tiles: &RawTiles,
+    processed_tiles: &mut ProcessedTiles,
+    expansions: &RefCell<ExpansionCache>,
+    lns: &[String],
+) -> Vec<String> {
+    r_format(raw_tiles, lns, |tile_name, placeholder| {
+        if raw_tiles.contains_key(tile_name) {
+            let tile_value = raw_tiles.get(tile_name).unwrap();
+            // the bound tile is expanded in a scratch copy, to keep the processed data of its inner tiles intact
+            let mut scratch_processed_tiles = if placeholder.bindings.is_empty() {
+                None
+            } else {
+                Some(processed_tiles.clone())
+            };
+            let processed_tiles = scratch_processed_tiles.as_mut().unwrap_or(processed_tiles);
+            check_for_recursion_of_tiles(raw_tiles, tile_name, tile_value);
+            process_all_required_tiles_data(
+                raw_tiles,
+                processed_tiles,
+                expansions,
+                tile_name,
+                tile_value,
+            );
+            processed_tiles.get(tile_name).cloned()
+        } else {
+            None
+        }
+    })
+}
+
+fn r_format<F>(raw_tiles: &RawTiles, lns: &[String], mut tile_value: F) -> Vec<String>
+where
+    F: FnMut(&String, &Placeholder) -> Option<Rc<str>>,
+{
+    if !lns.iter().any(|ln| ln.contains("@{def ")) {
+        // without any inline definitions, the lines are expanded as they are, instead of joining and splitting them again
+        let lns: Vec<&str> = if lns.is_empty() {
+            vec![""]
+        } else {
+            lns.iter().flat_map(|ln| ln.split('\n')).collect()
+        };
+        return r_format_lines(raw_tiles, &lns, &mut tile_value, &HashMap::new());
+    }
+    let (s, definitions) = expand_definitions(&lns.join("\n"));
+    let mut locals: HashMap<String, Rc<str>> = HashMap::new();
+    for (name, body) in definitions {
+        // a definition may use the definitions before it
+        let body: Vec<&str> = body.split('\n').collect();
+        let value = r_format_lines(raw_tiles, &body, &mut tile_value, &locals).join("\n");
+        locals.insert(name, value.into());
+    }
+    let lns: Vec<&str> = s.split('\n').collect();
+    r_format_lines(raw_tiles, &lns, &mut tile_value, &locals)
+}
+
+fn r_format_lines<F>(
+    raw_tiles: &RawTiles,
+    lns: &[&str],
+    tile_value: &mut F,
+    locals: &HashMap<String, Rc<str>>,
+) -> Vec<String>
+where
+    F: FnMut(&String, &Placeholder) -> Option<Rc<str>>,
+{
+    let drop_blank_placeholder_lines = get_render_options().drop_blank_placeholder_lines;
+    let content_stamp = get_render_options().content_stamp;
+    let mut res = Vec::with_capacity(lns.len());
+    for &ln in lns {
+        if next_placeholder_span(ln).is_none() {
+            // the text lines are taken as they are
+            res.push(ln.to_string());
+            continue;
+        }
+        let mut curr = vec![];
+        let mut end = 0;
+        let mut has_content = false;
+        let mut has_collapsed_placeholders = false;
+
+        for (start, placeholder_end, text) in placeholder_spans(ln) {
+            append(&mut curr, [&ln[end..start]]);
+            end = placeholder_end;
+            let placeholder = Placeholder::parse(text);
+            match placeholder.kind {
+                PlaceholderKind::Tile | PlaceholderKind::Expression(_) => {}
+                // the declaration of the parameters expands to nothing, and its line is skipped when it has nothing else
+                PlaceholderKind::Parameters(_) => {
+                    has_collapsed_placeholders = true;
+                    continue;
+                }
+                #[cfg(feature = "mustache")]
+                PlaceholderKind::Comment => continue,
+                _ => panic!("unexpected @{{end}} expression"),
+            }
+            let tile_name = resolve_tile_name(raw_tiles, placeholder.name.clone());
+            if placeholder.kind == PlaceholderKind::Tile && !locals.contains_key(&placeholder.name)
+            {
+                if let Some(Err(error)) = raw_tiles
+                    .get(&tile_name)
+                    .map(|tile| check_arguments(tile, &placeholder))
+                {
+                    panic!("{}", error);
+                }
+            }
+            let value = match (&placeholder.kind, locals.get(&placeholder.name)) {
+                // kept for the stamp, which is substituted once the whole output is rendered
+                (PlaceholderKind::Tile, None)
+                    if content_stamp && ln[start..end] == *CONTENT_SHA256_PLACEHOLDER =>
+                {
+                    Some(CONTENT_SHA256_PLACEHOLDER.into())
+                }
+                (PlaceholderKind::Expression(expression), _) => {
+                    let value = with_placeholder_bindings(&placeholder.bindings, || {
+                        expression.evaluate(&mut |operand| match locals.get(operand) {
+                            Some(value) => value.clone(),
+                            None => {
+                                let tile_name = resolve_tile_name(raw_tiles, operand.to_string());
+                                tile_value(&tile_name, &placeholder).unwrap_or_default()
+                            }
+                        })
+                    });
+                    Some(value.into())
+                }
+                (_, Some(value)) => Some(value.clone()),
+                (_, None) => with_placeholder_bindings(&placeholder.bindings, || {
+                    tile_value(&tile_name, &placeholder)
+                }),
+            };
+            if !locals.contains_key(&placeholder.name) {
+                testing::record_slot(
+                    &placeholder.name,
+                    value.as_ref().is_some_and(|value| !value.trim().is_empty()),
+                );
+            }
+            match value {
+                Some(value) => {
+                    if value.trim().is_empty()
+                        && (drop_blank_placeholder_lines
+                            || placeholder.collapse == CollapsePolicy::SkipIfBlank)
+                    {
+                        has_collapsed_placeholders = true;
+                    } else {
+                        has_content = true;
+                        append_placeholder_value(&mut curr, &placeholder, &value);
+                    }
+                }
+                None => match split_requirement(&tile_name) {
+                    Some(_) => panic!("{}", incompatible_version(&tile_name)),
+                    None => testing::warn(format!("{} tile is not found", tile_name)),
+                },
+            }
+        }
+        if end < ln.len() {
+            append(&mut curr, [&ln[end..]]);
+        }
+        if has_collapsed_placeholders && !has_content && is_blank_without_placeholders(ln) {
+            // the line has nothing other than white spaces and blank placeholders, so skip it
+            continue;
+        }
+        res.append(&mut curr);
+    }
+    res
+}
+
+fn check_for_recursion_of_tiles(raw_tiles: &RawTiles, tile_name: &String, tile_value: &RTile) {
+    walk(
+        raw_tiles,
+        Some(tile_name),
+        &tile_value.lns,
+        &mut RecursionCheck,
+    );
+}
+
+fn process_all_required_tiles_data(
+    raw_tiles: &RawTiles,
+    processed_tiles: &mut ProcessedTiles,
+    expansions: &RefCell<ExpansionCache>,
+    tile_name: &String,
+    tile_value: &RTile,
+) {
+    let mut inner_tiles = InnerTiles(vec![tile_name.clone()]);
+    walk(
+        raw_tiles,
+        Some(tile_name),
+        &tile_value.lns,
+        &mut inner_tiles,
+    );
+    let inner_tiles = inner_tiles.0;
+
+    if !inner_tiles.is_empty() {
+        for inner_tile_index in (0..inner_tiles.len()).rev() {
+            let inner_tile_name = inner_tiles.get(inner_tile_index).unwrap();
+
+            let result = if raw_tiles.contains_key(inner_tile_name) {
+                let inner_tile_value = raw_tiles.get(inner_tile_name).unwrap();
+                reevaluate_memoized(raw_tiles, processed_tiles, expansions, inner_tile_value)
+            } else {
+                //tile not found, so return emtpy string
+                Rc::from("")
+            };
+
+            processed_tiles.insert(inner_tile_name.clone(), result);
+        }
+    }
+}
+
+// walks the inner tiles of the given lines depth first, every tile is reached once, see `TileVisitor`
+fn walk(
+    raw_tiles: &RawTiles,
+    name: Option<&String>,
+    lns: &[String],
+    visitor: &mut impl TileVisitor,
+) {
+    let mut visited: HashSet<String> = name.into_iter().cloned().collect();
+    let mut parents: Vec<String> = name.into_iter().cloned().collect();
+    walk_lines(raw_tiles, lns, &mut visited, &mut parents, visitor);
+}
+
+fn walk_lines(
+    raw_tiles: &RawTiles,
+    lns: &[String],
+    visited: &mut HashSet<String>,
+    parents: &mut Vec<String>,
+    visitor: &mut impl TileVisitor,
+) {
+    let definitions = local_definitions(lns);
+    for ln in lns {
+        for placeholder in placeholders(ln) {
+            let inner_tile_names = placeholder.inner_tile_names(&definitions);
+            if inner_tile_names.is_empty() {
+                continue;
+            }
+            // the bound tiles are expanded on their own, so only their presence is checked
+            for (_, bound_tile_name) in &placeholder.bindings {
+                let bound_tile_name = resolve_tile_name(raw_tiles, bound_tile_name.clone());
+                if !raw_tiles.contains_key(&bound_tile_name)
+                    && visited.insert(bound_tile_name.clone())
+                {
+                    visitor.missing(&bound_tile_name, parents);
+                }
+            }
+            for inner_tile_name in inner_tile_names {
+                let inner_tile_name = resolve_tile_name(raw_tiles, inner_tile_name);
+                if parents.contains(&inner_tile_name) {
+                    visitor.recursion(&inner_tile_name, parents);
+                    continue;
+                }
+                if !visited.insert(inner_tile_name.clone()) {
+                    continue;
+                }
+                let Some(inner_tile_value) = raw_tiles.get(&inner_tile_name) else {
+                    visitor.missing(&inner_tile_name, parents);
+                    continue;
+                };
+                if visitor.enter(&inner_tile_name, inner_tile_value, parents) {
+                    parents.push(inner_tile_name.clone());
+                    walk_lines(raw_tiles, &inner_tile_value.lns, visited, parents, visitor);
+                    parents.pop();
+                }
+                visitor.leave(&inner_tile_name, inner_tile_value);
+            }
+        }
+    }
+}
+
+// panics on a recursion of tiles
+struct RecursionCheck;
+
+impl TileVisitor for RecursionCheck {
+    fn missing(&mut self, name: &str, _parents: &[String]) {
+        testing::warn(format!("{} tile is not found", name));
+    }
+
+    fn recursion(&mut self, _name: &str, _parents: &[String]) {
+        panic!("detected a recursion");
+    }
+}
+
+// the inner tiles in the order they are reached, a tile comes before its inner tiles
+struct InnerTiles(Vec<String>);
+
+impl TileVisitor for InnerTiles {
+    fn enter(&mut self, name: &str, _tile: &RTile, _parents: &[String]) -> bool {
+        self.0.push(name.to_string());
+        true
+    }
+
+    fn missing(&mut self, name: &str, _parents: &[String]) {
+        testing::warn(format!("{} tile is not found", name));
+    }
+}
+
+struct BlankInnerTiles(Vec<String>);
+
+impl TileVisitor for BlankInnerTiles {
+    fn enter(&mut self, name: &str, tile: &RTile, _parents: &[String]) -> bool {
+        if tile.lns.is_empty() {
+            self.0.push(name.to_string());
+        }
+        true
+    }
+
+    fn missing(&mut self, name: &str, _parents: &[String]) {
+        testing::warn(format!("{} tile is not found", name));
+    }
+}
+
+struct MissingInnerTiles(HashSet<String>);
+
+impl TileVisitor for MissingInnerTiles {
+    fn missing(&mut self, name: &str, _parents: &[String]) {
+        // a version requirement without any compatible version is reported by the render
+        if split_requirement(name).is_none() {
+            self.0.insert(name.to_string());
+        }
+    }
+}
+
+// the first problem of the inner tiles, the lines of a tile are checked before the walk parses them
+// the blank tiles created for the missing inner tiles are missing as well
+struct ExpansionCheck<'a> {
+    raw_tiles: &'a RawTiles,
+    implicit_tiles: &'a TileMap<()>,
+    error: Option<RTileError>,
+}
+
+impl TileVisitor for ExpansionCheck<'_> {
+    fn enter(&mut self, name: &str, tile: &RTile, parents: &[String]) -> bool {
+        if tile.lns.is_empty() && self.implicit_tiles.contains_key(name) {
+            self.missing(name, parents);
+        }
+        if self.error.is_none() {
+            self.error = check_placeholders(self.raw_tiles, &tile.lns).err();
+        }
+        self.error.is_none()
+    }
+
+    fn missing(&mut self, name: &str, parents: &[String]) {
+        self.error
+            .get_or_insert_with(|| match split_requirement(name) {
+                Some(_) => incompatible_version(name),
+                None => RTileError::MissingTile {
+                    name: name.to_string(),
+                    parents: parents.to_vec(),
+                },
+            });
+    }
+
+    fn recursion(&mut self, name: &str, parents: &[String]) {
+        self.error.get_or_insert_with(|| {
+            let start = parents
+                .iter()
+                .position(|parent| parent == name)
+                .unwrap_or(0);
+            let mut cycle = parents[start..].to_vec();
+            cycle.push(name.to_string());
+            RTileError::Recursion(CycleError { cycle })
+        });
+    }
+}
+
+// the inner tiles in the order they leave the walk, together with the first cycle found
+#[derive(Default)]
+struct TopoOrder {
+    names: Vec<String>,
+    cycle: Option<Vec<String>>,
+}
+
+impl TileVisitor for TopoOrder {
+    fn leave(&mut self, name: &str, _tile: &RTile) {
+        self.names.push(name.to_string());
+    }
+
+    fn recursion(&mut self, name: &str, parents: &[String]) {
+        if self.cycle.is_none() {
+            let start = parents
+                .iter()
+                .position(|parent| parent == name)
+                .unwrap_or(0);
+            let mut cycle = parents[start..].to_vec();
+            cycle.push(name.to_string());
+            self.cycle = Some(cycle);
+        }
+    }
+}
@@ -0,0 +1,589 @@
+//!
+//! Test harness for the templates, so the template packs can carry their own regression tests runnable via `cargo test`,
+//! and the coverage of the placeholders (slots) of the templates across a test run.
+//!
+//! The tests of the downstream crates are isolated from the tiles persisted by the other tests with `fresh_store` and
+//! `render_isolated`, and check the warnings printed by the renders with `capture_warnings`.
+//!
+
+use crate::slash_path;
+use crate::RTile;
+use crate::TileStore;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+///
+/// TemplateCase, a template along with a fixture of input tiles and the expected output
+///
+/// The template is rendered against a fresh store holding only the input tiles, i.e. the tls (thread local storage) is not used.
+/// The template, the inputs and the expected output are trimmed the same way as `t!`.
+/// ```
+/// use rtile::testing::TemplateCase;
+///
+/// TemplateCase::new("greet")
+///     .template("Hello @{name}!")
+///     .input("name", "Alice")
+///     .expected("Hello Alice!")
+///     .assert();
+/// ```
+///
+/// The fixture file lists the input tiles, each starting with a `--- name` header line followed by the lines of the tile:
+/// ```text
+/// --- name
+/// Alice
+/// --- salutation
+/// Dear
+/// ```
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateCase {
+    name: String,
+    template: String,
+    inputs: Vec<(String, String)>,
+    expected: String,
+}
+
+impl TemplateCase {
+    /// creates an empty case with the given name, used in the failure report
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// loads the case from the template file, the fixture file and the expected output file, the case is named after the template file
+    pub fn from_files(
+        template: impl AsRef<Path>,
+        fixture: impl AsRef<Path>,
+        expected: impl AsRef<Path>,
+    ) -> std::io::Result<Self> {
+        let template = template.as_ref();
+        let mut case = Self::new(&template.display().to_string())
+            .template(&std::fs::read_to_string(template)?)
+            .expected(&std::fs::read_to_string(expected)?);
+        case.inputs = parse_fixture(&std::fs::read_to_string(fixture)?);
+        Ok(case)
+    }
+
+    /// sets the template to render
+    pub fn template(mut self, template: &str) -> Self {
+        self.template = template.to_string();
+        self
+    }
+
+    /// adds an input tile, replacing any earlier input with the same name
+    pub fn input(mut self, name: &str, value: &str) -> Self {
+        self.inputs.retain(|(input_name, _)| input_name != name);
+        self.inputs.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// sets the expected output
+    pub fn expected(mut self, expected: &str) -> Self {
+        self.expected = expected.to_string();
+        self
+    }
+
+    /// renders the template against the inputs and returns the output
+    pub fn render(&self) -> String {
+        let template = crate::detached(|| RTile::construct_from_str(&self.template));
+        render_isolated(&template, self.inputs.iter().cloned())
+    }
+
+    /// renders the template and compares the output with the expected output
+    pub fn run(&self) -> Result<(), CaseFailure> {
+        let actual = self.render();
+        let expected = crate::detached(|| RTile::construct_from_str(&self.expected)).raw();
+        if actual == expected {
+            Ok(())
+        } else {
+            Err(CaseFailure {
+                name: self.name.clone(),
+                expected,
+                actual,
+            })
+        }
+    }
+
+    /// runs the case and panics with the failure report, if the output differs from the expected output
+    pub fn assert(&self) {
+        if let Err(failure) = self.run() {
+            panic!("{}", failure);
+        }
+    }
+}
+
+///
+/// CaseFailure, returned by `TemplateCase::run` when the output differs from the expected output
+///
+/// Its Display lists the lines of the expected output and the output, the differing lines are marked with `-` (expected) and `+` (actual)
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseFailure {
+    /// the name of the case
+    pub name: String,
+    /// the expected output
+    pub expected: String,
+    /// the output of the template
+    pub actual: String,
+}
+
+impl CaseFailure {
+    /// returns the number (starting from 1) of the first line which differs
+    pub fn first_difference(&self) -> usize {
+        let expected: Vec<&str> = self.expected.split('\n').collect();
+        let actual: Vec<&str> = self.actual.split('\n').collect();
+        (0..expected.len().max(actual.len()))
+            .find(|&i| expected.get(i) != actual.get(i))
+            .unwrap_or(0)
+            + 1
+    }
+}
+
+impl fmt::Display for CaseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "template case `{}` failed, the first difference is at line {}",
+            self.name,
+            self.first_difference()
+        )?;
+        write_line_diff(f, &self.expected, &self.actual)
+    }
+}
+
+// lists the lines, the differing lines are marked with `-` (expected) and `+` (actual)
+fn write_line_diff(f: &mut fmt::Formatter<'_>, expected: &str, actual: &str) -> fmt::Result {
+    let expected: Vec<&str> = expected.split('\n').collect();
+    let actual: Vec<&str> = actual.split('\n').collect();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => writeln!(f, "{:>4}   {}", i + 1, e)?,
+            (e, a) => {
+                if let Some(e) = e {
+                    writeln!(f, "{:>4} - {}", i + 1, e)?;
+                }
+                if let Some(a) = a {
+                    writeln!(f, "{:>4} + {}", i + 1, a)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+///
+/// compare_rendered_tree, compares the rendered files (relative path and content) against the files of the golden directory
+///
+/// The paths are compared with `/` separators and the contents are compared ignoring a single trailing newline.
+/// See `assert_rendered_tree_eq!`, which panics with the report of the differences.
+/// ```
+/// use rtile::testing::compare_rendered_tree;
+///
+/// let golden = std::env::temp_dir().join(format!("rtile_doc_golden_{}", std::process::id()));
+/// std::fs::create_dir_all(golden.join("src")).unwrap();
+/// std::fs::write(golden.join("src/lib.rs"), "pub mod a;\n").unwrap();
+///
+/// assert!(compare_rendered_tree(&golden, [("src/lib.rs", "pub mod a;")]).is_ok());
+/// let mismatch = compare_rendered_tree(&golden, [("src/lib.rs", "pub mod b;"), ("src/b.rs", "")]).unwrap_err();
+/// assert_eq!(mismatch.not_in_golden, vec!["src/b.rs"]);
+/// assert_eq!(mismatch.different[0].name, "src/lib.rs");
+/// std::fs::remove_dir_all(&golden).unwrap();
+/// ```
+///
+pub fn compare_rendered_tree<I, K, V>(
+    root: impl AsRef<Path>,
+    rendered_files: I,
+) -> Result<(), TreeMismatch>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<Path>,
+    V: AsRef<str>,
+{
+    let root = root.as_ref();
+    let mut golden_files = vec![];
+    collect_files(root, root, &mut golden_files);
+    let mut mismatch = TreeMismatch::default();
+    let mut rendered_paths = vec![];
+    for (path, content) in rendered_files {
+        let path = slash_path(path.as_ref());
+        let actual = content.as_ref();
+        match std::fs::read_to_string(root.join(&path)) {
+            Ok(expected) => {
+                let expected = expected.strip_suffix('\n').unwrap_or(&expected);
+                let actual = actual.strip_suffix('\n').unwrap_or(actual);
+                if expected != actual {
+                    mismatch.different.push(CaseFailure {
+                        name: path.clone(),
+                        expected: expected.to_string(),
+                        actual: actual.to_string(),
+                    });
+                }
+            }
+            Err(_) => mismatch.not_in_golden.push(path.clone()),
+        }
+        rendered_paths.push(path);
+    }
+    mismatch.not_rendered = golden_files
+        .into_iter()
+        .filter(|path| !rendered_paths.contains(path))
+        .collect();
+    mismatch.not_in_golden.sort();
+    mismatch.not_rendered.sort();
+    mismatch.different.sort_by(|a, b| a.name.cmp(&b.name));
+    if mismatch.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatch)
+    }
+}
+
+///
+/// TreeMismatch, the differences between the rendered files and the golden directory, returned by `compare_rendered_tree`
+///
+/// Its Display is the report used by `assert_rendered_tree_eq!`, with a line diff of every differing file.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TreeMismatch {
+    /// the rendered files which are not in the golden directory
+    pub not_in_golden: Vec<String>,
+    /// the files of the golden directory which are not rendered
+    pub not_rendered: Vec<String>,
+    /// the files whose contents differ, the name of the failure is the path of the file
+    pub different: Vec<CaseFailure>,
+}
+
+impl TreeMismatch {
+    /// returns true, if there are no differences
+    pub fn is_empty(&self) -> bool {
+        self.not_in_golden.is_empty() && self.not_rendered.is_empty() && self.different.is_empty()
+    }
+}
+
+impl fmt::Display for TreeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "the rendered files differ from the golden directory")?;
+        for path in &self.not_in_golden {
+            writeln!(f, "not in the golden directory: {}", path)?;
+        }
+        for path in &self.not_rendered {
+            writeln!(f, "not rendered: {}", path)?;
+        }
+        for failure in &self.different {
+            writeln!(
+                f,
+                "differs: {}, the first difference is at line {}",
+                failure.name,
+                failure.first_difference()
+            )?;
+            write_line_diff(f, &failure.expected, &failure.actual)?;
+        }
+        Ok(())
+    }
+}
+
+// the paths (relative to the root) of all the files under the directory
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, files);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(slash_path(relative));
+        }
+    }
+}
+
+// the input tiles of the fixture, each starts with a `--- name` header line
+fn parse_fixture(fixture: &str) -> Vec<(String, String)> {
+    let mut inputs: Vec<(String, Vec<&str>)> = vec![];
+    for ln in fixture.lines() {
+        match ln.strip_prefix("--- ") {
+            Some(name) => inputs.push((name.trim().to_string(), vec![])),
+            None => {
+                if let Some((_, lns)) = inputs.last_mut() {
+                    lns.push(ln);
+                }
+            }
+        }
+    }
+    inputs
+        .into_iter()
+        .map(|(name, lns)| (name, lns.join("\n")))
+        .collect()
+}
+
+///
+/// render_isolated, renders the tile against a fresh store holding only the given tiles, i.e. the tiles persisted in the tls
+/// (thread local storage) are not used, and the tls is not changed
+///
+/// The values of the tiles are trimmed the same way as `t!`.
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::testing::render_isolated;
+///
+/// tp!(doc_isolated_name, "Bob");
+/// let greet = t!("Hello @{doc_isolated_name}!");
+/// assert_eq!(render_isolated(&greet, [("doc_isolated_name", "Alice")]), "Hello Alice!");
+/// assert_eq!(render_isolated(&greet, [("doc_isolated_other", "Alice")]), "Hello !");
+/// assert_eq!(greet.to_string(), "Hello Bob!");
+/// ```
+///
+pub fn render_isolated<I, K, V>(tile: &RTile, bindings: I) -> String
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut store = TileStore::new();
+    for (name, value) in bindings {
+        let value = crate::detached(|| RTile::construct_from_str(value.as_ref()));
+        store.set(name.as_ref(), value);
+    }
+    store.render(tile)
+}
+
+///
+/// fresh_store, replaces the tls (thread local storage) with an empty store until the returned guard is dropped, when the
+/// previous store is restored, so a test sees only the tiles it persists, whichever tests ran before on the same thread
+///
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::testing::fresh_store;
+///
+/// tp!(doc_fresh_name, "Bob");
+/// {
+///     let _store = fresh_store();
+///     assert!(gtp!(doc_fresh_name).is_none());
+///     tp!(doc_fresh_name, "Alice");
+///     assert_eq!(ts!("@{doc_fresh_name}"), "Alice");
+/// }
+/// assert_eq!(ts!("@{doc_fresh_name}"), "Bob");
+/// ```
+///
+pub fn fresh_store() -> FreshStore {
+    let previous = crate::with_default_store(std::mem::take);
+    FreshStore {
+        previous: Some(previous),
+    }
+}
+
+/// FreshStore, the guard returned by `fresh_store`, which restores the previous store of the tls (thread local storage) when dropped
+#[derive(Debug)]
+#[must_use = "the previous store is restored as soon as the guard is dropped"]
+pub struct FreshStore {
+    previous: Option<TileStore>,
+}
+
+impl Drop for FreshStore {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            crate::with_default_store(|store| *store = previous);
+        }
+    }
+}
+
+thread_local! {
+    static TL_WARNINGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+///
+/// capture_warnings, collects the warnings of the current thread instead of printing them, e.g. `name tile is not found`,
+/// until the returned guard is dropped
+///
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::testing::capture_warnings;
+///
+/// let store = TileStore::new();
+/// let tile = detached(|| t!("a @{doc_warned_tile} b"));
+/// let warnings = capture_warnings();
+/// assert_eq!(store.render_cached(&tile), "a  b");
+/// assert_eq!(warnings.warnings(), vec!["doc_warned_tile tile is not found"]);
+/// ```
+///
+pub fn capture_warnings() -> CapturedWarnings {
+    let previous = TL_WARNINGS.replace(Some(vec![]));
+    CapturedWarnings { previous }
+}
+
+/// CapturedWarnings, the guard returned by `capture_warnings`, which stops capturing the warnings when dropped
+#[derive(Debug)]
+#[must_use = "the warnings are captured only until the guard is dropped"]
+pub struct CapturedWarnings {
+    previous: Option<Vec<String>>,
+}
+
+impl CapturedWarnings {
+    /// returns the warnings captured so far, in the order they were issued
+    pub fn warnings(&self) -> Vec<String> {
+        TL_WARNINGS.with_borrow(|warnings| warnings.clone().unwrap_or_default())
+    }
+}
+
+impl Drop for CapturedWarnings {
+    fn drop(&mut self) {
+        let warnings = TL_WARNINGS.replace(self.previous.take());
+        // a nested capture passes its warnings on to the enclosing capture
+        TL_WARNINGS.with_borrow_mut(|previous| {
+            if let (Some(previous), Some(warnings)) = (previous, warnings) {
+                previous.extend(warnings);
+            }
+        });
+    }
+}
+
+// prints the warning, or collects it, if the warnings are being captured, see `capture_warnings`
+pub(crate) fn warn(message: String) {
+    TL_WARNINGS.with_borrow_mut(|warnings| match warnings {
+        Some(warnings) => warnings.push(message),
+        None => println!("{}", message),
+    });
+}
+
+static SLOT_COVERAGE_ENABLED: AtomicBool = AtomicBool::new(false);
+static SLOT_COVERAGE: Mutex<BTreeMap<String, BTreeMap<String, bool>>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+    static TL_CURRENT_TEMPLATE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+///
+/// start_slot_coverage, starts recording which placeholders (slots) of the named templates are filled with non blank content
+///
+/// The recording is shared by all the threads, so the coverage can be collected across a test run, see `slot_coverage`.
+/// Only the persisted (named) tiles are recorded as templates, the placeholders of the inline definitions are not recorded.
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::testing::*;
+///
+/// start_slot_coverage();
+/// tp!(doc_cov_note);
+/// tp!(doc_cov_name, "Alice");
+/// tp!(doc_cov_letter, "Dear @{doc_cov_name},@{doc_cov_note}");
+/// let coverage = stop_slot_coverage();
+///
+/// assert_eq!(coverage.slots("doc_cov_letter"), vec![("doc_cov_name", true), ("doc_cov_note", false)]);
+/// assert!(coverage.dead_slots().contains(&("doc_cov_letter", "doc_cov_note")));
+/// ```
+///
+pub fn start_slot_coverage() {
+    SLOT_COVERAGE_ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// stops recording the slot coverage and returns the coverage recorded since it was started, the recording is cleared
+pub fn stop_slot_coverage() -> SlotCoverage {
+    SLOT_COVERAGE_ENABLED.store(false, Ordering::SeqCst);
+    let templates = std::mem::take(&mut *SLOT_COVERAGE.lock().unwrap_or_else(|e| e.into_inner()));
+    SlotCoverage { templates }
+}
+
+/// returns the slot coverage recorded so far, without stopping the recording
+pub fn slot_coverage() -> SlotCoverage {
+    let templates = SLOT_COVERAGE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    SlotCoverage { templates }
+}
+
+///
+/// SlotCoverage, the placeholders (slots) of every recorded template and whether they were ever filled with non blank content
+///
+/// Its Display is a summary listing the templates with the number of their filled slots, followed by their dead slots.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SlotCoverage {
+    templates: BTreeMap<String, BTreeMap<String, bool>>,
+}
+
+impl SlotCoverage {
+    /// returns the names of the recorded templates, sorted
+    pub fn templates(&self) -> Vec<&str> {
+        self.templates.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// returns the slots of the template, sorted by their names, along with whether they were ever filled
+    pub fn slots(&self, template: &str) -> Vec<(&str, bool)> {
+        self.templates.get(template).map_or(vec![], |slots| {
+            slots
+                .iter()
+                .map(|(slot, filled)| (slot.as_str(), *filled))
+                .collect()
+        })
+    }
+
+    /// returns the (template, slot) pairs of the slots which were never filled
+    pub fn dead_slots(&self) -> Vec<(&str, &str)> {
+        self.templates
+            .iter()
+            .flat_map(|(template, slots)| {
+                slots
+                    .iter()
+                    .filter(|(_, filled)| !**filled)
+                    .map(move |(slot, _)| (template.as_str(), slot.as_str()))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for SlotCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (template, slots) in &self.templates {
+            let filled = slots.values().filter(|filled| **filled).count();
+            writeln!(f, "{}: {}/{} slots filled", template, filled, slots.len())?;
+            for (slot, _) in slots.iter().filter(|(_, filled)| !**filled) {
+                writeln!(f, "    dead slot: {}", slot)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// true, if the slots are being recorded, see `start_slot_coverage`
+pub(crate) fn is_recording_slots() -> bool {
+    SLOT_COVERAGE_ENABLED.load(Ordering::Relaxed)
+}
+
+// runs f with the given template as the template whose placeholders are being expanded
+pub(crate) fn with_template<R>(template: Option<&str>, f: impl FnOnce() -> R) -> R {
+    if !SLOT_COVERAGE_ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+    struct TemplateGuard(Option<String>);
+    impl Drop for TemplateGuard {
+        fn drop(&mut self) {
+            TL_CURRENT_TEMPLATE.set(self.0.take());
+        }
+    }
+
+    let _guard = TemplateGuard(TL_CURRENT_TEMPLATE.replace(template.map(str::to_string)));
+    f()
+}
+
+// records the placeholder of the current template as filled or not
+pub(crate) fn record_slot(slot: &str, filled: bool) {
+    if !SLOT_COVERAGE_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    TL_CURRENT_TEMPLATE.with_borrow(|template| {
+        if let Some(template) = template {
+            let mut coverage = SLOT_COVERAGE.lock().unwrap_or_else(|e| e.into_inner());
+            let recorded = coverage
+                .entry(template.clone())
+                .or_default()
+                .entry(slot.to_string())
+                .or_insert(false);
+            *recorded |= filled;
+        }
+    });
+}
@@ -0,0 +1,291 @@
+//!
+//! Values of the standard configuration formats, i.e. the dotenv files and the Java properties files, every key is a tile
+//! holding its value, so the configuration driven templates are rendered against the configuration files as they are.
+//!
+
+use crate::detached;
+use crate::with_default_store;
+use crate::RTile;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+///
+/// Format, of a values file
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `KEY=VALUE` lines, optionally prefixed with `export`, the values are unquoted, single quoted (taken as they are) or
+    /// double quoted (with the `\n`, `\t`, `\"` and `\\` escapes), the quoted values may span lines
+    DotEnv,
+    /// `key=value`, `key: value` or `key value` lines, a line ending with `\` continues on the next line, with the escapes
+    /// of the format (including `\uXXXX`)
+    JavaProperties,
+}
+
+///
+/// load_values, used to persist every key of the values file as a tile in the tls (thread local storage) holding its value,
+/// returns the keys in the order of the file, a later definition of a key wins
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::values::*;
+///
+/// let path = std::env::temp_dir().join(format!("rtile_doc_values_{}.env", std::process::id()));
+/// std::fs::write(&path, "# the service\nexport SERVICE=billing\nPORT=8080 # http\n").unwrap();
+/// assert_eq!(load_values(&path, Format::DotEnv).unwrap(), vec!["SERVICE", "PORT"]);
+/// assert_eq!(t!("@{SERVICE} listens on @{PORT}").to_string(), "billing listens on 8080");
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+///
+pub fn load_values(path: impl AsRef<Path>, format: Format) -> Result<Vec<String>, ValuesError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|error| ValuesError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let mut names: Vec<String> = vec![];
+    for (name, value) in parse_values(&text, format)? {
+        let tile = detached(|| RTile::construct_from_str(&value));
+        with_default_store(|store| store.set(&name, tile));
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+///
+/// parse_values, used to parse the text of a values file into the keys and their values, in the order of the text
+/// ```
+/// use rtile::values::*;
+///
+/// let values = parse_values("db.url = jdbc:h2:mem\\\n    ;MODE=PostgreSQL\ngreeting: caf\\u00e9", Format::JavaProperties);
+/// assert_eq!(
+///     values.unwrap(),
+///     vec![
+///         ("db.url".to_string(), "jdbc:h2:mem;MODE=PostgreSQL".to_string()),
+///         ("greeting".to_string(), "café".to_string()),
+///     ]
+/// );
+/// ```
+///
+pub fn parse_values(text: &str, format: Format) -> Result<Vec<(String, String)>, ValuesError> {
+    match format {
+        Format::DotEnv => parse_dotenv(text),
+        Format::JavaProperties => parse_properties(text),
+    }
+}
+
+///
+/// ValuesError, returned when a values file can't be read or parsed
+///
+#[derive(Debug)]
+pub enum ValuesError {
+    /// the file could not be read
+    Io {
+        /// the path of the file
+        path: PathBuf,
+        /// the underlying error
+        error: io::Error,
+    },
+    /// the file is not valid
+    Parse {
+        /// the number (starting from 1) of the line of the error
+        line: usize,
+        /// the description of the error
+        message: String,
+    },
+}
+
+impl fmt::Display for ValuesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValuesError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ValuesError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ValuesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ValuesError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> ValuesError {
+    ValuesError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+fn parse_dotenv(text: &str) -> Result<Vec<(String, String)>, ValuesError> {
+    let mut values = vec![];
+    let mut lns = text.lines().enumerate();
+    while let Some((i, ln)) = lns.next() {
+        let ln = ln.trim();
+        if ln.is_empty() || ln.starts_with('#') {
+            continue;
+        }
+        let ln = ln.strip_prefix("export ").map_or(ln, str::trim_start);
+        let Some((key, value)) = ln.split_once('=') else {
+            return Err(parse_error(i + 1, format!("missing `=` after `{}`", ln)));
+        };
+        let key = key.trim();
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+        {
+            return Err(parse_error(i + 1, format!("invalid key `{}`", key)));
+        }
+        let value = value.trim();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                // a quoted value continues on the next lines until its closing quote
+                let mut quoted = value[1..].to_string();
+                let end = loop {
+                    if let Some(end) = closing_quote(&quoted, quote) {
+                        break end;
+                    }
+                    match lns.next() {
+                        Some((_, next)) => {
+                            quoted.push('\n');
+                            quoted.push_str(next);
+                        }
+                        None => {
+                            return Err(parse_error(i + 1, format!("unclosed {} quote", quote)))
+                        }
+                    }
+                };
+                match quote {
+                    '"' => unescape_dotenv(&quoted[..end]),
+                    _ => quoted[..end].to_string(),
+                }
+            }
+            // an unquoted value ends at a comment, i.e. a `#` after a white space
+            _ => match value.find(" #").or_else(|| value.find("\t#")) {
+                Some(comment) => value[..comment].trim_end().to_string(),
+                None => value.to_string(),
+            },
+        };
+        values.push((key.to_string(), value));
+    }
+    Ok(values)
+}
+
+// the index of the closing quote, a double quote is escaped by a backslash
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '\\' if quote == '"' && !escaped => escaped = true,
+            c if c == quote && !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape_dotenv(text: &str) -> String {
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some(c @ ('"' | '\\' | '$')) => value.push(c),
+            Some(c) => {
+                value.push('\\');
+                value.push(c);
+            }
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+fn parse_properties(text: &str) -> Result<Vec<(String, String)>, ValuesError> {
+    let mut values = vec![];
+    let mut lns = text.lines().enumerate();
+    while let Some((i, ln)) = lns.next() {
+        let ln = ln.trim_start();
+        if ln.is_empty() || ln.starts_with('#') || ln.starts_with('!') {
+            continue;
+        }
+        // the logical line, the leading white spaces of a continuation line are dropped
+        let mut logical = ln.to_string();
+        while ends_with_continuation(&logical) {
+            logical.pop();
+            match lns.next() {
+                Some((_, next)) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+        let (key, value) = split_property(&logical);
+        let key = unescape_properties(key).map_err(|message| parse_error(i + 1, message))?;
+        let value = unescape_properties(value).map_err(|message| parse_error(i + 1, message))?;
+        values.push((key, value));
+    }
+    Ok(values)
+}
+
+// true, if the line ends with an odd number of backslashes
+fn ends_with_continuation(ln: &str) -> bool {
+    ln.chars().rev().take_while(|&c| c == '\\').count() % 2 == 1
+}
+
+// the key ends at the first unescaped `=`, `:` or white space, the separator may be surrounded by white spaces
+fn split_property(ln: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (i, c) in ln.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '=' | ':' | ' ' | '\t' | '\u{c}' if !escaped => {
+                let rest = ln[i..].trim_start_matches([' ', '\t', '\u{c}']);
+                let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest);
+                return (&ln[..i], rest.trim_start_matches([' ', '\t', '\u{c}']));
+            }
+            _ => escaped = false,
+        }
+    }
+    (ln, "")
+}
+
+fn unescape_properties(text: &str) -> Result<String, String> {
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => value.push('\t'),
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('f') => value.push('\u{c}'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 4)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid escape `\\u{}`", hex))?;
+                value.push(c);
+            }
+            Some(c) => value.push(c),
+            None => {}
+        }
+    }
+    Ok(value)
+}
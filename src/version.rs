@@ -0,0 +1,176 @@
+//!
+//! Semantic versions of the tiles, i.e. `tpv!` persists a tile as `name@major.minor.patch`, and a placeholder `@{name@^2}`
+//! refers to the highest persisted version compatible with the requirement, the same way as the requirements of cargo.
+//!
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+}
+
+impl Version {
+    // `2`, `2.1` or `2.1.3`, the missing parts are zero
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let parts = parse_parts(text)?;
+        Some(Self {
+            major: parts[0],
+            minor: parts.get(1).copied().unwrap_or(0),
+            patch: parts.get(2).copied().unwrap_or(0),
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+fn parse_parts(text: &str) -> Option<Vec<u64>> {
+    let parts = text
+        .trim()
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    (1..=3).contains(&parts.len()).then_some(parts)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Caret,
+    Tilde,
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+// a comparator of a requirement, e.g. `^2.1`, the parts given are kept to match the partial versions the way cargo does
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparator {
+    op: Op,
+    parts: Vec<u64>,
+}
+
+impl Comparator {
+    fn matches(&self, version: Version) -> bool {
+        let part = |i: usize| self.parts.get(i).copied().unwrap_or(0);
+        let lower = Version {
+            major: part(0),
+            minor: part(1),
+            patch: part(2),
+        };
+        match self.op {
+            Op::Exact => (0..self.parts.len())
+                .all(|i| [version.major, version.minor, version.patch][i] == self.parts[i]),
+            Op::Greater => version >= self.upper_of_exact(),
+            Op::GreaterEq => version >= lower,
+            Op::Less => version < lower,
+            Op::LessEq => version < self.upper_of_exact(),
+            Op::Tilde => {
+                version >= lower
+                    && match self.parts.len() {
+                        1 => version.major == lower.major,
+                        _ => version.major == lower.major && version.minor == lower.minor,
+                    }
+            }
+            Op::Caret => {
+                version >= lower
+                    && match (self.parts.len(), lower.major, lower.minor) {
+                        (_, major, _) if major > 0 => version.major == major,
+                        (1, _, _) => version.major == 0,
+                        (2, _, minor) => version.major == 0 && version.minor == minor,
+                        (_, _, minor) if minor > 0 => version.major == 0 && version.minor == minor,
+                        _ => version == lower,
+                    }
+            }
+        }
+    }
+
+    // the first version past the versions matching the given parts exactly, e.g. `2.2.0` for `2.1`
+    fn upper_of_exact(&self) -> Version {
+        let part = |i: usize| self.parts.get(i).copied().unwrap_or(0);
+        match self.parts.len() {
+            1 => Version {
+                major: part(0) + 1,
+                minor: 0,
+                patch: 0,
+            },
+            2 => Version {
+                major: part(0),
+                minor: part(1) + 1,
+                patch: 0,
+            },
+            _ => Version {
+                major: part(0),
+                minor: part(1),
+                patch: part(2) + 1,
+            },
+        }
+    }
+}
+
+// a requirement, i.e. comparators separated by commas which all have to match, or `*` for any version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VersionReq {
+    comparators: Vec<Comparator>,
+}
+
+impl VersionReq {
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        if text.trim() == "*" {
+            return Some(Self {
+                comparators: vec![],
+            });
+        }
+        let comparators = text
+            .split(',')
+            .map(|comparator| {
+                let comparator = comparator.trim();
+                let (op, parts) = [
+                    (">=", Op::GreaterEq),
+                    ("<=", Op::LessEq),
+                    (">", Op::Greater),
+                    ("<", Op::Less),
+                    ("=", Op::Exact),
+                    ("^", Op::Caret),
+                    ("~", Op::Tilde),
+                ]
+                .iter()
+                .find_map(|(prefix, op)| comparator.strip_prefix(prefix).map(|rest| (*op, rest)))
+                .unwrap_or((Op::Caret, comparator));
+                Some(Comparator {
+                    op,
+                    parts: parse_parts(parts)?,
+                })
+            })
+            .collect::<Option<Vec<Comparator>>>()?;
+        Some(Self { comparators })
+    }
+
+    pub(crate) fn matches(&self, version: Version) -> bool {
+        self.comparators
+            .iter()
+            .all(|comparator| comparator.matches(version))
+    }
+}
+
+// the name and the requirement of a placeholder like `name@^2`, None for the other names
+pub(crate) fn split_requirement(tile_name: &str) -> Option<(&str, VersionReq)> {
+    let (name, requirement) = tile_name.rsplit_once('@')?;
+    if name.is_empty() {
+        return None;
+    }
+    Some((name, VersionReq::parse(requirement)?))
+}
+
+// the version of a tile persisted by tpv!, i.e. `name@2.1.0`
+pub(crate) fn split_version(tile_name: &str) -> Option<(&str, Version)> {
+    let (name, version) = tile_name.rsplit_once('@')?;
+    Some((name, Version::parse(version)?))
+}
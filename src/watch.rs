@@ -0,0 +1,194 @@
+//!
+//! Watch mode, reloads the changed template files into the tls (thread local storage) and regenerates the outputs, for a fast
+//! edit-preview loop while authoring the templates.
+//!
+//! The template files are named the same way as the templates of a project, i.e. after their paths relative to the watched
+//! directory (or after their file names, for the watched files), see `rtile::project`. The files are polled, i.e. their metadata
+//! is read again at every interval and a file is read again only when its modification time or its length changed, which needs
+//! no support of the platform and suits the small directories of the templates.
+//!
+//! The module is enabled by the `watch` feature, or by its alias `notify`.
+//!
+
+use crate::detached;
+use crate::project::template_files;
+use crate::with_default_store;
+use crate::RTile;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+/// the default interval between the polls of the template files
+pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+///
+/// TemplateWatcher, polls the template files and keeps the tiles of the tls in sync with them
+/// ```
+/// use rtile::prelude::*;
+/// use rtile::watch::TemplateWatcher;
+///
+/// let root = std::env::temp_dir().join(format!("rtile_doc_watch_{}", std::process::id()));
+/// std::fs::create_dir_all(&root).unwrap();
+/// std::fs::write(root.join("greet.txt"), "Hello @{name}!").unwrap();
+/// tp!(name, "Alice");
+///
+/// let mut watcher = TemplateWatcher::new([&root]);
+/// assert_eq!(watcher.poll().unwrap(), vec!["greet.txt"]);
+/// assert_eq!(ts!("@{greet.txt}"), "Hello Alice!");
+///
+/// std::fs::write(root.join("greet.txt"), "Hi @{name}!").unwrap();
+/// assert_eq!(watcher.poll().unwrap(), vec!["greet.txt"]);
+/// assert_eq!(ts!("@{greet.txt}"), "Hi Alice!");
+/// assert!(watcher.poll().unwrap().is_empty());
+/// std::fs::remove_dir_all(&root).unwrap();
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct TemplateWatcher {
+    paths: Vec<PathBuf>,
+    interval: Duration,
+    files: BTreeMap<String, WatchedFile>,
+}
+
+// the template file a tile was loaded from, with its metadata at the time it was read
+#[derive(Debug, Clone)]
+struct WatchedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+    text: String,
+}
+
+impl TemplateWatcher {
+    /// creates a watcher of the given directories or files, the first path holding a template name wins
+    pub fn new<I, P>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        Self {
+            paths: paths
+                .into_iter()
+                .map(|path| path.as_ref().to_path_buf())
+                .collect(),
+            interval: DEFAULT_INTERVAL,
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// sets the interval between the polls of `watch_templates`
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// returns the interval between the polls
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// reloads the template files which changed since the last poll (i.e. all of them, at the first poll) into the tls,
+    /// removes the tiles of the removed files, and returns the names of the changed tiles, sorted. A file is read again only
+    /// when its modification time or its length changed, and it counts as changed only when its text changed
+    pub fn poll(&mut self) -> io::Result<Vec<String>> {
+        let mut files = BTreeMap::new();
+        for path in &self.paths {
+            let templates = match template_files(path) {
+                Ok(templates) => templates,
+                // the directory may be created later
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for (name, file) in templates {
+                if files.contains_key(&name) {
+                    continue;
+                }
+                let metadata = match std::fs::metadata(&file) {
+                    Ok(metadata) => metadata,
+                    // the file is removed since the directory was read
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e),
+                };
+                let modified = metadata.modified().ok();
+                let len = metadata.len();
+                let text = match self.files.get(&name) {
+                    Some(watched)
+                        if watched.path == file
+                            && watched.modified.is_some()
+                            && watched.modified == modified
+                            && watched.len == len =>
+                    {
+                        watched.text.clone()
+                    }
+                    _ => match std::fs::read_to_string(&file) {
+                        Ok(text) => text,
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                        Err(e) => return Err(e),
+                    },
+                };
+                files.insert(
+                    name,
+                    WatchedFile {
+                        path: file,
+                        modified,
+                        len,
+                        text,
+                    },
+                );
+            }
+        }
+        let mut changed = vec![];
+        for (name, file) in &files {
+            if self.files.get(name).map(|watched| &watched.text) != Some(&file.text) {
+                let tile = detached(|| RTile::construct_from_str(&file.text));
+                with_default_store(|store| store.set(name, tile));
+                changed.push(name.clone());
+            }
+        }
+        for name in self.files.keys() {
+            if !files.contains_key(name) {
+                with_default_store(|store| store.remove(name));
+                changed.push(name.clone());
+            }
+        }
+        changed.sort();
+        self.files = files;
+        Ok(changed)
+    }
+}
+
+///
+/// watch_templates, loads the template files of the given directories or files into the tls and calls `regenerate` with the
+/// names of all of them, then polls the files and calls `regenerate` again with the names of the changed templates whenever any
+/// of them changed, so that only the affected outputs are regenerated, it returns only on an error
+/// ```no_run
+/// use rtile::prelude::*;
+/// use rtile::watch::watch_templates;
+///
+/// tp!(name, "Alice");
+/// watch_templates(["templates"], |changed| {
+///     if changed.iter().any(|name| name == "greet.txt") {
+///         std::fs::write("greet.txt", ts!("@{greet.txt}")).unwrap();
+///     }
+/// })
+/// .unwrap();
+/// ```
+///
+pub fn watch_templates<I, P>(paths: I, mut regenerate: impl FnMut(&[String])) -> io::Result<()>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let mut watcher = TemplateWatcher::new(paths);
+    regenerate(&watcher.poll()?);
+    loop {
+        std::thread::sleep(watcher.interval());
+        let changed = watcher.poll()?;
+        if !changed.is_empty() {
+            regenerate(&changed);
+        }
+    }
+}
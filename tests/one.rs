@@ -1,3 +1,10 @@
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::unnecessary_to_owned,
+    clippy::useless_vec,
+    clippy::unnecessary_lazy_evaluations
+)]
+
 #[cfg(test)]
 mod tests {
     use std::any::type_name;
@@ -2499,9 +2506,7 @@ mod tests {
             "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten"
         ]);
         let k2 = k!(vec!["1", "2", "3", "4", "5", "6", "7", "8", "9", "10"]);
-        let spaces = vec![" "; 10];
-
-        let result = k!(k1.clone() + k!(vec![spaces.join(""); 10]) + k2.clone());
+        let result = k!(k1.clone() + RSpacer::h(10) + k2.clone());
 
         let val = ts!("
             one            1
@@ -2518,7 +2523,7 @@ mod tests {
         assert_eq!(ts!(result), ts!(t!(val)));
         assert_eq!(ks!(result), ks!(k!(val)));
 
-        //println!("{}", ks!(k1 + k!(vec![spaces.join(""); 10]) + k2));
+        //println!("{}", ks!(k1 + RSpacer::h(10) + k2));
     }
 
     #[test]
@@ -2532,8 +2537,7 @@ mod tests {
             t_tws_2_2,
             vec!["1    ", "2", "3", "4", "5", "6", "7", "8", "9", "10"]
         );
-        let spaces = vec![" "; 10];
-        let _spaces_tile = kp!(t_tws_2_spaces, vec![spaces.join(""); 10]);
+        let _spaces_tile = kp!(t_tws_2_spaces, RSpacer::h(10).beside(&_k1));
 
         let result = k!("@{t_tws_2_1}@{t_tws_2_spaces}@{t_tws_2_2}");
         let val = ks!("one            1
@@ -2550,7 +2554,7 @@ ten            10   ");
         assert_eq!(ts!(result), ts!(t!(val)));
 
         assert_eq!(ks!(result), ks!(k!(val)));
-        //println!("{}", ks!(_k1 + k!(vec![spaces.join(""); 10]) + _k2));
+        //println!("{}", ks!(_k1 + RSpacer::h(10) + _k2));
     }
 
     #[test]
@@ -2943,4 +2947,3881 @@ ten            10   ");
 
         words.trim_end().to_string()
     }
+
+    #[test]
+    fn test_ops_hmerge_and_strip_margin() {
+        let left = t!(vec!["one", "two", "three"]);
+        let right = t!(vec!["1", "2"]);
+        let mut lns = left.lns.clone();
+        rtile::ops::hmerge(&mut lns, right.lns.clone());
+        assert_eq!(lns, (left + right).lns);
+
+        let raw = vec!["", "   fn main() {", "       body();", "   }", ""];
+        assert_eq!(rtile::ops::strip_margin(raw.clone()), t!(raw).lns);
+    }
+
+    #[test]
+    fn test_rename_placeholder_everywhere() {
+        tp!(rp_name, "Bob");
+        tp!(rp_numbers, vec!["one", "two"]);
+        tp!(rp_greet, "hello @{rp_name} @{rp_numbers}");
+        tp!(rp_outer, "[@{rp_greet}] @{rp_name}");
+        let before = ts!("@{rp_outer}");
+
+        let affected = rename_placeholder_everywhere("rp_name", "rp_first_name", true).unwrap();
+        assert_eq!(affected, vec!["rp_greet", "rp_name", "rp_outer"]);
+        assert!(gtp!(rp_name).is_some());
+        assert!(gtp!(rp_first_name).is_none());
+
+        let affected = rename_placeholder_everywhere("rp_name", "rp_first_name", false).unwrap();
+        assert_eq!(affected, vec!["rp_greet", "rp_name", "rp_outer"]);
+        assert!(gtp!(rp_name).is_none());
+        assert_eq!(
+            gtp!(rp_first_name).unwrap().name,
+            Some("rp_first_name".to_string())
+        );
+        assert_eq!(
+            sr!(gtp!(rp_outer).unwrap()),
+            "[@{rp_greet}] @{rp_first_name}"
+        );
+        assert_eq!(ts!("@{rp_outer}"), before);
+
+        let affected = rename_placeholder_everywhere("rp_not_used", "rp_other", false).unwrap();
+        assert!(affected.is_empty());
+    }
+
+    #[test]
+    fn test_to_string_with_report() {
+        tp!(rr_attributes);
+        tp!(rr_doc, "   ");
+        tp!(rr_field, "id: u32,");
+        tp!(rr_fields, "@{rr_field}\n@{rr_extra_field}");
+        let tile = t!("
+            @{rr_doc}
+            @{rr_attributes}struct Item {
+                @{rr_fields}
+            }
+        ");
+        let (output, report) = tile.to_string_with_report();
+        assert_eq!(output, tile.to_string());
+        assert_eq!(
+            report.empty_placeholders,
+            vec!["rr_doc", "rr_attributes", "rr_extra_field"]
+        );
+
+        tp!(rr_extra_field, "name: String,");
+        let (_, report) = tile.to_string_with_report();
+        assert_eq!(report.empty_placeholders, vec!["rr_doc", "rr_attributes"]);
+    }
+
+    #[test]
+    fn test_unique_tile_names() {
+        tp!(gs_cell_u1, "taken");
+        let names: Vec<String> = (1..=3).map(|i| tpu!(gs_cell, "{}", i * 10)).collect();
+        assert!(!names.contains(&"gs_cell_u1".to_string()));
+        assert_eq!(names.iter().collect::<HashSet<_>>().len(), 3);
+        let row = names
+            .iter()
+            .map(|name| format!("@{{{}}}", name))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(ts!(row), "10 20 30");
+        assert_eq!(ts!("@{gs_cell_u1}"), "taken");
+
+        let blank = tpu!(gs_blank);
+        assert!(gtq!(blank).unwrap().lns.is_empty());
+    }
+
+    #[test]
+    fn test_with_prefix() {
+        tp!(wp_name, "global");
+        let result = with_prefix("wpa_", || {
+            tp!(wp_name, "Alice");
+            tp!(wp_title, "Dr.");
+            let inner = with_prefix("b_", || {
+                tp!(wp_name, "Bob");
+                tq!("wp_greet", "hello @{wp_title} @{wp_name}");
+                // the tile of the enclosing prefix is found before the global one
+                assert_eq!(gtp!(wp_title).unwrap().raw(), "Dr.");
+                ts!("@{wp_greet}")
+            });
+            assert_eq!(ts!("@{wp_name}"), "Alice");
+            assert_eq!(gtp!(wp_greet), None);
+            inner
+        });
+        assert_eq!(result, "hello Dr. Bob");
+        assert_eq!(ts!("@{wp_name}"), "global");
+        assert_eq!(ts!("@{wpa_wp_name}, @{wpa_b_wp_name}"), "Alice, Bob");
+        assert!(gtq!("wpa_b_wp_greet").is_some());
+
+        with_prefix("wpa_", || remove_tile("wp_name"));
+        assert!(gtp!(wpa_wp_name).is_none());
+        assert_eq!(ts!("@{wp_name}"), "global");
+    }
+
+    #[test]
+    fn test_placeholder_align_option() {
+        tp!(al_args, vec!["foo int,", "bar char*,", "baz struct quux);"]);
+        let signature = t!("void frobnicate(@{al_args:align=4}");
+        assert_eq!(
+            signature.to_string(),
+            ts!("
+                void frobnicate(foo int,
+                    bar char*,
+                    baz struct quux);
+            ")
+        );
+
+        tp!(al_wrapped, "call(@{al_args:align=2}");
+        assert_eq!(
+            ts!("let x = @{al_wrapped}"),
+            "let x = call(foo int,\n          bar char*,\n          baz struct quux);"
+        );
+
+        tp!(al_single, "one");
+        assert_eq!(ts!("(@{al_single:align=0})"), "(one)");
+        assert_eq!(ts!("(@{al_args: align = 0 })"), ts!("(@{al_args:align=0})"));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown option")]
+    fn test_placeholder_unknown_option() {
+        tp!(al_unknown, "one");
+        ts!("@{al_unknown:frobnicate}");
+    }
+
+    #[test]
+    fn test_placeholder_skip_if_blank_option() {
+        tp!(sb_attrs);
+        tp!(sb_doc, "   ");
+        tp!(sb_fields, vec!["a: u8,", "b: u8,"]);
+        let tile = t!("
+            pub struct Item {
+                @{sb_doc:skip_if_blank}
+                @{sb_attrs:skip_if_blank} @{sb_doc:skip_if_blank}
+                @{sb_fields}
+            }
+        ");
+        assert_eq!(
+            tile.to_string(),
+            ts!("
+                pub struct Item {
+                    a: u8,
+                    b: u8,
+                }
+            ")
+        );
+
+        // with other text on the line, the blank tile renders as nothing
+        assert_eq!(ts!("#[@{sb_attrs:skip_if_blank}]"), "#[]");
+
+        // without the option, the blank lines are kept
+        let tile = t!("
+            one
+            @{sb_attrs}
+            two
+        ");
+        assert_eq!(tile.to_string(), "one\n\ntwo");
+
+        tp!(sb_attrs, "#[derive(Debug)]");
+        let tile = t!("
+            @{sb_attrs:skip_if_blank}
+            struct A;
+        ");
+        assert_eq!(tile.to_string(), "#[derive(Debug)]\nstruct A;");
+    }
+
+    #[test]
+    fn test_render_options_drop_blank_placeholder_lines() {
+        tp!(ro_attr);
+        tp!(ro_doc, "/// doc");
+        tp!(
+            ro_item,
+            "
+            @{ro_doc}
+            @{ro_attr}
+            fn f() {}
+        "
+        );
+        let tile = t!("
+            @{ro_item}
+                @{ro_attr}   @{ro_attr}
+            @{ro_item}
+        ");
+        let options = RenderOptions {
+            drop_blank_placeholder_lines: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            tile.to_string_with_options(&options),
+            "/// doc\nfn f() {}\n/// doc\nfn f() {}"
+        );
+        assert_eq!(
+            tile.to_string(),
+            "/// doc\n\nfn f() {}\n\n/// doc\n\nfn f() {}"
+        );
+
+        set_render_options(options.clone());
+        assert_eq!(get_render_options(), options);
+        assert_eq!(tile.to_string(), "/// doc\nfn f() {}\n/// doc\nfn f() {}");
+        assert_eq!(ts!("fn @{ro_attr}g() {}"), "fn g() {}");
+        set_render_options(RenderOptions::default());
+        assert_eq!(
+            tile.to_string(),
+            "/// doc\n\nfn f() {}\n\n/// doc\n\nfn f() {}"
+        );
+    }
+
+    #[test]
+    fn test_tile_store() {
+        let mut store = TileStore::new();
+        tp!(ms_name, "tls");
+        tp!(store => ms_name, "store");
+        kp!(store => ms_pad, "  x  ");
+        let name = "ms_greet";
+        tq!(store => name, "Hello @{ms_name} @{ms_missing}!");
+        assert_eq!(ts!(store => "@{ms_greet}"), "Hello store !");
+        assert_eq!(ts!("@{ms_name}"), "tls");
+        assert_eq!(ks!(store => "[@{ms_pad}]"), "[  x  ]");
+        assert_eq!(ts!(store => "{}-@{{ms_name}}", 1), "1-store");
+
+        // the blank tiles are created in the given store only
+        assert!(store.blank_tiles().contains("ms_missing"));
+        assert!(!get_blank_tiles().contains("ms_missing"));
+        assert!(gtp!(ms_greet).is_none());
+
+        stp!(store => ms_copy, gtp!(ms_name).unwrap());
+        assert_eq!(gtq!(store => "ms_copy").unwrap().raw(), "tls");
+        assert_eq!(store.remove("ms_copy").unwrap().raw(), "tls");
+        assert!(!store.contains("ms_copy"));
+        assert_eq!(store.len(), 4);
+        store.clear();
+        assert!(store.is_empty());
+        assert_eq!(ts!("@{ms_name}"), "tls");
+    }
+
+    #[test]
+    #[should_panic(expected = "detected a recursion")]
+    fn test_tile_store_recursion() {
+        let mut store = TileStore::new();
+        tp!(store => ms_a, "@{ms_b}");
+        tp!(store => ms_b, "@{ms_a}");
+        ts!(store => "@{ms_a}");
+    }
+
+    #[test]
+    fn test_tile_store_fork() {
+        let mut store = TileStore::new();
+        tp!(store => fk_name, "base");
+        tp!(store => fk_other, "other");
+        tp!(store => fk_greet, "Hello @{fk_name}");
+
+        let mut child = store.fork();
+        assert_eq!(child.len(), 3);
+        tp!(child => fk_name, "child");
+        child.remove("fk_other");
+        tp!(store => fk_new, "new");
+
+        assert_eq!(ts!(child => "@{fk_greet}"), "Hello child");
+        assert_eq!(ts!(store => "@{fk_greet}"), "Hello base");
+        assert!(!child.contains("fk_other"));
+        assert!(store.contains("fk_other"));
+        assert!(!child.contains("fk_new"));
+        assert_eq!(child.len(), 2);
+        assert_eq!(store.len(), 4);
+
+        let mut grandchild = child.fork();
+        assert_eq!(
+            grandchild
+                .rename_placeholder("fk_name", "fk_title", false)
+                .unwrap(),
+            vec!["fk_greet", "fk_name"]
+        );
+        assert_eq!(ts!(grandchild => "@{fk_greet}"), "Hello child");
+        assert_eq!(gtp!(child => fk_greet).unwrap().raw(), "Hello @{fk_name}");
+    }
+
+    #[test]
+    fn test_set_cached_tile() {
+        let mut computed = 0;
+        for key in ["a", "a", "b", "b"] {
+            set_cached_tile("ct_tile", key, || {
+                computed += 1;
+                t!("value {} @{{ct_inner}}", key)
+            });
+        }
+        assert_eq!(computed, 2);
+        assert_eq!(ts!("@{ct_tile}"), "value b");
+
+        // persisting the tile by any other means invalidates the key
+        tp!(ct_tile, "other");
+        let tile = set_cached_tile("ct_tile", "b", || {
+            computed += 1;
+            t!("recomputed")
+        });
+        assert_eq!(computed, 3);
+        assert_eq!(tile.raw(), "recomputed");
+
+        remove_tile("ct_tile");
+        set_cached_tile("ct_tile", "b", || {
+            computed += 1;
+            t!("recomputed")
+        });
+        assert_eq!(computed, 4);
+
+        // the keys made of several texts are not ambiguous
+        set_cached_tile("ct_pair", ("ab", "c"), || t!("first"));
+        let tile = set_cached_tile("ct_pair", ("a", "bc"), || t!("second"));
+        assert_eq!(tile.raw(), "second");
+    }
+
+    #[test]
+    fn test_content_hash_and_store_fingerprint() {
+        tp!(ch_name, "Person");
+        let tile = t!("struct @{ch_name};");
+        let hash = tile.content_hash();
+        assert_eq!(hash, t!("struct Person;").content_hash());
+        assert_eq!(t!("").content_hash(), 0xcbf29ce484222325);
+        assert_eq!(t!("a").content_hash(), 0xaf63dc4c8601ec8c);
+
+        let fingerprint = store_fingerprint();
+        tp!(ch_name, "Animal");
+        assert_ne!(tile.content_hash(), hash);
+        assert_ne!(store_fingerprint(), fingerprint);
+        tp!(ch_name, "Person");
+        assert_eq!(tile.content_hash(), hash);
+        assert_eq!(store_fingerprint(), fingerprint);
+
+        let mut store = TileStore::new();
+        tp!(store => ch_name, "Person");
+        let mut other = TileStore::new();
+        kp!(other => ch_name, "Person");
+        assert_ne!(store.fingerprint(), other.fingerprint());
+        assert_eq!(store.fingerprint(), store.fork().fingerprint());
+    }
+
+    #[test]
+    fn test_merge_store() {
+        let mut library = TileStore::new();
+        tp!(library => ms2_header, "// @{ms2_footer} by @{ms2_tool}");
+        tp!(library => ms2_footer, "generated");
+        tp!(library => ms2_tool, "library");
+
+        t!("@{ms2_footer}");
+        tp!(ms2_tool, "app");
+        tp!(ms2_same, "same");
+        library.set("ms2_same", t!("same"));
+
+        // the blank tile and the identical tile do not clash
+        assert_eq!(
+            merge_store(library.export(), ConflictPolicy::Error),
+            Err(vec!["ms2_tool".to_string()])
+        );
+        assert_eq!(ts!("@{ms2_header}"), "");
+        tp!(ms2_tool, "library");
+        assert_eq!(merge_store(library.export(), ConflictPolicy::Error), Ok(()));
+        assert_eq!(ts!("@{ms2_header}"), "// generated by library");
+        assert_eq!(
+            gtp!(ms2_header).unwrap().name,
+            Some("ms2_header".to_string())
+        );
+
+        let mut other = TileStore::new();
+        tp!(other => ms2_tool, "other");
+        other.merge(export_store(), ConflictPolicy::Skip).unwrap();
+        assert_eq!(ts!(other => "@{ms2_header}"), "// generated by other");
+        other
+            .merge(export_store(), ConflictPolicy::Overwrite)
+            .unwrap();
+        assert_eq!(ts!(other => "@{ms2_header}"), "// generated by library");
+
+        // the tiles are merged in the order of their names
+        let mut merged = TileStore::new();
+        merged.record_events(true);
+        merged
+            .merge(library.export(), ConflictPolicy::Error)
+            .unwrap();
+        let names: Vec<String> = merged
+            .events()
+            .iter()
+            .filter_map(|event| event.name.clone())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["ms2_footer", "ms2_header", "ms2_same", "ms2_tool"]
+        );
+    }
+
+    #[test]
+    fn test_freeze_tiles() {
+        tp!(fz_template, "Hello @{fz_name}");
+        tp!(fz_blank);
+        freeze_tiles();
+        tp!(fz_name, "Alice");
+        tp!(fz_blank, "filled");
+        tp!(fz_new, "new");
+        assert_eq!(ts!("@{fz_template} @{fz_blank}"), "Hello Alice filled");
+        assert!(with_default_store(|store| store.is_frozen("fz_template")));
+        assert!(!with_default_store(|store| store.is_frozen("fz_name")));
+
+        let result = std::panic::catch_unwind(|| {
+            stp!(fz_template, t!("Bye"));
+        });
+        assert!(result.is_err());
+        let result = std::panic::catch_unwind(|| remove_tile("fz_template"));
+        assert!(result.is_err());
+        assert_eq!(ts!("@{fz_template}"), "Hello Alice");
+
+        let mut store = TileStore::new();
+        tp!(store => fz_template, "store");
+        store.freeze();
+        let mut fork = store.fork();
+        assert!(fork.is_frozen("fz_template"));
+        assert_eq!(
+            fork.merge(export_store(), ConflictPolicy::Error),
+            Err(vec!["fz_template".to_string()])
+        );
+        // the frozen tile isn't overwritten, and none of the other tiles are merged
+        tp!(fork => fz_other, "fork");
+        assert_eq!(
+            fork.merge(export_store(), ConflictPolicy::Overwrite),
+            Err(vec!["fz_template".to_string()])
+        );
+        assert_eq!(ts!(fork => "@{fz_template} @{fz_other}"), "store fork");
+        assert!(!fork.contains("fz_name"));
+        // the frozen tile is kept, when it is skipped or identical
+        fork.merge(export_store(), ConflictPolicy::Skip).unwrap();
+        assert_eq!(ts!(fork => "@{fz_template} @{fz_name}"), "store Alice");
+        fork.merge(store.export(), ConflictPolicy::Overwrite)
+            .unwrap();
+        clear_tiles();
+        tp!(fz_template, "Bye");
+    }
+
+    #[test]
+    #[should_panic(expected = "the tile fz2_template is frozen")]
+    fn test_freeze_tiles_panics() {
+        tp!(fz2_template, "template");
+        freeze_tiles();
+        tp!(fz2_template, "overwritten");
+    }
+
+    #[test]
+    fn test_tiles_macro() {
+        let title = "Dr.";
+        tiles! {
+            tm_title: title,
+            tm_name: "@{tm_title} Who",
+            tm_lines: vec!["one", "two"],
+            tm_formatted: format!("{}-{}", 1, 2),
+        }
+        assert_eq!(ts!("@{tm_name} @{tm_formatted}"), "Dr. Who 1-2");
+        assert_eq!(ts!("@{tm_lines}"), "one\ntwo");
+
+        let mut store = TileStore::new();
+        tiles! { store =>
+            tm_name: "store",
+            tm_greet: "Hi @{tm_name}"
+        }
+        assert_eq!(ts!(store => "@{tm_greet}"), "Hi store");
+        assert_eq!(store.len(), 2);
+        tiles! {}
+    }
+
+    #[test]
+    fn test_inline_definitions() {
+        tp!(id_name, "Alice");
+        tp!(
+            id_list,
+            "
+            @{def row}| @{id_name} |@{end} @{def twice}@{row}@{row}@{end}
+            @{twice}
+            "
+        );
+        assert_eq!(
+            ts!("@{id_list}"),
+            "| Alice | | Alice || Alice |\n| Alice || Alice |"
+        );
+        assert!(gtp!(row).is_none());
+        assert!(!get_blank_tiles().contains("row"));
+        assert!(!get_blank_tiles().contains("twice"));
+        assert_eq!(
+            gtp!(id_list).unwrap().inner_tiles(),
+            ["id_name".to_string()].into_iter().collect()
+        );
+
+        // multi-line definitions expand as a block
+        let tile = t!("
+            @{def block}a
+            b@{end}
+            @{id_name}: @{block}
+            ");
+        assert_eq!(tile.to_string(), "a\nb\nAlice: a\n       b");
+
+        tp!(id_name, "Bob");
+        assert_eq!(ts!("@{id_list}"), "| Bob | | Bob || Bob |\n| Bob || Bob |");
+    }
+
+    #[test]
+    #[should_panic(expected = "unfinished @{def} expression")]
+    fn test_inline_definitions_unfinished() {
+        ts!("@{def row}@{id2_name}");
+    }
+
+    #[test]
+    fn test_placeholder_bindings() {
+        tp!(pb_greet, "Hello @{pb_name}");
+        tp!(pb_line, "@{pb_greet}, @{pb_name}!");
+        tp!(pb_alice, "Alice");
+        tp!(pb_bob, "Bob");
+        tp!(pb_name, "nobody");
+
+        assert_eq!(
+            ts!("@{pb_line with pb_name=pb_alice} / @{pb_line}"),
+            "Hello Alice, Alice! / Hello nobody, nobody!"
+        );
+        // the processed data of the inner tiles is not affected
+        assert_eq!(gtp!(pb_greet).unwrap().render_cached(), "Hello nobody");
+
+        // the bindings are forwarded to the outer bindings
+        tp!(pb_outer, "[@{pb_greet with pb_name=pb_who}]");
+        assert_eq!(ts!("@{pb_outer with pb_who=pb_bob}"), "[Hello Bob]");
+        tp!(pb_nested, "@{pb_outer with pb_who=pb_alice}");
+        assert_eq!(ts!("@{pb_nested}; @{pb_outer}"), "[Hello Alice]; [Hello]");
+
+        // a missing bound tile is created as a blank tile
+        assert_eq!(ts!("@{pb_greet with pb_name=pb_missing}"), "Hello");
+        assert!(get_blank_tiles().contains("pb_missing"));
+
+        // the options are still applied
+        tp!(pb_lines, "@{pb_name},\n@{pb_name})");
+        assert_eq!(
+            ts!("fn f(@{pb_lines with pb_name=pb_alice:align=2}"),
+            "fn f(Alice,\n  Alice)"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid binding")]
+    fn test_placeholder_bindings_invalid() {
+        ts!("@{pb2_greet with pb2_name}");
+    }
+
+    #[test]
+    fn test_operators_are_consistent() {
+        let left = t!("@{op_x}\nlong line");
+        let right = k!(" @{op_y} ");
+        let mut added = left.clone();
+        added += right.clone();
+        assert_eq!(added, left.clone() + right.clone());
+        let mut stacked = left.clone();
+        stacked |= right.clone();
+        assert_eq!(stacked, left.clone() | right.clone());
+
+        // the blank tiles are created when the tile is rendered, not when it is composed
+        remove_tile("op_y");
+        let tile = left + right;
+        assert!(!get_blank_tiles().contains("op_y"));
+        assert_eq!(tile.to_string(), "long line");
+        assert!(get_blank_tiles().contains("op_y"));
+    }
+
+    #[test]
+    fn test_add_with_fill() {
+        let toc = t!("Intro\nInstallation\nUsage").add_with_fill(t!("1\n4\n9\n10"), '.');
+        assert_eq!(
+            toc.to_string(),
+            "Intro.......1\nInstallation4\nUsage.......9\n............10"
+        );
+        let menu = t!("Tea\nCoffee").add_with_fill(k!(" -\n -"), '-') + t!("1.00\n2.50");
+        assert_eq!(menu.to_string(), "Tea--- -1.00\nCoffee -2.50");
+        assert_eq!(menu.name, None);
+        assert_eq!(t!("ä").add_with_fill(t!("x"), '·').to_string(), "äx");
+    }
+
+    #[test]
+    fn test_prepend_h_and_prepend_v() {
+        let mut row = tp!(pr_row, "c\nc");
+        row.prepend_h(t!("b")).prepend_h(t!("aaa\na"));
+        assert_eq!(
+            row.to_string(),
+            (t!("aaa\na") + t!("b") + t!("c\nc")).to_string()
+        );
+        assert_eq!(row.name, Some("pr_row".to_string()));
+
+        let mut column = t!("c");
+        column.prepend_v(t!("b")).prepend_v(t!("@{pr_row}"));
+        assert_eq!(column.to_string(), "c\nc\nb\nc");
+    }
+
+    #[test]
+    fn test_interleave() {
+        tp!(il_value, "1");
+        let code = t!("let a = @{il_value};\nlet b = a;\nlet c = b;");
+        let comments = t!("// one\n// copy");
+        let tile = RTile::interleave(&[code, comments], "//");
+        assert_eq!(
+            tile.to_string(),
+            "let a = 1;\n// one\nlet b = a;\n// copy\nlet c = b;\n//"
+        );
+        assert_eq!(RTile::interleave(&[], "").to_string(), "");
+        let tile = RTile::interleave(&[k!("a \nb "), t!("1\n2\n3"), t!("x")], "-");
+        assert_eq!(
+            tile.lns,
+            vec!["a ", "1", "x", "b ", "2", "-", "-", "3", "-"]
+        );
+        assert!(!tile.do_trimming);
+    }
+
+    #[test]
+    fn test_tagged_lines() {
+        let mut generated = t!();
+        generated
+            .push_line_tagged("fn a() {}", "generated")
+            .push_line_tagged("fn b() {}", "generated");
+        let mut tile = t!("// verbatim") | generated;
+        tile |= t!("// end");
+        tile.push_line_tagged("fn c() {}", "manual");
+        assert_eq!(
+            tile.lines_with_tags(),
+            vec![
+                ("// verbatim", None),
+                ("fn a() {}", Some("generated")),
+                ("fn b() {}", Some("generated")),
+                ("// end", None),
+                ("fn c() {}", Some("manual")),
+            ]
+        );
+        assert_eq!(
+            tile.to_string(),
+            "// verbatim\nfn a() {}\nfn b() {}\n// end\nfn c() {}"
+        );
+        // the tags aren't a part of the comparison of the tiles
+        assert_eq!(
+            tile,
+            t!("// verbatim\nfn a() {}\nfn b() {}\n// end\nfn c() {}")
+        );
+
+        let mut side = t!("x\ny");
+        side.push_line_tagged("z", "left");
+        let mut right = t!("1");
+        right.push_line_tagged("2", "right");
+        right.push_line_tagged("3", "right");
+        let combined = side.clone() + right.clone();
+        assert_eq!(
+            combined
+                .lines_with_tags()
+                .into_iter()
+                .map(|(_, tag)| tag)
+                .collect::<Vec<_>>(),
+            vec![None, Some("right"), Some("left")]
+        );
+        let mut upper = right;
+        upper.prepend_v(side);
+        assert_eq!(upper.lines_with_tags()[2], ("z", Some("left")));
+        assert_eq!(upper.lines_with_tags()[4], ("2", Some("right")));
+    }
+
+    #[test]
+    fn test_three_way_merge() {
+        use rtile::merge::three_way_merge;
+
+        // the user changed b and removed d, the regeneration changed f and appended g
+        let generated_old = k!("a\nb\nc\nd\ne\nf");
+        let user_edited = k!("a\nB\nc\ne\nf");
+        let regenerated = k!("a\nb\nc\nd\ne\nF\ng");
+        let outcome = three_way_merge(&regenerated, &generated_old, &user_edited);
+        assert!(outcome.is_clean());
+        assert_eq!(outcome.merged, vec!["a", "B", "c", "e", "F", "g"]);
+        assert_eq!(outcome.to_tile().to_string(), "a\nB\nc\ne\nF\ng");
+
+        let generated_old = k!("a\nb\nc\nd");
+        let user_edited = k!("a\nB\nc");
+
+        // both changed b differently
+        let regenerated = k!("a\nβ\nc\nd");
+        let outcome = three_way_merge(&regenerated, &generated_old, &user_edited);
+        assert!(!outcome.is_clean());
+        assert_eq!(
+            outcome.merged,
+            vec![
+                "a",
+                "<<<<<<< user",
+                "B",
+                "=======",
+                "β",
+                ">>>>>>> generated",
+                "c"
+            ]
+        );
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].line, 1);
+        assert_eq!(outcome.conflicts[0].user, vec!["B"]);
+        assert_eq!(outcome.conflicts[0].generated, vec!["β"]);
+
+        // the same change on both sides is not a conflict
+        let outcome = three_way_merge(&user_edited, &generated_old, &user_edited);
+        assert!(outcome.is_clean());
+        assert_eq!(outcome.merged, vec!["a", "B", "c"]);
+
+        let outcome = three_way_merge(&k!(""), &k!(""), &k!("x"));
+        assert_eq!(outcome.merged, vec!["x"]);
+
+        // the trailing white spaces are ignored by the comparison, but the lines of the user keep them
+        let generated_old = k!("a\nb\nc");
+        let user_edited = k!("a  \nb\t\nc");
+        let regenerated = k!("a\nb\nc\nd");
+        let outcome = three_way_merge(&regenerated, &generated_old, &user_edited);
+        assert!(outcome.is_clean());
+        assert_eq!(outcome.merged, vec!["a  ", "b\t ", "c  ", "d"]);
+
+        // the large outputs are matched without a quadratic table
+        let generated_old =
+            RTile::new_without_trimming((0..20000).map(|i| i.to_string()).collect());
+        let mut lns: Vec<String> = (0..20000).map(|i| i.to_string()).collect();
+        lns[10000] = "edited".to_string();
+        let user_edited = RTile::new_without_trimming(lns);
+        let mut lns: Vec<String> = (0..20000).map(|i| i.to_string()).collect();
+        lns.insert(0, "header".to_string());
+        let regenerated = RTile::new_without_trimming(lns);
+        let outcome = three_way_merge(&regenerated, &generated_old, &user_edited);
+        assert!(outcome.is_clean());
+        assert_eq!(outcome.merged.len(), 20001);
+        assert_eq!(outcome.merged[10001], "edited");
+    }
+
+    #[cfg(feature = "mustache")]
+    #[test]
+    fn test_mustache_placeholders() {
+        tp!(mu_name, "Alice");
+        tp!(mu_items, "one\ntwo");
+        let tile = t!("{{!comment}}{{ mu_name }}: {{{mu_items}}} @{mu_name}");
+        assert_eq!(tile.to_string(), "Alice: one Alice\n       two");
+        assert!(tile.has_inner_tiles_in_raw_data());
+        assert_eq!(
+            tile.inner_tiles_in_raw_data(),
+            vec![vec!["mu_name", "mu_items", "mu_name"]]
+        );
+        tp!(mu_blank);
+        let tile = t!("a\n  {{mu_blank:skip_if_blank}}\nb");
+        assert_eq!(tile.to_string(), "a\nb");
+
+        // a `{{` without its `}}` is a text, e.g. the nested blocks of the generated code
+        assert_eq!(ts!("fn f() {{ @{mu_name}"), "fn f() {{ Alice");
+        assert_eq!(
+            ts!("if (a) {{\n  {{mu_name}}\n}}"),
+            "if (a) {{\n  Alice\n}}"
+        );
+        // only the names, the comments and `skip_if_blank` are the mustache placeholders, any other `{{...}}` is a text
+        assert_eq!(ts!("println!(\"{{}}\")"), "println!(\"{{}}\")");
+        assert_eq!(ts!("struct A {{ a: u8 }}"), "struct A {{ a: u8 }}");
+        assert_eq!(
+            ts!("{{ a: u8 }} {{mu_name:align=2}} {{{ }}} {{mu_name}}"),
+            "{{ a: u8 }} {{mu_name:align=2}} {{{ }}} Alice"
+        );
+    }
+
+    #[test]
+    fn test_highlight_placeholders() {
+        let tile = t!("
+            fn @{hl_name}() -> Vec<@{hl_type:align=2}> {
+                @{hl_body}
+            }
+            ");
+        assert_eq!(
+            tile.highlight_placeholders(HighlightStyle::Ansi),
+            "fn \x1b[36m@{hl_name}\x1b[0m() -> Vec<\x1b[36m@{hl_type:align=2}\x1b[0m> {\n    \x1b[36m@{hl_body}\x1b[0m\n}"
+        );
+        assert_eq!(
+            tile.highlight_placeholders(HighlightStyle::Html),
+            "fn <span class=\"rtile-placeholder\">@{hl_name}</span>() -&gt; Vec&lt;<span class=\"rtile-placeholder\">@{hl_type:align=2}</span>&gt; {\n    <span class=\"rtile-placeholder\">@{hl_body}</span>\n}"
+        );
+        assert_eq!(
+            t!("a & b").highlight_placeholders(HighlightStyle::Html),
+            "a &amp; b"
+        );
+    }
+
+    #[test]
+    fn test_bake() {
+        tp!(bk_lang, "rust");
+        tp!(bk_version, "@{bk_major}.@{bk_minor}");
+        tp!(bk_major, "1");
+        tp!(bk_lines, "a,\nb");
+        let tile = t!("@{bk_lang} @{bk_version} @{bk_name} [@{bk_lines:align=1}]");
+        let baked = tile.bake(&["bk_lang", "bk_version", "bk_lines"]);
+        assert_eq!(baked.raw(), "rust 1. @{bk_name} [a,]\n b");
+        assert_eq!(baked.name, None);
+
+        // the baked tile can be persisted and reused with the remaining slots
+        stp!(bk_baked, baked);
+        tp!(bk_lang, "go");
+        tp!(bk_name, "x");
+        assert_eq!(ts!("@{bk_baked}"), "rust 1. x [a,]\n b");
+        assert_eq!(ts!("@{bk_version}"), "1.");
+        assert_eq!(tile.bake(&[]).raw(), tile.raw());
+    }
+
+    #[test]
+    fn test_declare_and_check_inputs() {
+        tp!(
+            ci_greet,
+            "@{def who}@{ci_name}@{end} @{ci_salutation} @{who} @{ci_name}"
+        );
+        assert_eq!(
+            check_inputs("ci_greet"),
+            InputReport {
+                extra: vec!["ci_name".to_string(), "ci_salutation".to_string()],
+                ..Default::default()
+            }
+        );
+        declare_inputs(
+            "ci_greet",
+            vec!["ci_name".to_string(), "ci_salutation".to_string()],
+        );
+        let report = check_inputs("ci_greet");
+        assert_eq!(report.unfilled, vec!["ci_name", "ci_salutation"]);
+        assert!(report.extra.is_empty() && report.unused.is_empty());
+        tiles! {
+            ci_name: "Alice",
+            ci_salutation: "Hi",
+        }
+        assert!(check_inputs("ci_greet").is_ok());
+
+        let mut store = TileStore::new();
+        store.declare_inputs("ci_greet", ["ci_other"]);
+        assert_eq!(store.check_inputs("ci_greet").unfilled, vec!["ci_other"]);
+        assert_eq!(store.check_inputs("ci_greet").unused, vec!["ci_other"]);
+        assert!(store.fork().check_inputs("ci_greet").unused == vec!["ci_other"]);
+    }
+
+    #[test]
+    fn test_optional_tiles() {
+        let present: Option<RTile> = Some(t!("present"));
+        let absent: Option<RTile> = None;
+        assert_eq!((t!("a") | t!(absent) | t!("b")).to_string(), "a\nb");
+        assert_eq!(
+            (t!("a") | t!(&present) | t!("b")).to_string(),
+            "a\npresent\nb"
+        );
+        assert_eq!((k!("a ") + k!(absent) + k!("b")).to_string(), "a b");
+
+        let items = [Some(t!("x")), None, Some(t!("y")), None];
+        assert_eq!(k!(", ").join_some(items.clone(), None).to_string(), "x, y");
+        assert_eq!(
+            t!(",")
+                .vjoin_some(items.iter().cloned(), true, Some(t!(";")))
+                .to_string(),
+            "x,\ny;"
+        );
+        // the tiles themselves are taken as well
+        assert_eq!(
+            k!(", ").join_some(vec![t!("x"), t!("y")], None).to_string(),
+            "x, y"
+        );
+
+        assert_eq!(t!(absent).or(t!("fallback")).to_string(), "fallback");
+        assert_eq!(t!("").or(t!("fallback")).to_string(), "fallback");
+        assert_eq!(t!(present).or(t!("fallback")).to_string(), "present");
+    }
+
+    #[test]
+    fn test_from_numbers() {
+        let salaries = RTile::from_numbers([7500, 125000, 48250], NumFormat::default());
+        assert_eq!(salaries.to_string(), "  7500\n125000\n 48250");
+
+        let format = NumFormat {
+            width: 12,
+            thousands_sep: Some(','),
+            ..Default::default()
+        };
+        let balances = RTile::from_numbers([-1234567.5, 12.25, 0.125], format);
+        assert_eq!(
+            balances.to_string(),
+            "-1,234,567.5  \n        12.25 \n         0.125"
+        );
+
+        let format = NumFormat {
+            width: 8,
+            precision: Some(1),
+            align_right: false,
+            ..Default::default()
+        };
+        let column = RTile::from_numbers([2.46_f32, 10.0], format);
+        assert_eq!(column.to_string(), " 2.5    \n10.0    ");
+        assert_eq!(
+            (k!("name ") + column + k!("|\n|")).to_string(),
+            "name  2.5    |\n     10.0    |"
+        );
+        assert_eq!(
+            RTile::from_numbers(Vec::<f64>::new(), format).to_string(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_with_ruler() {
+        assert_eq!(t!("ab\ncd").with_ruler(), "   12\n1 |ab\n2 |cd");
+
+        let left = k!("name:\nage:");
+        let right = k!("Alice\n42");
+        let tile = left + k!(" ") + right;
+        assert_eq!(
+            tile.with_ruler(),
+            "            1\n   12345678901\n1 |name: Alice\n2 |age:  42   "
+        );
+
+        let tile = t!("{}", "x".repeat(12));
+        assert_eq!(
+            tile.with_ruler(),
+            "            1\n   123456789012\n1 |xxxxxxxxxxxx"
+        );
+    }
+
+    #[test]
+    fn test_link() {
+        let url = "https://docs.rs/rtile";
+        let open = format!("\x1b]8;;{}\x1b\\", url);
+        let close = "\x1b]8;;\x1b\\";
+
+        let tile = t!("docs").link(url);
+        assert_eq!(tile.to_string(), format!("{}docs{}", open, close));
+        assert_eq!(tile.dimensions(), (4, 1));
+
+        let tile = t!("
+                one
+                  two
+            ")
+        .link(url);
+        assert_eq!(
+            tile.to_string(),
+            format!("{open}one{close}\n  {open}two{close}")
+        );
+
+        let report = k!("crate: ") + t!("rtile\nrtile-extra").link(url) + k!(" |\n |");
+        assert_eq!(
+            report.to_string(),
+            format!("crate: {open}rtile{close}       |\n       {open}rtile-extra{close} |")
+        );
+
+        tp!(link_test_one, "{}", t!("a").link(url));
+        let tile = k!("@{link_test_one}  ") + k!("b");
+        assert_eq!(tile.to_string(), format!("{open}a{close}  b"));
+    }
+
+    #[test]
+    fn test_render_options_max_width() {
+        let options = RenderOptions {
+            max_width: Some(16),
+            ..Default::default()
+        };
+        tp!(max_width_test_items, "- first item of the list\n- second");
+        let tile = t!("
+            Summary:
+                @{max_width_test_items}
+            Signed-off-by: a-very-long-name-without-spaces
+        ");
+        let output = tile.to_string_with_options(&options);
+        assert_eq!(
+            output,
+            "Summary:\n    - first item\n    of the list\n    - second\nSigned-off-by:\na-very-long-name\n-without-spaces"
+        );
+        assert!(output.lines().all(|ln| ln.chars().count() <= 16));
+
+        let tile = k!("left column ") + k!("right column");
+        assert_eq!(
+            tile.to_string_with_options(&RenderOptions {
+                max_width: Some(10),
+                ..Default::default()
+            }),
+            "left  \ncolumn\nright \ncolumn"
+        );
+        assert_eq!(tile.to_string(), "left column right column");
+
+        // the lines are cut by the columns shown, the escapes of the links and the colors are never broken
+        let options = RenderOptions {
+            max_width: Some(10),
+            ..Default::default()
+        };
+        let tile = t!("see the docs of the crate").link("https://docs.rs/rtile");
+        assert_eq!(
+            tile.to_string_with_options(&options),
+            "\x1b]8;;https://docs.rs/rtile\x1b\\see the\ndocs of\nthe crate\x1b]8;;\x1b\\"
+        );
+        let tile = t!("\x1b[36mabcdefghijkl\x1b[0m");
+        assert_eq!(
+            tile.to_string_with_options(&options),
+            "\x1b[36mabcdefghij\nkl\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_rendered_dimensions() {
+        tp!(rendered_dimensions_test_name, "Alice\nBob");
+        let tile = t!("name: @{rendered_dimensions_test_name}");
+        assert_eq!(tile.dimensions(), (38, 1));
+        assert_eq!(tile.rendered_dimensions(), (11, 2));
+
+        let tile = t!("@{rendered_dimensions_test_missing} and more");
+        assert_eq!(tile.rendered_dimensions(), (8, 1));
+        assert_eq!(t!().rendered_dimensions(), (0, 0));
+    }
+
+    #[test]
+    fn test_render_cached_and_render_fresh() {
+        tp!(rcf_name, "Alice");
+        tp!(rcf_greet, "Hello @{rcf_name}");
+        let tile = t!("@{rcf_greet}!");
+        assert_eq!(tile.render_cached(), "Hello Alice!");
+
+        stp!(rcf_name, t!("Bob"));
+        assert_eq!(tile.render_cached(), "Hello Alice!");
+        assert_eq!(tile.render_fresh(), "Hello Bob!");
+        assert_eq!(tile.reevaluate(), "Hello Bob!");
+        assert_eq!(tile.render_fresh(), tile.to_string());
+        // a render never changes the processed data, a refresh does
+        assert_eq!(tile.render_cached(), "Hello Alice!");
+        refresh_tiles();
+        assert_eq!(tile.render_cached(), "Hello Bob!");
+
+        let mut store = TileStore::new();
+        tp!(store => rcf_name, "Carol");
+        tp!(store => rcf_greet, "Hi @{rcf_name}");
+        tp!(store => rcf_name, "Dave");
+        assert_eq!(store.render_cached(&t!("@{rcf_greet}")), "Hi Carol");
+        assert_eq!(store.render(&t!("@{rcf_greet}")), "Hi Dave");
+        assert_eq!(store.render_cached(&t!("@{rcf_greet}")), "Hi Carol");
+        store.refresh();
+        assert_eq!(store.render_cached(&t!("@{rcf_greet}")), "Hi Dave");
+    }
+
+    #[test]
+    fn test_owned_tile() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<OwnedTile>();
+
+        tp!(ot_name, "Alice");
+        tp!(ot_other, "Carol");
+        tp!(ot_greet, "Hello @{ot_name}!");
+        tp!(ot_unrelated, "unrelated");
+        let owned = OwnedTile::new(&t!("@{ot_greet}\n@{ot_greet with ot_name=ot_other}"));
+        assert_eq!(
+            owned.dependencies(),
+            vec!["ot_greet", "ot_other", "ot_name"]
+        );
+
+        tp!(ot_name, "Bob");
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let owned = owned.clone();
+                std::thread::spawn(move || owned.render())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), "Hello Alice!\nHello Carol!");
+        }
+        assert_eq!(owned.to_string(), "Hello Alice!\nHello Carol!");
+
+        let mut store = TileStore::new();
+        kp!(store => ot_cell, "  x  ");
+        let owned = OwnedTile::from_store(&mut store, &k!("[@{ot_cell}]"));
+        assert_eq!(owned.render(), "[  x  ]");
+
+        // the dynamic tiles are captured with their values, and the tiles found through a prefix under their names as written
+        store.set_dynamic("ot_build", || t!("build @{ot_number}"));
+        tp!(store => ot_number, "7");
+        let owned = OwnedTile::from_store(&mut store, &t!("@{ot_build}"));
+        assert_eq!(
+            std::thread::spawn(move || owned.render()).join().unwrap(),
+            "build 7"
+        );
+        tp!(ot_title, "Company");
+        let owned = with_prefix("ot_payroll_", || {
+            tp!(ot_title, "ACME Inc");
+            tp!(ot_selector, "ot_title");
+            OwnedTile::new(&t!("@{ot_title} / @{@{ot_selector}}"))
+        });
+        assert_eq!(
+            owned.dependencies(),
+            vec![
+                "ot_payroll_ot_title",
+                "ot_title",
+                "ot_payroll_ot_selector",
+                "ot_selector"
+            ]
+        );
+        assert_eq!(
+            std::thread::spawn(move || owned.render()).join().unwrap(),
+            "ACME Inc / ACME Inc"
+        );
+    }
+
+    #[test]
+    fn test_template_case() {
+        use rtile::testing::TemplateCase;
+
+        let dir = std::env::temp_dir().join(format!("rtile_template_case_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("letter.rtile"),
+            "@{salutation} @{name},\n    @{body}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("letter.fixture"),
+            "--- salutation\nDear\n--- name\nAlice\n--- body\nline one\nline two\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("letter.expected"),
+            "Dear Alice,\n    line one\n    line two\n",
+        )
+        .unwrap();
+        let case = TemplateCase::from_files(
+            dir.join("letter.rtile"),
+            dir.join("letter.fixture"),
+            dir.join("letter.expected"),
+        )
+        .unwrap();
+        case.assert();
+
+        let failure = case.clone().input("name", "Bob").run().unwrap_err();
+        assert_eq!(failure.first_difference(), 1);
+        assert_eq!(failure.actual, "Dear Bob,\n    line one\n    line two");
+        assert!(failure.to_string().contains(
+            "failed, the first difference is at line 1\n   1 - Dear Alice,\n   1 + Dear Bob,\n   2       line one\n"
+        ));
+        // the tls (thread local storage) is not used
+        assert_eq!(gtp!(salutation), None);
+
+        assert!(TemplateCase::from_files(
+            dir.join("missing"),
+            dir.join("missing"),
+            dir.join("missing")
+        )
+        .is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_slot_coverage() {
+        use rtile::testing::*;
+
+        start_slot_coverage();
+        tp!(sc_header, "@{sc_title}\n@{sc_subtitle}");
+        tp!(sc_title, "Report");
+        tp!(sc_subtitle);
+        tp!(
+            sc_page,
+            "@{sc_header}\n@{sc_body:skip_if_blank}\n@{def sc_local}x@{end}@{sc_local}"
+        );
+        assert_eq!(
+            slot_coverage().slots("sc_page"),
+            vec![("sc_body", false), ("sc_header", true)]
+        );
+        tp!(sc_body, "text");
+        tp!(sc_page, "@{sc_header}\n@{sc_body:skip_if_blank}");
+        let coverage = stop_slot_coverage();
+
+        assert_eq!(
+            coverage.slots("sc_page"),
+            vec![("sc_body", true), ("sc_header", true)]
+        );
+        assert_eq!(
+            coverage.slots("sc_header"),
+            vec![("sc_subtitle", false), ("sc_title", true)]
+        );
+        assert!(coverage
+            .dead_slots()
+            .contains(&("sc_header", "sc_subtitle")));
+        assert!(!coverage
+            .dead_slots()
+            .iter()
+            .any(|(template, _)| *template == "sc_page"));
+        assert!(coverage
+            .to_string()
+            .contains("sc_header: 1/2 slots filled\n    dead slot: sc_subtitle\n"));
+
+        // nothing is recorded once stopped
+        tp!(sc_after, "@{sc_title}");
+        assert!(slot_coverage().slots("sc_after").is_empty());
+    }
+
+    #[test]
+    fn test_assert_rendered_tree_eq() {
+        use rtile::testing::compare_rendered_tree;
+        use std::collections::HashMap;
+
+        let golden = std::env::temp_dir().join(format!("rtile_golden_tree_{}", std::process::id()));
+        std::fs::create_dir_all(golden.join("src/model")).unwrap();
+        std::fs::write(golden.join("Cargo.toml"), "[package]\nname = \"gen\"\n").unwrap();
+        std::fs::write(
+            golden.join("src/model/person.rs"),
+            "pub struct Person {\n    name: String,\n}\n",
+        )
+        .unwrap();
+        std::fs::write(golden.join("src/model/old.rs"), "").unwrap();
+
+        tp!(art_name, "name");
+        let mut rendered: HashMap<String, String> = HashMap::new();
+        rendered.insert("Cargo.toml".to_string(), ts!("[package]\nname = \"gen\""));
+        rendered.insert(
+            "src/model/person.rs".to_string(),
+            ts!("pub struct Person {\n    @{art_name}: String,\n}"),
+        );
+        rendered.insert("src/model/old.rs".to_string(), String::new());
+        assert_rendered_tree_eq!(&golden, &rendered);
+
+        tp!(art_name, "full_name");
+        rendered.insert(
+            "src/model/person.rs".to_string(),
+            ts!("pub struct Person {\n    @{art_name}: String,\n}"),
+        );
+        rendered.remove("src/model/old.rs");
+        rendered.insert("src/lib.rs".to_string(), "pub mod model;".to_string());
+        let mismatch = compare_rendered_tree(&golden, &rendered).unwrap_err();
+        assert_eq!(mismatch.not_in_golden, vec!["src/lib.rs"]);
+        assert_eq!(mismatch.not_rendered, vec!["src/model/old.rs"]);
+        assert_eq!(mismatch.different.len(), 1);
+        assert!(mismatch.to_string().contains(
+            "differs: src/model/person.rs, the first difference is at line 2\n   1   pub struct Person {\n   2 -     name: String,\n   2 +     full_name: String,\n   3   }\n"
+        ));
+
+        let panic = std::panic::catch_unwind(|| assert_rendered_tree_eq!(&golden, &rendered));
+        assert!(panic.is_err());
+        std::fs::remove_dir_all(&golden).unwrap();
+    }
+
+    #[test]
+    fn test_emitter() {
+        use rtile::emit::{EmitReport, EmitStatus, Emitter};
+
+        let root = std::env::temp_dir().join(format!("rtile_emitter_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let mut emitter = Emitter::new(&root).with_manifest("gen/manifest.txt");
+        assert_eq!(emitter.emit("a.rs", t!("a")).unwrap(), EmitStatus::Created);
+        assert_eq!(
+            emitter.emit("m/b.rs", t!("b")).unwrap(),
+            EmitStatus::Created
+        );
+        assert_eq!(
+            emitter.emit("m/c.rs", k!("c ")).unwrap(),
+            EmitStatus::Created
+        );
+        assert_eq!(emitter.emitted(), ["a.rs", "m/b.rs", "m/c.rs"]);
+        assert_eq!(
+            emitter.finish().unwrap(),
+            EmitReport {
+                created: 3,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("gen/manifest.txt")).unwrap(),
+            "a.rs\nm/b.rs\nm/c.rs\n"
+        );
+
+        std::fs::write(root.join("user.rs"), "not generated").unwrap();
+        let mut emitter = Emitter::new(&root).with_manifest("gen/manifest.txt");
+        assert_eq!(
+            emitter.emit("a.rs", t!("a")).unwrap(),
+            EmitStatus::Unchanged
+        );
+        assert_eq!(
+            emitter.emit("m/b.rs", t!("b2")).unwrap(),
+            EmitStatus::Updated
+        );
+        assert_eq!(emitter.emit("d.rs", "d").unwrap(), EmitStatus::Created);
+        assert_eq!(
+            emitter.finish().unwrap(),
+            EmitReport {
+                created: 1,
+                updated: 1,
+                unchanged: 1,
+                deleted: vec!["m/c.rs".to_string()],
+            }
+        );
+        assert_eq!(std::fs::read_to_string(root.join("m/b.rs")).unwrap(), "b2");
+        assert!(!root.join("m/c.rs").exists());
+        assert!(root.join("user.rs").exists());
+
+        // the paths outside the root are neither written nor deleted, even when they are listed in the manifest
+        let outside =
+            std::env::temp_dir().join(format!("rtile_emitter_outside_{}", std::process::id()));
+        std::fs::write(&outside, "precious").unwrap();
+        let mut emitter = Emitter::new(&root).with_manifest("gen/manifest.txt");
+        for path in ["../escape.rs".to_string(), outside.display().to_string()] {
+            assert_eq!(
+                emitter.emit(&path, "x").unwrap_err().kind(),
+                std::io::ErrorKind::InvalidInput
+            );
+        }
+        assert!(emitter.preview([("../escape.rs", "x")]).is_err());
+        std::fs::write(
+            root.join("gen/manifest.txt"),
+            format!(
+                "a.rs\n../{}\n{}\n",
+                outside.file_name().unwrap().to_string_lossy(),
+                outside.display()
+            ),
+        )
+        .unwrap();
+        let report = emitter.finish().unwrap();
+        assert_eq!(report.deleted, vec!["a.rs".to_string()]);
+        assert_eq!(std::fs::read_to_string(&outside).unwrap(), "precious");
+        std::fs::remove_file(&outside).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_cli_generation() {
+        use rtile::cli::*;
+
+        let tool = Command::new("my-tool", "Renders the templates")
+            .option(
+                CliOption::new("quiet")
+                    .short('q')
+                    .help("Don't print [anything]"),
+            )
+            .option(CliOption::new("jobs").value("N").help("Use N threads"))
+            .subcommand(
+                Command::new("render", "Renders a template")
+                    .option(CliOption::new("out").short('o').value("FILE")),
+            )
+            .subcommand(Command::new("list", "Lists the templates"));
+
+        assert_eq!(
+            help(&tool),
+            "Renders the templates\n\nUsage: my-tool [OPTIONS] [COMMAND]\n\nOptions:\n  -q, --quiet     Don't print [anything]\n      --jobs <N>  Use N threads\n\nCommands:\n  render  Renders a template\n  list    Lists the templates"
+        );
+        assert_eq!(
+            help(&tool.subcommands[1]),
+            "Lists the templates\n\nUsage: list"
+        );
+
+        let bash = bash_completion(&tool);
+        assert!(bash.starts_with("_my_tool() {\n"));
+        assert!(bash.contains("render) words=\"-o --out\" ;;\n            list) words=\"\" ;;"));
+        assert!(bash.ends_with("complete -F _my_tool my-tool"));
+
+        let zsh = zsh_completion(&tool);
+        assert!(zsh.contains("'(-q --quiet)'{-q,--quiet}'[Don'\\''t print \\[anything\\]]' \\\n"));
+        assert!(zsh.contains("    '--jobs[Use N threads]:N:_files' \\\n"));
+        assert!(zsh.ends_with("'*::arg:->args'"));
+
+        let fish = fish_completion(&tool);
+        assert!(fish.contains("complete -c my-tool -l jobs -r -d 'Use N threads'"));
+        assert!(fish.contains(
+            "complete -c my-tool -n '__fish_seen_subcommand_from render' -s o -l out -r"
+        ));
+
+        let man = man_page(&tool, 1);
+        assert!(man.starts_with(".TH MY\\-TOOL 1\n.SH NAME\nmy\\-tool \\- Renders the templates\n"));
+        assert!(man.ends_with(".SH COMMANDS\n.TP\n\\fBrender\\fR\nRenders a template\n.TP\n\\fBlist\\fR\nLists the templates"));
+    }
+
+    #[test]
+    fn test_build_system_tiles() {
+        use rtile::build::*;
+
+        let rule = MakeRule::new("out/app")
+            .target("out/app.map")
+            .prerequisite("main.o")
+            .order_only("out")
+            .recipe("$(CC) -o $@ $^")
+            .recipe("@echo built");
+        tp!(
+            bst_rules,
+            "{}",
+            rule.to_tile()
+                | MakeRule::new("all")
+                    .phony()
+                    .prerequisite("out/app")
+                    .to_tile()
+        );
+        tp!(bst_vars, "{}", make_variable("CC", "gcc"));
+        let makefile = t!("
+            # generated
+            @{bst_vars}
+
+            @{bst_rules}
+        ");
+        assert_eq!(
+            makefile.to_string(),
+            "# generated\nCC := gcc\n\nout/app out/app.map: main.o | out\n\t$(CC) -o $@ $^\n\t@echo built\n.PHONY: all\nall: out/app"
+        );
+
+        let lib = CMakeTarget::new("core", CMakeTargetKind::InterfaceLibrary)
+            .include_directory("include")
+            .compile_definition("CORE_HEADER_ONLY=1");
+        assert_eq!(
+            lib.to_tile().to_string(),
+            "add_library(core INTERFACE)\ntarget_include_directories(core INTERFACE include)\ntarget_compile_definitions(core INTERFACE CORE_HEADER_ONLY=1)"
+        );
+        let shared =
+            CMakeTarget::new("plugin", CMakeTargetKind::SharedLibrary).sources(["plugin.c"]);
+        assert_eq!(
+            shared.to_tile().to_string(),
+            "add_library(plugin SHARED\n    plugin.c\n)"
+        );
+    }
+
+    #[test]
+    fn test_proto_builders() {
+        use rtile::proto::*;
+
+        assert_eq!(
+            reserved_numbers(&[
+                (5, Some(5)),
+                (1, Some(3)),
+                (4, Some(4)),
+                (10, None),
+                (20, Some(30))
+            ]),
+            Some("reserved 1 to 5, 10 to max;".to_string())
+        );
+        assert_eq!(
+            reserved_numbers(&[(7, Some(7)), (7, Some(7))]),
+            Some("reserved 7;".to_string())
+        );
+
+        let status = ProtoEnum::new("Status")
+            .value("STATUS_UNSPECIFIED", 0)
+            .reserved_range(2, Some(4))
+            .reserved_name("STATUS_OLD");
+        let address = ProtoMessage::new("Address").field(ProtoField::new("city", "string", 1));
+        let file = ProtoFile::new("acme.v1").proto_enum(status).message(
+            ProtoMessage::new("Person")
+                .nested_message(address)
+                .field(ProtoField::new("nickname", "string", 2).label(FieldLabel::Optional))
+                .field(ProtoField::new("tags", "map<string, string>", 4))
+                .reserved_range(100, None),
+        );
+        assert_eq!(
+            file.to_tile().to_string(),
+            "syntax = \"proto3\";
+
+package acme.v1;
+
+enum Status {
+  reserved 2 to 4;
+  reserved \"STATUS_OLD\";
+  STATUS_UNSPECIFIED = 0;
+}
+
+message Person {
+  message Address {
+    string city = 1;
+  }
+  reserved 100 to max;
+  optional string nickname = 2;
+  map<string, string> tags = 4;
+}"
+        );
+    }
+
+    #[cfg(feature = "scaffold")]
+    #[test]
+    fn test_scaffold() {
+        use rtile::scaffold::*;
+
+        let spec = ApiSpec {
+            name: "users".to_string(),
+            routes: vec![
+                Route::new(Method::Get, "/users/{id}", "get_user", "Returns the user"),
+                Route::new(
+                    Method::Delete,
+                    "/users/{id}",
+                    "delete_user",
+                    "Deletes the user",
+                ),
+            ],
+        };
+        let mut scaffold = scaffold(&spec);
+        let files = scaffold.files();
+        assert_eq!(
+            files["src/router.rs"],
+            "// generated by the rtile scaffold of the users api
+
+pub fn register(router: &mut Router) {
+    router.route(\"GET\", \"/users/{id}\", handlers::get_user);
+    router.route(\"DELETE\", \"/users/{id}\", handlers::delete_user);
+}"
+        );
+        assert_eq!(
+            files["src/handlers.rs"],
+            "// generated by the rtile scaffold of the users api
+
+/// Returns the user
+///
+/// GET /users/{id}
+pub async fn get_user(request: Request) -> Response {
+    todo!(\"get_user\")
+}
+
+/// Deletes the user
+///
+/// DELETE /users/{id}
+pub async fn delete_user(request: Request) -> Response {
+    todo!(\"delete_user\")
+}"
+        );
+        assert!(
+            files["tests/api.rs"].contains("fn test_delete_user() {\n    // DELETE /users/{id}\n")
+        );
+        assert_eq!(scaffold.tile("get_user_method").unwrap().raw(), "GET");
+        // the tls (thread local storage) is not used
+        assert!(gtp!(get_user_handler).is_none());
+    }
+
+    #[test]
+    fn test_indent_style() {
+        tp!(go_body, "if err != nil {\n\treturn err\n}");
+        let tile = t!("
+            func run() error {
+                @{go_body}
+                return nil
+            }
+        ");
+        let options = RenderOptions {
+            indent_style: IndentStyle::for_language("Go"),
+            ..Default::default()
+        };
+        assert_eq!(
+            tile.to_string_with_options(&options),
+            "func run() error {\n\tif err != nil {\n\t\treturn err\n\t}\n\treturn nil\n}"
+        );
+
+        // the columns left over by the unit are kept as spaces
+        let tile = t!("
+            fn f() {
+                call(a,
+                      b);
+            }
+        ");
+        let options = RenderOptions {
+            indent_style: Some(IndentStyle::spaces(2)),
+            ..Default::default()
+        };
+        assert_eq!(
+            tile.to_string_with_options(&options),
+            "fn f() {\n  call(a,\n      b);\n}"
+        );
+        let tile = t!("
+            items:
+              - a
+                 b
+        ");
+        assert_eq!(
+            tile.to_string_with_options(&options),
+            "items:\n  - a\n     b"
+        );
+        assert_eq!(IndentStyle::tabs().unit(), "\t");
+        assert_eq!(IndentStyle::for_language("cobol"), None);
+    }
+
+    #[test]
+    fn test_check_indentation_consistency() {
+        tp!(
+            steps,
+            "
+            - run: build
+            - run: test
+        "
+        );
+        let tile = t!("
+            jobs:
+              ci:
+                steps:
+                  @{steps}
+              # comment lines are skipped
+                 bad: 1
+        ");
+        let output = tile.to_string();
+        assert_eq!(
+            check_indentation_consistency(&output),
+            vec![IndentationIssue {
+                line: 7,
+                kind: IndentationIssueKind::UnexpectedDedent
+            }]
+        );
+
+        let output = "if a:\n\tb()\nif c:\n    d()";
+        let issues = check_indentation_consistency(output);
+        assert_eq!(
+            issues,
+            vec![IndentationIssue {
+                line: 4,
+                kind: IndentationIssueKind::MixedTabsAndSpaces
+            }]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "line 4: mixed tabs and spaces in the indentation"
+        );
+
+        let options = RenderOptions {
+            indent_style: Some(IndentStyle::Spaces(4)),
+            ..Default::default()
+        };
+        let tile = t!("
+            def f():
+            \treturn 1
+            def g():
+              return 2
+        ");
+        assert!(check_indentation_consistency(&tile.to_string_with_options(&options)).is_empty());
+    }
+
+    #[test]
+    fn test_stable_ordering() {
+        tp!(full_name, "@{first} @{last}");
+        let tile = t!("@{greeting}, @{full_name}! @{greeting}");
+        assert_eq!(
+            tile.inner_tiles_in_order(),
+            vec!["greeting", "full_name", "first", "last"]
+        );
+        let blank_tiles: Vec<String> = get_blank_tiles_sorted().into_iter().collect();
+        assert_eq!(blank_tiles, vec!["first", "greeting", "last"]);
+
+        let mut store = TileStore::new();
+        for name in ["zeta", "alpha", "mu"] {
+            store.set(name, t!("{}", name));
+        }
+        let names: Vec<String> = store.export_sorted().into_keys().collect();
+        assert_eq!(names, vec!["alpha", "mu", "zeta"]);
+        let mut child = store.fork();
+        child.set("beta", t!("beta"));
+        let names: Vec<String> = child.export_sorted().into_keys().collect();
+        assert_eq!(names, vec!["alpha", "beta", "mu", "zeta"]);
+    }
+
+    #[test]
+    fn test_indirect_placeholders() {
+        tp!(greet_en, "Hello @{user}!");
+        tp!(greet_fr, "Bonjour @{user} !");
+        tp!(user, "Alice");
+        let tile = t!("
+            @{@{language}}
+            -- @{language}
+        ");
+        // a blank selector expands to nothing
+        assert_eq!(tile.to_string(), "--");
+        tp!(language, "greet_fr");
+        assert_eq!(tile.to_string(), "Bonjour Alice !\n-- greet_fr");
+        tp!(language, "greet_en");
+        assert_eq!(tile.to_string(), "Hello Alice!\n-- greet_en");
+        assert_eq!(ts!("[@{@{language}:align=2}]"), "[Hello Alice!]");
+
+        // the selector can be bound like any other placeholder
+        tp!(row, "@{@{cell}}");
+        tp!(cell, "user");
+        assert_eq!(ts!("@{row}"), "Alice");
+        assert_eq!(ts!("@{row with cell=language}"), "Hello Alice!");
+
+        // the selector and the target are dependencies of the tile
+        let owned = OwnedTile::new(&t!("@{@{language}}"));
+        assert_eq!(owned.dependencies(), vec!["greet_en", "language", "user"]);
+        assert_eq!(owned.to_string(), "Hello Alice!");
+    }
+
+    #[test]
+    fn test_dynamic_tiles() {
+        let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = calls.clone();
+        set_dynamic_tile("generated_at", move || {
+            counter.set(counter.get() + 1);
+            t!("run {} of @{{tool}}", counter.get())
+        });
+        tp!(tool, "rtile");
+        tp!(header, "// @{generated_at}");
+        assert_eq!(calls.get(), 0);
+        assert_eq!(ts!("@{header}"), "// run 1 of rtile");
+        assert_eq!(t!("@{header}").to_string(), "// run 2 of rtile");
+        // not called for the tiles which don't depend on it
+        assert_eq!(ts!("@{tool}"), "rtile");
+        assert_eq!(calls.get(), 2);
+
+        // a dynamic tile can depend on another one
+        set_dynamic_tile("outer", || t!("[@{inner}]"));
+        set_dynamic_tile("inner", || t!("inner"));
+        assert_eq!(ts!("@{outer}"), "[inner]");
+        assert!(with_default_store(|store| store.is_dynamic("outer")));
+
+        // persisting a static tile replaces the closure
+        tp!(generated_at, "static");
+        assert_eq!(ts!("@{header}"), "// static");
+        assert_eq!(calls.get(), 2);
+        assert!(!with_default_store(|store| store.is_dynamic("generated_at")));
+    }
+
+    #[test]
+    fn test_expansion_cache() {
+        let mut store = TileStore::new();
+        tp!(store => page, "
+            @{header}
+            Dear @{recipient},
+            @{body}
+        ");
+        tp!(store => header, "@{company} - @{department}");
+        tp!(store => company, "ACME");
+        tp!(store => department, "Sales");
+        tp!(store => body, "Thanks for choosing @{company}.");
+        store.clear_cache();
+        assert_eq!(store.cache_stats(), CacheStats::default());
+        assert_eq!(store.cache_stats().hit_rate(), 0.0);
+
+        let mut outputs = vec![];
+        for recipient in ["Alice", "Bob", "Carol"] {
+            tp!(store => recipient, recipient);
+            outputs.push(ts!(store => "@{page}"));
+        }
+        assert_eq!(
+            outputs[2],
+            "ACME - Sales\nDear Carol,\nThanks for choosing ACME."
+        );
+        let stats = store.cache_stats();
+        assert!(stats.hits > 0);
+        assert!(stats.entries > 0);
+        assert!(stats.hit_rate() > 0.0 && stats.hit_rate() < 1.0);
+
+        // a changed inner tile is not served from the memo
+        tp!(store => department, "Support");
+        assert_eq!(
+            ts!(store => "@{page}"),
+            "ACME - Support\nDear Carol,\nThanks for choosing ACME."
+        );
+        // the bindings bypass the memo
+        tp!(store => greet, "Hi @{name}");
+        tp!(store => alice, "Alice");
+        tp!(store => bob, "Bob");
+        assert_eq!(
+            ts!(store => "@{greet with name=alice}, @{greet with name=bob}"),
+            "Hi Alice, Hi Bob"
+        );
+    }
+
+    #[test]
+    fn test_bounded_store() {
+        let mut store = TileStore::new();
+        assert_eq!(store.capacity(), None);
+        for id in 0..10 {
+            store.set(&format!("tile_{}", id), t!("{}", id));
+        }
+        assert_eq!(store.len(), 10);
+
+        store.set_capacity(Some(4));
+        assert_eq!(store.capacity(), Some(4));
+        assert_eq!(store.len(), 4);
+        store.pin("header");
+        assert!(store.is_pinned("header"));
+        tp!(store => header, "== @{section} ==");
+        tp!(store => section, "intro");
+        // the tiles used recently are kept
+        for id in 0..20 {
+            assert_eq!(ts!(store => "@{header}"), "== intro ==");
+            store.set(&format!("page_{}", id), t!("{}", id));
+        }
+        assert_eq!(store.len(), 4);
+        assert!(store.contains("header"));
+        assert!(store.contains("section"));
+        assert!(store.contains("page_19"));
+        assert!(!store.contains("page_0"));
+
+        // the unpinned tiles are evicted
+        store.unpin("header");
+        assert!(!store.is_pinned("header"));
+        for id in 0..4 {
+            store.set(&format!("other_{}", id), t!("{}", id));
+        }
+        assert!(!store.contains("header"));
+        assert_eq!(ts!(store => "[@{header}]"), "[]");
+
+        store.set_capacity(None);
+        for id in 0..10 {
+            store.set(&format!("more_{}", id), t!("{}", id));
+        }
+        assert!(store.len() > 10);
+    }
+
+    #[test]
+    fn test_wide_placeholder_lines() {
+        for cell in 0..300 {
+            set_raw_tiles(format!("cell_{}", cell), t!(format!("{}", cell)));
+        }
+        let line = (0..300)
+            .map(|cell| format!("@{{cell_{}}}", cell))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let tile = RTile::new(vec![line]);
+        let expected = (0..300)
+            .map(|cell| cell.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(tile.to_string(), expected);
+        assert_eq!(tile.inner_tiles_in_raw_data()[0].len(), 300);
+
+        // a lone `@` or `{` is text, only `@{` starts a placeholder
+        tp!(host, "example.org");
+        assert_eq!(
+            ts!("mail@@{host} {x} @ { @{cell_7}@"),
+            "mail@example.org {x} @ { 7@"
+        );
+        assert_eq!(
+            t!("@{cell_1}\n  @{cell_2}:@").bake(&["cell_2"]).raw(),
+            "@{cell_1}\n  2:@"
+        );
+    }
+
+    #[test]
+    fn test_walk_dependencies() {
+        #[derive(Default)]
+        struct Events(Vec<String>);
+
+        impl TileVisitor for Events {
+            fn enter(&mut self, name: &str, _tile: &RTile, _parents: &[String]) -> bool {
+                self.0.push(format!("enter {}", name));
+                // the inner tiles of a skipped tile are not walked
+                name != "skipped"
+            }
+
+            fn leave(&mut self, name: &str, _tile: &RTile) {
+                self.0.push(format!("leave {}", name));
+            }
+
+            fn missing(&mut self, name: &str, parents: &[String]) {
+                self.0
+                    .push(format!("missing {} in {}", name, parents.join("/")));
+            }
+
+            fn recursion(&mut self, name: &str, parents: &[String]) {
+                self.0
+                    .push(format!("recursion {} in {}", name, parents.join("/")));
+            }
+        }
+
+        set_raw_tiles("root".to_string(), t!("@{a} @{b} @{a}"));
+        set_raw_tiles("a".to_string(), t!("@{leaf} @{skipped}"));
+        set_raw_tiles("b".to_string(), t!("@{leaf with item=unknown} @{root}"));
+        set_raw_tiles("leaf".to_string(), t!("leaf"));
+        set_raw_tiles("skipped".to_string(), t!("@{hidden}"));
+        let mut events = Events::default();
+        walk_dependencies(&t!("@{root}"), &mut events);
+        assert_eq!(
+            events.0,
+            vec![
+                "enter root",
+                "enter a",
+                "enter leaf",
+                "leave leaf",
+                "enter skipped",
+                "leave skipped",
+                "leave a",
+                "enter b",
+                "recursion root in root/b",
+                "leave b",
+                "leave root",
+            ]
+        );
+
+        // the tiles missing from the store, including the bound ones, are reported once
+        let mut events = Events::default();
+        let store = TileStore::new();
+        let tile = RTile::new(vec!["@{gone with item=unknown} @{gone}".to_string()]);
+        store.walk_dependencies(&tile, &mut events);
+        assert_eq!(events.0, vec!["missing unknown in ", "missing gone in "]);
+    }
+
+    #[test]
+    fn test_topo_order() {
+        tp!(script, "@{main}\n@{helpers}");
+        tp!(main, "main() { @{greet}; @{log} }");
+        tp!(greet, "greet() { @{log}; }");
+        tp!(log, "log() { :; }");
+        tp!(helpers, "@{log}\n@{greet}");
+        let order = topo_order("script").unwrap();
+        assert_eq!(order, vec!["log", "greet", "main", "helpers", "script"]);
+        assert_eq!(topo_order("script").unwrap(), order);
+        assert_eq!(topo_order("log").unwrap(), vec!["log"]);
+        assert_eq!(topo_order("not_persisted").unwrap(), Vec::<String>::new());
+
+        set_raw_tiles("log".to_string(), t!("log() { @{main}; }"));
+        let error = topo_order("script").unwrap_err();
+        assert_eq!(error.cycle, vec!["main", "greet", "log", "main"]);
+        assert_eq!(
+            error.to_string(),
+            "detected a recursion: main -> greet -> log -> main"
+        );
+        let error = topo_order("log").unwrap_err();
+        assert_eq!(error.cycle, vec!["log", "main", "greet", "log"]);
+    }
+
+    #[test]
+    fn test_persist_composed_tiles_deep() {
+        let left = t!("
+            fn one() {}
+            fn two() {}
+        ");
+        let right = t!("// first") | t!("// second\n// third");
+        let composed = t!("begin") | (left + t!(" | ") + right) | t!("end");
+        stp!(flat, composed);
+        let parts = stp_deep!(deep, composed);
+        assert_eq!(ts!("@{deep}"), ts!("@{flat}"));
+        assert_eq!(parts.last().unwrap(), "deep_2");
+        assert!(parts.contains(&"deep_1_2_1".to_string()));
+
+        // the parts are compared by their lines only
+        assert_eq!(t!("a") + t!("b"), t!("ab"));
+
+        // the leaves can be updated after persisting
+        tp!(deep_1_2_1, "// updated");
+        assert_eq!(
+            ts!("@{deep}"),
+            "begin\nfn one() {}|// first\nfn two() {} // updated\nend"
+        );
+
+        // the named parts are referred to, instead of being copied
+        let title = tp!(title, "draft");
+        let mut page = title | t!("body");
+        stp_deep!(page, page);
+        tp!(title, "final");
+        assert_eq!(ts!("@{page}"), "final\nbody");
+
+        // a tile named like its own part is extended, without referring to itself
+        page = gtp!(page).unwrap() | t!("footer");
+        stp_deep!(page, page);
+        assert_eq!(ts!("@{page}"), "final\nbody\nfooter");
+
+        // the lines changed after the composition are persisted as they are
+        let mut edited = t!("a") | t!("b");
+        edited.lns.push("c".to_string());
+        assert!(stp_deep!(edited, edited).is_empty());
+        assert_eq!(ts!("@{edited}"), "a\nb\nc");
+
+        let mut store = TileStore::new();
+        let parts = stp_deep!(store => grid, (t!("1") + t!("2")) | t!("3"));
+        assert_eq!(parts, vec!["grid_0_0", "grid_0_1", "grid_0", "grid_1"]);
+        assert_eq!(ts!(store => "@{grid}"), "12\n3");
+    }
+
+    #[test]
+    fn test_user_code_during_render() {
+        struct Version;
+
+        impl std::fmt::Display for Version {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                // a Display impl touching the tiles, called while a tile is rendered
+                write!(f, "v{}", ts!("@{major}.@{minor}"))
+            }
+        }
+
+        tp!(major, "1");
+        tp!(minor, "2");
+        set_dynamic_tile("version", || t!("{}", Version));
+        set_dynamic_tile("banner", || {
+            let name = gtp!(app).map(|tile| tile.to_string()).unwrap_or_default();
+            tp!(last_banner, "{} @{{version}}", name);
+            t!("@{last_banner}")
+        });
+        tp!(app, "tool");
+        let page = t!("@{banner} (@{version})");
+        assert_eq!(page.to_string(), "tool v1.2 (v1.2)");
+        assert_eq!(gtp!(last_banner).unwrap().to_string(), "tool v1.2");
+        assert_eq!(page.rendered_dimensions(), (16, 1));
+        tp!(minor, "3");
+        assert_eq!(page.render_fresh(), "tool v1.3 (v1.3)");
+
+        // a closure rendering its own tile gets the previous value of the tile
+        set_dynamic_tile("counter", || {
+            let previous = ts!("@{counter}").parse::<u32>().unwrap_or(0);
+            t!("{}", previous + 1)
+        });
+        assert_eq!(ts!("@{counter}"), "1");
+        assert_eq!(ts!("@{counter}"), "2");
+
+        // the render options are restored, even if the render panics
+        set_raw_tiles("looping".to_string(), t!("@{looping}"));
+        let options = RenderOptions {
+            drop_blank_placeholder_lines: true,
+            ..Default::default()
+        };
+        let result = std::panic::catch_unwind(|| t!("@{looping}").to_string_with_options(&options));
+        assert!(result.is_err());
+        assert!(!get_render_options().drop_blank_placeholder_lines);
+        assert_eq!(ts!("@{app}"), "tool");
+    }
+
+    #[test]
+    fn test_gtp_snapshot_and_ref() {
+        tp!(snap_name, "Alice");
+        tp!(snap_greet, "hello @{snap_name}\n\n  bye");
+        let copy = gtp!(snap_greet).unwrap();
+        let snapshot = gtp_snapshot!(snap_greet).unwrap();
+        let handle = gtp_ref!(snap_greet);
+        assert_eq!(handle.name(), "snap_greet");
+
+        tp!(snap_name, "Bob");
+        // the copy keeps the raw lines, but expands them using the tls
+        assert_eq!(copy.to_string(), "hello Bob\n\n  bye");
+        // the snapshot keeps the output as it was
+        assert_eq!(snapshot.to_string(), "hello Alice\n\n  bye");
+        assert_eq!(handle.to_string(), "hello Bob\n\n  bye");
+
+        tp!(snap_greet, "hi @{snap_name}");
+        assert_eq!(copy.to_string(), "hello Bob\n\n  bye");
+        assert_eq!(snapshot.to_string(), "hello Alice\n\n  bye");
+        // the handle follows the tile persisted again
+        assert_eq!(handle.to_string(), "hi Bob");
+        assert_eq!(handle.get().unwrap().raw(), "hi @{snap_name}");
+        let composed = handle.to_tile() | snapshot.clone();
+        tp!(snap_name, "Carol");
+        assert_eq!(composed.to_string(), "hi Carol\nhello Alice\n\n  bye");
+
+        remove_tile("snap_greet");
+        assert!(!handle.exists());
+        assert_eq!(handle.to_string(), "");
+        assert!(!get_blank_tiles().contains("snap_greet"));
+
+        // the snapshot of a tile without trimming renders as the tile
+        kp!(snap_box, "  a\n  bbb  ");
+        let snapshot = gtp_snapshot!(snap_box).unwrap();
+        assert_eq!(snapshot.to_string(), gtp!(snap_box).unwrap().to_string());
+
+        let mut store = TileStore::new();
+        tp!(store => lang, "rust");
+        tp!(store => title, "@{lang} book");
+        let snapshot = gtp_snapshot!(store => title).unwrap();
+        tp!(store => lang, "go");
+        assert_eq!(ts!(store => "@{title}"), "go book");
+        assert_eq!(store.render(&snapshot), "rust book");
+    }
+
+    #[test]
+    fn test_per_render_isolation() {
+        tp!(pri_name, "Alice");
+        tp!(pri_greet, "Hello @{pri_name}");
+        tp!(pri_farewell, "Bye @{pri_name}");
+        stp!(pri_name, t!("Bob"));
+        let greet = t!("@{pri_greet}");
+        let farewell = t!("@{pri_farewell}");
+
+        // rendering one tile doesn't change what the other tiles see
+        assert_eq!(greet.to_string(), "Hello Bob");
+        assert_eq!(farewell.render_cached(), "Bye Alice");
+        assert_eq!(greet.render_cached(), "Hello Alice");
+
+        // the order of the renders doesn't matter
+        let first = (farewell.reevaluate(), greet.reevaluate());
+        let second = (greet.reevaluate(), farewell.reevaluate());
+        assert_eq!(first, ("Bye Bob".to_string(), "Hello Bob".to_string()));
+        assert_eq!((first.1, first.0), second);
+
+        // a render nested in a dynamic tile is isolated as well
+        set_dynamic_tile("pri_nested", || t!("{}", t!("@{pri_greet}")));
+        assert_eq!(
+            ts!("@{pri_nested} / @{pri_farewell}"),
+            "Hello Bob / Bye Bob"
+        );
+        assert_eq!(farewell.render_cached(), "Bye Alice");
+    }
+
+    #[test]
+    fn test_run_project() {
+        use rtile::project::{run_project, Project, ProjectError};
+
+        let root = std::env::temp_dir().join(format!("rtile_test_project_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("templates/partials")).unwrap();
+        std::fs::create_dir_all(root.join("overrides")).unwrap();
+        std::fs::write(
+            root.join("templates/lib.rs"),
+            "@{partials/header.rs}\npub const VERSION: &str = \"@{version}\";",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("templates/partials/header.rs"),
+            "// @{crate_name}, generated",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("overrides/README.md"),
+            "# @{crate_name}\n\n@{about}",
+        )
+        .unwrap();
+        std::fs::write(root.join("templates/README.md"), "not used").unwrap();
+        std::fs::write(
+            root.join("rtile.toml"),
+            r#"
+            # the generation plan
+            [project]
+            templates = ["overrides", 'templates']
+            output = "out"
+
+            [values]
+            crate_name = "demo"
+            version = "0.1.0"
+            about = """
+            A demo crate,
+            with a "quoted" word"""
+            retries = 3
+
+            [[output]]
+            template = "lib.rs"
+            path = "src/@{crate_name}.rs" # expanded
+
+            [[output]]
+            template = "README.md"
+            path = "README.md"
+            "#,
+        )
+        .unwrap();
+
+        let report = run_project(&root).unwrap();
+        assert_eq!(report.created, 2);
+        assert_eq!(
+            std::fs::read_to_string(root.join("out/src/demo.rs")).unwrap(),
+            "// demo, generated\npub const VERSION: &str = \"0.1.0\";\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("out/README.md")).unwrap(),
+            "# demo\n\nA demo crate,\nwith a \"quoted\" word\n"
+        );
+        let report = run_project(root.join("rtile.toml")).unwrap();
+        assert_eq!((report.created, report.unchanged), (0, 2));
+
+        let project =
+            Project::parse("[[output]]\ntemplate = \"lib.rs\"\npath = \"@{missing}.rs\"").unwrap();
+        let project = Project {
+            template_roots: vec![root.join("templates")],
+            ..project
+        };
+        match project.render() {
+            Err(ProjectError::MissingTiles { template, names }) => {
+                assert_eq!(template, "lib.rs");
+                assert_eq!(names, vec!["crate_name", "missing", "version"]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        for (text, line) in [
+            ("[project]\noutput = generated", 2),
+            ("[values]\nname = \"unfinished", 2),
+            ("name = \"outside\"", 1),
+            ("[values]\na = 1\na = 2", 3),
+            ("[project]\n[project]", 2),
+            ("[outputs]", 1),
+            ("\n[[output]]\ntemplate = \"lib.rs\"", 2),
+        ] {
+            match Project::parse(text) {
+                Err(ProjectError::Parse { line: actual, .. }) => {
+                    assert_eq!(actual, line, "{}", text)
+                }
+                other => panic!("unexpected {:?} for {}", other, text),
+            }
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn test_template_watcher() {
+        use rtile::watch::TemplateWatcher;
+
+        let root = std::env::temp_dir().join(format!("rtile_test_watch_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let overrides = root.join("overrides");
+        let templates = root.join("templates");
+        std::fs::create_dir_all(templates.join("partials")).unwrap();
+        std::fs::write(templates.join("page.txt"), "@{partials/header.txt}\nbody").unwrap();
+        std::fs::write(templates.join("partials/header.txt"), "header").unwrap();
+
+        let mut watcher = TemplateWatcher::new([&overrides, &templates]);
+        assert_eq!(
+            watcher.poll().unwrap(),
+            vec!["page.txt", "partials/header.txt"]
+        );
+        assert_eq!(ts!("@{page.txt}"), "header\nbody");
+
+        // an edit of an inner template reloads only that template, the outputs depending on it are re-rendered
+        std::fs::write(templates.join("partials/header.txt"), "new header").unwrap();
+        assert_eq!(watcher.poll().unwrap(), vec!["partials/header.txt"]);
+        assert_eq!(ts!("@{page.txt}"), "new header\nbody");
+        assert!(watcher.poll().unwrap().is_empty());
+
+        // a directory created later is picked up, and it wins as it is listed first
+        std::fs::create_dir_all(overrides.join("partials")).unwrap();
+        std::fs::write(overrides.join("partials/header.txt"), "overridden").unwrap();
+        assert_eq!(watcher.poll().unwrap(), vec!["partials/header.txt"]);
+        assert_eq!(ts!("@{page.txt}"), "overridden\nbody");
+
+        std::fs::remove_file(templates.join("page.txt")).unwrap();
+        assert_eq!(watcher.poll().unwrap(), vec!["page.txt"]);
+        assert!(!with_default_store(|store| store.contains("page.txt")));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_placeholder_expressions() {
+        tp!(ex_count, "41");
+        tp!(ex_items, vec!["a", "b", "c"]);
+        tp!(ex_zip, "007");
+        tq!("ex_name-with.dots/and-slashes", "x");
+        tp!(ex_empty);
+        assert_eq!(ts!("@{ex_count + 1}"), "42");
+        assert_eq!(ts!("@{(ex_count + 1) * 2 - 4 / 2 % 3}"), "82");
+        assert_eq!(ts!("@{-ex_count * 2}"), "-82");
+        assert_eq!(ts!("@{len(ex_items)} @{len(ex_empty)}"), "3 0");
+        assert_eq!(ts!("@{ex_zip + \"\"} @{ex_zip + 1}"), "007 8");
+        assert_eq!(
+            ts!("@{\"a:b\" + ex_name-with.dots/and-slashes:align=0}"),
+            "a:bx"
+        );
+        assert_eq!(ts!("@{lower(\"A with B\")}"), "a with b");
+        assert_eq!(ts!("@{trim(\"  x  \") + \"|\"}"), "x|");
+        // a multi-line value is aligned the same way as a tile
+        assert_eq!(ts!("- @{ex_items + \"!\"}"), "- a\n  b\n  c!");
+        // the inline definitions are operands as well
+        assert_eq!(ts!("@{def two}2@{end}@{two * ex_count}"), "282");
+
+        // the operands are the inner tiles of the tile
+        let tile = t!("@{ex_count + ex_missing_operand}");
+        assert!(gtp!(ex_missing_operand).is_some());
+        assert_eq!(
+            tile.inner_tiles_in_raw_data(),
+            vec![vec!["ex_count", "ex_missing_operand"]]
+        );
+        assert_eq!(tile.to_string(), "41");
+
+        // the memoized expansions see the changes of the operands
+        tp!(ex_next, "@{ex_count + 1}");
+        assert_eq!(ts!("@{ex_next}"), "42");
+        tp!(ex_count, "1");
+        assert_eq!(ts!("@{ex_next}"), "2");
+
+        for (template, message) in [
+            (
+                "@{ex_items * 2}",
+                "the operand `a\nb\nc` is not an integer in @{ex_items * 2} expression",
+            ),
+            (
+                "@{ex_count / 0}",
+                "division by zero in @{ex_count / 0} expression",
+            ),
+            (
+                "@{size(ex_items)}",
+                "unknown function `size` in @{size(ex_items)} expression",
+            ),
+            (
+                "@{(ex_count + 1}",
+                "missing `)` in @{(ex_count + 1} expression",
+            ),
+        ] {
+            let result = std::panic::catch_unwind(|| ts!(template));
+            let error = result.unwrap_err();
+            assert_eq!(error.downcast_ref::<String>().unwrap(), message);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "detected a recursion")]
+    fn test_placeholder_expression_recursion() {
+        tp!(ex_self, "@{ex_self + 1}");
+        ts!("@{ex_self}");
+    }
+
+    #[test]
+    fn test_from_range() {
+        let arms = RTile::from_range(1..=3, "{i} => Some(Case{i}),");
+        assert_eq!(
+            arms.to_string(),
+            "1 => Some(Case1),\n2 => Some(Case2),\n3 => Some(Case3),"
+        );
+        let tile = t!("match n {\n    @{arms}\n    _ => None,\n}");
+        tp!(arms, arms);
+        assert_eq!(
+            tile.to_string(),
+            "match n {\n    1 => Some(Case1),\n    2 => Some(Case2),\n    3 => Some(Case3),\n    _ => None,\n}"
+        );
+
+        // a multi-line template, stepping through the range
+        let tests = RTile::from_range(
+            (0..6).step_by(3),
+            "
+                #[test]
+                fn case_{i}() {
+                    check({i});
+                }
+            ",
+        );
+        assert_eq!(
+            tests.to_string(),
+            "#[test]\nfn case_0() {\n    check(0);\n}\n#[test]\nfn case_3() {\n    check(3);\n}"
+        );
+
+        // the placeholders of the template refer to the tiles of every value
+        tp!(fr_field_x, "1.5");
+        tp!(fr_field_y, "-2");
+        let init = RTile::from_range(["x", "y"], "{i}: @{fr_field_{i}},");
+        assert_eq!(init.to_string(), "x: 1.5,\ny: -2,");
+        assert!(!with_default_store(|store| store.contains("fr_field_{i}")));
+        assert_eq!(RTile::from_range(0..0, "{i}").to_string(), "");
+    }
+
+    #[test]
+    fn test_from_matrix() {
+        let rows = vec![vec![1, 200, 3], vec![40, 5, 600]];
+        assert_eq!(
+            RTile::from_matrix(&rows, ColumnSpec::default()).to_string(),
+            "1  200 3  \n40 5   600"
+        );
+
+        let spec = ColumnSpec {
+            align: vec![ColumnAlign::Right, ColumnAlign::Center],
+            separators: vec![", ".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            RTile::from_matrix(&rows, spec.clone()).to_string(),
+            " 1, 200, 3  \n40,  5 , 600"
+        );
+
+        // a cell spanning lines makes its row taller, a short row has blank cells
+        let rows = [
+            vec![t!("a"), t!("one\ntwo"), t!("x")],
+            vec![t!("bb"), t!("three")],
+        ];
+        let grid = RTile::from_matrix(&rows, ColumnSpec::default());
+        assert_eq!(grid.to_string(), "a  one   x\n   two    \nbb three  ");
+        assert_eq!(grid.dimensions(), (10, 3));
+
+        // the grid composes as a tile
+        let framed =
+            k!("| \n| ") + RTile::from_matrix(&[["k", "v"], ["key", "value"]], spec) + k!(" |\n |");
+        assert_eq!(framed.to_string(), "|   k,   v   |\n| key, value |");
+        assert_eq!(
+            RTile::from_matrix(&Vec::<Vec<u8>>::new(), ColumnSpec::default()).to_string(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_from_matrix_overflow() {
+        let rows = [
+            ["id", "summary", "owner"],
+            ["1", "fix the parser", "ann"],
+            ["22", "ok", "bartholomew"],
+        ];
+        let spec = ColumnSpec {
+            separators: vec![" | ".to_string()],
+            max_widths: vec![None, Some(7), Some(5)],
+            overflow: vec![
+                Overflow::Grow,
+                Overflow::Wrap,
+                Overflow::Truncate("~".to_string()),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            RTile::from_matrix(&rows, spec).to_string(),
+            "id | summary | owner\n\
+             1  | fix the | ann  \n   \
+                | parser  |      \n\
+             22 | ok      | bart~"
+        );
+
+        // a cell as wide as the maximum width is kept, a marker wider than the maximum width is cut
+        let spec = ColumnSpec {
+            max_widths: vec![Some(3)],
+            overflow: vec![Overflow::Truncate("...".to_string())],
+            ..Default::default()
+        };
+        assert_eq!(
+            RTile::from_matrix(&[["abc"], ["abcd"]], spec.clone()).to_string(),
+            "abc\n..."
+        );
+        let spec = ColumnSpec {
+            max_widths: vec![Some(2)],
+            ..spec
+        };
+        assert_eq!(RTile::from_matrix(&[["abcd"]], spec).to_string(), "..");
+
+        // the columns without a maximum width grow
+        let spec = ColumnSpec {
+            overflow: vec![Overflow::Wrap],
+            ..Default::default()
+        };
+        assert_eq!(
+            RTile::from_matrix(&[["a long cell"]], spec).to_string(),
+            "a long cell"
+        );
+    }
+
+    #[test]
+    fn test_hjoin_with_separator() {
+        let left = t!("fn a()\nfn b()\nfn c()");
+        let right = t!("// one");
+        assert_eq!(
+            left.clone()
+                .add_with_separator(right.clone(), " │ ")
+                .to_string(),
+            "fn a() │ // one\nfn b() │\nfn c() │"
+        );
+        // every separator column is as tall as the tallest tile
+        let tile = RTile::hjoin(&[right.clone(), t!("x"), left.clone()], " | ");
+        assert_eq!(
+            tile.to_string(),
+            "// one | x | fn a()\n       |   | fn b()\n       |   | fn c()"
+        );
+        assert_eq!(tile.dimensions(), (19, 3));
+        // the placeholders of the tiles are kept, the widths are measured on the raw lines, the same as `+`
+        tp!(hj_name, "rtile");
+        let tile = RTile::hjoin(&[tp!(hj_left, "@{hj_name}"), left.clone()], ":");
+        assert_eq!(
+            tile.to_string(),
+            (t!("@{hj_name}") + k!(":\n:\n:") + left).to_string()
+        );
+        assert_eq!(tile.name, None);
+        assert_eq!(RTile::hjoin(&[right], " | ").to_string(), "// one");
+        assert_eq!(RTile::hjoin(&[], " | ").to_string(), "");
+    }
+
+    #[test]
+    fn test_rspacer() {
+        let names = k!(vec!["one", "two", "three"]);
+        let numbers = k!(vec!["1", "2", "3"]);
+
+        let result = names.clone() + RSpacer::h(3) + numbers.clone();
+        assert_eq!(ks!(result), "one     1\ntwo     2\nthree   3");
+
+        let result = names.clone() | RSpacer::v(2) | k!("four");
+        assert_eq!(ks!(result), "one  \ntwo  \nthree\n     \n     \nfour ");
+
+        let mut result = RSpacer::flex() + numbers.clone();
+        result += RSpacer::h(1);
+        assert_eq!(ks!(result), " 1 \n 2 \n 3 ");
+
+        let mut result = numbers.clone();
+        result |= RSpacer::flex();
+        assert_eq!(result.dimensions(), (1, 6));
+
+        assert_eq!(RSpacer::h(2).below(&names).dimensions(), (0, 0));
+        assert_eq!(RSpacer::v(2).beside(&names).dimensions(), (0, 2));
+        assert_eq!(ks!(RSpacer::v(2).to_tile()), " \n ");
+    }
+
+    #[test]
+    fn test_document_store() {
+        clear_tiles();
+        tp!(
+            doc_struct,
+            "@# A struct with its derives
+             @#
+             @#   @{doc_struct_name} is the name of the struct
+             @{doc_derives}
+             pub struct @{doc_struct_name} {
+                 @{doc_fields}
+             }"
+        );
+        tp!(doc_derives, "#[derive(@{doc_traits})]");
+        t!("@{doc_missing}");
+
+        let doc = describe_template("doc_struct").unwrap();
+        assert_eq!(
+            doc.doc,
+            vec![
+                "A struct with its derives",
+                "",
+                "  @{doc_struct_name} is the name of the struct"
+            ]
+        );
+        assert_eq!(
+            doc.placeholders,
+            vec!["doc_derives", "doc_struct_name", "doc_fields"]
+        );
+        assert!(describe_template("doc_unknown").is_none());
+
+        let expected = "# Templates
+
+## `doc_derives`
+
+Placeholders: `doc_traits`
+
+## `doc_struct`
+
+A struct with its derives
+
+  @{doc_struct_name} is the name of the struct
+
+Placeholders: `doc_derives`, `doc_struct_name`, `doc_fields`
+";
+        assert_eq!(document_store(), expected);
+    }
+
+    #[cfg(feature = "repl")]
+    #[test]
+    fn test_repl() {
+        use rtile::repl::run_repl;
+
+        clear_tiles();
+        let input = "\
+:let repl_page = @# The page\\n@{repl_header}\\n@{repl_body}
+:let repl_header = # @{repl_title}
+:let repl_body = @{repl_page}
+:tiles
+:tree repl_page
+:raw repl_header
+:doc repl_page
+:frob
+@{repl_header}
+@{1 / 0}
+:quit
+:tiles
+";
+        let mut output = vec![];
+        run_repl(input.as_bytes(), &mut output).unwrap();
+        let expected = "\
+rtile> rtile> rtile> rtile> repl_body
+repl_header
+repl_page
+repl_title (blank)
+rtile> repl_page
+|-- repl_header
+|   `-- repl_title (blank)
+`-- repl_body
+    `-- repl_page (cycle)
+rtile> # @{repl_title}
+rtile> ## `repl_page`
+
+The page
+
+Placeholders: `repl_header`, `repl_body`
+
+rtile> unknown command `:frob`, see :help
+rtile> #
+rtile> error: division by zero in @{1 / 0} expression
+rtile> ";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+        assert!(get_blank_tiles().contains("repl_title"));
+    }
+
+    #[test]
+    fn test_analyze_template() {
+        use rtile::analysis::*;
+
+        let mut store = TileStore::new();
+        store.set("an_loop_a", t!("@{an_loop_b}"));
+        store.set("an_loop_b", t!("@{an_loop_a}"));
+        store.set("an_row", t!("| @{cell} |"));
+        store.set("an_item", t!("x"));
+        store.set("an_count", t!("3"));
+        let template = "\
+ünï @{an_count + 1} @{an_loop_a}
+@{def an_local}local@{end} @{an_local} @{an_row with cell=an_item} @{an_row with cell=an_missing}
+@{an_count:frob} @{an_unfinished";
+        let analysis = analyze_template(template, &store);
+
+        let texts: Vec<&str> = analysis
+            .placeholders
+            .iter()
+            .map(|p| p.text.as_str())
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                "an_count + 1",
+                "an_loop_a",
+                "def an_local",
+                "end",
+                "an_local",
+                "an_row with cell=an_item",
+                "an_row with cell=an_missing"
+            ]
+        );
+        assert_eq!(
+            analysis.placeholders[0].range,
+            Range::new(Position::new(0, 4), Position::new(0, 19))
+        );
+        assert!(analysis.placeholders[4].tiles.is_empty());
+        assert_eq!(analysis.placeholders[6].tiles, vec!["an_row", "an_missing"]);
+
+        let diagnostics: Vec<(Severity, &str, Option<&str>, usize)> = analysis
+            .diagnostics
+            .iter()
+            .map(|d| {
+                (
+                    d.severity,
+                    d.message.as_str(),
+                    d.related_tile.as_deref(),
+                    d.range.start.line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (
+                    Severity::Error,
+                    "detected a recursion: an_loop_a -> an_loop_b -> an_loop_a",
+                    Some("an_loop_a"),
+                    0
+                ),
+                (
+                    Severity::Warning,
+                    "undefined tile `an_missing`",
+                    Some("an_missing"),
+                    1
+                ),
+                (
+                    Severity::Error,
+                    "unknown option in @{an_count:frob} expression",
+                    None,
+                    2
+                ),
+                (Severity::Error, "unfinished @{} expression", None, 2),
+            ]
+        );
+        assert!(!analysis.is_ok());
+
+        assert_eq!(analysis.tile_at(Position::new(1, 70)), Some("an_row"));
+        assert_eq!(analysis.tile_at(Position::new(1, 0)), None);
+        assert_eq!(analysis.references("an_row").len(), 2);
+        assert!(analyze_template("@{an_item}", &store).is_ok());
+    }
+
+    #[test]
+    fn test_analysis_to_json() {
+        use rtile::analysis::*;
+
+        let mut store = TileStore::new();
+        store.set("aj_name", t!("Alice"));
+        let analysis = analyze_template("\t@{aj_name + \"\\q\"}\n@{aj_name} @{aj_title}", &store);
+        let expected = r#"[
+  {"range":{"start":{"line":0,"character":1},"end":{"line":0,"character":18}},"severity":1,"source":"rtile","message":"invalid escape in @{aj_name + \"\\q\"} expression"},
+  {"range":{"start":{"line":1,"character":11},"end":{"line":1,"character":22}},"severity":2,"source":"rtile","message":"undefined tile `aj_title`","relatedTile":"aj_title"}
+]"#;
+        assert_eq!(analysis_to_json(&analysis), expected);
+        assert_eq!(
+            analysis.diagnostics[1].to_json(),
+            expected
+                .lines()
+                .nth(2)
+                .unwrap()
+                .trim()
+                .trim_end_matches(',')
+        );
+    }
+
+    #[test]
+    fn test_tcat() {
+        tp!(tcat_unit, "kg\nlb");
+        let label = String::from("weight:");
+        let amounts = k!(vec!["  1", "2.2"]);
+        let row = tcat![&label, ' ', amounts, " ", t!("@{tcat_unit}"), ";"];
+        assert_eq!(ks!(row), "weight:   1 kg;\n        2.2 lb ");
+
+        let price = 9.5;
+        assert_eq!(tcat![1, "x", price, true].to_string(), "1x9.5true");
+        assert_eq!(tcat![].to_string(), "");
+    }
+
+    #[test]
+    fn test_locale() {
+        let invoice = tcat![
+            k!(vec!["Item", "Widget", "Gadget"]),
+            "  ",
+            k!(vec!["Preis".to_string()])
+                | RTile::from_numbers([1234.5, 99.0], Locale::DE.num_format(Some(2))),
+        ];
+        assert_eq!(
+            ks!(invoice),
+            "Item    Preis   \nWidget  1.234,50\nGadget     99,00"
+        );
+
+        let format = NumFormat {
+            precision: Some(3),
+            locale: Some(Locale::ES),
+            ..Default::default()
+        };
+        assert_eq!(
+            RTile::from_numbers([0.5, -12.25], format).to_string(),
+            "  0,500\n-12,250"
+        );
+        assert_eq!(Locale::EN.format_number(1234567.0, None), "1,234,567");
+        assert_eq!(
+            Locale::FR.format_date("le %e %B %Y (%d/%m, %b) 100%%", 2025, 8, 1),
+            "le 1 août 2025 (01/08, août) 100%"
+        );
+        assert_eq!(Locale::ES.format_date("%B", 2025, 12, 1), "diciembre");
+    }
+
+    #[test]
+    fn test_payroll_example_three() {
+        let employees = vec![
+            ("Alice", 3000.0),
+            ("Bob", 950.5),
+            ("Carol", 12800.0),
+            ("Dylan", 75.25),
+        ];
+
+        tp!(tpe3_payroll_header, "ACME Inc - Payroll");
+        let names = k!(employees
+            .iter()
+            .map(|employee| employee.0)
+            .collect::<Vec<&str>>());
+        let format = NumFormat {
+            precision: Some(2),
+            thousands_sep: Some(','),
+            symbol: Some("$"),
+            ..Default::default()
+        };
+        let salaries = RTile::from_numbers(employees.iter().map(|employee| employee.1), format);
+        let payroll_data = names + RSpacer::h(1) + salaries;
+        stp!(tpe3_payroll_data, payroll_data);
+
+        let payroll = ts!(r#"
+                                    @{tpe3_payroll_header}
+                                        @{tpe3_payroll_data}
+                                "#);
+
+        let expected_result = ts!(r#"
+                        ACME Inc - Payroll
+                            Alice $ 3,000.00
+                            Bob   $   950.50
+                            Carol $12,800.00
+                            Dylan $    75.25
+        "#);
+        assert_eq!(payroll.to_string(), expected_result);
+
+        let format = NumFormat {
+            precision: Some(1),
+            symbol: Some(" kg"),
+            symbol_position: SymbolPosition::Trailing,
+            width: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            RTile::from_numbers([2.5, 130.0], format).to_string(),
+            "    2.5 kg\n  130.0 kg"
+        );
+        let format = NumFormat {
+            symbol: Some("€"),
+            symbol_position: SymbolPosition::Attached,
+            locale: Some(Locale::DE),
+            thousands_sep: Some('.'),
+            precision: Some(2),
+            align_right: false,
+            width: 11,
+        };
+        assert_eq!(
+            RTile::from_numbers([1234.5, -8.0], format).to_string(),
+            "€1.234,50  \n   -€8,00  "
+        );
+    }
+
+    #[test]
+    fn test_content_stamp() {
+        tp!(tcs_banner, "# sha256: @{content_sha256}");
+        tp!(tcs_items, "item one\nitem two");
+        let tile = t!("
+            header
+            @{tcs_banner}
+              @{tcs_items}
+            The quick brown fox jumps over the lazy dog, the quick brown fox jumps over the lazy dog.
+        ");
+        assert_eq!(
+            tile.to_string(),
+            "header\n# sha256:\n  item one\n  item two\nThe quick brown fox jumps over the lazy dog, the quick brown fox jumps over the lazy dog."
+        );
+
+        let options = RenderOptions {
+            content_stamp: true,
+            ..Default::default()
+        };
+        let stamped = tile.to_string_with_options(&options);
+        assert_eq!(
+            stamped.lines().nth(1).unwrap(),
+            "# sha256: 2d5289fe7fb32ecc68cef63f6657b8009e3b4d0e76b7e30e95168db8a5427ec9"
+        );
+        assert!(verify_content_sha256(&stamped));
+        assert!(!verify_content_sha256(&stamped.replace("one", "1")));
+        assert!(!verify_content_sha256(&tile.to_string()));
+        // the memoized expansions of a render without the stamp are not reused
+        assert_eq!(tile.to_string_with_options(&options), stamped);
+        assert!(tile.to_string().starts_with("header\n# sha256:\n"));
+        assert_eq!(stamp_content_sha256("no stamp"), "no stamp");
+    }
+
+    #[test]
+    fn test_with_provenance() {
+        use rtile::provenance::*;
+
+        tp!(twp_name, "Person");
+        tp!(twp_struct, "pub struct @{twp_name};");
+        let tile = gtp!(twp_struct).unwrap();
+        assert_eq!(
+            tile.clone()
+                .with_provenance("codegen", "1.0.0", "schema/person.json")
+                .to_string(),
+            "// @generated by codegen 1.0.0, do not edit\n// source: schema/person.json\n// template: twp_struct\npub struct Person;"
+        );
+
+        let provenance = Provenance::new("codegen", "1.0.0", "")
+            .template("page.html")
+            .comment(CommentStyle::for_language("HTML").unwrap());
+        assert_eq!(
+            t!("<p>@{twp_name}</p>").with_banner(&provenance).to_string(),
+            "<!-- @generated by codegen 1.0.0, do not edit -->\n<!-- template: page.html -->\n<p>Person</p>"
+        );
+
+        std::env::set_var("SOURCE_DATE_EPOCH", "1709251199");
+        let provenance = Provenance::new("gen", "2", "a.sql")
+            .comment(CommentStyle::for_language("sql").unwrap())
+            .timestamp(Timestamp::Now);
+        assert_eq!(
+            provenance.banner().to_string(),
+            "-- @generated by gen 2, do not edit\n-- source: a.sql\n-- timestamp: 2024-02-29T23:59:59Z"
+        );
+        std::env::remove_var("SOURCE_DATE_EPOCH");
+        assert_eq!(Timestamp::None.value(), None);
+        assert!(CommentStyle::for_language("brainfuck").is_none());
+    }
+
+    #[test]
+    fn test_regen_report() {
+        use rtile::emit::*;
+
+        let root = std::env::temp_dir().join(format!("rtile_test_regen_{}", std::process::id()));
+        let mut emitter = Emitter::new(&root);
+        emitter
+            .emit("src/lib.rs", "mod a;\nmod b;\nmod c;")
+            .unwrap();
+        emitter.emit("src/a.rs", "pub struct A;").unwrap();
+        emitter.finish().unwrap();
+
+        let report = Emitter::new(&root)
+            .preview([
+                ("src/lib.rs", "mod a;\nmod c;\nmod d;"),
+                ("src/a.rs", "pub struct A;"),
+            ])
+            .unwrap();
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.files[0].status, FileStatus::Unchanged);
+        assert_eq!((report.files[1].added, report.files[1].removed), (1, 1));
+        assert_eq!(
+            report.to_string(),
+            "src/lib.rs | +1 -1\n1 file changed, 1 insertion(+), 1 deletion(-)"
+        );
+        // nothing is written by a preview
+        assert_eq!(
+            std::fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "mod a;\nmod b;\nmod c;"
+        );
+
+        let report = Emitter::new(&root)
+            .preview([("src/lib.rs", "mod a;\nmod b;\nmod c;")])
+            .unwrap();
+        assert_eq!(report.changed().count(), 1);
+        assert_eq!(report.files[0].status, FileStatus::Deleted);
+        assert_eq!(
+            report.to_string(),
+            "src/a.rs |  -1 deleted\n1 file changed, 0 insertions(+), 1 deletion(-)"
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_chunk_hints() {
+        use rtile::provenance::CommentStyle;
+
+        tp!(chunk_header, "[package]\nname = \"demo\"");
+        tp!(chunk_deps, "[dependencies]\nserde = \"1\"");
+        tp!(
+            chunk_manifest,
+            "@# chunks: toml
+@{chunk_header}
+
+@{chunk_deps}"
+        );
+        tp!(chunk_page, "<main>\n  @{chunk_manifest}\n</main>");
+        assert_eq!(
+            gtp!(chunk_manifest).unwrap().to_string(),
+            "@# chunks: toml\n# section: chunk_header\n[package]\nname = \"demo\"\n\n# section: chunk_deps\n[dependencies]\nserde = \"1\""
+        );
+        // the metadata applies to the nested template, the separators keep the indentation of the sections
+        assert_eq!(
+            gtp!(chunk_page).unwrap().to_string(),
+            "<main>\n  @# chunks: toml\n  # section: chunk_header\n  [package]\n  name = \"demo\"\n\n  # section: chunk_deps\n  [dependencies]\n  serde = \"1\"\n</main>"
+        );
+
+        let options = RenderOptions {
+            chunk_hints: CommentStyle::for_language("html"),
+            ..Default::default()
+        };
+        let page = gtp!(chunk_page).unwrap();
+        assert!(page
+            .to_string_with_options(&options)
+            .starts_with("<main>\n  <!-- section: chunk_manifest -->\n  @# chunks: toml\n  # section: chunk_header"));
+
+        // the metadata turns the option off for the template, and the lines with more than a placeholder aren't sections
+        tp!(chunk_plain, "@# chunks: none\n@{chunk_header}");
+        tp!(chunk_inline, "name: @{chunk_header}");
+        assert_eq!(
+            gtp!(chunk_plain).unwrap().to_string_with_options(&options),
+            "@# chunks: none\n[package]\nname = \"demo\""
+        );
+        assert!(!gtp!(chunk_inline)
+            .unwrap()
+            .to_string_with_options(&options)
+            .contains("section"));
+    }
+
+    #[test]
+    fn test_load_values() {
+        use rtile::values::*;
+
+        let values = parse_values(
+            "# comment\nNAME='Ada # Lovelace'\nGREETING=\"Hello\\n\\\"world\\\"\"\nKEY=\"multi\nline\"\nEMPTY=\n",
+            Format::DotEnv,
+        )
+        .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                ("NAME".to_string(), "Ada # Lovelace".to_string()),
+                ("GREETING".to_string(), "Hello\n\"world\"".to_string()),
+                ("KEY".to_string(), "multi\nline".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+        let error = parse_values("A=1\nB \"2\"", Format::DotEnv).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: missing `=` after `B \"2\"`");
+        let error = parse_values("A=1\nB=\"2", Format::DotEnv).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unclosed \" quote");
+
+        let values = parse_values(
+            "! comment\nkey1 value1\nkey\\ 2 = a\\tb\nfruits = apple, \\\n         banana\nbare\n",
+            Format::JavaProperties,
+        )
+        .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                ("key1".to_string(), "value1".to_string()),
+                ("key 2".to_string(), "a\tb".to_string()),
+                ("fruits".to_string(), "apple, banana".to_string()),
+                ("bare".to_string(), String::new()),
+            ]
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "rtile_test_values_{}.properties",
+            std::process::id()
+        ));
+        std::fs::write(&path, "lv_app=billing\nlv_port=80\nlv_port=8080\n").unwrap();
+        assert_eq!(
+            load_values(&path, Format::JavaProperties).unwrap(),
+            vec!["lv_app", "lv_port"]
+        );
+        assert_eq!(t!("@{lv_app}:@{lv_port}").to_string(), "billing:8080");
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            load_values(&path, Format::JavaProperties),
+            Err(ValuesError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn test_to_ident_and_lit_str_tiles() {
+        use rtile::rust::*;
+
+        assert_eq!(to_ident_tile("name").unwrap().to_string(), "name");
+        assert_eq!(to_ident_tile("match").unwrap().to_string(), "r#match");
+        assert_eq!(to_ident_tile("größe").unwrap().to_string(), "größe");
+        assert_eq!(to_ident_tile(""), Err(IdentError::Empty));
+        assert_eq!(
+            to_ident_tile("self"),
+            Err(IdentError::Reserved("self".to_string()))
+        );
+        assert_eq!(
+            to_ident_tile("1st"),
+            Err(IdentError::InvalidChar {
+                ident: "1st".to_string(),
+                c: '1'
+            })
+        );
+
+        assert_eq!(
+            to_lit_str_tile("  tab\there \\ \"q\"\u{7}").to_string(),
+            r#""  tab\there \\ \"q\"\u{7}""#
+        );
+        // user data with a placeholder is kept as it is, not expanded
+        tp!(lit_str_user, "Alice");
+        tp!(lit_str_value, to_lit_str_tile("@{lit_str_user}"));
+        assert_eq!(
+            t!("const NAME: &str = @{lit_str_value};").to_string(),
+            r#"const NAME: &str = "@\u{7b}lit_str_user}";"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_ident() {
+        use rtile::ident::*;
+        use std::collections::HashSet;
+
+        let mut scope = HashSet::new();
+        let fields: Vec<String> = ["id", "type", "self", "", "Id", "id", "type", "größe"]
+            .iter()
+            .map(|name| sanitize_ident(name, Lang::Rust, &mut scope))
+            .collect();
+        assert_eq!(
+            fields,
+            vec!["id", "r#type", "self_", "__", "Id", "id_2", "type_2", "größe"]
+        );
+
+        let mut scope = HashSet::new();
+        let columns: Vec<String> = ["Order", "user name", "UserName", "größe", "select"]
+            .iter()
+            .map(|name| sanitize_ident(name, Lang::Sql, &mut scope))
+            .collect();
+        assert_eq!(
+            columns,
+            vec!["Order_", "username", "UserName_2", "gre", "select_"]
+        );
+
+        let mut scope: HashSet<String> = ["from_".to_string()].into();
+        assert_eq!(sanitize_ident("from", Lang::Python, &mut scope), "from_2");
+        assert_eq!(sanitize_ident("None", Lang::Python, &mut scope), "None_");
+        assert_eq!(sanitize_ident("type", Lang::Python, &mut scope), "type");
+    }
+
+    #[test]
+    fn test_rtile_context() {
+        clear_tiles();
+        tp!(ctx_lang, "en");
+        let mut first = RTileContext::new();
+        let mut second = RTileContext::new();
+        first.enter(|| {
+            tp!(ctx_lang, "fr");
+            tp!(ctx_greet, "@{ctx_lang}: bonjour");
+        });
+        second.enter(|| tp!(ctx_greet, "@{ctx_lang}: hello"));
+        tp!(second => ctx_lang, "de");
+
+        assert_eq!(ts!(first => "@{ctx_greet}"), "fr: bonjour");
+        assert_eq!(
+            second.enter(|| gtp!(ctx_greet).unwrap().to_string()),
+            "de: hello"
+        );
+        // nested contexts restore the outer one
+        let nested = first.enter(|| second.enter(|| ts!("@{ctx_lang}")) + &ts!("@{ctx_lang}"));
+        assert_eq!(nested, "defr");
+        assert_eq!(ts!("@{ctx_lang}"), "en");
+        assert!(gtp!(ctx_greet).is_none());
+
+        // the tls is restored after a panic too
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            first.enter(|| panic!("failed render"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(ts!("@{ctx_lang}"), "en");
+        assert!(first.contains("ctx_greet"));
+    }
+
+    #[test]
+    fn test_post_processors() {
+        fn semicolons(output: &str) -> String {
+            output
+                .lines()
+                .map(|ln| match ln.ends_with(';') || ln.is_empty() {
+                    true => ln.to_string(),
+                    false => format!("{};", ln),
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        }
+        fn banner(output: &str) -> String {
+            format!("-- generated\n{}", output)
+        }
+
+        clear_post_processors();
+        tp!(pp_stmt, "SELECT 1\nSELECT 2;");
+        tp!(pp_script, "@{pp_stmt}\n\nCOMMIT");
+        add_template_post_processor("pp_stmt", semicolons);
+        add_post_processor(banner);
+        // the template post processor applies to the inner tile only, the global one to the whole output once
+        assert_eq!(
+            gtp!(pp_script).unwrap().to_string(),
+            "-- generated\nSELECT 1;\nSELECT 2;\n\nCOMMIT"
+        );
+        assert_eq!(
+            gtp!(pp_stmt).unwrap().to_string(),
+            "-- generated\nSELECT 1;\nSELECT 2;"
+        );
+
+        clear_post_processors();
+        assert_eq!(
+            gtp!(pp_script).unwrap().to_string(),
+            "SELECT 1\nSELECT 2;\n\nCOMMIT"
+        );
+        let options = RenderOptions {
+            post_processors: vec![semicolons, banner],
+            ..Default::default()
+        };
+        assert_eq!(
+            gtp!(pp_script).unwrap().to_string_with_options(&options),
+            "-- generated\nSELECT 1;\nSELECT 2;\n\nCOMMIT;"
+        );
+    }
+
+    #[test]
+    fn test_registration_validator() {
+        fn at_most_two_lines(name: &str, tile: &RTile) -> Result<(), String> {
+            match tile.lns.len() > 2 {
+                true => Err(format!("{} has {} lines", name, tile.lns.len())),
+                false => Ok(()),
+            }
+        }
+
+        set_registration_validator(at_most_two_lines);
+        tp!(rv_short, "one\ntwo");
+        let result = std::panic::catch_unwind(|| {
+            tq!("rv_long", "one\ntwo\nthree");
+        });
+        let message = result.unwrap_err();
+        assert_eq!(
+            message.downcast_ref::<String>().unwrap(),
+            "the tile rv_long is rejected: rv_long has 3 lines"
+        );
+        assert!(gtp!(rv_long).is_none());
+
+        let mut store = TileStore::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            stp!(store => rv_long, t!("one\ntwo\nthree"));
+        }));
+        assert!(result.is_err());
+        assert!(!store.contains("rv_long"));
+
+        clear_registration_validator();
+        tq!("rv_long", "one\ntwo\nthree");
+        assert_eq!(ts!("@{rv_short} @{rv_long}"), "one one\ntwo two\n    three");
+    }
+
+    #[test]
+    fn test_try_expand() {
+        tp!(te_page, "<body>@{te_body}</body>");
+        assert_eq!(
+            t!("@{te_page}").try_expand(),
+            Err(RTileError::MissingTile {
+                name: "te_body".to_string(),
+                parents: vec!["te_page".to_string()]
+            })
+        );
+        // a blank tile persisted on purpose isn't missing
+        tp!(te_body);
+        assert_eq!(
+            t!("@{te_page}").try_expand(),
+            Ok("<body></body>".to_string())
+        );
+
+        tp!(te_a, "a @{te_b}");
+        tp!(te_b, "b @{te_a}");
+        let error = t!("@{te_a}").try_expand().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "detected a recursion: te_a -> te_b -> te_a"
+        );
+
+        let tile = detached(|| RTile::new(vec!["x @{te_body".to_string()]));
+        assert_eq!(
+            tile.try_expand(),
+            Err(RTileError::UnfinishedPlaceholder {
+                line: "x @{te_body".to_string()
+            })
+        );
+        let tile = detached(|| RTile::new(vec!["@{te_page} @{len(te_body}".to_string()]));
+        assert!(matches!(
+            tile.try_expand(),
+            Err(RTileError::InvalidPlaceholder { placeholder, .. }) if placeholder == "len(te_body"
+        ));
+        // the fallible constructor reports the same problems instead of panicking
+        assert_eq!(
+            RTile::try_new(vec!["x @{te_body".to_string()]),
+            Err(RTileError::UnfinishedPlaceholder {
+                line: "x @{te_body".to_string()
+            })
+        );
+        assert!(matches!(
+            RTile::try_construct_from_str("@{te_body:bogus}"),
+            Err(RTileError::InvalidPlaceholder { placeholder, .. }) if placeholder == "te_body:bogus"
+        ));
+        assert_eq!(
+            RTile::try_construct_from_str("@{te_page}").map(|tile| tile.to_string()),
+            Ok("<body></body>".to_string())
+        );
+    }
+
+    #[test]
+    fn test_store_events() {
+        clear_tiles();
+        record_store_events(true);
+        tp!(ev_title, "Report");
+        let set_line = line!() - 1;
+        tq!("ev_title", "Report");
+        tp!(ev_title, "Payroll");
+        remove_tile("ev_missing");
+        remove_tile("ev_title");
+        clear_tiles();
+        let clear_line = line!() - 1;
+        record_store_events(false);
+
+        let events = store_events();
+        let kinds: Vec<StoreEventKind> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                StoreEventKind::Set,
+                StoreEventKind::Set,
+                StoreEventKind::Set,
+                StoreEventKind::Remove,
+                StoreEventKind::Clear
+            ]
+        );
+        // the same raw lines keep the hash, so an overwrite with another text stands out
+        assert_eq!(events[1].old_hash, events[1].new_hash);
+        assert_ne!(events[2].old_hash, events[2].new_hash);
+        assert_eq!(events[3].old_hash, events[2].new_hash);
+        assert_eq!(events[4].name, None);
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        assert_eq!(events[0].location.file(), file!());
+        assert_eq!(events[0].location.line(), set_line);
+        assert_eq!(events[4].location.line(), clear_line);
+
+        // the log is append only, the events are kept after the recording stops
+        tp!(ev_title, "Summary");
+        assert_eq!(store_events().len(), 5);
+        limit_store_events(Some(2));
+        assert_eq!(store_events()[1].kind, StoreEventKind::Clear);
+        assert_eq!(drain_store_events().len(), 2);
+        assert!(store_events().is_empty());
+        limit_store_events(None);
+
+        let mut store = TileStore::new();
+        store.set("ev_store", t!("one"));
+        assert!(store.events().is_empty());
+        store.record_events(true);
+        store.set("ev_store", t!("two"));
+        store.clear();
+        assert_eq!(store.events().len(), 2);
+        assert_eq!(store.events()[0].location.file(), file!());
+
+        // a fork records its own events from an empty log
+        let mut fork = store.fork();
+        assert!(fork.events().is_empty());
+        fork.set("ev_store", t!("three"));
+        assert_eq!(fork.events().len(), 1);
+        assert_eq!(store.events().len(), 2);
+    }
+
+    #[test]
+    fn test_tpv() {
+        clear_tiles();
+        tpv!(tpv_stub, "1.4", "v1.4");
+        tpv!(tpv_stub, "2", "v2.0");
+        // the version is normalized, i.e. `2` is persisted as `tpv_stub@2.0.0`
+        tpv!(tpv_stub, "2.1.3", "v2.1.3");
+        tpv!(tpv_stub, "3", "v3");
+        assert_eq!(
+            get_raw_tile(&"tpv_stub@2.1.3".to_string()).map(|t| t.to_string()),
+            Some("v2.1.3".to_string())
+        );
+        assert_eq!(ts!("@{tpv_stub@^1}"), "v1.4");
+        assert_eq!(ts!("@{tpv_stub@^2}"), "v2.1.3");
+        assert_eq!(ts!("@{tpv_stub@~2.0}"), "v2.0");
+        assert_eq!(ts!("@{tpv_stub@>=1.5, <3}"), "v2.1.3");
+        assert_eq!(ts!("@{tpv_stub@=2.0.0}"), "v2.0");
+        assert_eq!(ts!("@{tpv_stub@*}"), "v3");
+
+        // no compatible version, the requirement is not turned into a blank tile
+        let error = t!("@{tpv_stub@^4}").try_expand().unwrap_err();
+        assert_eq!(
+            error,
+            RTileError::IncompatibleVersion {
+                name: "tpv_stub".to_string(),
+                requirement: "^4".to_string()
+            }
+        );
+        let result = std::panic::catch_unwind(|| ts!("@{tpv_stub@^4}"));
+        assert!(result.is_err());
+        assert!(get_raw_tile(&"tpv_stub@^4".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_multiline_placeholders() {
+        clear_tiles();
+        tp!(ml_first, "Ada");
+        tp!(ml_last, "Lovelace");
+        tp!(ml_greeting, "Hello @{given} @{family}");
+        let tile = t!("
+            fn main() {
+                println!(\"@{ml_greeting with
+                    given=ml_first,
+                    family=ml_last
+                }!\");
+                let name = \"@{ml_first:
+                    skip_if_blank
+                } @{ml_last}\";
+            }
+        ");
+        // the placeholders spanning lines are joined into a single line
+        assert_eq!(
+            sr!(tile),
+            "fn main() {\n    println!(\"@{ml_greeting with given=ml_first, family=ml_last}!\");\n    let name = \"@{ml_first: skip_if_blank} @{ml_last}\";\n}"
+        );
+        assert_eq!(
+            tile.to_string(),
+            "fn main() {\n    println!(\"Hello Ada Lovelace!\");\n    let name = \"Ada Lovelace\";\n}"
+        );
+        // the braces of an indirect reference are tracked across the lines
+        tp!(ml_selector, "ml_last");
+        assert_eq!(ts!("@{@{\n    ml_selector\n}}"), "Lovelace");
+        // a placeholder which is never closed is still reported
+        let error = detached(|| t!("@{ml_first with\n    given=ml_last")).try_expand();
+        assert!(matches!(
+            error,
+            Err(RTileError::UnfinishedPlaceholder { .. })
+        ));
+    }
+
+    #[test]
+    fn test_placeholders_adjacent_to_braces() {
+        clear_tiles();
+        tp!(nb_name, "Point");
+        tp!(nb_body, "x: i32");
+        tp!(nb_selector, "nb_name");
+        assert_eq!(ts!("@{nb_name}}"), "Point}");
+        assert_eq!(ts!("{@{nb_name}}"), "{Point}");
+        // with the mustache feature, `{{` opens a mustache placeholder instead
+        #[cfg(not(feature = "mustache"))]
+        assert_eq!(ts!("{{@{nb_name}}}"), "{{Point}}");
+        assert_eq!(
+            ts!("struct @{nb_name} {@{nb_body}}"),
+            "struct Point {x: i32}"
+        );
+        assert_eq!(
+            ts!("int f() { return @{nb_name}; }"),
+            "int f() { return Point; }"
+        );
+        assert_eq!(
+            ts!("format!(\"{}\", @{nb_name}){}"),
+            "format!(\"{}\", Point){}"
+        );
+        // the nested placeholder of an indirect reference
+        assert_eq!(ts!("{@{@{nb_selector}}}"), "{Point}");
+        // the braces and the escaped quotes in the strings of an expression
+        assert_eq!(ts!("@{\"}\" + nb_name + \"{\"}"), "}Point{");
+        assert_eq!(ts!("@{\"\\\"}\" + nb_name}}"), "\"}Point}");
+        assert_eq!(ts!("@{\"{\\\\\" + nb_name}"), "{\\Point");
+    }
+
+    #[test]
+    fn test_parameterized_tiles() {
+        clear_tiles();
+        tp!(pt_alice, "Alice");
+        tp!(pt_bob, "Bob");
+        tp!(pt_dr, "Dr.");
+        tp!(pt_greet, "@{params title, name}\nHello @{title} @{name}!");
+        assert_eq!(
+            ts!("@{pt_greet(title=pt_dr, name=pt_alice)}\n@{pt_greet(name=pt_bob, title=pt_dr)}"),
+            "Hello Dr. Alice!\nHello Dr. Bob!"
+        );
+        // the declaration may follow the comments of the template
+        tp!(pt_doc, "@# Greets a person\n@{params name}\nHi @{name}");
+        assert_eq!(ts!("@{pt_doc(name=pt_bob)}"), "@# Greets a person\nHi Bob");
+        // the arguments are forwarded, and the options still apply
+        tp!(
+            pt_letter,
+            "@{params who}\nDear @{pt_greet(title=pt_dr, name=who)}"
+        );
+        assert_eq!(ts!("[@{pt_letter(who=pt_bob)}]"), "[Dear Hello Dr. Bob!]");
+        tp!(pt_pair, "@{params name}\n@{name} &\n@{name}");
+        assert_eq!(
+            ts!("(@{pt_pair(name=pt_alice):align=1}"),
+            "(Alice &\n Alice"
+        );
+        // the functions of the expressions are not calls
+        tp!(pt_items, "a\nb");
+        assert_eq!(ts!("@{len(pt_items)}"), "2");
+
+        let error = t!("@{pt_greet(name=pt_alice)}").try_expand().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid arguments of the tile pt_greet: the parameter `title` is not given"
+        );
+        let error = t!("@{pt_greet(title=pt_dr, name=pt_alice, nick=pt_bob)}")
+            .try_expand()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            RTileError::InvalidArguments {
+                name: "pt_greet".to_string(),
+                message: "`nick` is not a parameter".to_string()
+            }
+        );
+        // the arguments of the inner tiles are checked too
+        let mut store = TileStore::new();
+        store.set("pt_page", detached(|| t!("<p>@{pt_hi}</p>")));
+        store.set("pt_hi", detached(|| t!("@{params name}\nHi @{name}")));
+        assert!(matches!(
+            store.try_render(&detached(|| t!("@{pt_page}"))),
+            Err(RTileError::InvalidArguments { .. })
+        ));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "invalid arguments of the tile pt2_greet: the parameter `name` is not given"
+    )]
+    fn test_parameterized_tiles_missing_argument() {
+        tp!(pt2_greet, "@{params name}\nHello @{name}!");
+        ts!("@{pt2_greet}");
+    }
+
+    #[test]
+    fn test_raw_blocks() {
+        clear_tiles();
+        tp!(rb_name, "Alice");
+        assert_eq!(
+            ts!("@{rb_name} says @{raw}@{rb_name}}{@{endraw}!"),
+            "Alice says @{rb_name}}{!"
+        );
+        let tile = t!(r#"
+            fn render() -> String {
+                @{raw}
+                let path = "C:\temp\@{dir}";
+                    ts!("Hello @{name} {{user}}")
+                @{endraw}
+            }
+            // by @{rb_name}
+        "#);
+        assert_eq!(
+            tile.to_string(),
+            "fn render() -> String {\n    let path = \"C:\\temp\\@{dir}\";\n        ts!(\"Hello @{name} {{user}}\")\n}\n// by Alice"
+        );
+        // the placeholders of a raw block are not inner tiles
+        assert!(!get_blank_tiles().contains("name"));
+        assert!(!get_blank_tiles().contains("dir"));
+    }
+
+    #[test]
+    #[should_panic(expected = "unfinished @{raw} expression")]
+    fn test_raw_blocks_unfinished() {
+        t!("@{raw}@{name}");
+    }
+
+    #[test]
+    fn test_trim_markers() {
+        clear_tiles();
+        tp!(tm_imports);
+        tp!(tm_fields, "x: i32, y: i32");
+        let tile = t!("
+            use std::fmt;
+            @{-tm_imports}
+
+            struct Point { @{tm_fields-}
+
+            }
+        ");
+        assert_eq!(
+            sr!(tile),
+            "use std::fmt;@{tm_imports}\n\nstruct Point { @{tm_fields}}"
+        );
+        assert_eq!(
+            tile.to_string(),
+            "use std::fmt;\n\nstruct Point { x: i32, y: i32}"
+        );
+        // both sides, and within a line
+        tp!(tm_name, "Alice");
+        assert_eq!(ts!("[ \n  @{-tm_name-}  \n ]"), "[Alice]");
+        assert_eq!(ts!("a @{-tm_name} b"), "aAlice b");
+        // the trim markers are not the operators of the expressions
+        tp!(tm_count, "3");
+        assert_eq!(ts!("@{tm_count - 1}"), "2");
+    }
+
+    #[test]
+    fn test_includes() {
+        clear_tiles();
+        let root = std::env::temp_dir().join(format!("rtile_includes_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("partials")).unwrap();
+        std::fs::write(
+            root.join("partials/fields.rtile"),
+            "id: u64,\nname: @{inc_type},",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("partials/struct.rtile"),
+            "struct User {\n    @{include:./partials/fields.rtile}\n}",
+        )
+        .unwrap();
+        std::fs::write(root.join("a.rtile"), "a @{include:partials/../b.rtile}").unwrap();
+        std::fs::write(root.join("b.rtile"), "b @{include:a.rtile}").unwrap();
+        set_template_root(&root);
+
+        tp!(inc_type, "String");
+        let tile = t!("
+            // @{include:partials/struct.rtile}
+            @{include:partials/struct.rtile}
+        ");
+        assert_eq!(
+            tile.to_string(),
+            "// struct User {\n       id: u64,\n       name: String,\n   }\nstruct User {\n    id: u64,\n    name: String,\n}"
+        );
+        // the included files are tiles, named after their paths
+        assert_eq!(
+            sr!(get_raw_tile("partials/struct.rtile").unwrap()),
+            "struct User {\n    @{partials/fields.rtile}\n}"
+        );
+
+        let cycle = std::panic::catch_unwind(|| t!("@{include:a.rtile}"));
+        let message = cycle.unwrap_err();
+        assert_eq!(
+            message.downcast_ref::<String>().unwrap(),
+            "the include of a.rtile is a cycle: a.rtile -> b.rtile -> a.rtile"
+        );
+        let outside = std::panic::catch_unwind(|| t!("@{include:../secret.rtile}"));
+        assert!(outside.is_err());
+        // the includes of a raw block are kept
+        assert_eq!(
+            ts!("@{raw}@{include:a.rtile}@{endraw}"),
+            "@{include:a.rtile}"
+        );
+
+        clear_template_root();
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_expand_each() {
+        clear_tiles();
+        tp!(ee_vis, "pub");
+        let items = [t!("id: u64,"), t!("tags: Vec<String>,\n// sorted")];
+        let fields = t!("@{ee_vis} @{ee_field}").expand_each("ee_field", &items);
+        assert_eq!(
+            fields.to_string(),
+            "pub id: u64,\npub tags: Vec<String>,\n    // sorted"
+        );
+        // the stamps are tiles, composed side by side with the other tiles
+        let names = t!("@{ee_name},").expand_each("ee_name", &[t!("a"), t!("b")]);
+        assert_eq!(
+            (t!("enum E {") + names + t!("}")).to_string(),
+            "enum E {a,}\n        b,"
+        );
+        // the options of the placeholder are applied to every item
+        let args = t!("f(@{ee_arg:align=2})").expand_each("ee_arg", &[t!("x,\ny")]);
+        assert_eq!(args.to_string(), "f(x,)\n  y");
+        assert_eq!(t!("@{ee_none}").expand_each("ee_none", &[]).to_string(), "");
+        // the tls is left untouched
+        assert_eq!(gtp!(ee_field).unwrap().to_string(), "");
+    }
+
+    #[test]
+    fn test_global_tiles() {
+        gp!(test_global_item, "item");
+        gq!("test_global_list", "- @{test_global_item}");
+        let output = std::thread::spawn(|| t!("list:\n@{test_global_list}").to_string())
+            .join()
+            .unwrap();
+        assert_eq!(output, "list:\n- item");
+        assert_eq!(
+            get_global_tile("test_global_item").unwrap().to_string(),
+            "item"
+        );
+        assert_eq!(
+            remove_global_tile("test_global_item").unwrap().to_string(),
+            "item"
+        );
+        assert!(get_global_tile("test_global_item").is_none());
+        let output = std::thread::spawn(|| t!("@{test_global_item}").to_string())
+            .join()
+            .unwrap();
+        assert_eq!(output, "");
+        // the later changes of the registry are seen by the thread, also for the names referenced before they are registered
+        let output = std::thread::spawn(|| {
+            let tile = t!("Hello @{test_global_user}, @{test_global_late}");
+            let mut outputs = vec![tile.to_string()];
+            set_global_tile("test_global_user", &t!("Alice"));
+            outputs.push(tile.to_string());
+            set_global_tile("test_global_user", &t!("Bob"));
+            set_global_tile("test_global_late", &t!("Carol"));
+            outputs.push(tile.to_string());
+            remove_global_tile("test_global_user");
+            outputs.push(tile.to_string());
+            outputs
+        })
+        .join()
+        .unwrap();
+        assert_eq!(
+            output,
+            vec![
+                "Hello ,",
+                "Hello Alice,",
+                "Hello Bob, Carol",
+                "Hello , Carol"
+            ]
+        );
+        remove_global_tile("test_global_late");
+    }
+
+    #[test]
+    fn test_template_render_options() {
+        fn upper(output: &str) -> String {
+            output.to_uppercase()
+        }
+
+        tp!(tro_script, "echo @{tro_word}\n@{tro_missing}");
+        tp!(tro_word, "hi");
+        set_template_render_options(
+            "tro_script",
+            RenderOptions {
+                line_ending: LineEnding::CrLf,
+                drop_blank_placeholder_lines: true,
+                post_processors: vec![upper],
+                ..Default::default()
+            },
+        );
+        let script = gtp!(tro_script).unwrap();
+        assert_eq!(script.to_string(), "ECHO HI");
+        assert_eq!(t!("@{tro_script}\nend").to_string(), "echo hi\nend");
+        assert_eq!(
+            script.to_string_with_options(&RenderOptions::default()),
+            "echo hi"
+        );
+
+        let mut store = with_default_store(|store| store.fork());
+        tp!(store => tro_page, "a\n@{tro_body}");
+        store.set_render_options(
+            "tro_page",
+            RenderOptions {
+                line_ending: LineEnding::CrLf,
+                strict: true,
+                ..Default::default()
+            },
+        );
+        let page = store.get("tro_page").unwrap();
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| store.clone().render(&page)));
+        assert!(result.is_err());
+        tp!(store => tro_body, "b");
+        assert_eq!(store.render(&page), "a\r\nb");
+        assert_eq!(store.fork().render(&page), "a\r\nb");
+        store.clear_render_options("tro_page");
+        assert_eq!(store.render(&page), "a\nb");
+        assert!(store.render_options("tro_page").is_none());
+        clear_template_render_options("tro_script");
+    }
+
+    #[test]
+    #[deny(non_snake_case)]
+    fn test_tps() {
+        let tps_name = "outer";
+        tps!(TPS_TITLE, "{}", tps_name.to_uppercase());
+        kps!(TPS_PADDED, " @{TPS_TITLE} ");
+        let persist = |value: &str| tps!(TPS_VALUE, "{}", value);
+        assert_eq!(persist("a").name, Some("TPS_VALUE".to_string()));
+        assert_eq!(ts!("[@{TPS_PADDED}] @{TPS_VALUE}"), "[ OUTER ] a");
+        assert_eq!(tps_name, "outer");
+
+        let mut store = TileStore::new();
+        tps!(store => TPS_TITLE, "store");
+        kps!(store => TPS_PADDED, " @{TPS_TITLE} ");
+        assert_eq!(ts!(store => "[@{TPS_PADDED}]"), "[ store ]");
+    }
+
+    #[test]
+    fn test_declare_tiles() {
+        declare_tiles! {
+            "declared.name": "Alice",
+            "declared.note",
+            "declared.card": "@{declared.name}:@{declared.note}",
+        }
+        assert_eq!(ts!("@{declared.card}"), "Alice:");
+        assert!(gtq!("declared.note").unwrap().lns.is_empty());
+        declare_tiles! {}
+
+        let mut store = TileStore::new();
+        declare_tiles!(store => "declared.card": "[@{declared.name}]", "declared.name",);
+        assert_eq!(ts!(store => "@{declared.card}"), "[]");
+        assert!(store.contains("declared.name"));
+    }
+
+    #[test]
+    fn test_testing_isolation() {
+        use rtile::testing::{capture_warnings, fresh_store, render_isolated};
+
+        tp!(iso_name, "outer");
+        let greet = t!("Hi @{iso_name}");
+        assert_eq!(
+            render_isolated(&greet, vec![("iso_name", "inner")]),
+            "Hi inner"
+        );
+        assert_eq!(render_isolated(&greet, Vec::<(&str, &str)>::new()), "Hi");
+        {
+            let _store = fresh_store();
+            assert!(gtp!(iso_name).is_none());
+            tp!(iso_name, "fresh");
+            assert_eq!(greet.to_string(), "Hi fresh");
+        }
+        assert_eq!(greet.to_string(), "Hi outer");
+
+        let store = TileStore::new();
+        let tile = detached(|| t!("@{iso_missing_one}@{iso_missing_two}"));
+        let outer = capture_warnings();
+        {
+            let inner = capture_warnings();
+            store.render_cached(&tile);
+            assert_eq!(
+                inner.warnings(),
+                vec![
+                    "iso_missing_one tile is not found",
+                    "iso_missing_two tile is not found"
+                ]
+            );
+        }
+        assert_eq!(outer.warnings().len(), 2);
+    }
+
+    #[test]
+    fn test_rename_placeholder_onto_existing_tile() {
+        let mut store = TileStore::new();
+        tp!(store => rpe_a, "A");
+        tp!(store => rpe_b, "B");
+        tp!(store => rpe_c, "@{rpe_a}-@{rpe_b}");
+        let error = Err(RTileError::TileExists {
+            name: "rpe_b".to_string(),
+        });
+        assert_eq!(store.rename_placeholder("rpe_a", "rpe_b", true), error);
+        assert_eq!(store.rename_placeholder("rpe_a", "rpe_b", false), error);
+        assert_eq!(ts!(store => "@{rpe_c}"), "A-B");
+        assert_eq!(store.get("rpe_b").unwrap().raw(), "B");
+
+        tp!(store => rpe_greet, "Hi @{rpe_who}");
+        tp!(store => rpe_page, "@{rpe_greet with rpe_who=rpe_a} @{rpe_greet:skip_if_blank}");
+        assert_eq!(
+            store
+                .rename_placeholder("rpe_greet", "rpe_hello", false)
+                .unwrap(),
+            vec!["rpe_greet", "rpe_page"]
+        );
+        assert_eq!(
+            store.get("rpe_page").unwrap().raw(),
+            "@{rpe_hello with rpe_who=rpe_a} @{rpe_hello:skip_if_blank}"
+        );
+        assert_eq!(ts!(store => "@{rpe_page}"), "Hi A Hi");
+    }
+
+    #[test]
+    fn test_rename_placeholder_forms() {
+        let mut store = TileStore::new();
+        tpv!(store => rpf_api, "1.4", "v1");
+        tpv!(store => rpf_api, "2.0", "v2");
+        tp!(store => rpf_count, "3");
+        tp!(store => rpf_select, "rpf_count");
+        tp!(store => rpf_page, "@{rpf_api@^2} @{rpf_api@^1:skip_if_blank}\n@{rpf_count + 1} @{len(rpf_count)} @{@{rpf_select}}");
+        tp!(store => rpf_other, "@{rpf_api_v} \"@{\" rpf_api \"}\"");
+        let before = ts!(store => "@{rpf_page}");
+        store.record_events(true);
+        assert_eq!(
+            store
+                .rename_placeholder("rpf_api", "rpf_stub", false)
+                .unwrap(),
+            vec!["rpf_api@1.4.0", "rpf_api@2.0.0", "rpf_page"]
+        );
+        store.drain_events();
+        assert_eq!(
+            store
+                .rename_placeholder("rpf_count", "rpf_total", false)
+                .unwrap(),
+            vec!["rpf_count", "rpf_page"]
+        );
+        let events: Vec<_> = store
+            .drain_events()
+            .into_iter()
+            .map(|event| (event.kind, event.name.unwrap()))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (StoreEventKind::Set, "rpf_page".to_string()),
+                (StoreEventKind::Rename, "rpf_count".to_string()),
+                (StoreEventKind::Set, "rpf_total".to_string()),
+            ]
+        );
+        assert_eq!(
+            store.get("rpf_page").unwrap().raw(),
+            "@{rpf_stub@^2} @{rpf_stub@^1:skip_if_blank}\n@{rpf_total + 1} @{len(rpf_total)} @{@{rpf_select}}"
+        );
+        assert_eq!(
+            store.get("rpf_other").unwrap().raw(),
+            "@{rpf_api_v} \"@{\" rpf_api \"}\""
+        );
+        assert!(store.get("rpf_stub@2.0.0").is_some());
+        // the selector holds the name as a text, it is left as it is
+        tp!(store => rpf_select, "rpf_total");
+        assert_eq!(ts!(store => "@{rpf_page}"), before);
+
+        #[cfg(feature = "mustache")]
+        {
+            tp!(store => rpf_mustache, "{{rpf_total}} {{{ rpf_total }}} {{rpf_total:skip_if_blank}} {{! rpf_total }}");
+            store
+                .rename_placeholder("rpf_total", "rpf_sum", false)
+                .unwrap();
+            assert_eq!(
+                store.get("rpf_mustache").unwrap().raw(),
+                "{{rpf_sum}} {{{ rpf_sum }}} {{rpf_sum:skip_if_blank}} {{! rpf_total }}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_cached_tile_across_runs() {
+        let dir = std::env::temp_dir().join(format!("rtile_test_cache_{}", std::process::id()));
+        set_cache_dir(&dir);
+        let mut computed = 0;
+        for (run, key) in [(0, 42u64), (1, 42), (2, 43)] {
+            let _store = rtile::testing::fresh_store();
+            set_cached_tile("ctr_types", key, || {
+                computed += 1;
+                t!("struct Run{};", run)
+            });
+            let expected = if run < 2 {
+                "struct Run0;"
+            } else {
+                "struct Run2;"
+            };
+            assert_eq!(ts!("@{ctr_types}"), expected);
+        }
+        assert_eq!(computed, 2);
+
+        // the key is hashed the same way in every run and on every platform
+        set_cached_tile("ctr_stable", 42u64, || t!("stable"));
+        let file = std::fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .find(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("ctr_stable-")
+            })
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(file.path()).unwrap(),
+            "rtile-cache ff3add6b3789daef true 1\nstable"
+        );
+        clear_cache_dir();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,16 +1,13 @@
+#![allow(clippy::useless_vec)]
+
 #[cfg(test)]
 mod tests {
     use rtile::prelude::*;
 
     fn set_spacing(width_spacing: usize, height_spacing: usize) {
         //set the spacing if required - maintain unit spacing, that is sufficient to create the frame
-        let left_right_spaces = vec![" "; width_spacing];
-        kp!(main_left_right_spaces, vec![left_right_spaces.join(""); 1]);
-        let top_bottom_spaces = vec![" "; 1];
-        kp!(
-            main_top_bottom_spaces,
-            vec![top_bottom_spaces.join(""); height_spacing]
-        );
+        kp!(main_left_right_spaces, RSpacer::h(width_spacing).to_tile());
+        kp!(main_top_bottom_spaces, RSpacer::v(height_spacing).to_tile());
     }
 
     fn frame_tile(input: &RTile, width_spacing: usize, height_spacing: usize) -> RTile {
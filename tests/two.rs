@@ -1,3 +1,9 @@
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::needless_borrow,
+    clippy::same_item_push
+)]
+
 #[cfg(test)]
 mod tests {
     use rtile::prelude::*;